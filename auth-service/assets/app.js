@@ -32,9 +32,41 @@ signupLoginLink.addEventListener("click", (e) => {
 
 // -----------------------------------------------------
 
+// Pages that send users here to log in, such as an OAuth authorization request, pass
+// `return_to`. Only /authorize and the device page are accepted so the parameter cannot send
+// users to another site.
+function returnAfterLogin() {
+    const returnTo = new URLSearchParams(window.location.search).get("return_to");
+    if (returnTo !== null
+        && (returnTo.startsWith("/authorize?") || returnTo === "/device" || returnTo.startsWith("/device?"))) {
+        window.location.assign(returnTo);
+        return true;
+    }
+    return false;
+}
+
+// Offer logging in through the external identity provider when one is configured, coming back
+// to the same page afterwards
+const externalLoginLink = document.getElementById("external-login-link");
+
+fetch('/login/external/provider').then(response => {
+    if (response.status !== 200) {
+        return;
+    }
+    response.json().then(data => {
+        const returnTo = new URLSearchParams(window.location.search).get("return_to");
+        if (returnTo !== null) {
+            externalLoginLink.href = `/login/external?${new URLSearchParams({ return_to: returnTo })}`;
+        }
+        externalLoginLink.textContent = `Log in with ${data.displayName}`;
+        externalLoginLink.style.setProperty("display", "block", "important");
+    });
+});
+
 const loginForm = document.getElementById("login-form");
 const loginButton = document.getElementById("login-form-submit");
 const loginErrAlter = document.getElementById("login-err-alert");
+const loginInfoAlert = document.getElementById("login-info-alert");
 
 loginButton.addEventListener("click", (e) => {
     e.preventDefault();
@@ -48,39 +80,88 @@ loginButton.addEventListener("click", (e) => {
             'Content-Type': 'application/json',
         },
         body: JSON.stringify({ email, password }),
-    }).then(response => {
-        if (response.status === 206) {
-            TwoFAForm.email.value = email;
-            response.json().then(data => {
-                TwoFAForm.login_attempt_id.value = data.loginAttemptId;
-            });
-
-            loginForm.email.value = "";
-            loginForm.password.value = "";
+    }).then(response => finishLogin(response, email));
+});
 
-            loginSection.style.display = "none";
-            twoFASection.style.display = "block";
-            signupSection.style.display = "none";
-            loginErrAlter.style.display = "none";
-        } else if (response.status === 200) {
-            loginForm.email.value = "";
-            loginForm.password.value = "";
-            loginErrAlter.style.display = "none";
+// Shared by password and magic link logins, which answer the same way
+function finishLogin(response, email) {
+    if (response.status === 206) {
+        TwoFAForm.email.value = email;
+        response.json().then(data => {
+            TwoFAForm.login_attempt_id.value = data.loginAttemptId;
+        });
+
+        loginForm.email.value = "";
+        loginForm.password.value = "";
+
+        loginSection.style.display = "none";
+        twoFASection.style.display = "block";
+        signupSection.style.display = "none";
+        loginErrAlter.style.display = "none";
+    } else if (response.status === 200) {
+        loginForm.email.value = "";
+        loginForm.password.value = "";
+        loginErrAlter.style.display = "none";
+        if (!returnAfterLogin()) {
             alert("You have successfully logged in.");
-        } else {
-            response.json().then(data => {
-                let error_msg = data.error;
-                if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
-                    loginErrAlter.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
-                    loginErrAlter.style.display = "block";
-                } else {
-                    loginErrAlter.style.display = "none";
-                }
-            });
         }
+    } else {
+        response.json().then(data => {
+            let error_msg = data.error;
+            if (error_msg !== undefined && error_msg !== null && error_msg !== "") {
+                loginErrAlter.innerHTML = `<span><strong>Error: </strong>${error_msg}</span>`;
+                loginErrAlter.style.display = "block";
+            } else {
+                loginErrAlter.style.display = "none";
+            }
+        });
+    }
+}
+
+const magicLinkButton = document.getElementById("magic-link-submit");
+
+magicLinkButton.addEventListener("click", (e) => {
+    e.preventDefault();
+
+    const email = loginForm.email.value;
+
+    fetch('/login/magic-link', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ email }),
+    }).then(response => {
+        response.json().then(data => {
+            if (response.ok) {
+                loginErrAlter.style.display = "none";
+                loginInfoAlert.textContent = data.message;
+                loginInfoAlert.style.display = "block";
+            } else {
+                loginInfoAlert.style.display = "none";
+                loginErrAlter.innerHTML = `<span><strong>Error: </strong>${data.error}</span>`;
+                loginErrAlter.style.display = "block";
+            }
+        });
     });
 });
 
+// Emailed login links land here with the token in the fragment. It is cleared straight away so
+// the spent link does not linger in the address bar or history.
+const magicLinkParams = new URLSearchParams(window.location.hash.slice(1));
+const magicLinkToken = magicLinkParams.get("magic_link");
+if (magicLinkToken !== null) {
+    history.replaceState(null, "", window.location.pathname + window.location.search);
+
+    fetch('/login/magic-link/callback', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ token: magicLinkToken }),
+    }).then(response => finishLogin(response, magicLinkParams.get("email")));
+}
+
 const signupForm = document.getElementById("signup-form");
 const signupButton = document.getElementById("signup-form-submit");
 const signupErrAlter = document.getElementById("signup-err-alert");
@@ -145,6 +226,9 @@ TwoFAButton.addEventListener("click", (e) => {
             TwoFAForm.email_code.value = "";
             TwoFAForm.login_attempt_id.value = "";
             TwoFAErrAlter.style.display = "none";
+            if (returnAfterLogin()) {
+                return;
+            }
             alert("You have successfully logged in.");
             loginSection.style.display = "block";
             twoFASection.style.display = "none";
@@ -0,0 +1,90 @@
+const codeSection = document.getElementById("code-section");
+const approveSection = document.getElementById("approve-section");
+const doneSection = document.getElementById("done-section");
+
+const codeForm = document.getElementById("code-form");
+const codeButton = document.getElementById("code-form-submit");
+const codeErrAlert = document.getElementById("code-err-alert");
+const approveErrAlert = document.getElementById("approve-err-alert");
+
+let userCode = null;
+
+// The page is reached from the device's verification_uri_complete with the code filled in
+const queryCode = new URLSearchParams(window.location.search).get("user_code");
+if (queryCode !== null) {
+    codeForm.user_code.value = queryCode;
+}
+
+// Logging in with the auth cookie sets a readable CSRF cookie, echoed on state-changing requests
+function csrfToken() {
+    for (const cookie of document.cookie.split("; ")) {
+        const [name, value] = cookie.split("=");
+        if (name === "csrf_token" || name === "__Host-csrf_token") {
+            return value;
+        }
+    }
+    return "";
+}
+
+function showError(alert, message) {
+    alert.innerHTML = `<span><strong>Error: </strong>${message}</span>`;
+    alert.style.display = "block";
+}
+
+codeButton.addEventListener("click", (e) => {
+    e.preventDefault();
+
+    const code = codeForm.user_code.value.trim();
+    fetch(`/device/verify?user_code=${encodeURIComponent(code)}`).then(response => {
+        if (response.status === 200) {
+            response.json().then(request => {
+                userCode = request.userCode;
+                document.getElementById("approve-title").textContent =
+                    `Connect ${request.clientName}?`;
+                document.getElementById("approve-code").textContent = request.userCode;
+                const scopes = document.getElementById("approve-scopes");
+                scopes.replaceChildren(...request.scopes.map(scope => {
+                    const item = document.createElement("li");
+                    item.textContent = scope;
+                    return item;
+                }));
+                codeErrAlert.style.display = "none";
+                codeSection.style.display = "none";
+                approveSection.style.display = "block";
+            });
+        } else if (response.status === 400 || response.status === 401) {
+            // Not logged in: come back here, with the code, afterwards
+            const returnTo = `/device?user_code=${encodeURIComponent(code)}`;
+            window.location.assign(`/?return_to=${encodeURIComponent(returnTo)}`);
+        } else {
+            response.json().then(data => {
+                showError(codeErrAlert, data.error || "Unknown code");
+            });
+        }
+    });
+});
+
+function decide(decision) {
+    fetch('/device/verify', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+            'X-CSRF-Token': csrfToken(),
+        },
+        body: JSON.stringify({ userCode, decision }),
+    }).then(response => {
+        if (response.status === 204) {
+            document.getElementById("done-message").textContent =
+                decision === "allow" ? "Device connected" : "Device denied";
+            approveSection.style.display = "none";
+            doneSection.style.display = "block";
+        } else {
+            response.json().then(data => {
+                showError(approveErrAlert, data.error || "Something went wrong");
+            });
+        }
+    });
+}
+
+document.getElementById("approve-allow").addEventListener("click", () => decide("allow"));
+document.getElementById("approve-deny").addEventListener("click", () => decide("deny"));
@@ -0,0 +1,39 @@
+const reportButton = document.getElementById("report-submit");
+const reportErrAlert = document.getElementById("report-err-alert");
+
+// New-device emails link here with the token in the fragment. Nothing is reported until the
+// user confirms, so mail scanners that open the link do no harm.
+const token = new URLSearchParams(window.location.hash.slice(1)).get("token");
+history.replaceState(null, "", window.location.pathname);
+
+function showError(message) {
+    reportErrAlert.innerHTML = `<span><strong>Error: </strong>${message}</span>`;
+    reportErrAlert.style.display = "block";
+}
+
+if (token === null) {
+    reportButton.disabled = true;
+    showError("This link is incomplete. Open it again from the email.");
+}
+
+reportButton.addEventListener("click", () => {
+    reportButton.disabled = true;
+
+    fetch('/account/report-login', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ token }),
+    }).then(response => {
+        if (response.ok) {
+            // The same token sets the new password
+            window.location.assign(`/account/reset-password#token=${encodeURIComponent(token)}`);
+        } else {
+            reportButton.disabled = false;
+            response.json().then(data => {
+                showError(data.error || "Something went wrong");
+            });
+        }
+    });
+});
@@ -0,0 +1,48 @@
+const resetSection = document.getElementById("reset-section");
+const doneSection = document.getElementById("done-section");
+
+const resetForm = document.getElementById("reset-form");
+const resetButton = document.getElementById("reset-form-submit");
+const resetErrAlert = document.getElementById("reset-err-alert");
+
+// Reached from the report page with the token from the new-device email in the fragment
+const token = new URLSearchParams(window.location.hash.slice(1)).get("token");
+history.replaceState(null, "", window.location.pathname);
+
+function showError(message) {
+    resetErrAlert.innerHTML = `<span><strong>Error: </strong>${message}</span>`;
+    resetErrAlert.style.display = "block";
+}
+
+if (token === null) {
+    resetButton.disabled = true;
+    showError("This link is incomplete. Open it again from the email.");
+}
+
+resetButton.addEventListener("click", (e) => {
+    e.preventDefault();
+
+    const newPassword = resetForm.new_password.value;
+    if (newPassword !== resetForm.confirm_password.value) {
+        showError("The passwords do not match");
+        return;
+    }
+
+    fetch('/account/reset-password', {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: JSON.stringify({ token, newPassword }),
+    }).then(response => {
+        if (response.ok) {
+            resetForm.reset();
+            resetSection.style.display = "none";
+            doneSection.style.display = "block";
+        } else {
+            response.json().then(data => {
+                showError(data.error || "Something went wrong");
+            });
+        }
+    });
+});
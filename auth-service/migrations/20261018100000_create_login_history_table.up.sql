@@ -0,0 +1,14 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS login_history(
+   id BIGSERIAL PRIMARY KEY,
+   email TEXT NOT NULL,
+   occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+   ip_address TEXT,
+   user_agent TEXT,
+   success BOOLEAN NOT NULL,
+   failure_reason TEXT,
+   used_2fa BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX IF NOT EXISTS login_history_email_occurred_at_idx ON login_history (email, occurred_at DESC);
+CREATE INDEX IF NOT EXISTS login_history_occurred_at_idx ON login_history (occurred_at);
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS known_devices;
+
+ALTER TABLE users DROP COLUMN IF EXISTS requires_password_reset;
@@ -0,0 +1,11 @@
+-- Add up migration script here
+ALTER TABLE users ADD COLUMN IF NOT EXISTS requires_password_reset BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS known_devices(
+   email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE,
+   user_agent TEXT NOT NULL,
+   ip_range TEXT NOT NULL,
+   first_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+   last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+   PRIMARY KEY (email, user_agent, ip_range)
+);
@@ -0,0 +1,10 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS audit_log(
+   sequence BIGINT NOT NULL PRIMARY KEY,
+   occurred_at TIMESTAMPTZ NOT NULL,
+   event_type TEXT NOT NULL,
+   -- Kept as the exact serialized text so the hash chain can be recomputed
+   event TEXT NOT NULL,
+   previous_hash TEXT NOT NULL UNIQUE,
+   hash TEXT NOT NULL UNIQUE
+);
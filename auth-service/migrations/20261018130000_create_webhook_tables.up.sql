@@ -0,0 +1,32 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS webhook_subscriptions(
+   id UUID PRIMARY KEY,
+   url TEXT NOT NULL,
+   -- Needed in plaintext to sign each delivery
+   secret TEXT NOT NULL,
+   events TEXT[] NOT NULL,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries(
+   id UUID PRIMARY KEY,
+   subscription_id UUID NOT NULL REFERENCES webhook_subscriptions(id) ON DELETE CASCADE,
+   event_type TEXT NOT NULL,
+   payload TEXT NOT NULL,
+   attempts INTEGER NOT NULL DEFAULT 0,
+   next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+   last_error TEXT,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS webhook_deliveries_next_attempt_at_idx ON webhook_deliveries (next_attempt_at);
+
+CREATE TABLE IF NOT EXISTS webhook_dead_letters(
+   id UUID PRIMARY KEY,
+   subscription_id UUID NOT NULL REFERENCES webhook_subscriptions(id) ON DELETE CASCADE,
+   event_type TEXT NOT NULL,
+   payload TEXT NOT NULL,
+   attempts INTEGER NOT NULL,
+   last_error TEXT,
+   failed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,8 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS service_clients(
+   client_id TEXT PRIMARY KEY,
+   name TEXT NOT NULL,
+   scopes TEXT[] NOT NULL,
+   secret_hash TEXT NOT NULL,
+   created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,8 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS external_identities(
+   issuer TEXT NOT NULL,
+   subject TEXT NOT NULL,
+   email TEXT NOT NULL REFERENCES users(email) ON DELETE CASCADE,
+   linked_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+   PRIMARY KEY (issuer, subject)
+);
@@ -1,33 +1,147 @@
+use sqlx::PgPool;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use crate::domain::{BannedTokenStore, EmailClient, TwoFACodeStore, UserStore};
+use crate::{
+    domain::{
+        AuditSink, AuthorizationCodeStore, BannedTokenStore, DeviceAuthorizationStore, EmailClient,
+        ExternalIdentityStore, ExternalLoginStore, GeoLocator, KnownDeviceStore, LoginAlertStore,
+        LoginHistoryStore, MagicLinkStore, OAuthClientStore, RateLimitStore, ServiceClientStore,
+        TwoFACodeStore, UserStore, WebhookStore,
+    },
+    services::Readiness,
+    utils::{IdTokenSigner, Settings},
+};
 
 // Using a type alias to improve readability!
 pub type UserStoreType = Arc<RwLock<dyn UserStore + Send + Sync>>;
 pub type BannedTokenStoreType = Arc<RwLock<dyn BannedTokenStore + Send + Sync>>;
 pub type TwoFACodeStoreType = Arc<RwLock<dyn TwoFACodeStore + Send + Sync>>;
 pub type EmailClientType = Arc<RwLock<dyn EmailClient + Send + Sync>>;
+pub type RateLimitStoreType = Arc<RwLock<dyn RateLimitStore + Send + Sync>>;
+pub type LoginHistoryStoreType = Arc<RwLock<dyn LoginHistoryStore + Send + Sync>>;
+pub type KnownDeviceStoreType = Arc<RwLock<dyn KnownDeviceStore + Send + Sync>>;
+pub type LoginAlertStoreType = Arc<RwLock<dyn LoginAlertStore + Send + Sync>>;
+pub type GeoLocatorType = Arc<RwLock<dyn GeoLocator + Send + Sync>>;
+pub type AuditSinkType = Arc<RwLock<dyn AuditSink + Send + Sync>>;
+pub type WebhookStoreType = Arc<RwLock<dyn WebhookStore + Send + Sync>>;
+pub type OAuthClientStoreType = Arc<RwLock<dyn OAuthClientStore + Send + Sync>>;
+pub type AuthorizationCodeStoreType = Arc<RwLock<dyn AuthorizationCodeStore + Send + Sync>>;
+pub type ServiceClientStoreType = Arc<RwLock<dyn ServiceClientStore + Send + Sync>>;
+pub type DeviceAuthorizationStoreType = Arc<RwLock<dyn DeviceAuthorizationStore + Send + Sync>>;
+pub type ExternalIdentityStoreType = Arc<RwLock<dyn ExternalIdentityStore + Send + Sync>>;
+pub type ExternalLoginStoreType = Arc<RwLock<dyn ExternalLoginStore + Send + Sync>>;
+pub type MagicLinkStoreType = Arc<RwLock<dyn MagicLinkStore + Send + Sync>>;
+pub type RedisConnectionType = Arc<RwLock<redis::Connection>>;
+pub type ReadinessType = Arc<Readiness>;
+pub type IdTokenSignerType = Arc<IdTokenSigner>;
+pub type SettingsType = Arc<Settings>;
 #[derive(Clone)]
 pub struct AppState {
     pub user_store: UserStoreType,
     pub banned_token_store: BannedTokenStoreType,
     pub two_fa_code_store: TwoFACodeStoreType,
     pub email_client: EmailClientType,
+    pub rate_limit_store: RateLimitStoreType,
+    pub login_history_store: LoginHistoryStoreType,
+    pub known_device_store: KnownDeviceStoreType,
+    pub login_alert_store: LoginAlertStoreType,
+    pub geo_locator: GeoLocatorType,
+    pub audit_sink: AuditSinkType,
+    pub webhook_store: WebhookStoreType,
+    pub oauth_client_store: OAuthClientStoreType,
+    pub authorization_code_store: AuthorizationCodeStoreType,
+    pub service_client_store: ServiceClientStoreType,
+    pub device_authorization_store: DeviceAuthorizationStoreType,
+    pub external_identity_store: ExternalIdentityStoreType,
+    pub external_login_store: ExternalLoginStoreType,
+    pub magic_link_store: MagicLinkStoreType,
+    // Shared connections, kept for metrics and health checks
+    pub pg_pool: PgPool,
+    pub redis_conn: RedisConnectionType,
+    pub readiness: ReadinessType,
+    // Built once at startup from `oauth.id_token_signing_key`. None leaves OpenID Connect off.
+    pub id_token_signer: Option<IdTokenSignerType>,
+    pub settings: SettingsType,
+}
+
+// The stores and clients the routes depend on. Named fields keep the wiring in `main.rs` and
+// the tests readable, whatever order the stores were added in.
+pub struct AppStores {
+    pub user_store: UserStoreType,
+    pub banned_token_store: BannedTokenStoreType,
+    pub two_fa_code_store: TwoFACodeStoreType,
+    pub email_client: EmailClientType,
+    pub rate_limit_store: RateLimitStoreType,
+    pub login_history_store: LoginHistoryStoreType,
+    pub known_device_store: KnownDeviceStoreType,
+    pub login_alert_store: LoginAlertStoreType,
+    pub geo_locator: GeoLocatorType,
+    pub audit_sink: AuditSinkType,
+    pub webhook_store: WebhookStoreType,
+    pub oauth_client_store: OAuthClientStoreType,
+    pub authorization_code_store: AuthorizationCodeStoreType,
+    pub service_client_store: ServiceClientStoreType,
+    pub device_authorization_store: DeviceAuthorizationStoreType,
+    pub external_identity_store: ExternalIdentityStoreType,
+    pub external_login_store: ExternalLoginStoreType,
+    pub magic_link_store: MagicLinkStoreType,
 }
 
 impl AppState {
     pub fn new(
-        user_store: UserStoreType,
-        banned_token_store: BannedTokenStoreType,
-        two_fa_code_store: TwoFACodeStoreType,
-        email_client: EmailClientType,
+        stores: AppStores,
+        pg_pool: PgPool,
+        redis_conn: RedisConnectionType,
+        readiness: ReadinessType,
+        id_token_signer: Option<IdTokenSignerType>,
+        settings: SettingsType,
     ) -> Self {
+        let AppStores {
+            user_store,
+            banned_token_store,
+            two_fa_code_store,
+            email_client,
+            rate_limit_store,
+            login_history_store,
+            known_device_store,
+            login_alert_store,
+            geo_locator,
+            audit_sink,
+            webhook_store,
+            oauth_client_store,
+            authorization_code_store,
+            service_client_store,
+            device_authorization_store,
+            external_identity_store,
+            external_login_store,
+            magic_link_store,
+        } = stores;
+
         Self {
             user_store,
             banned_token_store,
             two_fa_code_store,
             email_client,
+            rate_limit_store,
+            login_history_store,
+            known_device_store,
+            login_alert_store,
+            geo_locator,
+            audit_sink,
+            webhook_store,
+            oauth_client_store,
+            authorization_code_store,
+            service_client_store,
+            device_authorization_store,
+            external_identity_store,
+            external_login_store,
+            magic_link_store,
+            pg_pool,
+            redis_conn,
+            readiness,
+            id_token_signer,
+            settings,
         }
     }
 }
@@ -0,0 +1,303 @@
+use chrono::{DateTime, SubsecRound, Utc};
+use color_eyre::eyre::Report;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+// Security-relevant things that happened to an account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditEvent {
+    UserSignedUp {
+        email: String,
+        requires_2fa: bool,
+    },
+    LoginSucceeded {
+        email: String,
+        used_2fa: bool,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+    },
+    LoginFailed {
+        email: String,
+        reason: String,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+    },
+    #[serde(rename = "two_fa_code_sent")]
+    TwoFACodeSent {
+        email: String,
+    },
+    #[serde(rename = "two_fa_verified")]
+    TwoFAVerified {
+        email: String,
+    },
+    LoggedOut {
+        email: String,
+    },
+    TokenBanned {
+        email: String,
+        reason: TokenBanReason,
+    },
+    AccountExported {
+        email: String,
+    },
+    LoginReported {
+        email: String,
+    },
+    PasswordReset {
+        email: String,
+    },
+    #[serde(rename = "oauth_consent_granted")]
+    OAuthConsentGranted {
+        email: String,
+        client_id: String,
+        scope: String,
+    },
+    // A service client exchanged the user's token for one to call `audience` with
+    TokenExchanged {
+        email: String,
+        client_id: String,
+        audience: String,
+        scope: String,
+    },
+    // An account at the external identity provider can now log in as this user
+    ExternalIdentityLinked {
+        email: String,
+        issuer: String,
+        subject: String,
+    },
+    MagicLinkSent {
+        email: String,
+    },
+}
+
+impl AuditEvent {
+    // The account the event happened to
+    pub fn email(&self) -> &str {
+        match self {
+            Self::UserSignedUp { email, .. }
+            | Self::LoginSucceeded { email, .. }
+            | Self::LoginFailed { email, .. }
+            | Self::TwoFACodeSent { email }
+            | Self::TwoFAVerified { email }
+            | Self::LoggedOut { email }
+            | Self::TokenBanned { email, .. }
+            | Self::AccountExported { email }
+            | Self::LoginReported { email }
+            | Self::PasswordReset { email }
+            | Self::OAuthConsentGranted { email, .. }
+            | Self::TokenExchanged { email, .. }
+            | Self::ExternalIdentityLinked { email, .. }
+            | Self::MagicLinkSent { email } => email,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenBanReason {
+    Logout,
+    ReportedLogin,
+    // Through `/revoke`
+    Revoked,
+}
+
+impl TokenBanReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Logout => "logout",
+            Self::ReportedLogin => "reported_login",
+            Self::Revoked => "revoked",
+        }
+    }
+}
+
+// Hash that the first record in a chain points back to
+pub const AUDIT_GENESIS_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+// An audit event plus its position in the hash chain. Each record's hash covers the previous
+// record's hash, so removing or editing any entry breaks every link after it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub sequence: i64,
+    pub occurred_at: DateTime<Utc>,
+    pub event: AuditEvent,
+    pub previous_hash: String,
+    pub hash: String,
+}
+
+impl AuditRecord {
+    // Builds the record that follows `previous` (or starts a new chain)
+    pub fn chain(previous: Option<&AuditRecord>, event: AuditEvent) -> Self {
+        let (sequence, previous_hash) = match previous {
+            Some(previous) => (previous.sequence + 1, previous.hash.clone()),
+            None => (1, AUDIT_GENESIS_HASH.to_owned()),
+        };
+
+        // Stores such as Postgres keep microseconds, so truncate up front to keep hashes stable
+        let occurred_at = Utc::now().trunc_subsecs(6);
+        let hash = compute_hash(sequence, &occurred_at, &event, &previous_hash);
+
+        Self {
+            sequence,
+            occurred_at,
+            event,
+            previous_hash,
+            hash,
+        }
+    }
+
+    pub fn computed_hash(&self) -> String {
+        compute_hash(
+            self.sequence,
+            &self.occurred_at,
+            &self.event,
+            &self.previous_hash,
+        )
+    }
+}
+
+fn compute_hash(
+    sequence: i64,
+    occurred_at: &DateTime<Utc>,
+    event: &AuditEvent,
+    previous_hash: &str,
+) -> String {
+    // Serializing a typed event is deterministic, so the same record always hashes the same
+    let event_json = serde_json::to_string(event).unwrap_or_default();
+
+    let mut hasher = Sha256::new();
+    hasher.update(previous_hash.as_bytes());
+    hasher.update(sequence.to_be_bytes());
+    hasher.update(occurred_at.to_rfc3339().as_bytes());
+    hasher.update(event_json.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum AuditChainError {
+    #[error("Audit record {sequence} has been modified")]
+    TamperedRecord { sequence: i64 },
+    #[error("Audit chain is broken before record {sequence}")]
+    BrokenLink { sequence: i64 },
+}
+
+// Checks that `records` (ordered by sequence) form an unbroken chain from the genesis hash
+pub fn verify_audit_chain(records: &[AuditRecord]) -> Result<(), AuditChainError> {
+    let mut previous: Option<&AuditRecord> = None;
+
+    for record in records {
+        let (expected_sequence, expected_previous_hash) = match previous {
+            Some(previous) => (previous.sequence + 1, previous.hash.as_str()),
+            None => (1, AUDIT_GENESIS_HASH),
+        };
+
+        if record.sequence != expected_sequence || record.previous_hash != expected_previous_hash {
+            return Err(AuditChainError::BrokenLink {
+                sequence: record.sequence,
+            });
+        }
+
+        if record.hash != record.computed_hash() {
+            return Err(AuditChainError::TamperedRecord {
+                sequence: record.sequence,
+            });
+        }
+
+        previous = Some(record);
+    }
+
+    Ok(())
+}
+
+// This trait represents the interface all concrete audit log destinations should implement
+#[async_trait::async_trait]
+pub trait AuditSink {
+    // Chains `event` onto the most recent record and appends it. Sinks take `&self` and keep
+    // concurrent writers, including other replicas sharing the sink, from forking the chain.
+    async fn record(&self, event: AuditEvent) -> Result<AuditRecord, AuditSinkError>;
+    // Every record in sequence order, e.g. to check the chain with `verify_audit_chain`
+    async fn records(&self) -> Result<Vec<AuditRecord>, AuditSinkError>;
+    // The records of events that happened to `email`, in sequence order
+    async fn records_for(&self, email: &str) -> Result<Vec<AuditRecord>, AuditSinkError>;
+}
+
+#[derive(Debug, Error)]
+pub enum AuditSinkError {
+    // The sink only writes, e.g. to standard output
+    #[error("Audit records cannot be read back")]
+    Unreadable,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(n: usize) -> AuditEvent {
+        AuditEvent::TwoFACodeSent {
+            email: format!("user{}@example.com", n),
+        }
+    }
+
+    fn chain_of(len: usize) -> Vec<AuditRecord> {
+        let mut records: Vec<AuditRecord> = Vec::new();
+        for n in 0..len {
+            let record = AuditRecord::chain(records.last(), event(n));
+            records.push(record);
+        }
+        records
+    }
+
+    #[test]
+    fn records_link_to_their_predecessor() {
+        let records = chain_of(3);
+
+        assert_eq!(records[0].sequence, 1);
+        assert_eq!(records[0].previous_hash, AUDIT_GENESIS_HASH);
+        assert_eq!(records[1].previous_hash, records[0].hash);
+        assert_eq!(records[2].previous_hash, records[1].hash);
+        assert_eq!(verify_audit_chain(&records), Ok(()));
+    }
+
+    #[test]
+    fn deleting_a_record_is_detected() {
+        let mut records = chain_of(3);
+        records.remove(1);
+
+        assert_eq!(
+            verify_audit_chain(&records),
+            Err(AuditChainError::BrokenLink { sequence: 3 })
+        );
+    }
+
+    #[test]
+    fn editing_a_record_is_detected() {
+        let mut records = chain_of(3);
+        records[1].event = event(99);
+
+        assert_eq!(
+            verify_audit_chain(&records),
+            Err(AuditChainError::TamperedRecord { sequence: 2 })
+        );
+    }
+
+    #[test]
+    fn events_serialize_with_a_type_tag() {
+        let json = serde_json::to_value(AuditEvent::TokenBanned {
+            email: "user@example.com".to_owned(),
+            reason: TokenBanReason::Logout,
+        })
+        .unwrap();
+
+        assert_eq!(json["type"], "token_banned");
+        assert_eq!(json["reason"], "logout");
+        assert_eq!(
+            serde_json::to_value(event(0)).unwrap()["type"],
+            "two_fa_code_sent"
+        );
+    }
+}
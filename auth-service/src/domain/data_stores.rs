@@ -1,10 +1,18 @@
-use crate::domain::{Email, Password};
+use crate::domain::{
+    AuthorizationCode, AuthorizationGrant, DeviceAuthorization, DeviceCode, DeviceFingerprint,
+    Email, ExternalIdentity, ExternalLoginState, LoginAlert, LoginAlertToken, LoginHistoryEntry,
+    MagicLinkToken, OAuthClient, Password, PendingExternalLogin, ServiceClient, UserCode,
+    WebhookDeadLetter, WebhookDelivery, WebhookSubscription,
+};
 
 use super::User;
+use chrono::{DateTime, Utc};
 use color_eyre::eyre::{eyre, Context, Report, Result};
 use rand::Rng;
 use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use uuid::Uuid;
 
 #[async_trait::async_trait]
 pub trait UserStore {
@@ -13,6 +21,14 @@ pub trait UserStore {
     async fn get_user(&self, email: &Email) -> Result<User, UserStoreError>;
     async fn validate_user(&self, email: &Email, password: &Password)
         -> Result<(), UserStoreError>;
+    // Blocks password logins until the user picks a new password
+    async fn require_password_reset(&mut self, email: &Email) -> Result<(), UserStoreError>;
+    // Replaces the password and lifts any pending reset requirement
+    async fn reset_password(
+        &mut self,
+        email: &Email,
+        password: Password,
+    ) -> Result<(), UserStoreError>;
 }
 
 #[async_trait::async_trait]
@@ -32,12 +48,403 @@ pub trait TwoFACodeStore {
         email: Email,
         login_attempt_id: LoginAttemptId,
         code: TwoFACode,
+        first_factor: FirstFactor,
     ) -> Result<(), TwoFACodeStoreError>;
     async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError>;
     async fn get_code(
         &self,
         email: &Email,
-    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError>;
+    ) -> Result<(LoginAttemptId, TwoFACode, FirstFactor), TwoFACodeStoreError>;
+}
+
+// This trait represents the interface all concrete rate limit stores should implement
+#[async_trait::async_trait]
+pub trait RateLimitStore {
+    // Records a hit for `key` and returns how many hits were seen in the current window
+    async fn record_hit(
+        &mut self,
+        key: &str,
+        window_seconds: u64,
+    ) -> Result<u64, RateLimitStoreError>;
+}
+
+#[async_trait::async_trait]
+pub trait LoginHistoryStore {
+    async fn add_entry(&mut self, entry: LoginHistoryEntry) -> Result<(), LoginHistoryStoreError>;
+    // Entries are returned newest first
+    async fn get_entries(
+        &self,
+        email: &Email,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<LoginHistoryEntry>, LoginHistoryStoreError>;
+    async fn count_entries(&self, email: &Email) -> Result<u64, LoginHistoryStoreError>;
+    // Removes every entry older than `cutoff` and returns how many were removed
+    async fn delete_entries_before(
+        &mut self,
+        cutoff: DateTime<Utc>,
+    ) -> Result<u64, LoginHistoryStoreError>;
+}
+
+// Devices an account has successfully logged in from
+#[async_trait::async_trait]
+pub trait KnownDeviceStore {
+    // Records the device, or refreshes when it was last seen if it is already known
+    async fn add_device(
+        &mut self,
+        email: &Email,
+        device: &DeviceFingerprint,
+    ) -> Result<(), KnownDeviceStoreError>;
+    async fn is_known_device(
+        &self,
+        email: &Email,
+        device: &DeviceFingerprint,
+    ) -> Result<bool, KnownDeviceStoreError>;
+    async fn has_known_devices(&self, email: &Email) -> Result<bool, KnownDeviceStoreError>;
+    async fn remove_device(
+        &mut self,
+        email: &Email,
+        device: &DeviceFingerprint,
+    ) -> Result<(), KnownDeviceStoreError>;
+}
+
+// Pending "this wasn't me" links sent in new-device emails
+#[async_trait::async_trait]
+pub trait LoginAlertStore {
+    async fn add_alert(
+        &mut self,
+        token: LoginAlertToken,
+        alert: LoginAlert,
+    ) -> Result<(), LoginAlertStoreError>;
+    async fn get_alert(&self, token: &LoginAlertToken) -> Result<LoginAlert, LoginAlertStoreError>;
+    async fn remove_alert(&mut self, token: &LoginAlertToken) -> Result<(), LoginAlertStoreError>;
+}
+
+// Webhook subscriptions plus the queue of deliveries the webhook worker drains
+#[async_trait::async_trait]
+pub trait WebhookStore {
+    async fn add_subscription(
+        &mut self,
+        subscription: WebhookSubscription,
+    ) -> Result<(), WebhookStoreError>;
+    async fn get_subscription(&self, id: Uuid) -> Result<WebhookSubscription, WebhookStoreError>;
+    async fn get_subscriptions(&self) -> Result<Vec<WebhookSubscription>, WebhookStoreError>;
+    // Also drops the subscription's pending deliveries and dead letters
+    async fn delete_subscription(&mut self, id: Uuid) -> Result<(), WebhookStoreError>;
+    async fn enqueue_delivery(
+        &mut self,
+        delivery: WebhookDelivery,
+    ) -> Result<(), WebhookStoreError>;
+    // Returns up to `limit` deliveries that are due and hides them from other workers for `lease`
+    async fn claim_due_deliveries(
+        &mut self,
+        limit: u32,
+        lease: std::time::Duration,
+    ) -> Result<Vec<WebhookDelivery>, WebhookStoreError>;
+    async fn remove_delivery(&mut self, id: Uuid) -> Result<(), WebhookStoreError>;
+    // Saves the delivery's attempt count, next attempt time and last error
+    async fn schedule_retry(&mut self, delivery: &WebhookDelivery)
+        -> Result<(), WebhookStoreError>;
+    // Moves a delivery that ran out of attempts from the queue to the dead-letter table
+    async fn dead_letter(&mut self, delivery: WebhookDelivery) -> Result<(), WebhookStoreError>;
+    async fn get_dead_letters(&self) -> Result<Vec<WebhookDeadLetter>, WebhookStoreError>;
+    // Moves a dead letter back onto the queue with a fresh set of attempts
+    async fn replay_dead_letter(&mut self, id: Uuid) -> Result<(), WebhookStoreError>;
+}
+
+// Applications registered to request access through `/authorize`
+#[async_trait::async_trait]
+pub trait OAuthClientStore {
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError>;
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError>;
+    async fn get_clients(&self) -> Result<Vec<OAuthClient>, OAuthClientStoreError>;
+    async fn delete_client(&mut self, client_id: &str) -> Result<(), OAuthClientStoreError>;
+}
+
+// Clients that authenticate with a secret to get tokens for themselves
+#[async_trait::async_trait]
+pub trait ServiceClientStore {
+    async fn add_client(&mut self, client: ServiceClient) -> Result<(), ServiceClientStoreError>;
+    async fn get_client(&self, client_id: &str) -> Result<ServiceClient, ServiceClientStoreError>;
+    async fn get_clients(&self) -> Result<Vec<ServiceClient>, ServiceClientStoreError>;
+    async fn delete_client(&mut self, client_id: &str) -> Result<(), ServiceClientStoreError>;
+}
+
+// Authorization codes waiting to be redeemed at `/token`
+#[async_trait::async_trait]
+pub trait AuthorizationCodeStore {
+    async fn add_code(
+        &mut self,
+        code: AuthorizationCode,
+        grant: AuthorizationGrant,
+    ) -> Result<(), AuthorizationCodeStoreError>;
+    // Removes the code as it is read, so a code can only ever be redeemed once
+    async fn take_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationGrant, AuthorizationCodeStoreError>;
+}
+
+// Device authorization requests, kept until they expire or the device redeems them
+#[async_trait::async_trait]
+pub trait DeviceAuthorizationStore {
+    async fn add_authorization(
+        &mut self,
+        device_code: DeviceCode,
+        authorization: DeviceAuthorization,
+    ) -> Result<(), DeviceAuthorizationStoreError>;
+    async fn get_authorization(
+        &self,
+        device_code: &DeviceCode,
+    ) -> Result<DeviceAuthorization, DeviceAuthorizationStoreError>;
+    // The request a user is approving, from the code they entered
+    async fn get_device_code(
+        &self,
+        user_code: &UserCode,
+    ) -> Result<DeviceCode, DeviceAuthorizationStoreError>;
+    async fn update_authorization(
+        &mut self,
+        device_code: &DeviceCode,
+        authorization: DeviceAuthorization,
+    ) -> Result<(), DeviceAuthorizationStoreError>;
+    // Removes the request as it is read, so an approval can only ever be redeemed once
+    async fn take_authorization(
+        &mut self,
+        device_code: &DeviceCode,
+    ) -> Result<DeviceAuthorization, DeviceAuthorizationStoreError>;
+    // Records a poll made at `now`, returning true when it came too soon (see `DevicePoll::next`)
+    async fn record_poll(
+        &mut self,
+        device_code: &DeviceCode,
+        now: DateTime<Utc>,
+    ) -> Result<bool, DeviceAuthorizationStoreError>;
+}
+
+// Accounts at the external identity provider and the users they log in as
+#[async_trait::async_trait]
+pub trait ExternalIdentityStore {
+    async fn add_identity(
+        &mut self,
+        identity: ExternalIdentity,
+    ) -> Result<(), ExternalIdentityStoreError>;
+    async fn get_identity(
+        &self,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<ExternalIdentity, ExternalIdentityStoreError>;
+}
+
+// Logins waiting for the external identity provider to redirect back
+#[async_trait::async_trait]
+pub trait ExternalLoginStore {
+    async fn add_login(
+        &mut self,
+        state: ExternalLoginState,
+        login: PendingExternalLogin,
+    ) -> Result<(), ExternalLoginStoreError>;
+    // Removes the login as it is read, so each redirect back can only be used once
+    async fn take_login(
+        &mut self,
+        state: &ExternalLoginState,
+    ) -> Result<PendingExternalLogin, ExternalLoginStoreError>;
+}
+
+// Login links sent by email, each good for one login
+#[async_trait::async_trait]
+pub trait MagicLinkStore {
+    async fn add_link(
+        &mut self,
+        token: MagicLinkToken,
+        email: Email,
+    ) -> Result<(), MagicLinkStoreError>;
+    // Removes the link as it is read, so each one can only be used once
+    async fn take_link(&mut self, token: &MagicLinkToken) -> Result<Email, MagicLinkStoreError>;
+}
+
+#[derive(Debug, Error)]
+pub enum OAuthClientStoreError {
+    #[error("OAuth client not found")]
+    ClientNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for OAuthClientStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::ClientNotFound, Self::ClientNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ServiceClientStoreError {
+    #[error("Service client not found")]
+    ClientNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for ServiceClientStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::ClientNotFound, Self::ClientNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AuthorizationCodeStoreError {
+    #[error("Authorization code not found")]
+    CodeNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for AuthorizationCodeStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::CodeNotFound, Self::CodeNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DeviceAuthorizationStoreError {
+    #[error("Device authorization not found")]
+    AuthorizationNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for DeviceAuthorizationStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::AuthorizationNotFound, Self::AuthorizationNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ExternalIdentityStoreError {
+    #[error("External identity already linked")]
+    IdentityAlreadyLinked,
+    #[error("External identity not found")]
+    IdentityNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for ExternalIdentityStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::IdentityAlreadyLinked, Self::IdentityAlreadyLinked)
+                | (Self::IdentityNotFound, Self::IdentityNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ExternalLoginStoreError {
+    #[error("External login not found")]
+    LoginNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for ExternalLoginStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::LoginNotFound, Self::LoginNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum MagicLinkStoreError {
+    #[error("Magic link not found")]
+    LinkNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for MagicLinkStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::LinkNotFound, Self::LinkNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum WebhookStoreError {
+    #[error("Webhook subscription not found")]
+    SubscriptionNotFound,
+    #[error("Webhook delivery not found")]
+    DeliveryNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for WebhookStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::SubscriptionNotFound, Self::SubscriptionNotFound)
+                | (Self::DeliveryNotFound, Self::DeliveryNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum KnownDeviceStoreError {
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+#[derive(Debug, Error)]
+pub enum LoginAlertStoreError {
+    #[error("Login alert not found")]
+    AlertNotFound,
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+impl PartialEq for LoginAlertStoreError {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::AlertNotFound, Self::AlertNotFound)
+                | (Self::UnexpectedError(_), Self::UnexpectedError(_))
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LoginHistoryStoreError {
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
+}
+
+#[derive(Debug, Error)]
+pub enum RateLimitStoreError {
+    #[error("Unexpected error")]
+    UnexpectedError(#[source] Report),
 }
 
 #[derive(Debug, Error)]
@@ -79,7 +486,7 @@ impl Default for LoginAttemptId {
 
 impl AsRef<str> for LoginAttemptId {
     fn as_ref(&self) -> &str {
-        &self.0.expose_secret()
+        self.0.expose_secret()
     }
 }
 
@@ -116,7 +523,7 @@ impl Default for TwoFACode {
 
 impl AsRef<str> for TwoFACode {
     fn as_ref(&self) -> &str {
-        &self.0.expose_secret()
+        self.0.expose_secret()
     }
 }
 
@@ -126,6 +533,16 @@ impl PartialEq for TwoFACode {
     }
 }
 
+// How the user got as far as the 2FA code, so the session it opens reports how it was
+// authenticated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FirstFactor {
+    #[default]
+    Password,
+    MagicLink,
+}
+
 #[derive(Debug, Error)]
 pub enum UserStoreError {
     #[error("User already exists")]
@@ -134,6 +551,8 @@ pub enum UserStoreError {
     UserNotFound,
     #[error("Invalid credentials")]
     InvalidCredentials,
+    #[error("Password reset required")]
+    PasswordResetRequired,
     #[error("Unexpected error")]
     UnexpectedError(#[source] Report),
 }
@@ -145,6 +564,7 @@ impl PartialEq for UserStoreError {
             (Self::UserAlreadyExists, Self::UserAlreadyExists)
                 | (Self::UserNotFound, Self::UserNotFound)
                 | (Self::InvalidCredentials, Self::InvalidCredentials)
+                | (Self::PasswordResetRequired, Self::PasswordResetRequired)
                 | (Self::UnexpectedError(_), Self::UnexpectedError(_))
         )
     }
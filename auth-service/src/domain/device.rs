@@ -0,0 +1,95 @@
+use std::net::IpAddr;
+
+use super::ClientInfo;
+
+// Identifies the device a login came from: its user agent plus the network range of its address.
+// Logins from a different IPv4 /24 or IPv6 /64 count as a new device even with the same browser.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DeviceFingerprint {
+    pub user_agent: String,
+    pub ip_range: String,
+}
+
+impl DeviceFingerprint {
+    pub fn from_client_info(client: &ClientInfo) -> Self {
+        let ip_range = client
+            .ip_address
+            .as_deref()
+            .and_then(|ip| ip.parse::<IpAddr>().ok())
+            .map(ip_range)
+            .unwrap_or_else(|| UNKNOWN.to_owned());
+
+        Self {
+            user_agent: client
+                .user_agent
+                .clone()
+                .unwrap_or_else(|| UNKNOWN.to_owned()),
+            ip_range,
+        }
+    }
+}
+
+const UNKNOWN: &str = "unknown";
+
+fn ip_range(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            format!("{}.{}.{}.0/24", a, b, c)
+        }
+        IpAddr::V6(ip) => {
+            let s = ip.segments();
+            format!("{:x}:{:x}:{:x}:{:x}::/64", s[0], s[1], s[2], s[3])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(ip: Option<&str>, user_agent: Option<&str>) -> ClientInfo {
+        ClientInfo {
+            ip_address: ip.map(str::to_owned),
+            user_agent: user_agent.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn addresses_in_the_same_range_share_a_fingerprint() {
+        let first = DeviceFingerprint::from_client_info(&client(Some("203.0.113.7"), Some("ua")));
+        let second =
+            DeviceFingerprint::from_client_info(&client(Some("203.0.113.200"), Some("ua")));
+
+        assert_eq!(first, second);
+        assert_eq!(first.ip_range, "203.0.113.0/24");
+    }
+
+    #[test]
+    fn ipv6_addresses_are_grouped_by_64_bit_prefix() {
+        let device =
+            DeviceFingerprint::from_client_info(&client(Some("2001:db8:1:2:3:4:5:6"), Some("ua")));
+
+        assert_eq!(device.ip_range, "2001:db8:1:2::/64");
+    }
+
+    #[test]
+    fn different_ranges_or_agents_are_different_devices() {
+        let base = DeviceFingerprint::from_client_info(&client(Some("203.0.113.7"), Some("ua")));
+        let other_range =
+            DeviceFingerprint::from_client_info(&client(Some("198.51.100.7"), Some("ua")));
+        let other_agent =
+            DeviceFingerprint::from_client_info(&client(Some("203.0.113.7"), Some("other")));
+
+        assert_ne!(base, other_range);
+        assert_ne!(base, other_agent);
+    }
+
+    #[test]
+    fn missing_client_details_are_recorded_as_unknown() {
+        let device = DeviceFingerprint::from_client_info(&ClientInfo::default());
+
+        assert_eq!(device.user_agent, "unknown");
+        assert_eq!(device.ip_range, "unknown");
+    }
+}
@@ -0,0 +1,218 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::eyre::{eyre, Context, Result};
+use rand::{seq::SliceRandom, Rng};
+use secrecy::{ExposeSecret, Secret};
+
+use super::Email;
+
+// The secret a device polls `/token` with (RFC 8628 section 3.2)
+#[derive(Debug, Clone)]
+pub struct DeviceCode(Secret<String>);
+
+impl DeviceCode {
+    pub fn parse(code: String) -> Result<Self> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(&code)
+            .wrap_err("Invalid device code")?;
+        if bytes.len() != 32 {
+            return Err(eyre!("Invalid device code"));
+        }
+        Ok(Self(Secret::new(code)))
+    }
+}
+
+impl Default for DeviceCode {
+    fn default() -> Self {
+        Self(Secret::new(
+            URL_SAFE_NO_PAD.encode(rand::thread_rng().gen::<[u8; 32]>()),
+        ))
+    }
+}
+
+impl AsRef<str> for DeviceCode {
+    fn as_ref(&self) -> &str {
+        self.0.expose_secret()
+    }
+}
+
+impl PartialEq for DeviceCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.expose_secret() == other.0.expose_secret()
+    }
+}
+
+// Consonants without look-alikes, so codes are easy to read off a terminal and type on a phone
+// and never spell a word (RFC 8628 section 6.1)
+const USER_CODE_ALPHABET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+const USER_CODE_LENGTH: usize = 8;
+
+// The short code users enter on the verification page, shown as XXXX-XXXX. 20^8 codes are
+// plenty for the few that are live at any moment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserCode(String);
+
+impl UserCode {
+    // Accepts what users type: any case, with or without the dash and spaces
+    pub fn parse(input: &str) -> Result<Self> {
+        let code: String = input
+            .chars()
+            .filter(|c| *c != '-' && !c.is_whitespace())
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        let valid =
+            code.len() == USER_CODE_LENGTH && code.bytes().all(|b| USER_CODE_ALPHABET.contains(&b));
+        if !valid {
+            return Err(eyre!("Invalid user code"));
+        }
+        Ok(Self(code))
+    }
+
+    pub fn formatted(&self) -> String {
+        let (first, second) = self.0.split_at(USER_CODE_LENGTH / 2);
+        format!("{}-{}", first, second)
+    }
+}
+
+impl Default for UserCode {
+    fn default() -> Self {
+        let mut rng = rand::thread_rng();
+        let code = (0..USER_CODE_LENGTH)
+            .map(|_| *USER_CODE_ALPHABET.choose(&mut rng).unwrap() as char)
+            .collect();
+        Self(code)
+    }
+}
+
+impl AsRef<str> for UserCode {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// A device authorization request, from `/device/code` until the device redeems it or it expires
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceAuthorization {
+    pub client_id: String,
+    pub scopes: Vec<String>,
+    pub user_code: UserCode,
+    pub status: DeviceAuthorizationStatus,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl DeviceAuthorization {
+    pub fn scope(&self) -> String {
+        self.scopes.join(" ")
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now >= self.expires_at
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceAuthorizationStatus {
+    Pending,
+    Approved(DeviceApproval),
+    Denied,
+}
+
+// The user who approved a device, and how they had logged in
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceApproval {
+    pub email: Email,
+    pub auth_time: Option<usize>,
+    pub amr: Vec<String>,
+    pub sid: Option<String>,
+}
+
+// When a device last polled, and how long it has to wait between polls. Kept apart from the
+// authorization so recording a poll never overwrites an approval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DevicePoll {
+    pub polled_at: DateTime<Utc>,
+    pub interval: u64,
+}
+
+// Devices have this long to be approved and redeem the approval
+pub const DEVICE_CODE_TTL_SECONDS: u64 = 600; // 10 minutes
+
+// Polls wait at least this long unless told to slow down
+pub const DEVICE_POLL_INTERVAL_SECONDS: u64 = 5;
+
+impl DevicePoll {
+    // The poll made at `now`, and whether it came too soon after `previous`. Every poll that is
+    // too soon adds 5 seconds to the interval for all later ones (RFC 8628 section 3.5).
+    pub fn next(previous: Option<&DevicePoll>, now: DateTime<Utc>) -> (Self, bool) {
+        let Some(previous) = previous else {
+            let poll = Self {
+                polled_at: now,
+                interval: DEVICE_POLL_INTERVAL_SECONDS,
+            };
+            return (poll, false);
+        };
+
+        let interval = Duration::seconds(previous.interval.try_into().unwrap_or(i64::MAX));
+        let too_soon = now < previous.polled_at + interval;
+        let poll = Self {
+            polled_at: now,
+            interval: if too_soon {
+                previous.interval + 5
+            } else {
+                previous.interval
+            },
+        };
+        (poll, too_soon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_codes_accept_what_users_type() {
+        let code = UserCode::default();
+        assert_eq!(code.as_ref().len(), 8);
+        let formatted = code.formatted();
+        assert_eq!(&formatted[4..5], "-");
+        assert_eq!(UserCode::parse(&formatted).unwrap(), code);
+        assert_eq!(
+            UserCode::parse(&formatted.to_lowercase().replace('-', " ")).unwrap(),
+            code
+        );
+
+        assert!(UserCode::parse("BCDF-GHJ").is_err());
+        assert!(UserCode::parse("BCDF-GHJA").is_err());
+        assert!(UserCode::parse("").is_err());
+    }
+
+    #[test]
+    fn device_codes_round_trip() {
+        let code = DeviceCode::default();
+        assert_eq!(DeviceCode::parse(code.as_ref().to_owned()).unwrap(), code);
+        assert!(DeviceCode::parse("too-short".to_owned()).is_err());
+    }
+
+    #[test]
+    fn polling_too_soon_slows_the_device_down() {
+        let start = Utc::now();
+        let (first, too_soon) = DevicePoll::next(None, start);
+        assert!(!too_soon);
+        assert_eq!(first.interval, DEVICE_POLL_INTERVAL_SECONDS);
+
+        let (second, too_soon) = DevicePoll::next(Some(&first), start + Duration::seconds(1));
+        assert!(too_soon);
+        assert_eq!(second.interval, DEVICE_POLL_INTERVAL_SECONDS + 5);
+
+        // The longer interval sticks
+        let (third, too_soon) = DevicePoll::next(Some(&second), start + Duration::seconds(8));
+        assert!(too_soon);
+        assert_eq!(third.interval, DEVICE_POLL_INTERVAL_SECONDS + 10);
+
+        let later = third.polled_at + Duration::seconds(third.interval as i64);
+        let (fourth, too_soon) = DevicePoll::next(Some(&third), later);
+        assert!(!too_soon);
+        assert_eq!(fourth.interval, third.interval);
+    }
+}
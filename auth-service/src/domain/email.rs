@@ -1,6 +1,6 @@
 use color_eyre::eyre::{eyre, Result};
 use secrecy::{ExposeSecret, Secret};
-use std::{hash::Hash, hash::Hasher};
+use std::hash::Hash;
 use thiserror::Error;
 use validator::ValidateEmail;
 
@@ -94,7 +94,7 @@ mod tests {
     struct ValidEmailFixture(pub String);
 
     impl Arbitrary for ValidEmailFixture {
-        fn arbitrary(g: &mut Gen) -> Self {
+        fn arbitrary(_g: &mut Gen) -> Self {
             let email: String = SafeEmail().fake();
             Self(email)
         }
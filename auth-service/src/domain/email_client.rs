@@ -6,4 +6,6 @@ use super::Email;
 #[async_trait::async_trait]
 pub trait EmailClient {
     async fn send_email(&self, recipient: &Email, subject: &str, content: &str) -> Result<()>;
+    // Confirms the provider is reachable and accepts our credentials, without sending anything
+    async fn check_health(&self) -> Result<()>;
 }
@@ -13,12 +13,56 @@ pub enum AuthAPIError {
     MissingToken,
     #[error("Invalid token")]
     InvalidToken,
+    #[error("Invalid CSRF token")]
+    InvalidCsrfToken,
     #[error("Invalid login attempt ID")]
     InvalidLoginAttemptId,
     #[error("Token already banned")]
     TokenAlreadyBanned,
     #[error("TwoFA code store error")]
     TwoFACodeStoreError,
+    #[error("Too many requests")]
+    TooManyRequests,
+    #[error("Invalid pagination")]
+    InvalidPagination,
+    #[error("Password reset required")]
+    PasswordResetRequired,
+    #[error("Invalid login alert token")]
+    InvalidLoginAlertToken,
+    #[error("Invalid webhook subscription")]
+    InvalidWebhookSubscription,
+    #[error("Webhook subscription not found")]
+    WebhookSubscriptionNotFound,
+    #[error("Webhook delivery not found")]
+    WebhookDeliveryNotFound,
+    #[error("Invalid OAuth client")]
+    InvalidOAuthClient,
+    #[error("Invalid redirect URI")]
+    InvalidRedirectUri,
+    #[error("OAuth client not found")]
+    OAuthClientNotFound,
+    #[error("Invalid service client")]
+    InvalidServiceClient,
+    #[error("Service client not found")]
+    ServiceClientNotFound,
+    #[error("User code not found")]
+    UserCodeNotFound,
+    #[error("Insufficient scope")]
+    InsufficientScope,
+    #[error("OpenID Connect is not configured")]
+    OpenIdConnectDisabled,
+    #[error("External login is not configured")]
+    ExternalLoginDisabled,
+    #[error("Invalid external login")]
+    InvalidExternalLogin,
+    #[error("External account not allowed")]
+    ExternalAccountNotAllowed,
+    #[error("Invalid magic link")]
+    InvalidMagicLink,
+    #[error("2FA required")]
+    TwoFactorRequired,
+    #[error("Audit log cannot be read back")]
+    AuditLogUnreadable,
     #[error("Unexpected error")]
     UnexpectedError(#[source] Report),
 }
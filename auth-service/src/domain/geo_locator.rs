@@ -0,0 +1,44 @@
+use std::{fmt, net::IpAddr};
+
+// Approximate location of an IP address, as far as the GeoIP database knows
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Location {
+    pub city: Option<String>,
+    pub country: Option<String>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.city, &self.country) {
+            (Some(city), Some(country)) => write!(f, "{}, {}", city, country),
+            (Some(place), None) | (None, Some(place)) => write!(f, "{}", place),
+            (None, None) => write!(f, "Unknown location"),
+        }
+    }
+}
+
+// This trait represents the interface all concrete GeoIP lookups should implement
+pub trait GeoLocator {
+    fn locate(&self, ip: IpAddr) -> Option<Location>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn location_displays_the_parts_it_knows() {
+        let full = Location {
+            city: Some("Berlin".to_owned()),
+            country: Some("Germany".to_owned()),
+        };
+        let country_only = Location {
+            city: None,
+            country: Some("Germany".to_owned()),
+        };
+
+        assert_eq!(full.to_string(), "Berlin, Germany");
+        assert_eq!(country_only.to_string(), "Germany");
+        assert_eq!(Location::default().to_string(), "Unknown location");
+    }
+}
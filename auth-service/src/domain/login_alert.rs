@@ -0,0 +1,41 @@
+use color_eyre::eyre::{Context, Result};
+use secrecy::{ExposeSecret, Secret};
+
+use super::{DeviceFingerprint, Email};
+
+// A new-device login the owner was told about. Reporting it through the
+// "this wasn't me" link revokes `session_token` and forces a password reset.
+#[derive(Debug, Clone)]
+pub struct LoginAlert {
+    pub email: Email,
+    pub session_token: Secret<String>,
+    pub device: DeviceFingerprint,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoginAlertToken(Secret<String>);
+
+impl LoginAlertToken {
+    pub fn parse(token: String) -> Result<Self> {
+        let parsed_token = uuid::Uuid::parse_str(&token).wrap_err("Invalid login alert token")?;
+        Ok(Self(Secret::new(parsed_token.to_string())))
+    }
+}
+
+impl Default for LoginAlertToken {
+    fn default() -> Self {
+        LoginAlertToken(Secret::new(uuid::Uuid::new_v4().to_string()))
+    }
+}
+
+impl AsRef<str> for LoginAlertToken {
+    fn as_ref(&self) -> &str {
+        self.0.expose_secret()
+    }
+}
+
+impl PartialEq for LoginAlertToken {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.expose_secret() == other.0.expose_secret()
+    }
+}
@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{eyre, Result};
+
+use super::Email;
+
+// A single recorded login outcome for an account
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginHistoryEntry {
+    pub email: Email,
+    pub occurred_at: DateTime<Utc>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub outcome: LoginOutcome,
+    pub used_2fa: bool,
+}
+
+impl LoginHistoryEntry {
+    pub fn new(email: Email, client: &ClientInfo, outcome: LoginOutcome, used_2fa: bool) -> Self {
+        Self {
+            email,
+            occurred_at: Utc::now(),
+            ip_address: client.ip_address.clone(),
+            user_agent: client.user_agent.clone(),
+            outcome,
+            used_2fa,
+        }
+    }
+}
+
+// Where a request came from, as far as the service can tell
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoginOutcome {
+    Success,
+    Failure(LoginFailureReason),
+}
+
+impl LoginOutcome {
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Success)
+    }
+
+    pub fn failure_reason(&self) -> Option<LoginFailureReason> {
+        match self {
+            Self::Success => None,
+            Self::Failure(reason) => Some(*reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoginFailureReason {
+    InvalidPassword,
+    UnknownUser,
+    IncorrectPassword,
+    InvalidTwoFACode,
+    IncorrectTwoFACode,
+    PasswordResetRequired,
+}
+
+impl LoginFailureReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidPassword => "invalid_password",
+            Self::UnknownUser => "unknown_user",
+            Self::IncorrectPassword => "incorrect_password",
+            Self::InvalidTwoFACode => "invalid_2fa_code",
+            Self::IncorrectTwoFACode => "incorrect_2fa_code",
+            Self::PasswordResetRequired => "password_reset_required",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "invalid_password" => Ok(Self::InvalidPassword),
+            "unknown_user" => Ok(Self::UnknownUser),
+            "incorrect_password" => Ok(Self::IncorrectPassword),
+            "invalid_2fa_code" => Ok(Self::InvalidTwoFACode),
+            "incorrect_2fa_code" => Ok(Self::IncorrectTwoFACode),
+            "password_reset_required" => Ok(Self::PasswordResetRequired),
+            _ => Err(eyre!("Unknown login failure reason: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failure_reasons_round_trip_through_strings() {
+        let reasons = [
+            LoginFailureReason::InvalidPassword,
+            LoginFailureReason::UnknownUser,
+            LoginFailureReason::IncorrectPassword,
+            LoginFailureReason::InvalidTwoFACode,
+            LoginFailureReason::IncorrectTwoFACode,
+            LoginFailureReason::PasswordResetRequired,
+        ];
+
+        for reason in reasons {
+            assert_eq!(LoginFailureReason::parse(reason.as_str()).unwrap(), reason);
+        }
+    }
+
+    #[test]
+    fn unknown_failure_reason_is_rejected() {
+        assert!(LoginFailureReason::parse("not_a_reason").is_err());
+    }
+}
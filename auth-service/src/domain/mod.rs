@@ -1,13 +1,33 @@
+pub mod audit;
 pub mod data_stores;
+pub mod device;
+pub mod device_authorization;
 pub mod email;
+pub mod email_client;
 pub mod error;
+pub mod external_login;
+pub mod geo_locator;
+pub mod login_alert;
+pub mod login_history;
+pub mod magic_link;
+pub mod oauth;
 pub mod password;
 pub mod user;
-pub mod email_client;
+pub mod webhook;
 
+pub use audit::*;
 pub use data_stores::*;
+pub use device::*;
+pub use device_authorization::*;
 pub use email::*;
+pub use email_client::*;
 pub use error::*;
+pub use external_login::*;
+pub use geo_locator::*;
+pub use login_alert::*;
+pub use login_history::*;
+pub use magic_link::*;
+pub use oauth::*;
 pub use password::*;
 pub use user::*;
-pub use email_client::*;
\ No newline at end of file
+pub use webhook::*;
@@ -0,0 +1,301 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{eyre, Context, Result};
+use rand::Rng;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::Email;
+
+// A third-party application allowed to ask users for access through `/authorize`. Clients are
+// public: they prove they started an authorization with PKCE instead of a client secret.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OAuthClient {
+    pub client_id: String,
+    pub name: String,
+    // Codes are only ever sent to one of these, compared exactly
+    pub redirect_uris: Vec<String>,
+    // The most a user can grant this client
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl OAuthClient {
+    pub fn new(name: String, redirect_uris: Vec<String>, scopes: Vec<String>) -> Self {
+        Self {
+            client_id: uuid::Uuid::new_v4().to_string(),
+            name,
+            redirect_uris,
+            scopes,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn allows_redirect_uri(&self, redirect_uri: &str) -> bool {
+        self.redirect_uris.iter().any(|uri| uri == redirect_uri)
+    }
+
+    pub fn allows_scopes(&self, scopes: &[String]) -> bool {
+        scopes.iter().all(|scope| self.scopes.contains(scope))
+    }
+}
+
+// A backend job or service that gets tokens for itself with the client credentials grant
+// (RFC 6749 section 4.4). Only a hash of its secret is kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceClient {
+    pub client_id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub secret_hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ServiceClient {
+    // Returns the new client with its secret, which is never available again
+    pub fn new(name: String, scopes: Vec<String>) -> (Self, Secret<String>) {
+        let secret = URL_SAFE_NO_PAD.encode(rand::thread_rng().gen::<[u8; 32]>());
+        let client = Self {
+            client_id: uuid::Uuid::new_v4().to_string(),
+            name,
+            scopes,
+            secret_hash: hash_client_secret(&secret),
+            created_at: Utc::now(),
+        };
+        (client, Secret::new(secret))
+    }
+
+    // Comparing hashes reveals nothing useful about the secret, so no constant-time compare
+    pub fn verify_secret(&self, secret: &str) -> bool {
+        hash_client_secret(secret) == self.secret_hash
+    }
+
+    pub fn allows_scopes(&self, scopes: &[String]) -> bool {
+        scopes.iter().all(|scope| self.scopes.contains(scope))
+    }
+}
+
+// Client secrets are 256 random bits, unlike passwords, so a fast hash protects them as well as
+// Argon2 would and keeps authenticating a client cheap enough to do on every request
+pub fn hash_client_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+// The scope that makes an authorization request an OpenID Connect one, answered with an ID token
+pub const OPENID_SCOPE: &str = "openid";
+
+// Splits a space-delimited `scope` parameter, dropping duplicates (RFC 6749 section 3.3)
+pub fn parse_scope(scope: &str) -> Result<Vec<String>> {
+    let mut scopes: Vec<String> = Vec::new();
+    for token in scope.split(' ').filter(|token| !token.is_empty()) {
+        if !is_scope_token(token) {
+            return Err(eyre!("Invalid scope: {}", token));
+        }
+        if !scopes.iter().any(|scope| scope == token) {
+            scopes.push(token.to_owned());
+        }
+    }
+    Ok(scopes)
+}
+
+// scope-token = 1*( %x21 / %x23-5B / %x5D-7E )
+pub fn is_scope_token(token: &str) -> bool {
+    !token.is_empty()
+        && token
+            .bytes()
+            .all(|b| b == 0x21 || (0x23..=0x5B).contains(&b) || (0x5D..=0x7E).contains(&b))
+}
+
+// Redirect URIs must be absolute and carry no fragment (RFC 6749 section 3.1.2)
+pub fn is_valid_redirect_uri(redirect_uri: &str) -> bool {
+    reqwest::Url::parse(redirect_uri)
+        .map(|url| url.fragment().is_none() && !url.cannot_be_a_base())
+        .unwrap_or(false)
+}
+
+// The single-use code `/authorize` redirects back with
+#[derive(Debug, Clone)]
+pub struct AuthorizationCode(Secret<String>);
+
+impl AuthorizationCode {
+    pub fn parse(code: String) -> Result<Self> {
+        let parsed_code = uuid::Uuid::parse_str(&code).wrap_err("Invalid authorization code")?;
+        Ok(Self(Secret::new(parsed_code.to_string())))
+    }
+}
+
+impl Default for AuthorizationCode {
+    fn default() -> Self {
+        AuthorizationCode(Secret::new(uuid::Uuid::new_v4().to_string()))
+    }
+}
+
+impl AsRef<str> for AuthorizationCode {
+    fn as_ref(&self) -> &str {
+        self.0.expose_secret()
+    }
+}
+
+impl PartialEq for AuthorizationCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.expose_secret() == other.0.expose_secret()
+    }
+}
+
+// What a user approved on the consent page, redeemable once at `/token`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthorizationGrant {
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub scopes: Vec<String>,
+    pub email: Email,
+    // BASE64URL(SHA256(code_verifier)); only the S256 method is accepted
+    pub code_challenge: String,
+    // OpenID Connect: the client's nonce, and when and how the user had logged in
+    pub nonce: Option<String>,
+    pub auth_time: Option<usize>,
+    pub amr: Vec<String>,
+    // The session the user approved the client from
+    pub sid: Option<String>,
+}
+
+impl AuthorizationGrant {
+    pub fn scope(&self) -> String {
+        self.scopes.join(" ")
+    }
+
+    pub fn is_openid(&self) -> bool {
+        self.scopes.iter().any(|scope| scope == OPENID_SCOPE)
+    }
+}
+
+// A verifier is 43 to 128 unreserved characters (RFC 7636 section 4.1)
+pub fn verify_pkce(code_verifier: &str, code_challenge: &str) -> bool {
+    let well_formed = (43..=128).contains(&code_verifier.len())
+        && code_verifier
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"-._~".contains(&b));
+
+    well_formed && pkce_challenge(code_verifier) == code_challenge
+}
+
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+// Error codes from RFC 6749 sections 4.1.2.1 and 5.2, OpenID Connect Core section 3.1.2.6,
+// RFC 8628 section 3.5 and RFC 8693 section 2.2.2
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OAuthErrorCode {
+    InvalidRequest,
+    InvalidClient,
+    InvalidGrant,
+    UnauthorizedClient,
+    UnsupportedGrantType,
+    UnsupportedResponseType,
+    InvalidScope,
+    AccessDenied,
+    ServerError,
+    LoginRequired,
+    ConsentRequired,
+    AuthorizationPending,
+    SlowDown,
+    ExpiredToken,
+    InvalidTarget,
+}
+
+impl OAuthErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidRequest => "invalid_request",
+            Self::InvalidClient => "invalid_client",
+            Self::InvalidGrant => "invalid_grant",
+            Self::UnauthorizedClient => "unauthorized_client",
+            Self::UnsupportedGrantType => "unsupported_grant_type",
+            Self::UnsupportedResponseType => "unsupported_response_type",
+            Self::InvalidScope => "invalid_scope",
+            Self::AccessDenied => "access_denied",
+            Self::ServerError => "server_error",
+            Self::LoginRequired => "login_required",
+            Self::ConsentRequired => "consent_required",
+            Self::AuthorizationPending => "authorization_pending",
+            Self::SlowDown => "slow_down",
+            Self::ExpiredToken => "expired_token",
+            Self::InvalidTarget => "invalid_target",
+        }
+    }
+}
+
+// Errors from the OAuth endpoints, which use the RFC 6749 response shape instead of
+// `ErrorResponse`
+#[derive(Debug, thiserror::Error)]
+#[error("{}: {description}", .code.as_str())]
+pub struct OAuthError {
+    pub code: OAuthErrorCode,
+    pub description: String,
+}
+
+impl OAuthError {
+    pub fn new(code: OAuthErrorCode, description: impl Into<String>) -> Self {
+        Self {
+            code,
+            description: description.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_space_delimited_scopes() {
+        assert_eq!(
+            parse_scope("read  write read").unwrap(),
+            vec!["read".to_owned(), "write".to_owned()]
+        );
+        assert!(parse_scope("").unwrap().is_empty());
+        assert!(parse_scope("read \"write\"").is_err());
+        assert!(parse_scope("read\\write").is_err());
+    }
+
+    #[test]
+    fn validates_redirect_uris() {
+        assert!(is_valid_redirect_uri("https://app.example.com/callback"));
+        assert!(is_valid_redirect_uri("http://127.0.0.1:8080/callback?x=1"));
+        assert!(!is_valid_redirect_uri("/callback"));
+        assert!(!is_valid_redirect_uri(
+            "https://app.example.com/callback#frag"
+        ));
+        assert!(!is_valid_redirect_uri("mailto:user@example.com"));
+    }
+
+    #[test]
+    fn service_clients_verify_only_their_own_secret() {
+        let (client, secret) = ServiceClient::new("Job".to_owned(), vec!["read".to_owned()]);
+        let (_, other_secret) = ServiceClient::new("Job".to_owned(), vec!["read".to_owned()]);
+
+        assert!(client.verify_secret(secret.expose_secret()));
+        assert!(!client.verify_secret(other_secret.expose_secret()));
+        assert!(!client.verify_secret(""));
+        assert_ne!(&client.secret_hash, secret.expose_secret());
+    }
+
+    #[test]
+    fn verifies_the_rfc_7636_example() {
+        // Appendix B of RFC 7636
+        let verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+        let challenge = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+        assert_eq!(pkce_challenge(verifier), challenge);
+        assert!(verify_pkce(verifier, challenge));
+        assert!(!verify_pkce(
+            verifier,
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cN"
+        ));
+        // Too short to be a verifier, even though it hashes to its own challenge
+        assert!(!verify_pkce("short", &pkce_challenge("short")));
+    }
+}
@@ -1,4 +1,6 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use color_eyre::eyre::{eyre, Result};
+use rand::Rng;
 use secrecy::{ExposeSecret, Secret};
 use thiserror::Error;
 
@@ -13,6 +15,13 @@ impl Password {
             Err(eyre!("Failed to parse string to a Password type"))
         }
     }
+
+    // A password nobody knows, for users who log in some other way
+    pub fn random() -> Self {
+        Self(Secret::new(
+            URL_SAFE_NO_PAD.encode(rand::thread_rng().gen::<[u8; 32]>()),
+        ))
+    }
 }
 
 impl PartialEq for Password {
@@ -16,12 +16,12 @@ impl User {
         password: &str,
         requires_2fa: bool,
     ) -> Result<Self, UserValidationError> {
-        return Ok(User {
+        Ok(User {
             email: Email::parse(Secret::new(email.to_string()))
                 .map_err(|_| UserValidationError::InvalidEmail)?,
             password: Password::parse(Secret::new(password.to_string()))
                 .map_err(|_| UserValidationError::InvalidPassword)?,
             requires_2fa,
-        });
+        })
     }
 }
@@ -0,0 +1,195 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{eyre, Result};
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+// Account lifecycle events downstream systems can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEventType {
+    #[serde(rename = "user.signed_up")]
+    UserSignedUp,
+    #[serde(rename = "user.two_factor_enabled")]
+    TwoFactorEnabled,
+}
+
+impl WebhookEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UserSignedUp => "user.signed_up",
+            Self::TwoFactorEnabled => "user.two_factor_enabled",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "user.signed_up" => Ok(Self::UserSignedUp),
+            "user.two_factor_enabled" => Ok(Self::TwoFactorEnabled),
+            _ => Err(eyre!("Unknown webhook event type: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookSubscription {
+    pub id: Uuid,
+    pub url: String,
+    pub secret: Secret<String>,
+    // Event types this subscription receives
+    pub events: Vec<WebhookEventType>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WebhookSubscription {
+    pub fn new(url: String, secret: Secret<String>, events: Vec<WebhookEventType>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            url,
+            secret,
+            events,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn wants(&self, event_type: WebhookEventType) -> bool {
+        self.events.contains(&event_type)
+    }
+}
+
+// JSON body POSTed to subscribers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookPayload {
+    pub id: Uuid,
+    #[serde(rename = "type")]
+    pub event_type: WebhookEventType,
+    pub occurred_at: DateTime<Utc>,
+    pub data: serde_json::Value,
+}
+
+// One payload on its way to one subscription
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub event_type: WebhookEventType,
+    // Serialized once so retries and replays send byte-identical (and identically signed) bodies
+    pub payload: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+}
+
+impl WebhookDelivery {
+    pub fn new(subscription_id: Uuid, event_type: WebhookEventType, payload: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            subscription_id,
+            event_type,
+            payload,
+            attempts: 0,
+            next_attempt_at: Utc::now(),
+            last_error: None,
+        }
+    }
+}
+
+// A delivery that ran out of attempts. Kept until it is replayed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookDeadLetter {
+    pub delivery: WebhookDelivery,
+    pub failed_at: DateTime<Utc>,
+}
+
+// How hard the delivery worker tries before giving up on a delivery
+#[derive(Debug, Clone)]
+pub struct WebhookDeliveryPolicy {
+    pub max_attempts: i32,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl WebhookDeliveryPolicy {
+    // Delay before the next try after `attempts` failed ones: base * 2^(attempts - 1), capped
+    pub fn backoff(&self, attempts: i32) -> Duration {
+        let exponent = attempts.saturating_sub(1).clamp(0, 31) as u32;
+        self.base_backoff
+            .saturating_mul(2u32.saturating_pow(exponent))
+            .min(self.max_backoff)
+    }
+
+    pub fn is_exhausted(&self, attempts: i32) -> bool {
+        attempts >= self.max_attempts
+    }
+}
+
+// Hex-encoded HMAC-SHA256 over "<timestamp>.<body>". Including the timestamp lets receivers
+// reject replayed requests.
+pub fn sign_webhook_payload(secret: &Secret<String>, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.expose_secret().as_bytes())
+        .expect("HMAC can take a key of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_types_round_trip_through_strings() {
+        let event_types = [
+            WebhookEventType::UserSignedUp,
+            WebhookEventType::TwoFactorEnabled,
+        ];
+
+        for event_type in event_types {
+            assert_eq!(
+                WebhookEventType::parse(event_type.as_str()).unwrap(),
+                event_type
+            );
+            assert_eq!(
+                serde_json::to_value(event_type).unwrap(),
+                event_type.as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_until_capped() {
+        let policy = WebhookDeliveryPolicy {
+            max_attempts: 5,
+            base_backoff: Duration::from_secs(10),
+            max_backoff: Duration::from_secs(60),
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_secs(10));
+        assert_eq!(policy.backoff(2), Duration::from_secs(20));
+        assert_eq!(policy.backoff(3), Duration::from_secs(40));
+        assert_eq!(policy.backoff(4), Duration::from_secs(60));
+        assert_eq!(policy.backoff(100), Duration::from_secs(60));
+        assert!(!policy.is_exhausted(4));
+        assert!(policy.is_exhausted(5));
+    }
+
+    #[test]
+    fn signature_depends_on_secret_timestamp_and_body() {
+        let secret = Secret::new("secret".to_owned());
+        let signature = sign_webhook_payload(&secret, 1, "{}");
+
+        assert_eq!(signature.len(), 64);
+        assert_eq!(signature, sign_webhook_payload(&secret, 1, "{}"));
+        assert_ne!(signature, sign_webhook_payload(&secret, 2, "{}"));
+        assert_ne!(signature, sign_webhook_payload(&secret, 1, "{\"a\":1}"));
+        assert_ne!(
+            signature,
+            sign_webhook_payload(&Secret::new("other".to_owned()), 1, "{}")
+        );
+    }
+}
@@ -1,23 +1,43 @@
 use axum::{
-    http::{Method, StatusCode},
+    http::{
+        header::{CACHE_CONTROL, STRICT_TRANSPORT_SECURITY, WWW_AUTHENTICATE},
+        HeaderValue, StatusCode,
+    },
+    middleware,
     response::{IntoResponse, Response},
-    routing::post,
+    routing::{delete, get, get_service, post},
+    serve::ListenerExt,
     Json, Router,
 };
 use redis::{Client, RedisResult};
 use sqlx::{postgres::PgPoolOptions, PgPool};
 
 use crate::{
-    domain::AuthAPIError,
+    domain::{AuthAPIError, OAuthError, OAuthErrorCode},
     routes::*,
-    utils::localhost::{AUTH_SERVICE_DROPLET_URL, AUTH_SERVICE_LOCAL_URL},
 };
 use secrecy::ExposeSecret;
 use secrecy::Secret;
 use serde::{Deserialize, Serialize};
-use std::error::Error;
-use tower_http::{cors::CorsLayer, services::ServeDir, trace::TraceLayer};
-use utils::tracing::{make_span_with_request_id, on_request, on_response};
+use std::{error::Error, future::IntoFuture, net::SocketAddr, sync::Arc, time::Duration};
+use tokio::net::TcpListener;
+use tower_http::{
+    services::{ServeDir, ServeFile},
+    set_header::SetResponseHeaderLayer,
+    trace::TraceLayer,
+};
+use utils::{
+    authenticated_user::require_authentication,
+    cors::build_cors_layer,
+    csrf::require_csrf_token,
+    metrics::track_http_metrics,
+    shutdown::ShutdownHandle,
+    tls::{
+        https_redirect_router, spawn_certificate_reloader, tls_server_config,
+        ReloadableCertificate, ServerIo, ServerListener,
+    },
+    tracing::{make_span_with_request_id, on_request, on_response},
+};
 
 pub mod app_state;
 pub mod domain;
@@ -25,37 +45,117 @@ pub mod routes;
 pub mod services;
 pub mod utils;
 
-use app_state::AppState;
+use app_state::{AppState, ReadinessType, RedisConnectionType};
 // This struct encapsulates our application-related logic.
 pub struct Application {
     router: Router,
-    listener: tokio::net::TcpListener,
+    listener: ServerListener,
     // address is exposed as a public field
     // so we have access to it in tests.
     pub address: String,
+    // Plain-HTTP listener that redirects to HTTPS, when TLS is enabled and it is configured
+    redirect: Option<(TcpListener, Router)>,
+    pub redirect_address: Option<String>,
+    shutdown: ShutdownHandle,
+    shutdown_timeout: Duration,
+    // Kept outside the router so they can be flipped and closed once the server stops
+    readiness: ReadinessType,
+    pg_pool: PgPool,
+    redis_conn: RedisConnectionType,
 }
 
 impl Application {
-    pub async fn build(app_state: AppState, address: &str) -> Result<Self, Box<dyn Error>> {
-        // Allow the app service(running on our local machine and in production) to call the auth service
-        let allowed_origins = [
-            AUTH_SERVICE_LOCAL_URL.parse()?,
-            AUTH_SERVICE_DROPLET_URL.parse()?,
-        ];
-
-        let cors = CorsLayer::new()
-            // Allow GET and POST requests
-            .allow_methods([Method::GET, Method::POST])
-            // Allow cookies to be included in requests
-            .allow_credentials(true)
-            .allow_origin(allowed_origins);
-
-        let router = Router::new()
+    pub async fn build(app_state: AppState) -> Result<Self, Box<dyn Error>> {
+        let settings = app_state.settings.clone();
+
+        // Allow the app service (running on our local machine and in production) to call the auth service
+        let cors = build_cors_layer(&settings.cors)?;
+
+        let readiness = app_state.readiness.clone();
+        let pg_pool = app_state.pg_pool.clone();
+        let redis_conn = app_state.redis_conn.clone();
+        let shutdown = ShutdownHandle::new();
+
+        // Routes that require a valid auth token, checked once for the whole group. Layers run
+        // last to first, so the CSRF check sees the authenticated user.
+        let authenticated_routes = Router::new()
+            .route("/logout", post(logout))
+            .route("/account/export", get(account_export))
+            .route("/account/login-history", get(login_history))
+            .route(
+                "/device/verify",
+                get(device_verification).post(device_verification_decision),
+            )
+            .route_layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                require_csrf_token,
+            ))
+            .route_layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                require_authentication,
+            ));
+
+        let mut router = Router::new()
             .route("/signup", post(signup))
             .route("/login", post(login))
             .route("/verify-2fa", post(verify_2fa))
-            .route("/logout", post(logout))
+            .route("/login/external", get(external_login))
+            .route("/login/external/callback", get(external_login_callback))
+            .route("/login/external/provider", get(external_login_provider))
+            .route("/login/magic-link", post(magic_link))
+            .route("/login/magic-link/callback", post(magic_link_callback))
             .route("/verify-token", post(verify_token))
+            .merge(authenticated_routes)
+            // Links in emails open the pages; only their buttons change anything
+            .route(
+                "/account/report-login",
+                get_service(ServeFile::new("assets/report-login.html")).post(report_login),
+            )
+            .route(
+                "/account/reset-password",
+                get_service(ServeFile::new("assets/reset-password.html")).post(reset_password),
+            )
+            .route("/admin/audit/verify", get(verify_audit_log))
+            .route("/admin/webhooks", post(create_webhook).get(list_webhooks))
+            .route("/admin/webhooks/{id}", delete(delete_webhook))
+            .route(
+                "/admin/webhooks/dead-letters",
+                get(list_webhook_dead_letters),
+            )
+            .route(
+                "/admin/webhooks/dead-letters/{id}/replay",
+                post(replay_webhook_delivery),
+            )
+            .route("/authorize", get(authorize).post(authorize_consent))
+            .route("/token", post(token))
+            .route("/device/code", post(device_authorization))
+            .route_service("/device", ServeFile::new("assets/device.html"))
+            .route("/introspect", post(introspect))
+            .route("/revoke", post(revoke))
+            .route("/userinfo", get(userinfo).post(userinfo))
+            .route(
+                "/.well-known/openid-configuration",
+                get(openid_configuration),
+            )
+            .route("/.well-known/jwks.json", get(jwks))
+            .route(
+                "/admin/oauth/clients",
+                post(create_oauth_client).get(list_oauth_clients),
+            )
+            .route("/admin/oauth/clients/{client_id}", delete(delete_oauth_client))
+            .route(
+                "/admin/oauth/service-clients",
+                post(create_service_client).get(list_service_clients),
+            )
+            .route(
+                "/admin/oauth/service-clients/{client_id}",
+                delete(delete_service_client),
+            )
+            .route("/metrics", get(metrics))
+            .route("/health/live", get(health_live))
+            .route("/health/ready", get(health_ready))
+            // Route layers only see matched requests, so metrics are labelled by route template
+            .route_layer(middleware::from_fn(track_http_metrics))
             .with_state(app_state)
             .layer(cors)
             .layer(
@@ -68,23 +168,139 @@ impl Application {
                     .on_response(on_response),
             )
             .fallback_service(ServeDir::new("assets"));
-        let listener = tokio::net::TcpListener::bind(address).await?;
+        let listener = TcpListener::bind(&settings.application.address).await?;
         let address = listener.local_addr()?.to_string();
 
+        let mut redirect = None;
+        let mut redirect_address = None;
+        let listener = if settings.tls.enabled {
+            // Fails the build if the certificate cannot be loaded; later reloads only log
+            let certificate = Arc::new(ReloadableCertificate::load(
+                &settings.tls.cert_path,
+                &settings.tls.key_path,
+            )?);
+            spawn_certificate_reloader(
+                certificate.clone(),
+                settings.tls.reload_interval(),
+                shutdown.clone(),
+            );
+
+            if let Some(hsts) = settings.tls.hsts_header() {
+                router = router.layer(SetResponseHeaderLayer::if_not_present(
+                    STRICT_TRANSPORT_SECURITY,
+                    HeaderValue::from_str(&hsts)?,
+                ));
+            }
+
+            if let Some(addr) = &settings.tls.redirect_address {
+                let redirect_listener = TcpListener::bind(addr).await?;
+                redirect_address = Some(redirect_listener.local_addr()?.to_string());
+                let https_port = listener.local_addr()?.port();
+                redirect = Some((redirect_listener, https_redirect_router(https_port)));
+            }
+
+            ServerListener::tls(listener, tls_server_config(certificate)?)?
+        } else {
+            ServerListener::Plain(listener)
+        };
+
         println!("address: {}", address);
         // Create a new Application instance and return it
         Ok(Application {
             router,
             listener,
             address,
+            redirect,
+            redirect_address,
+            shutdown,
+            shutdown_timeout: settings.application.shutdown_timeout(),
+            readiness,
+            pg_pool,
+            redis_conn,
         })
     }
 
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    // Serves until the shutdown handle fires, then stops accepting connections, fails readiness,
+    // drains in-flight requests and closes the Postgres pool and Redis connection
     pub async fn run(self) -> Result<(), std::io::Error> {
         tracing::info!("listening on {}", &self.address);
-        axum::serve(self.listener, self.router)
-            .await
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+
+        if let Some((listener, router)) = self.redirect {
+            tracing::info!("redirecting to HTTPS from {}", listener.local_addr()?);
+            let shutdown = self.shutdown.clone();
+            tokio::spawn(async move {
+                let server = axum::serve(listener, router)
+                    .with_graceful_shutdown(async move { shutdown.wait().await });
+                if let Err(e) = server.await {
+                    tracing::error!("HTTPS redirect listener failed: {:?}", e);
+                }
+            });
+        }
+
+        let shutdown = self.shutdown.clone();
+        // Serve with connection info so handlers can see the peer address. axum only provides
+        // it for its own listener types, which include `tap_io` wrappers around any listener.
+        let server = axum::serve(
+            self.listener.tap_io(|_: &mut Box<dyn ServerIo>| {}),
+            self.router
+                .into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async move { shutdown.wait().await })
+        .into_future();
+        tokio::pin!(server);
+
+        tokio::select! {
+            result = &mut server => return result.map_err(std::io::Error::other),
+            _ = self.shutdown.wait() => {}
+        }
+
+        tracing::info!(
+            "Shutting down, draining in-flight requests for up to {:?}",
+            self.shutdown_timeout
+        );
+        self.readiness.start_draining();
+        let deadline = tokio::time::Instant::now() + self.shutdown_timeout;
+
+        match tokio::time::timeout_at(deadline, server).await {
+            Ok(result) => result.map_err(std::io::Error::other)?,
+            // Connections still open are abandoned; the process is about to exit anyway
+            Err(_) => tracing::warn!("Shutdown deadline reached with requests still in flight"),
+        }
+
+        close_connections(&self.pg_pool, self.redis_conn, deadline).await;
+        tracing::info!("Shutdown complete");
+
+        Ok(())
+    }
+}
+
+async fn close_connections(
+    pg_pool: &PgPool,
+    redis_conn: RedisConnectionType,
+    deadline: tokio::time::Instant,
+) {
+    if tokio::time::timeout_at(deadline, pg_pool.close())
+        .await
+        .is_err()
+    {
+        tracing::warn!("Timed out waiting for Postgres connections to be returned to the pool");
+    }
+
+    // Taking the write lock waits out any command still using the connection. QUIT is sent off
+    // the async runtime because the Redis connection is synchronous.
+    let quit = tokio::task::spawn_blocking(move || {
+        let mut conn = redis_conn.blocking_write();
+        redis::cmd("QUIT").query::<()>(&mut *conn)
+    });
+    match tokio::time::timeout_at(deadline, quit).await {
+        Ok(Ok(Ok(()))) => {}
+        Ok(Ok(Err(e))) => tracing::warn!("Failed to close the Redis connection: {:?}", e),
+        Ok(Err(e)) => tracing::warn!("Failed to close the Redis connection: {:?}", e),
+        Err(_) => tracing::warn!("Timed out waiting to close the Redis connection"),
     }
 }
 
@@ -104,6 +320,7 @@ impl IntoResponse for AuthAPIError {
             }
             AuthAPIError::MissingToken => (StatusCode::BAD_REQUEST, "Missing token"),
             AuthAPIError::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid token"),
+            AuthAPIError::InvalidCsrfToken => (StatusCode::FORBIDDEN, "Invalid CSRF token"),
             AuthAPIError::UnexpectedError(_) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, "Unexpected error")
             }
@@ -114,6 +331,53 @@ impl IntoResponse for AuthAPIError {
             AuthAPIError::InvalidLoginAttemptId => {
                 (StatusCode::BAD_REQUEST, "Invalid login attempt ID")
             }
+            AuthAPIError::TooManyRequests => (StatusCode::TOO_MANY_REQUESTS, "Too many requests"),
+            AuthAPIError::InvalidPagination => (StatusCode::BAD_REQUEST, "Invalid pagination"),
+            AuthAPIError::PasswordResetRequired => {
+                (StatusCode::FORBIDDEN, "Password reset required")
+            }
+            AuthAPIError::InvalidLoginAlertToken => {
+                (StatusCode::BAD_REQUEST, "Invalid login alert token")
+            }
+            AuthAPIError::InvalidWebhookSubscription => {
+                (StatusCode::BAD_REQUEST, "Invalid webhook subscription")
+            }
+            AuthAPIError::WebhookSubscriptionNotFound => {
+                (StatusCode::NOT_FOUND, "Webhook subscription not found")
+            }
+            AuthAPIError::WebhookDeliveryNotFound => {
+                (StatusCode::NOT_FOUND, "Webhook delivery not found")
+            }
+            AuthAPIError::InvalidOAuthClient => (StatusCode::BAD_REQUEST, "Invalid OAuth client"),
+            AuthAPIError::InvalidRedirectUri => (StatusCode::BAD_REQUEST, "Invalid redirect URI"),
+            AuthAPIError::OAuthClientNotFound => {
+                (StatusCode::NOT_FOUND, "OAuth client not found")
+            }
+            AuthAPIError::InvalidServiceClient => {
+                (StatusCode::BAD_REQUEST, "Invalid service client")
+            }
+            AuthAPIError::ServiceClientNotFound => {
+                (StatusCode::NOT_FOUND, "Service client not found")
+            }
+            AuthAPIError::UserCodeNotFound => (StatusCode::NOT_FOUND, "User code not found"),
+            AuthAPIError::InsufficientScope => (StatusCode::FORBIDDEN, "Insufficient scope"),
+            AuthAPIError::OpenIdConnectDisabled => {
+                (StatusCode::NOT_FOUND, "OpenID Connect is not configured")
+            }
+            AuthAPIError::ExternalLoginDisabled => {
+                (StatusCode::NOT_FOUND, "External login is not configured")
+            }
+            AuthAPIError::InvalidExternalLogin => {
+                (StatusCode::BAD_REQUEST, "Invalid external login")
+            }
+            AuthAPIError::ExternalAccountNotAllowed => {
+                (StatusCode::FORBIDDEN, "External account not allowed")
+            }
+            AuthAPIError::InvalidMagicLink => (StatusCode::BAD_REQUEST, "Invalid magic link"),
+            AuthAPIError::TwoFactorRequired => (StatusCode::FORBIDDEN, "2FA required"),
+            AuthAPIError::AuditLogUnreadable => {
+                (StatusCode::CONFLICT, "Audit log cannot be read back")
+            }
         };
         let body = Json(ErrorResponse {
             error: error_message.to_string(),
@@ -122,10 +386,41 @@ impl IntoResponse for AuthAPIError {
     }
 }
 
-pub async fn get_postgres_pool(url: Secret<String>) -> Result<PgPool, sqlx::Error> {
+// The OAuth endpoints answer in the RFC 6749 section 5.2 shape
+#[derive(Serialize, Deserialize)]
+pub struct OAuthErrorResponse {
+    pub error: OAuthErrorCode,
+    pub error_description: String,
+}
+
+impl IntoResponse for OAuthError {
+    fn into_response(self) -> Response {
+        let status = match self.code {
+            OAuthErrorCode::InvalidClient => StatusCode::UNAUTHORIZED,
+            OAuthErrorCode::ServerError => {
+                log_error_chain(&self);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            _ => StatusCode::BAD_REQUEST,
+        };
+        // Clients that fail to authenticate are told how to (RFC 6749 section 5.2)
+        let authenticate = (self.code == OAuthErrorCode::InvalidClient)
+            .then_some([(WWW_AUTHENTICATE, "Basic")]);
+        let body = Json(OAuthErrorResponse {
+            error: self.code,
+            error_description: self.description,
+        });
+        (status, [(CACHE_CONTROL, "no-store")], authenticate, body).into_response()
+    }
+}
+
+pub async fn get_postgres_pool(
+    url: Secret<String>,
+    max_connections: u32,
+) -> Result<PgPool, sqlx::Error> {
     // Create a new PostgreSQL connection pool
     PgPoolOptions::new()
-        .max_connections(5)
+        .max_connections(max_connections)
         .connect(url.expose_secret().as_str())
         .await
 }
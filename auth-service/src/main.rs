@@ -1,59 +1,157 @@
 use std::sync::Arc;
 
 use auth_service::{
+    app_state::SettingsType,
     app_state::{
-        AppState, BannedTokenStoreType, EmailClientType, TwoFACodeStoreType, UserStoreType,
+        AppState, AppStores, AuditSinkType, AuthorizationCodeStoreType, BannedTokenStoreType,
+        DeviceAuthorizationStoreType, EmailClientType, ExternalIdentityStoreType,
+        ExternalLoginStoreType, GeoLocatorType, IdTokenSignerType, KnownDeviceStoreType,
+        LoginAlertStoreType, LoginHistoryStoreType, MagicLinkStoreType, OAuthClientStoreType,
+        RateLimitStoreType, ServiceClientStoreType, TwoFACodeStoreType, UserStoreType,
+        WebhookStoreType,
     },
-    domain::Email,
     get_postgres_pool, get_redis_client,
     services::{
         redis_banned_token_store::RedisBannedTokenStore,
-        redis_two_fa_code_store::RedisTwoFACodeStore, HashSetBannedTokenStore, HashmapUserStore,
-        MockEmailClient, PostgresUserStore, PostmarkEmailClient,
+        redis_login_alert_store::RedisLoginAlertStore, redis_rate_limit_store::RedisRateLimitStore,
+        redis_two_fa_code_store::RedisTwoFACodeStore, spawn_login_history_retention,
+        spawn_webhook_worker, CompositeUserStore, JsonLinesFileAuditSink, LdapUserStore,
+        MaxMindGeoLocator, MockGeoLocator, PostgresAuditSink, PostgresExternalIdentityStore,
+        PostgresKnownDeviceStore, PostgresLoginHistoryStore, PostgresOAuthClientStore,
+        PostgresServiceClientStore, PostgresUserStore, PostgresWebhookStore, PostmarkEmailClient,
+        Readiness, RedisAuthorizationCodeStore, RedisDeviceAuthorizationStore,
+        RedisExternalLoginStore, RedisMagicLinkStore, StdoutAuditSink,
+    },
+    utils::{
+        init_tracing, wait_for_shutdown_signal, AuditSinkKind, IdTokenSigner, Settings,
+        LOGIN_HISTORY_PRUNE_INTERVAL,
     },
-    utils::{init_tracing, prod, DATABASE_URL, POSTMARK_AUTH_TOKEN, REDIS_HOST_NAME},
     Application,
 };
+use color_eyre::eyre::Result;
 use reqwest::Client;
-use secrecy::Secret;
 use sqlx::PgPool;
 use tokio::sync::RwLock;
 
 #[tokio::main]
-async fn main() {
+async fn main() -> Result<()> {
     color_eyre::install().expect("Failed to install color_eyre");
     init_tracing().expect("Failed to initialize tracing");
 
-    let pg_pool = configure_postgresql().await;
-    let redis_conn = configure_redis();
+    // Bad configuration stops the service here with a list of what to fix
+    let settings: SettingsType = Arc::new(Settings::load()?);
+
+    let pg_pool = configure_postgresql(&settings).await;
+    let redis_conn = configure_redis(&settings);
     let shared_redis_conn = Arc::new(RwLock::new(redis_conn));
 
-    let user_store = Arc::new(RwLock::new(PostgresUserStore::new(pg_pool))) as UserStoreType;
+    let user_store = configure_user_store(&settings, pg_pool.clone());
     let banned_token_store = Arc::new(RwLock::new(RedisBannedTokenStore::new(
         shared_redis_conn.clone(),
     ))) as BannedTokenStoreType;
-    let two_fa_code_store =
-        Arc::new(RwLock::new(RedisTwoFACodeStore::new(shared_redis_conn))) as TwoFACodeStoreType;
+    let two_fa_code_store = Arc::new(RwLock::new(RedisTwoFACodeStore::new(
+        shared_redis_conn.clone(),
+    ))) as TwoFACodeStoreType;
+    let rate_limit_store = Arc::new(RwLock::new(RedisRateLimitStore::new(
+        shared_redis_conn.clone(),
+    ))) as RateLimitStoreType;
+    let login_alert_store = Arc::new(RwLock::new(RedisLoginAlertStore::new(
+        shared_redis_conn.clone(),
+    ))) as LoginAlertStoreType;
+    let login_history_store = Arc::new(RwLock::new(PostgresLoginHistoryStore::new(pg_pool.clone())))
+        as LoginHistoryStoreType;
+    let known_device_store = Arc::new(RwLock::new(PostgresKnownDeviceStore::new(pg_pool.clone())))
+        as KnownDeviceStoreType;
+    let webhook_store =
+        Arc::new(RwLock::new(PostgresWebhookStore::new(pg_pool.clone()))) as WebhookStoreType;
+    let oauth_client_store = Arc::new(RwLock::new(PostgresOAuthClientStore::new(pg_pool.clone())))
+        as OAuthClientStoreType;
+    let authorization_code_store = Arc::new(RwLock::new(RedisAuthorizationCodeStore::new(
+        shared_redis_conn.clone(),
+    ))) as AuthorizationCodeStoreType;
+    let service_client_store = Arc::new(RwLock::new(PostgresServiceClientStore::new(
+        pg_pool.clone(),
+    ))) as ServiceClientStoreType;
+    let device_authorization_store = Arc::new(RwLock::new(RedisDeviceAuthorizationStore::new(
+        shared_redis_conn.clone(),
+    ))) as DeviceAuthorizationStoreType;
+    let external_identity_store = Arc::new(RwLock::new(PostgresExternalIdentityStore::new(
+        pg_pool.clone(),
+    ))) as ExternalIdentityStoreType;
+    let external_login_store = Arc::new(RwLock::new(RedisExternalLoginStore::new(
+        shared_redis_conn.clone(),
+    ))) as ExternalLoginStoreType;
+    let magic_link_store = Arc::new(RwLock::new(RedisMagicLinkStore::new(
+        shared_redis_conn.clone(),
+    ))) as MagicLinkStoreType;
+    let audit_sink = configure_audit_sink(&settings, pg_pool.clone()).await;
+    let geo_locator = configure_geo_locator(&settings);
+
+    spawn_login_history_retention(
+        login_history_store.clone(),
+        settings.login_history.retention_days,
+        LOGIN_HISTORY_PRUNE_INTERVAL,
+    );
 
-    let email_client = Arc::new(RwLock::new(configure_postmark_email_client())) as EmailClientType;
+    spawn_webhook_worker(
+        webhook_store.clone(),
+        configure_webhook_http_client(&settings),
+        settings.webhooks.delivery_policy(),
+        settings.webhooks.poll_interval(),
+    );
+
+    let email_client =
+        Arc::new(RwLock::new(configure_postmark_email_client(&settings)?)) as EmailClientType;
     let app_state = AppState::new(
-        user_store,
-        banned_token_store,
-        two_fa_code_store,
-        email_client,
+        AppStores {
+            user_store,
+            banned_token_store,
+            two_fa_code_store,
+            email_client,
+            rate_limit_store,
+            login_history_store,
+            known_device_store,
+            login_alert_store,
+            geo_locator,
+            audit_sink,
+            webhook_store,
+            oauth_client_store,
+            authorization_code_store,
+            service_client_store,
+            device_authorization_store,
+            external_identity_store,
+            external_login_store,
+            magic_link_store,
+        },
+        pg_pool,
+        shared_redis_conn,
+        Arc::new(Readiness::new(settings.health.check_email_provider)),
+        configure_id_token_signer(&settings),
+        settings.clone(),
     );
-    let app = Application::build(app_state, prod::APP_ADDRESS)
+    let app = Application::build(app_state)
         .await
         .expect("Failed to build app");
 
+    let shutdown = app.shutdown_handle();
+    tokio::spawn(async move {
+        wait_for_shutdown_signal().await;
+        shutdown.shutdown();
+    });
+
     app.run().await.expect("Failed to run app");
+
+    Ok(())
 }
 
-async fn configure_postgresql() -> PgPool {
+async fn configure_postgresql(settings: &Settings) -> PgPool {
     // Create a new database connection pool
-    let pg_pool = get_postgres_pool(DATABASE_URL.clone())
-        .await
-        .expect("Failed to create Postgres connection pool!");
+    let pg_pool = get_postgres_pool(
+        settings.database.url.clone(),
+        settings.database.max_connections,
+    )
+    .await
+    .expect("Failed to create Postgres connection pool!");
 
     // Run database migrations against our test database!
     sqlx::migrate!()
@@ -64,23 +162,71 @@ async fn configure_postgresql() -> PgPool {
     pg_pool
 }
 
-fn configure_redis() -> redis::Connection {
-    get_redis_client(REDIS_HOST_NAME.to_owned())
+fn configure_redis(settings: &Settings) -> redis::Connection {
+    get_redis_client(settings.redis.host_name.clone())
         .expect("Failed to get Redis client")
         .get_connection()
         .expect("Failed to get Redis connection")
 }
 
-fn configure_postmark_email_client() -> PostmarkEmailClient {
+fn configure_user_store(settings: &Settings, pg_pool: PgPool) -> UserStoreType {
+    let local = Arc::new(RwLock::new(PostgresUserStore::new(pg_pool))) as UserStoreType;
+    if settings.ldap.url.is_none() {
+        return local;
+    }
+
+    let directory = Arc::new(RwLock::new(LdapUserStore::new(settings.ldap.clone())));
+    Arc::new(RwLock::new(CompositeUserStore::new(directory, local)))
+}
+
+async fn configure_audit_sink(settings: &Settings, pg_pool: PgPool) -> AuditSinkType {
+    match settings.audit.sink {
+        AuditSinkKind::File => Arc::new(RwLock::new(
+            JsonLinesFileAuditSink::open(&settings.audit.log_path)
+                .await
+                .expect("Failed to open audit log file"),
+        )),
+        AuditSinkKind::Stdout => Arc::new(RwLock::new(StdoutAuditSink::default())),
+        AuditSinkKind::Postgres => Arc::new(RwLock::new(PostgresAuditSink::new(pg_pool))),
+    }
+}
+
+fn configure_geo_locator(settings: &Settings) -> GeoLocatorType {
+    match settings.geoip.database_path.as_deref() {
+        Some(path) => Arc::new(RwLock::new(
+            MaxMindGeoLocator::open(path).expect("Failed to open GeoIP database"),
+        )),
+        None => {
+            tracing::warn!("geoip.database_path is not set; login locations will be unknown");
+            Arc::new(RwLock::new(MockGeoLocator::default()))
+        }
+    }
+}
+
+// A bad key stops the service here rather than failing every ID token and JWKS request
+fn configure_id_token_signer(settings: &Settings) -> Option<IdTokenSignerType> {
+    IdTokenSigner::from_settings(settings)
+        .expect("Failed to load the ID token signing key")
+        .map(Arc::new)
+}
+
+fn configure_webhook_http_client(settings: &Settings) -> Client {
+    Client::builder()
+        .timeout(settings.webhooks.timeout())
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+fn configure_postmark_email_client(settings: &Settings) -> Result<PostmarkEmailClient> {
     let http_client = Client::builder()
-        .timeout(prod::email_client::TIMEOUT)
+        .timeout(settings.email_client.timeout())
         .build()
         .expect("Failed to build HTTP client");
 
-    PostmarkEmailClient::new(
-        prod::email_client::BASE_URL.to_owned(),
-        Email::parse(Secret::new(prod::email_client::SENDER.to_owned())).unwrap(),
-        POSTMARK_AUTH_TOKEN.to_owned(),
+    Ok(PostmarkEmailClient::new(
+        settings.email_client.base_url.clone(),
+        settings.email_client.sender()?,
+        settings.email_client.auth_token.clone(),
         http_client,
-    )
+    ))
 }
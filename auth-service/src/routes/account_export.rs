@@ -0,0 +1,230 @@
+use std::io::{Cursor, Write};
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{eyre, Context};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use super::LoginHistoryItem;
+use crate::{
+    app_state::AppState,
+    domain::{AuditEvent, AuditSinkError, AuthAPIError, Email, UserStoreError},
+    services::record_audit_event,
+    utils::{
+        constants::{ACCOUNT_EXPORT_MAX_REQUESTS, ACCOUNT_EXPORT_WINDOW_SECONDS},
+        AuthenticatedUser,
+    },
+};
+
+#[tracing::instrument(name = "Account Export", skip_all)]
+pub async fn account_export(
+    State(state): State<AppState>,
+    AuthenticatedUser { email, claims, .. }: AuthenticatedUser,
+    Query(params): Query<AccountExportParams>,
+) -> Result<Response, AuthAPIError> {
+    // Exports are expensive, so each user only gets a handful per window
+    let hits = state
+        .rate_limit_store
+        .write()
+        .await
+        .record_hit(&rate_limit_key(&email), ACCOUNT_EXPORT_WINDOW_SECONDS)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    if hits > ACCOUNT_EXPORT_MAX_REQUESTS {
+        return Err(AuthAPIError::TooManyRequests);
+    }
+
+    let user = match state.user_store.read().await.get_user(&email).await {
+        Ok(user) => user,
+        Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    // A pending 2FA challenge only tells the user a code is outstanding; the code itself is secret
+    let pending_challenge = state
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&email)
+        .await
+        .is_ok();
+
+    // The export covers the full retained login history rather than a single page
+    let login_history = state
+        .login_history_store
+        .read()
+        .await
+        .get_entries(&email, u32::MAX, 0)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    // Sinks that only write, such as stdout, have nothing to add
+    let audit_events = match state
+        .audit_sink
+        .read()
+        .await
+        .records_for(email.as_ref().expose_secret())
+        .await
+    {
+        Ok(records) => records,
+        Err(AuditSinkError::Unreadable) => Vec::new(),
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    let session_expires_at = DateTime::<Utc>::from_timestamp(claims.exp as i64, 0)
+        .ok_or_else(|| AuthAPIError::UnexpectedError(eyre!("Invalid token expiry")))?;
+
+    // The password hash is deliberately left out of the export
+    let export = AccountExport {
+        generated_at: Utc::now(),
+        profile: ProfileExport {
+            email: user.email.as_ref().expose_secret().to_owned(),
+        },
+        two_factor: TwoFactorExport {
+            enabled: user.requires_2fa,
+            pending_challenge,
+        },
+        current_session: SessionExport {
+            expires_at: session_expires_at,
+        },
+        login_history: login_history.iter().map(LoginHistoryItem::from).collect(),
+        audit_events: audit_events
+            .into_iter()
+            .map(|record| AuditEventExport {
+                occurred_at: record.occurred_at,
+                event: record.event,
+            })
+            .collect(),
+    };
+
+    record_audit_event(
+        &state,
+        AuditEvent::AccountExported {
+            email: claims.sub.clone(),
+        },
+    )
+    .await;
+
+    match params.format {
+        ExportFormat::Json => Ok((
+            StatusCode::OK,
+            [(
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.json\"", EXPORT_FILE_NAME),
+            )],
+            Json(export),
+        )
+            .into_response()),
+        ExportFormat::Zip => {
+            let archive = build_zip_archive(&export).map_err(AuthAPIError::UnexpectedError)?;
+            Ok((
+                StatusCode::OK,
+                [
+                    (header::CONTENT_TYPE, "application/zip".to_owned()),
+                    (
+                        header::CONTENT_DISPOSITION,
+                        format!("attachment; filename=\"{}.zip\"", EXPORT_FILE_NAME),
+                    ),
+                ],
+                archive,
+            )
+                .into_response())
+        }
+    }
+}
+
+// Package the JSON export as the single entry of a zip archive
+#[tracing::instrument(name = "Build Export Archive", skip_all)]
+fn build_zip_archive(export: &AccountExport) -> color_eyre::eyre::Result<Vec<u8>> {
+    let json = serde_json::to_vec_pretty(export).wrap_err("failed to serialize account export")?;
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    writer
+        .start_file(
+            format!("{}.json", EXPORT_FILE_NAME),
+            SimpleFileOptions::default(),
+        )
+        .wrap_err("failed to start export archive entry")?;
+    writer
+        .write_all(&json)
+        .wrap_err("failed to write export archive entry")?;
+
+    let cursor = writer
+        .finish()
+        .wrap_err("failed to finish export archive")?;
+
+    Ok(cursor.into_inner())
+}
+
+const EXPORT_FILE_NAME: &str = "account-export";
+const RATE_LIMIT_KEY_PREFIX: &str = "account_export:";
+
+fn rate_limit_key(email: &Email) -> String {
+    format!(
+        "{}{}",
+        RATE_LIMIT_KEY_PREFIX,
+        email.as_ref().expose_secret()
+    )
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Zip,
+}
+
+#[derive(Deserialize)]
+pub struct AccountExportParams {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountExport {
+    pub generated_at: DateTime<Utc>,
+    pub profile: ProfileExport,
+    pub two_factor: TwoFactorExport,
+    // Sessions are stateless JWTs, so the one making the request is the only one we can list
+    pub current_session: SessionExport,
+    pub login_history: Vec<LoginHistoryItem>,
+    pub audit_events: Vec<AuditEventExport>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileExport {
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorExport {
+    pub enabled: bool,
+    pub pending_challenge: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExport {
+    pub expires_at: DateTime<Utc>,
+}
+
+// The event's own fields, including its `type`, sit next to `occurredAt`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEventExport {
+    pub occurred_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
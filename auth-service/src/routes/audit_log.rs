@@ -0,0 +1,44 @@
+use axum::{extract::State, http::HeaderMap, Json};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::AppState,
+    domain::{verify_audit_chain, AuditSinkError, AuthAPIError},
+    utils::admin::require_admin_token,
+};
+
+// Recomputes the audit log's hash chain, so edited or deleted records can be found in a
+// running service. Restricted to holders of the audit admin token.
+#[tracing::instrument(name = "Verify Audit Log", skip_all)]
+pub async fn verify_audit_log(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<AuditLogVerificationResponse>, AuthAPIError> {
+    require_admin_token(&headers, state.settings.audit.admin_token.as_ref())?;
+
+    let records = state
+        .audit_sink
+        .read()
+        .await
+        .records()
+        .await
+        .map_err(|e| match e {
+            AuditSinkError::Unreadable => AuthAPIError::AuditLogUnreadable,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    let outcome = verify_audit_chain(&records);
+    Ok(Json(AuditLogVerificationResponse {
+        records: records.len(),
+        valid: outcome.is_ok(),
+        error: outcome.err().map(|e| e.to_string()),
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditLogVerificationResponse {
+    pub records: usize,
+    pub valid: bool,
+    // The first problem found, e.g. "Audit record 3 has been modified"
+    pub error: Option<String>,
+}
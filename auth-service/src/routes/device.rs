@@ -0,0 +1,383 @@
+use axum::{
+    extract::{Query, State},
+    http::{header::CACHE_CONTROL, StatusCode},
+    response::{IntoResponse, Response},
+    Form, Json,
+};
+use chrono::{Duration, Utc};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+
+use super::oauth::{
+    generate_id_token, token_response, AccessTokenResponse, ConsentDecision, TokenRequest,
+};
+use crate::{
+    app_state::AppState,
+    domain::{
+        parse_scope, AuditEvent, AuthAPIError, DeviceApproval, DeviceAuthorization,
+        DeviceAuthorizationStatus, DeviceAuthorizationStoreError, DeviceCode, Email,
+        OAuthClientStoreError, OAuthError, OAuthErrorCode, UserCode, DEVICE_CODE_TTL_SECONDS,
+        DEVICE_POLL_INTERVAL_SECONDS, OPENID_SCOPE,
+    },
+    services::record_audit_event,
+    utils::{
+        constants::{DEVICE_VERIFICATION_MAX_ATTEMPTS, DEVICE_VERIFICATION_WINDOW_SECONDS},
+        generate_access_token, oidc_issuer, AuthenticatedUser, TOKEN_TTL_SECONDS,
+    },
+};
+
+pub const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+const RATE_LIMIT_KEY_PREFIX: &str = "device_verification:";
+
+// Starts a device authorization (RFC 8628 section 3.1), for clients such as CLIs that cannot
+// receive a redirect. The device shows the user code and polls `/token` while the user approves
+// it on the verification page from any browser.
+#[tracing::instrument(name = "Device Authorization", skip_all)]
+pub async fn device_authorization(
+    State(state): State<AppState>,
+    Form(request): Form<DeviceAuthorizationRequest>,
+) -> Result<Response, OAuthError> {
+    let client_id = request
+        .client_id
+        .ok_or_else(|| OAuthError::new(OAuthErrorCode::InvalidRequest, "Missing client_id"))?;
+    let client = state
+        .oauth_client_store
+        .read()
+        .await
+        .get_client(&client_id)
+        .await
+        .map_err(|e| match e {
+            OAuthClientStoreError::ClientNotFound => {
+                OAuthError::new(OAuthErrorCode::InvalidClient, "Unknown client")
+            }
+            e => OAuthError::new(OAuthErrorCode::ServerError, e.to_string()),
+        })?;
+
+    let scopes = parse_scope(request.scope.as_deref().unwrap_or_default())
+        .ok()
+        .filter(|scopes| !scopes.is_empty() && client.allows_scopes(scopes))
+        .filter(|scopes| {
+            !scopes.iter().any(|scope| scope == OPENID_SCOPE) || state.id_token_signer.is_some()
+        })
+        .ok_or_else(|| {
+            OAuthError::new(
+                OAuthErrorCode::InvalidScope,
+                "The requested scope is invalid or not allowed for this client",
+            )
+        })?;
+
+    let device_code = DeviceCode::default();
+    let user_code = UserCode::default();
+    let authorization = DeviceAuthorization {
+        client_id: client.client_id,
+        scopes,
+        user_code: user_code.clone(),
+        status: DeviceAuthorizationStatus::Pending,
+        expires_at: Utc::now() + Duration::seconds(DEVICE_CODE_TTL_SECONDS as i64),
+    };
+
+    state
+        .device_authorization_store
+        .write()
+        .await
+        .add_authorization(device_code.clone(), authorization)
+        .await
+        .map_err(|e| OAuthError::new(OAuthErrorCode::ServerError, e.to_string()))?;
+
+    let verification_uri = format!("{}/device", oidc_issuer(&state.settings));
+    let response = DeviceAuthorizationResponse {
+        device_code: device_code.as_ref().to_owned(),
+        user_code: user_code.formatted(),
+        verification_uri_complete: format!(
+            "{}?user_code={}",
+            verification_uri,
+            user_code.formatted()
+        ),
+        verification_uri,
+        expires_in: DEVICE_CODE_TTL_SECONDS,
+        interval: DEVICE_POLL_INTERVAL_SECONDS,
+    };
+
+    Ok(([(CACHE_CONTROL, "no-store")], Json(response)).into_response())
+}
+
+// What the verification page shows a logged-in user before they approve a device
+#[tracing::instrument(name = "Get Device Verification", skip_all)]
+pub async fn device_verification(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(query): Query<DeviceVerificationQuery>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let (_, authorization) = pending_authorization(&state, &user, &query.user_code).await?;
+    let client = state
+        .oauth_client_store
+        .read()
+        .await
+        .get_client(&authorization.client_id)
+        .await
+        .map_err(|e| match e {
+            // The client was deleted after the device started
+            OAuthClientStoreError::ClientNotFound => AuthAPIError::UserCodeNotFound,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    Ok(Json(DeviceVerificationResponse {
+        user_code: authorization.user_code.formatted(),
+        client_name: client.name,
+        scopes: authorization.scopes,
+    }))
+}
+
+// The user's decision on the verification page. Approving records how the user logged in, for
+// the tokens the device gets.
+#[tracing::instrument(name = "Device Verification", skip_all)]
+pub async fn device_verification_decision(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Json(request): Json<DeviceVerificationRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let (device_code, mut authorization) =
+        pending_authorization(&state, &user, &request.user_code).await?;
+
+    authorization.status = match request.decision {
+        ConsentDecision::Allow => DeviceAuthorizationStatus::Approved(DeviceApproval {
+            email: user.email.clone(),
+            auth_time: user.claims.auth_time,
+            amr: user.claims.amr.clone(),
+            sid: user.claims.sid.clone(),
+        }),
+        ConsentDecision::Deny => DeviceAuthorizationStatus::Denied,
+    };
+    let client_id = authorization.client_id.clone();
+    let scope = authorization.scope();
+
+    state
+        .device_authorization_store
+        .write()
+        .await
+        .update_authorization(&device_code, authorization)
+        .await
+        .map_err(|e| match e {
+            DeviceAuthorizationStoreError::AuthorizationNotFound => AuthAPIError::UserCodeNotFound,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    if request.decision == ConsentDecision::Allow {
+        record_audit_event(
+            &state,
+            AuditEvent::OAuthConsentGranted {
+                email: user.email.as_ref().expose_secret().to_owned(),
+                client_id,
+                scope,
+            },
+        )
+        .await;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// A request still waiting for a decision. Decided and expired requests cannot be decided again.
+// Only users who finished logging in may look codes up, and only a few times per window.
+async fn pending_authorization(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    user_code: &str,
+) -> Result<(DeviceCode, DeviceAuthorization), AuthAPIError> {
+    if !user.completed_login(state).await? {
+        return Err(AuthAPIError::TwoFactorRequired);
+    }
+
+    let hits = state
+        .rate_limit_store
+        .write()
+        .await
+        .record_hit(
+            &rate_limit_key(&user.email),
+            DEVICE_VERIFICATION_WINDOW_SECONDS,
+        )
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    if hits > DEVICE_VERIFICATION_MAX_ATTEMPTS {
+        return Err(AuthAPIError::TooManyRequests);
+    }
+
+    let user_code = UserCode::parse(user_code).map_err(|_| AuthAPIError::UserCodeNotFound)?;
+    let store = state.device_authorization_store.read().await;
+    let not_found = |e| match e {
+        DeviceAuthorizationStoreError::AuthorizationNotFound => AuthAPIError::UserCodeNotFound,
+        e => AuthAPIError::UnexpectedError(e.into()),
+    };
+
+    let device_code = store.get_device_code(&user_code).await.map_err(not_found)?;
+    let authorization = store
+        .get_authorization(&device_code)
+        .await
+        .map_err(not_found)?;
+
+    if authorization.status != DeviceAuthorizationStatus::Pending
+        || authorization.is_expired(Utc::now())
+    {
+        return Err(AuthAPIError::UserCodeNotFound);
+    }
+
+    Ok((device_code, authorization))
+}
+
+fn rate_limit_key(email: &Email) -> String {
+    format!(
+        "{}{}",
+        RATE_LIMIT_KEY_PREFIX,
+        email.as_ref().expose_secret()
+    )
+}
+
+// Polling `/token` with the device code (RFC 8628 section 3.4). An unknown code is reported as
+// expired: codes are unguessable, so it is one that expired or was already redeemed, and the
+// device should start over either way.
+pub(crate) async fn device_code_grant(
+    state: &AppState,
+    request: TokenRequest,
+) -> Result<Response, OAuthError> {
+    let (Some(device_code), Some(client_id)) = (request.device_code, request.client_id) else {
+        return Err(OAuthError::new(
+            OAuthErrorCode::InvalidRequest,
+            "device_code and client_id are required",
+        ));
+    };
+
+    let expired = || {
+        OAuthError::new(
+            OAuthErrorCode::ExpiredToken,
+            "The device code has expired or was already used",
+        )
+    };
+    let store_error = |e| match e {
+        DeviceAuthorizationStoreError::AuthorizationNotFound => expired(),
+        e => OAuthError::new(OAuthErrorCode::ServerError, e.to_string()),
+    };
+
+    let device_code = DeviceCode::parse(device_code)
+        .map_err(|_| OAuthError::new(OAuthErrorCode::InvalidGrant, "Invalid device code"))?;
+    let mut store = state.device_authorization_store.write().await;
+    let authorization = store
+        .get_authorization(&device_code)
+        .await
+        .map_err(store_error)?;
+
+    if authorization.client_id != client_id {
+        return Err(OAuthError::new(
+            OAuthErrorCode::InvalidGrant,
+            "The device code was issued to another client",
+        ));
+    }
+    let now = Utc::now();
+    if authorization.is_expired(now) {
+        return Err(expired());
+    }
+
+    if authorization.status == DeviceAuthorizationStatus::Pending {
+        let too_soon = store
+            .record_poll(&device_code, now)
+            .await
+            .map_err(store_error)?;
+        return Err(if too_soon {
+            OAuthError::new(OAuthErrorCode::SlowDown, "Polling too often")
+        } else {
+            OAuthError::new(
+                OAuthErrorCode::AuthorizationPending,
+                "The user has not yet approved the device",
+            )
+        });
+    }
+
+    // Taking the request means it can only be redeemed once, even by concurrent polls
+    let authorization = store
+        .take_authorization(&device_code)
+        .await
+        .map_err(store_error)?;
+    drop(store);
+
+    let scope = authorization.scope();
+    let DeviceAuthorizationStatus::Approved(approval) = authorization.status else {
+        return Err(OAuthError::new(
+            OAuthErrorCode::AccessDenied,
+            "The user denied the device",
+        ));
+    };
+
+    let server_error =
+        |e: color_eyre::eyre::Report| OAuthError::new(OAuthErrorCode::ServerError, e.to_string());
+    let access_token = generate_access_token(
+        &approval.email,
+        &authorization.client_id,
+        &scope,
+        approval.sid.as_deref(),
+        &state.settings.jwt,
+    )
+    .map_err(server_error)?;
+    let id_token = if authorization
+        .scopes
+        .iter()
+        .any(|scope| scope == OPENID_SCOPE)
+    {
+        Some(
+            generate_id_token(
+                state,
+                &authorization.client_id,
+                &approval.email,
+                approval.auth_time,
+                None,
+                approval.amr,
+            )
+            .map_err(server_error)?,
+        )
+    } else {
+        None
+    };
+
+    Ok(token_response(AccessTokenResponse {
+        access_token,
+        token_type: "Bearer".to_owned(),
+        expires_in: TOKEN_TTL_SECONDS,
+        scope,
+        id_token,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct DeviceAuthorizationRequest {
+    pub client_id: Option<String>,
+    pub scope: Option<String>,
+}
+
+// RFC 8628 section 3.2
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceAuthorizationResponse {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub verification_uri_complete: String,
+    pub expires_in: u64,
+    pub interval: u64,
+}
+
+#[derive(Deserialize)]
+pub struct DeviceVerificationQuery {
+    pub user_code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceVerificationResponse {
+    pub user_code: String,
+    pub client_name: String,
+    pub scopes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceVerificationRequest {
+    pub user_code: String,
+    pub decision: ConsentDecision,
+}
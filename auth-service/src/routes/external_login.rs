@@ -0,0 +1,380 @@
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Redirect},
+    Json,
+};
+use axum_extra::extract::{
+    cookie::{Cookie, SameSite},
+    CookieJar,
+};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+    app_state::AppState,
+    domain::{
+        pkce_challenge, AuditEvent, AuthAPIError, ClientInfo, Email, ExternalIdentity,
+        ExternalIdentityStoreError, ExternalLoginState, Password, PendingExternalLogin, User,
+        UserStoreError, WebhookEventType, EXTERNAL_LOGIN_TTL_SECONDS,
+    },
+    routes::record_login_success,
+    services::{
+        publish_webhook_event, record_audit_event, ExternalIdTokenClaims, ExternalIdpClient,
+    },
+    utils::{
+        add_session_cookies, build_session_cookie, generate_auth_cookie, oidc_issuer,
+        AuthenticationMethod, CookieSettings, JwtSettings,
+    },
+};
+
+// Tells the login page whether to offer logging in through the external identity provider
+#[tracing::instrument(name = "External Login Provider", skip_all)]
+pub async fn external_login_provider(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let settings = &state.settings.external_idp;
+    if settings.issuer_url.is_none() {
+        return Err(AuthAPIError::ExternalLoginDisabled);
+    }
+
+    Ok(Json(ExternalLoginProviderResponse {
+        display_name: settings.display_name.clone(),
+    }))
+}
+
+// Starts a login at the external identity provider: remembers the state, nonce and PKCE
+// verifier, then sends the browser to the provider's authorization endpoint. A cookie ties the
+// state to this browser, so nobody can finish the login in someone else's.
+#[tracing::instrument(name = "External Login", skip_all)]
+pub async fn external_login(
+    State(state): State<AppState>,
+    jar: CookieJar,
+    Query(request): Query<ExternalLoginRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let idp = ExternalIdpClient::from_settings(&state.settings.external_idp)
+        .ok_or(AuthAPIError::ExternalLoginDisabled)?;
+    let metadata = idp
+        .discover()
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    // Anything else is dropped rather than rejected; the user still ends up logged in
+    let return_to = request.return_to.filter(|path| is_allowed_return_to(path));
+    let login_state = ExternalLoginState::default();
+    let login = PendingExternalLogin::new(return_to);
+
+    let location = idp
+        .authorization_url(
+            &metadata,
+            &redirect_uri(&state),
+            login_state.as_ref(),
+            &login.nonce,
+            &pkce_challenge(&login.code_verifier),
+        )
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    let mut cookie = external_login_cookie(
+        browser_binding(&login_state, &state.settings.jwt),
+        &state.settings.cookie,
+    );
+    cookie.set_max_age(time::Duration::seconds(EXTERNAL_LOGIN_TTL_SECONDS as i64));
+
+    state
+        .external_login_store
+        .write()
+        .await
+        .add_login(login_state, login)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok((jar.add(cookie), Redirect::to(location.as_str())))
+}
+
+// Where the external identity provider sends the browser back to. A valid ID token logs the
+// user in as the account it is linked to, linking or creating one first if the configuration
+// allows it. The provider's login stands in for ours, so our own 2FA is not asked for.
+#[tracing::instrument(name = "External Login Callback", skip_all)]
+pub async fn external_login_callback(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    jar: CookieJar,
+    Query(callback): Query<ExternalLoginCallback>,
+) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+    let binding = jar
+        .get(&state.settings.cookie.external_login_cookie_name())
+        .map(|cookie| cookie.value().to_owned());
+    let jar = jar.remove(external_login_cookie(String::new(), &state.settings.cookie));
+
+    match complete_external_login(&state, callback, binding.as_deref()).await {
+        Ok((email, return_to)) => {
+            let auth_cookie = match generate_auth_cookie(
+                &email,
+                AuthenticationMethod::External,
+                &state.settings.jwt,
+                &state.settings.cookie,
+            ) {
+                Ok(cookie) => cookie,
+                Err(e) => return (jar, Err(AuthAPIError::UnexpectedError(e))),
+            };
+
+            record_login_success(&state, &email, &client, auth_cookie.value(), false).await;
+
+            let jar = add_session_cookies(jar, auth_cookie, &state.settings);
+            let location = return_to.unwrap_or_else(|| "/".to_owned());
+            (jar, Ok(Redirect::to(&location)))
+        }
+        Err(e) => (jar, Err(e)),
+    }
+}
+
+// The user the provider vouched for, and where to send them next. `binding` is the external
+// login cookie of the browser the provider sent back.
+async fn complete_external_login(
+    state: &AppState,
+    callback: ExternalLoginCallback,
+    binding: Option<&str>,
+) -> Result<(Email, Option<String>), AuthAPIError> {
+    let idp = ExternalIdpClient::from_settings(&state.settings.external_idp)
+        .ok_or(AuthAPIError::ExternalLoginDisabled)?;
+
+    let login_state = callback
+        .state
+        .and_then(|login_state| ExternalLoginState::parse(login_state).ok())
+        .ok_or(AuthAPIError::InvalidExternalLogin)?;
+    // Someone else's callback URL, e.g. one an attacker sends to log the victim in as themselves
+    if !binding
+        .is_some_and(|binding| verify_browser_binding(&login_state, binding, &state.settings.jwt))
+    {
+        return Err(AuthAPIError::InvalidExternalLogin);
+    }
+    // Taken even when the provider reports an error, so the state can never be replayed
+    let login = state
+        .external_login_store
+        .write()
+        .await
+        .take_login(&login_state)
+        .await
+        .map_err(|_| AuthAPIError::InvalidExternalLogin)?;
+
+    if let Some(error) = callback.error {
+        tracing::warn!("External identity provider returned error: {}", error);
+        return Err(AuthAPIError::InvalidExternalLogin);
+    }
+    let code = callback.code.ok_or(AuthAPIError::InvalidExternalLogin)?;
+
+    let metadata = idp
+        .discover()
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+    let id_token = idp
+        .redeem_code(&metadata, &code, &redirect_uri(state), &login.code_verifier)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Failed to redeem external authorization code: {:?}", e);
+            AuthAPIError::InvalidExternalLogin
+        })?;
+    let claims = idp
+        .verify_id_token(&metadata, &id_token, &login.nonce)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Rejected external ID token: {:?}", e);
+            AuthAPIError::InvalidExternalLogin
+        })?;
+
+    let email = resolve_external_user(state, claims).await?;
+    Ok((email, login.return_to))
+}
+
+// Finds the user an external account logs in as. Accounts already linked keep logging in as
+// their user; anything else is matched by verified email address, within the configured
+// domains, and linked or provisioned only when the settings allow it.
+async fn resolve_external_user(
+    state: &AppState,
+    claims: ExternalIdTokenClaims,
+) -> Result<Email, AuthAPIError> {
+    let settings = &state.settings.external_idp;
+
+    match state
+        .external_identity_store
+        .read()
+        .await
+        .get_identity(&claims.iss, &claims.sub)
+        .await
+    {
+        Ok(identity) => return Ok(identity.email),
+        Err(ExternalIdentityStoreError::IdentityNotFound) => {}
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    let email = match claims.email.as_deref() {
+        Some(email) if claims.email_verified && settings.allows_email(email) => {
+            Email::parse(Secret::new(email.to_owned()))
+                .map_err(|_| AuthAPIError::ExternalAccountNotAllowed)?
+        }
+        _ => return Err(AuthAPIError::ExternalAccountNotAllowed),
+    };
+
+    let user_exists = match state.user_store.read().await.get_user(&email).await {
+        Ok(_) => true,
+        Err(UserStoreError::UserNotFound) => false,
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+    if user_exists {
+        if !settings.link_existing_users {
+            return Err(AuthAPIError::ExternalAccountNotAllowed);
+        }
+    } else if settings.jit_provisioning {
+        provision_user(state, &email).await?;
+    } else {
+        return Err(AuthAPIError::ExternalAccountNotAllowed);
+    }
+
+    let identity = ExternalIdentity {
+        issuer: claims.iss,
+        subject: claims.sub,
+        email: email.clone(),
+        linked_at: Utc::now(),
+    };
+    let event = AuditEvent::ExternalIdentityLinked {
+        email: email.as_ref().expose_secret().to_owned(),
+        issuer: identity.issuer.clone(),
+        subject: identity.subject.clone(),
+    };
+    match state
+        .external_identity_store
+        .write()
+        .await
+        .add_identity(identity)
+        .await
+    {
+        // A concurrent callback for the same account linked it first
+        Ok(()) | Err(ExternalIdentityStoreError::IdentityAlreadyLinked) => {}
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+    record_audit_event(state, event).await;
+
+    Ok(email)
+}
+
+// Creates the user an external account logs in as. They get a random password they never see
+// and must reset it before logging in with a password.
+async fn provision_user(state: &AppState, email: &Email) -> Result<(), AuthAPIError> {
+    let user = User {
+        email: email.clone(),
+        password: Password::random(),
+        requires_2fa: false,
+    };
+
+    let mut user_store = state.user_store.write().await;
+    user_store
+        .add_user(user)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    user_store
+        .require_password_reset(email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    drop(user_store);
+
+    let email = email.as_ref().expose_secret().to_owned();
+    record_audit_event(
+        state,
+        AuditEvent::UserSignedUp {
+            email: email.clone(),
+            requires_2fa: false,
+        },
+    )
+    .await;
+    publish_webhook_event(
+        state,
+        WebhookEventType::UserSignedUp,
+        serde_json::json!({ "email": email, "requires2FA": false }),
+    )
+    .await;
+
+    Ok(())
+}
+
+// Lax whatever the session cookies use, since the provider sends the browser back from another
+// site. Also used to remove the cookie, so it must carry the same attributes every time.
+fn external_login_cookie(value: String, settings: &CookieSettings) -> Cookie<'static> {
+    let mut cookie = build_session_cookie(settings.external_login_cookie_name(), value, settings);
+    cookie.set_http_only(true);
+    cookie.set_same_site(SameSite::Lax);
+
+    cookie
+}
+
+// The cookie holds an HMAC of the state rather than the state itself
+fn browser_binding(login_state: &ExternalLoginState, jwt: &JwtSettings) -> String {
+    hex::encode(binding_mac(login_state, jwt).finalize().into_bytes())
+}
+
+// Compares in constant time
+fn verify_browser_binding(
+    login_state: &ExternalLoginState,
+    binding: &str,
+    jwt: &JwtSettings,
+) -> bool {
+    hex::decode(binding)
+        .map(|binding| binding_mac(login_state, jwt).verify_slice(&binding).is_ok())
+        .unwrap_or(false)
+}
+
+fn binding_mac(login_state: &ExternalLoginState, jwt: &JwtSettings) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(jwt.secret.expose_secret().as_bytes())
+        .expect("HMAC can take a key of any size");
+    // Keeps these MACs distinct from anything else keyed with the JWT secret
+    mac.update(b"external-login.");
+    mac.update(login_state.as_ref().as_bytes());
+    mac
+}
+
+fn redirect_uri(state: &AppState) -> String {
+    format!("{}/login/external/callback", oidc_issuer(&state.settings))
+}
+
+// The pages a login may return to, as on the login page, so the parameter cannot send users to
+// another site
+fn is_allowed_return_to(path: &str) -> bool {
+    path.starts_with("/authorize?") || path == "/device" || path.starts_with("/device?")
+}
+
+#[derive(Deserialize)]
+pub struct ExternalLoginRequest {
+    pub return_to: Option<String>,
+}
+
+// Everything is optional so a bad redirect gets our error instead of axum's
+#[derive(Deserialize)]
+pub struct ExternalLoginCallback {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExternalLoginProviderResponse {
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_returns_to_local_login_pages() {
+        assert!(is_allowed_return_to("/authorize?client_id=abc"));
+        assert!(is_allowed_return_to("/device"));
+        assert!(is_allowed_return_to("/device?user_code=BCDF-GHJK"));
+
+        assert!(!is_allowed_return_to("https://evil.example.com/"));
+        assert!(!is_allowed_return_to("//evil.example.com/authorize?"));
+        assert!(!is_allowed_return_to("/devices"));
+        assert!(!is_allowed_return_to("/authorize"));
+    }
+}
@@ -0,0 +1,34 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::AppState,
+    services::{check_readiness, ReadinessStatus},
+};
+
+// Liveness only says the process is serving requests; it never touches dependencies, so a
+// database outage does not get the service restarted
+#[tracing::instrument(name = "Health Live", skip_all)]
+pub async fn health_live() -> impl IntoResponse {
+    Json(LiveResponse {
+        status: "ok".to_owned(),
+    })
+}
+
+// Readiness fails with 503 while any required dependency is down
+#[tracing::instrument(name = "Health Ready", skip_all)]
+pub async fn health_ready(State(state): State<AppState>) -> impl IntoResponse {
+    let report = check_readiness(&state).await;
+
+    let status = match report.status {
+        ReadinessStatus::Ready => StatusCode::OK,
+        ReadinessStatus::NotReady => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (status, Json(report))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LiveResponse {
+    pub status: String,
+}
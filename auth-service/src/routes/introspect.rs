@@ -0,0 +1,98 @@
+use axum::{
+    extract::State,
+    http::{header::CACHE_CONTROL, HeaderMap},
+    response::{IntoResponse, Response},
+    Form, Json,
+};
+use serde::{Deserialize, Serialize};
+
+use super::oauth::authenticate_service_client;
+use crate::{
+    app_state::AppState,
+    domain::{OAuthError, OAuthErrorCode},
+    utils::{validate_token_for, Actor, Claims, ExpectedAudience},
+};
+
+// Token introspection (RFC 7662), for services that need to know who a token belongs to. Callers
+// authenticate as a service client. Tokens that are malformed, expired or revoked, or restricted
+// to another audience than the caller, are only reported as inactive.
+#[tracing::instrument(name = "Introspect", skip_all)]
+pub async fn introspect(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(request): Form<IntrospectionRequest>,
+) -> Result<Response, OAuthError> {
+    let client = authenticate_service_client(
+        &state,
+        &headers,
+        request.client_id.as_deref(),
+        request.client_secret.as_deref(),
+    )
+    .await?;
+
+    let token = request
+        .token
+        .ok_or_else(|| OAuthError::new(OAuthErrorCode::InvalidRequest, "Missing token"))?;
+
+    // Every token this service issues is a bearer JWT, so `token_type_hint` changes nothing
+    let response = validate_token_for(
+        &token,
+        ExpectedAudience::Service(&client.client_id),
+        &state.banned_token_store,
+        &state.settings.jwt,
+    )
+    .await
+    .map(IntrospectionResponse::from)
+    .unwrap_or_default();
+
+    Ok(([(CACHE_CONTROL, "no-store")], Json(response)).into_response())
+}
+
+#[derive(Deserialize)]
+pub struct IntrospectionRequest {
+    pub token: Option<String>,
+    pub token_type_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+// RFC 7662 section 2.2, plus the session the token belongs to and the RFC 8693 actor
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IntrospectionResponse {
+    pub active: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,
+}
+
+impl From<Claims> for IntrospectionResponse {
+    fn from(claims: Claims) -> Self {
+        Self {
+            active: true,
+            token_type: Some("Bearer".to_owned()),
+            sub: Some(claims.sub),
+            exp: Some(claims.exp),
+            iat: claims.iat,
+            scope: claims.scope,
+            client_id: claims.client_id,
+            sid: claims.sid,
+            aud: claims.aud,
+            act: claims.act,
+        }
+    }
+}
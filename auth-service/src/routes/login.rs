@@ -3,21 +3,28 @@ use axum_extra::extract::CookieJar;
 use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Serialize};
 
-use color_eyre::eyre::eyre;
-
 use crate::{
     app_state::AppState,
-    domain::{AuthAPIError, Email, LoginAttemptId, Password, TwoFACode, User},
-    utils::generate_auth_cookie,
+    domain::{
+        AuditEvent, AuthAPIError, ClientInfo, Email, FirstFactor, LoginAttemptId,
+        LoginFailureReason, LoginHistoryEntry, LoginOutcome, Password, TwoFACode, UserStoreError,
+    },
+    services::{notify_if_new_device, record_audit_event},
+    utils::{
+        add_session_cookies, generate_auth_cookie, generate_auth_token, labels,
+        AuthenticationMethod, METRICS, TOKEN_TTL_SECONDS,
+    },
 };
 #[tracing::instrument(name = "Login", skip_all)]
 pub async fn login(
     State(state): State<AppState>,
+    client: ClientInfo,
     jar: CookieJar,
     Json(request): Json<LoginRequest>,
 ) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
     let email = request.email;
     let password = request.password;
+    let response_mode = request.response_mode;
 
     //USE Email and Passowrd parse method
     let email = match Email::parse(Secret::new(email.to_string())) {
@@ -27,7 +34,17 @@ pub async fn login(
 
     let password = match Password::parse(Secret::new(password.expose_secret().to_string())) {
         Ok(password) => password,
-        Err(_) => return (jar, Err(AuthAPIError::InvalidCredentials)),
+        Err(_) => {
+            record_login_failure(
+                &state,
+                email,
+                &client,
+                LoginFailureReason::InvalidPassword,
+                false,
+            )
+            .await;
+            return (jar, Err(AuthAPIError::InvalidCredentials));
+        }
     };
 
     let user_store = state.user_store.read().await;
@@ -35,41 +52,147 @@ pub async fn login(
     //Check if user exists first
     let user = match user_store.get_user(&email).await {
         Ok(user) => user,
-        Err(_) => return (jar, Err(AuthAPIError::InvalidCredentials)),
+        Err(_) => {
+            record_login_failure(
+                &state,
+                email,
+                &client,
+                LoginFailureReason::UnknownUser,
+                false,
+            )
+            .await;
+            return (jar, Err(AuthAPIError::InvalidCredentials));
+        }
     };
 
     //Check if user credentials are correct. E.g password is correct.
     match user_store.validate_user(&email, &password).await {
         Ok(_) => {}
-        Err(_) => return (jar, Err(AuthAPIError::IncorrectCredentials)),
-    }
-
-    // Call the generate_auth_cookie function defined in the auth module.
-    // If the function call fails return AuthAPIError::UnexpectedError.
-    let auth_cookie = match generate_auth_cookie(&user.email) {
-        Ok(cookie) => cookie,
+        Err(UserStoreError::PasswordResetRequired) => {
+            record_login_failure(
+                &state,
+                email,
+                &client,
+                LoginFailureReason::PasswordResetRequired,
+                false,
+            )
+            .await;
+            return (jar, Err(AuthAPIError::PasswordResetRequired));
+        }
         Err(_) => {
-            return (
-                jar,
-                Err(AuthAPIError::UnexpectedError(eyre!(
-                    "Failed to generate auth cookie"
-                ))),
+            record_login_failure(
+                &state,
+                email,
+                &client,
+                LoginFailureReason::IncorrectPassword,
+                false,
             )
+            .await;
+            return (jar, Err(AuthAPIError::IncorrectCredentials));
         }
-    };
-
-    let jar = jar.add(auth_cookie);
+    }
 
-    // Handle request based on user's 2FA configuration
+    // Handle request based on user's 2FA configuration. Users with 2FA only get an auth cookie
+    // or token from `/verify-2fa`, so a password alone never opens a session for them.
     match user.requires_2fa {
-        true => handle_2fa(user.email, &state, jar).await,
-        false => handle_no_2fa(&user.email, jar).await,
+        true => handle_2fa(user.email, FirstFactor::Password, &state, jar).await,
+        false => handle_no_2fa(&user.email, &state, &client, jar, response_mode).await,
+    }
+}
+
+#[tracing::instrument(name = "Record Login Failure", skip_all)]
+pub(crate) async fn record_login_failure(
+    state: &AppState,
+    email: Email,
+    client: &ClientInfo,
+    reason: LoginFailureReason,
+    used_2fa: bool,
+) {
+    METRICS
+        .login_attempts
+        .with_label_values(&[reason.as_str()])
+        .inc();
+    if used_2fa {
+        METRICS
+            .two_fa_codes
+            .with_label_values(&[labels::TWO_FA_CODE_FAILED])
+            .inc();
+    }
+
+    record_audit_event(
+        state,
+        AuditEvent::LoginFailed {
+            email: email.as_ref().expose_secret().to_owned(),
+            reason: reason.as_str().to_owned(),
+            ip_address: client.ip_address.clone(),
+            user_agent: client.user_agent.clone(),
+        },
+    )
+    .await;
+
+    let outcome = LoginOutcome::Failure(reason);
+    record_login_attempt(
+        state,
+        LoginHistoryEntry::new(email, client, outcome, used_2fa),
+    )
+    .await;
+}
+
+// Record a completed login and alert the owner if it came from a new device
+#[tracing::instrument(name = "Record Login Success", skip_all)]
+pub(crate) async fn record_login_success(
+    state: &AppState,
+    email: &Email,
+    client: &ClientInfo,
+    session_token: &str,
+    used_2fa: bool,
+) {
+    METRICS
+        .login_attempts
+        .with_label_values(&[labels::LOGIN_SUCCESS])
+        .inc();
+
+    record_audit_event(
+        state,
+        AuditEvent::LoginSucceeded {
+            email: email.as_ref().expose_secret().to_owned(),
+            used_2fa,
+            ip_address: client.ip_address.clone(),
+            user_agent: client.user_agent.clone(),
+        },
+    )
+    .await;
+
+    record_login_attempt(
+        state,
+        LoginHistoryEntry::new(email.clone(), client, LoginOutcome::Success, used_2fa),
+    )
+    .await;
+
+    // The login itself already succeeded, so a failed alert is only logged
+    if let Err(e) = notify_if_new_device(state, email, client, session_token).await {
+        tracing::error!("Failed to send new device alert: {:?}", e);
+    }
+}
+
+// Persist a login outcome. Failing to record history must not fail the login itself.
+#[tracing::instrument(name = "Record Login Attempt", skip_all)]
+pub(crate) async fn record_login_attempt(state: &AppState, entry: LoginHistoryEntry) {
+    if let Err(e) = state
+        .login_history_store
+        .write()
+        .await
+        .add_entry(entry)
+        .await
+    {
+        tracing::error!("Failed to record login attempt: {:?}", e);
     }
 }
 
 #[tracing::instrument(name = "Handle 2FA", skip_all)]
-async fn handle_2fa(
+pub(crate) async fn handle_2fa(
     email: Email,
+    first_factor: FirstFactor,
     state: &AppState,
     jar: CookieJar,
 ) -> (
@@ -86,7 +209,12 @@ async fn handle_2fa(
         .two_fa_code_store
         .write()
         .await
-        .add_code(email.clone(), login_attempt_id.clone(), two_fa_code.clone())
+        .add_code(
+            email.clone(),
+            login_attempt_id.clone(),
+            two_fa_code.clone(),
+            first_factor,
+        )
         .await
         .is_err()
     {
@@ -104,6 +232,24 @@ async fn handle_2fa(
     {
         return (jar, Err(AuthAPIError::UnexpectedError(e)));
     }
+
+    METRICS
+        .login_attempts
+        .with_label_values(&[labels::LOGIN_TWO_FA_REQUIRED])
+        .inc();
+    METRICS
+        .two_fa_codes
+        .with_label_values(&[labels::TWO_FA_CODE_SENT])
+        .inc();
+
+    record_audit_event(
+        state,
+        AuditEvent::TwoFACodeSent {
+            email: email.as_ref().expose_secret().to_owned(),
+        },
+    )
+    .await;
+
     let two_fa_response = TwoFactorAuthResponse {
         message: "2FA required".to_string(),
         login_attempt_id: login_attempt_id.as_ref().to_string(),
@@ -120,17 +266,45 @@ async fn handle_2fa(
 #[tracing::instrument(name = "Handle NO 2FA", skip_all)]
 async fn handle_no_2fa(
     email: &Email,
+    state: &AppState,
+    client: &ClientInfo,
     jar: CookieJar,
+    response_mode: LoginResponseMode,
 ) -> (
     CookieJar,
     Result<(StatusCode, Json<LoginResponse>), AuthAPIError>,
 ) {
-    let auth_cookie = match generate_auth_cookie(email) {
+    if response_mode == LoginResponseMode::Token {
+        let token =
+            match generate_auth_token(email, AuthenticationMethod::Password, &state.settings.jwt) {
+                Ok(token) => token,
+                Err(e) => return (jar, Err(AuthAPIError::UnexpectedError(e))),
+            };
+
+        record_login_success(state, email, client, &token, false).await;
+
+        return (
+            jar,
+            Ok((
+                StatusCode::OK,
+                Json(LoginResponse::Token(AuthTokenResponse::new(token))),
+            )),
+        );
+    }
+
+    let auth_cookie = match generate_auth_cookie(
+        email,
+        AuthenticationMethod::Password,
+        &state.settings.jwt,
+        &state.settings.cookie,
+    ) {
         Ok(cookie) => cookie,
         Err(e) => return (jar, Err(AuthAPIError::UnexpectedError(e))), // Updated!
     };
 
-    let updated_jar = jar.add(auth_cookie);
+    record_login_success(state, email, client, auth_cookie.value(), false).await;
+
+    let updated_jar = add_session_cookies(jar, auth_cookie, &state.settings);
 
     (
         updated_jar,
@@ -145,6 +319,7 @@ async fn handle_no_2fa(
 pub enum LoginResponse {
     RegularAuth,
     TwoFactorAuth(TwoFactorAuthResponse),
+    Token(AuthTokenResponse),
 }
 
 // If a user requires 2FA, this JSON body should be returned!
@@ -155,8 +330,42 @@ pub struct TwoFactorAuthResponse {
     pub login_attempt_id: String,
 }
 
+// Returned instead of the auth cookie when a client logs in with `"responseMode": "token"`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuthTokenResponse {
+    #[serde(rename = "accessToken")]
+    pub access_token: String,
+    #[serde(rename = "tokenType")]
+    pub token_type: String,
+    // Seconds until the token expires
+    #[serde(rename = "expiresIn")]
+    pub expires_in: i64,
+}
+
+impl AuthTokenResponse {
+    pub fn new(access_token: String) -> Self {
+        Self {
+            access_token,
+            token_type: "Bearer".to_owned(),
+            expires_in: TOKEN_TTL_SECONDS,
+        }
+    }
+}
+
+// How a completed login hands over the auth token. Browsers use the cookie; other clients
+// ask for the token in the response body and send it back as a bearer token.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoginResponseMode {
+    #[default]
+    Cookie,
+    Token,
+}
+
 #[derive(Deserialize)]
 pub struct LoginRequest {
     pub email: String,
     pub password: Secret<String>,
+    #[serde(rename = "responseMode", default)]
+    pub response_mode: LoginResponseMode,
 }
@@ -0,0 +1,101 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, LoginHistoryEntry},
+    utils::{
+        constants::{LOGIN_HISTORY_DEFAULT_PAGE_SIZE, LOGIN_HISTORY_MAX_PAGE_SIZE},
+        AuthenticatedUser,
+    },
+};
+
+#[tracing::instrument(name = "Login History", skip_all)]
+pub async fn login_history(
+    State(state): State<AppState>,
+    AuthenticatedUser { email, .. }: AuthenticatedUser,
+    Query(params): Query<LoginHistoryParams>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    // Pages are 1-based and page sizes are capped to keep queries cheap
+    let page = params.page.unwrap_or(1);
+    let per_page = params.per_page.unwrap_or(LOGIN_HISTORY_DEFAULT_PAGE_SIZE);
+
+    if page == 0 || per_page == 0 || per_page > LOGIN_HISTORY_MAX_PAGE_SIZE {
+        return Err(AuthAPIError::InvalidPagination);
+    }
+
+    let offset = (page - 1)
+        .checked_mul(per_page)
+        .ok_or(AuthAPIError::InvalidPagination)?;
+
+    let login_history_store = state.login_history_store.read().await;
+
+    let total = login_history_store
+        .count_entries(&email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let entries = login_history_store
+        .get_entries(&email, per_page, offset)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let response = LoginHistoryResponse {
+        entries: entries.iter().map(LoginHistoryItem::from).collect(),
+        page,
+        per_page,
+        total,
+    };
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginHistoryParams {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginHistoryResponse {
+    pub entries: Vec<LoginHistoryItem>,
+    pub page: u32,
+    pub per_page: u32,
+    pub total: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginHistoryItem {
+    pub occurred_at: DateTime<Utc>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub success: bool,
+    pub failure_reason: Option<String>,
+    #[serde(rename = "used2FA")]
+    pub used_2fa: bool,
+}
+
+impl From<&LoginHistoryEntry> for LoginHistoryItem {
+    fn from(entry: &LoginHistoryEntry) -> Self {
+        Self {
+            occurred_at: entry.occurred_at,
+            ip_address: entry.ip_address.clone(),
+            user_agent: entry.user_agent.clone(),
+            success: entry.outcome.is_success(),
+            failure_reason: entry
+                .outcome
+                .failure_reason()
+                .map(|reason| reason.as_str().to_owned()),
+            used_2fa: entry.used_2fa,
+        }
+    }
+}
@@ -1,34 +1,29 @@
 use axum::{extract::State, http::StatusCode, response::IntoResponse};
-use axum_extra::extract::{cookie::Cookie, CookieJar};
-use secrecy::Secret;
+use axum_extra::extract::CookieJar;
+use secrecy::{ExposeSecret, Secret};
 
 use crate::{
     app_state::AppState,
-    domain::AuthAPIError,
-    utils::{auth::validate_token, constants::JWT_COOKIE_NAME},
+    domain::{AuditEvent, AuthAPIError, TokenBanReason},
+    services::record_audit_event,
+    utils::{
+        auth::removal_auth_cookie, removal_csrf_cookie, AuthToken, AuthTokenSource,
+        AuthenticatedUser, METRICS,
+    },
 };
 
+// Missing and invalid tokens are rejected by `require_authentication`, and cookie sessions
+// without their CSRF token by `require_csrf_token`
 #[tracing::instrument(name = "Logout", skip_all)]
 pub async fn logout(
     State(state): State<AppState>,
     jar: CookieJar,
+    AuthenticatedUser {
+        email,
+        session: AuthToken { token, source },
+        ..
+    }: AuthenticatedUser,
 ) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
-    // Retrieve JWT cookie from the `CookieJar`
-    // Return AuthAPIError::MissingToken is the cookie is not found
-    let cookie = match jar.get(JWT_COOKIE_NAME) {
-        Some(cookie) => cookie,
-        None => return (jar, Err(AuthAPIError::MissingToken)),
-    };
-
-    let token = cookie.value().to_owned();
-
-    // If the token is valid you can ignore the returned claims for now.
-    // Return AuthAPIError::InvalidToken is validation fails.
-    let _ = match validate_token(&token, &state.banned_token_store).await {
-        Ok(claims) => claims,
-        Err(_) => return (jar, Err(AuthAPIError::InvalidToken)),
-    };
-
     if let Err(e) = state
         .banned_token_store
         .write()
@@ -38,9 +33,30 @@ pub async fn logout(
     {
         return (jar, Err(AuthAPIError::UnexpectedError(e.into())));
     }
+    METRICS
+        .tokens_banned
+        .with_label_values(&[TokenBanReason::Logout.as_str()])
+        .inc();
+
+    let email = email.as_ref().expose_secret().to_owned();
+    record_audit_event(
+        &state,
+        AuditEvent::TokenBanned {
+            email: email.clone(),
+            reason: TokenBanReason::Logout,
+        },
+    )
+    .await;
+    record_audit_event(&state, AuditEvent::LoggedOut { email }).await;
 
-    //Remove jwt cookie from jar
-    let updated_jar = jar.remove(Cookie::from(JWT_COOKIE_NAME));
+    // Clear the jwt and CSRF cookies with the attributes they were set with. Bearer clients
+    // have no cookies.
+    let updated_jar = match source {
+        AuthTokenSource::Cookie => jar
+            .remove(removal_auth_cookie(&state.settings.cookie))
+            .remove(removal_csrf_cookie(&state.settings)),
+        AuthTokenSource::AuthorizationHeader => jar,
+    };
 
     (updated_jar, Ok(StatusCode::OK))
 }
@@ -0,0 +1,271 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum_extra::extract::CookieJar;
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tracing::Instrument;
+
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuditEvent, AuthAPIError, ClientInfo, Email, FirstFactor, MagicLinkStoreError,
+        MagicLinkToken, UserStoreError, MAGIC_LINK_TTL_SECONDS,
+    },
+    routes::{handle_2fa, record_login_success, LoginResponse},
+    services::record_audit_event,
+    utils::{
+        add_session_cookies,
+        constants::{MAGIC_LINK_MAX_REQUESTS, MAGIC_LINK_WINDOW_SECONDS},
+        generate_auth_cookie, AuthenticationMethod, JwtSettings,
+    },
+};
+
+// Emails a single-use login link. The answer is the same whether or not the address has an
+// account, and comes as quickly, so the route cannot be used to find out who has one.
+#[tracing::instrument(name = "Magic Link", skip_all)]
+pub async fn magic_link(
+    State(state): State<AppState>,
+    Json(request): Json<MagicLinkRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let email =
+        Email::parse(Secret::new(request.email)).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    // Counted for every address, so the limit does not give away which ones have accounts
+    let hits = state
+        .rate_limit_store
+        .write()
+        .await
+        .record_hit(&rate_limit_key(&email), MAGIC_LINK_WINDOW_SECONDS)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    if hits > MAGIC_LINK_MAX_REQUESTS {
+        return Err(AuthAPIError::TooManyRequests);
+    }
+
+    match state.user_store.read().await.get_user(&email).await {
+        // Sent after answering, since waiting on the email provider would give the account away
+        Ok(_) => {
+            let state = state.clone();
+            tokio::spawn(
+                async move {
+                    if let Err(e) = send_magic_link(&state, &email).await {
+                        tracing::error!("Failed to send magic link: {:?}", e);
+                    }
+                }
+                .instrument(tracing::Span::current()),
+            );
+        }
+        Err(UserStoreError::UserNotFound) => {}
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+
+    Ok(Json(MagicLinkResponse {
+        message: "If the address has an account, a login link is on its way".to_owned(),
+    }))
+}
+
+async fn send_magic_link(state: &AppState, email: &Email) -> Result<(), AuthAPIError> {
+    let token = MagicLinkToken::default();
+    let link = magic_link_url(
+        &state.settings.application.public_url,
+        &sign_link_token(&token, &state.settings.jwt),
+        email,
+    );
+
+    state
+        .magic_link_store
+        .write()
+        .await
+        .add_link(token, email.clone())
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .email_client
+        .read()
+        .await
+        .send_email(
+            email,
+            MAGIC_LINK_EMAIL_SUBJECT,
+            &magic_link_email_content(&link),
+        )
+        .await
+        .map_err(AuthAPIError::UnexpectedError)?;
+
+    record_audit_event(
+        state,
+        AuditEvent::MagicLinkSent {
+            email: email.as_ref().expose_secret().to_owned(),
+        },
+    )
+    .await;
+
+    Ok(())
+}
+
+// Logs in with the token from an emailed link, which is spent whatever happens next. Answers
+// like `/login`: users with 2FA get a code by email and a login attempt ID to verify it with,
+// everyone else gets the auth cookie. A pending password reset does not block these logins,
+// since the link proves the user holds the mailbox and no password is involved.
+#[tracing::instrument(name = "Magic Link Callback", skip_all)]
+pub async fn magic_link_callback(
+    State(state): State<AppState>,
+    client: ClientInfo,
+    jar: CookieJar,
+    Json(request): Json<MagicLinkCallbackRequest>,
+) -> (
+    CookieJar,
+    Result<(StatusCode, Json<LoginResponse>), AuthAPIError>,
+) {
+    let user = match take_magic_link(&state, request.token.expose_secret()).await {
+        Ok(email) => match state.user_store.read().await.get_user(&email).await {
+            Ok(user) => user,
+            Err(UserStoreError::UserNotFound) => return (jar, Err(AuthAPIError::InvalidMagicLink)),
+            Err(e) => return (jar, Err(AuthAPIError::UnexpectedError(e.into()))),
+        },
+        Err(e) => return (jar, Err(e)),
+    };
+
+    if user.requires_2fa {
+        return handle_2fa(user.email, FirstFactor::MagicLink, &state, jar).await;
+    }
+
+    let auth_cookie = match generate_auth_cookie(
+        &user.email,
+        AuthenticationMethod::MagicLink,
+        &state.settings.jwt,
+        &state.settings.cookie,
+    ) {
+        Ok(cookie) => cookie,
+        Err(e) => return (jar, Err(AuthAPIError::UnexpectedError(e))),
+    };
+
+    record_login_success(&state, &user.email, &client, auth_cookie.value(), false).await;
+
+    let jar = add_session_cookies(jar, auth_cookie, &state.settings);
+    (jar, Ok((StatusCode::OK, Json(LoginResponse::RegularAuth))))
+}
+
+// The address a link was sent to, provided the link is ours and has not been used or expired
+async fn take_magic_link(state: &AppState, signed_token: &str) -> Result<Email, AuthAPIError> {
+    let token = verify_link_token(signed_token, &state.settings.jwt)
+        .ok_or(AuthAPIError::InvalidMagicLink)?;
+
+    match state.magic_link_store.write().await.take_link(&token).await {
+        Ok(email) => Ok(email),
+        Err(MagicLinkStoreError::LinkNotFound) => Err(AuthAPIError::InvalidMagicLink),
+        Err(e) => Err(AuthAPIError::UnexpectedError(e.into())),
+    }
+}
+
+const MAGIC_LINK_EMAIL_SUBJECT: &str = "Your login link";
+const RATE_LIMIT_KEY_PREFIX: &str = "magic_link:";
+
+fn rate_limit_key(email: &Email) -> String {
+    format!(
+        "{}{}",
+        RATE_LIMIT_KEY_PREFIX,
+        email.as_ref().expose_secret()
+    )
+}
+
+// The login page picks the token up and posts it to `/login/magic-link/callback`. Keeping it in
+// the fragment keeps it out of server logs and away from mail scanners that open every link.
+// The address is there so the page can ask for a 2FA code.
+fn magic_link_url(public_url: &str, signed_token: &str, email: &Email) -> String {
+    let fragment = form_urlencoded::Serializer::new(String::new())
+        .append_pair("magic_link", signed_token)
+        .append_pair("email", email.as_ref().expose_secret())
+        .finish();
+    format!("{}/#{}", public_url.trim_end_matches('/'), fragment)
+}
+
+fn magic_link_email_content(link: &str) -> String {
+    format!(
+        "Use the link below to log in. It works once and expires in {} minutes.\n\n\
+         {}\n\n\
+         If you didn't ask for this link, you can ignore this email.",
+        MAGIC_LINK_TTL_SECONDS / 60,
+        link
+    )
+}
+
+// Links carry the token with an HMAC of it, so only links we sent are looked up at all
+fn sign_link_token(token: &MagicLinkToken, jwt: &JwtSettings) -> String {
+    let signature = link_mac(token.as_ref(), jwt).finalize().into_bytes();
+    format!("{}.{}", token.as_ref(), hex::encode(signature))
+}
+
+// Compares in constant time
+fn verify_link_token(signed_token: &str, jwt: &JwtSettings) -> Option<MagicLinkToken> {
+    let (token, signature) = signed_token.split_once('.')?;
+    let token = MagicLinkToken::parse(token.to_owned()).ok()?;
+    let signature = hex::decode(signature).ok()?;
+    link_mac(token.as_ref(), jwt)
+        .verify_slice(&signature)
+        .ok()
+        .map(|_| token)
+}
+
+fn link_mac(token: &str, jwt: &JwtSettings) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(jwt.secret.expose_secret().as_bytes())
+        .expect("HMAC can take a key of any size");
+    // Keeps these MACs distinct from anything else keyed with the JWT secret
+    mac.update(b"magic-link.");
+    mac.update(token.as_bytes());
+    mac
+}
+
+#[derive(Deserialize)]
+pub struct MagicLinkRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MagicLinkResponse {
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct MagicLinkCallbackRequest {
+    pub token: Secret<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jwt_settings(secret: &str) -> JwtSettings {
+        JwtSettings {
+            secret: Secret::new(secret.to_owned()),
+        }
+    }
+
+    #[test]
+    fn only_accepts_links_signed_with_our_secret() {
+        let jwt = jwt_settings("test-jwt-secret");
+        let token = MagicLinkToken::default();
+        let signed = sign_link_token(&token, &jwt);
+        assert_eq!(verify_link_token(&signed, &jwt), Some(token.clone()));
+
+        let other = sign_link_token(&token, &jwt_settings("another-secret"));
+        assert_eq!(verify_link_token(&other, &jwt), None);
+
+        let (_, signature) = signed.split_once('.').unwrap();
+        let forged = format!("{}.{}", MagicLinkToken::default().as_ref(), signature);
+        assert_eq!(verify_link_token(&forged, &jwt), None);
+
+        assert_eq!(verify_link_token(token.as_ref(), &jwt), None);
+        assert_eq!(verify_link_token("", &jwt), None);
+    }
+
+    #[test]
+    fn links_point_at_the_login_page() {
+        let email = Email::parse(Secret::new("user+tag@example.com".to_owned())).unwrap();
+        assert_eq!(
+            magic_link_url("https://auth.example.com/", "abc.123", &email),
+            "https://auth.example.com/#magic_link=abc.123&email=user%2Btag%40example.com"
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use axum::{
+    extract::State,
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::IntoResponse,
+};
+
+use crate::{app_state::AppState, domain::AuthAPIError, utils::METRICS};
+
+// Prometheus scrape target
+#[tracing::instrument(name = "Metrics", skip_all)]
+pub async fn metrics(State(state): State<AppState>) -> Result<impl IntoResponse, AuthAPIError> {
+    METRICS.record_pool_stats(&state.pg_pool, &state.redis_conn);
+
+    let body = METRICS.encode().map_err(AuthAPIError::UnexpectedError)?;
+
+    Ok((
+        StatusCode::OK,
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        body,
+    ))
+}
@@ -1,13 +1,49 @@
+mod account_export;
+mod audit_log;
+mod device;
+mod external_login;
+mod health;
+mod introspect;
 mod login;
+mod login_history;
 mod logout;
+mod magic_link;
+mod metrics;
+mod oauth;
+mod oauth_clients;
+mod oidc;
+mod report_login;
+mod reset_password;
+mod revoke;
+mod service_clients;
 mod signup;
+mod token_exchange;
 mod verify_2fa;
 mod verify_token;
+mod webhooks;
 
 //re-expoort items from the submodules
 
+pub use account_export::*;
+pub use audit_log::*;
+pub use device::*;
+pub use external_login::*;
+pub use health::*;
+pub use introspect::*;
 pub use login::*;
+pub use login_history::*;
 pub use logout::*;
+pub use magic_link::*;
+pub use metrics::*;
+pub use oauth::*;
+pub use oauth_clients::*;
+pub use oidc::*;
+pub use report_login::*;
+pub use reset_password::*;
+pub use revoke::*;
+pub use service_clients::*;
 pub use signup::*;
+pub use token_exchange::*;
 pub use verify_2fa::*;
 pub use verify_token::*;
+pub use webhooks::*;
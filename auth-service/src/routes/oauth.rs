@@ -0,0 +1,776 @@
+use axum::{
+    extract::{Query, State},
+    http::{
+        header::{AUTHORIZATION, CACHE_CONTROL, PRAGMA, X_FRAME_OPTIONS},
+        HeaderMap,
+    },
+    response::{Html, IntoResponse, Redirect, Response},
+    Form, Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use color_eyre::eyre::{eyre, Result};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    device::{device_code_grant, DEVICE_CODE_GRANT_TYPE},
+    token_exchange::{token_exchange_grant, TOKEN_EXCHANGE_GRANT_TYPE},
+};
+use crate::{
+    app_state::AppState,
+    domain::{
+        is_valid_redirect_uri, parse_scope, verify_pkce, AuditEvent, AuthAPIError,
+        AuthorizationCode, AuthorizationCodeStoreError, AuthorizationGrant, Email, OAuthClient,
+        OAuthClientStoreError, OAuthError, OAuthErrorCode, ServiceClient, ServiceClientStoreError,
+        OPENID_SCOPE,
+    },
+    services::record_audit_event,
+    utils::{
+        csrf_token, generate_access_token, generate_client_token, oidc_issuer, verify_csrf_token,
+        AuthTokenSource, AuthenticatedUser, IdTokenClaims, CLIENT_TOKEN_TTL_SECONDS,
+        TOKEN_TTL_SECONDS,
+    },
+};
+
+// Starts an authorization code flow (RFC 6749 section 4.1). Users who are not logged in, or
+// whose login is older than `prompt`/`max_age` allow, are sent to the login page first, which
+// brings them back here afterwards.
+#[tracing::instrument(name = "Authorize", skip_all)]
+pub async fn authorize(
+    State(state): State<AppState>,
+    user: Result<AuthenticatedUser, AuthAPIError>,
+    Query(request): Query<AuthorizationRequest>,
+) -> Response {
+    let authorization = match validate_authorization_request(&state, &request).await {
+        Ok(authorization) => authorization,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    // A session still waiting for its 2FA code is no login at all
+    let user = match user {
+        Ok(user) if !authorization.requires_login(&user) => {
+            match user.completed_login(&state).await {
+                Ok(true) => Some(user),
+                Ok(false) => None,
+                Err(e) => return e.into_response(),
+            }
+        }
+        _ => None,
+    };
+    let Some(user) = user else {
+        if authorization.prompt_none {
+            return authorization
+                .error_redirect(OAuthErrorCode::LoginRequired)
+                .into_response();
+        }
+        return login_redirect(&request).into_response();
+    };
+
+    // Consent is asked for on every authorization, so it can never be given silently
+    if authorization.prompt_none {
+        return authorization
+            .error_redirect(OAuthErrorCode::ConsentRequired)
+            .into_response();
+    }
+
+    let csrf_token = csrf_token(&user.session.token, &state.settings.jwt);
+    (
+        // The consent page must not be framed, or another site could trick users into approving
+        [(X_FRAME_OPTIONS, "DENY")],
+        Html(consent_page(&authorization, &request, &csrf_token)),
+    )
+        .into_response()
+}
+
+// The consent page's form. The session's CSRF token travels in the form, since a plain form
+// post cannot set the `X-CSRF-Token` header.
+#[tracing::instrument(name = "Authorize Consent", skip_all)]
+pub async fn authorize_consent(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Form(consent): Form<ConsentForm>,
+) -> Result<Response, AuthAPIError> {
+    if user.session.source == AuthTokenSource::Cookie
+        && !verify_csrf_token(
+            &user.session.token,
+            &consent.csrf_token,
+            &state.settings.jwt,
+        )
+    {
+        return Err(AuthAPIError::InvalidCsrfToken);
+    }
+    if !user.completed_login(&state).await? {
+        return Err(AuthAPIError::TwoFactorRequired);
+    }
+
+    let authorization = match validate_authorization_request(&state, &consent.request).await {
+        Ok(authorization) => authorization,
+        Err(rejection) => return Ok(rejection.into_response()),
+    };
+
+    if consent.decision != ConsentDecision::Allow {
+        return Ok(authorization
+            .error_redirect(OAuthErrorCode::AccessDenied)
+            .into_response());
+    }
+
+    let code = AuthorizationCode::default();
+    let grant = AuthorizationGrant {
+        client_id: authorization.client.client_id.clone(),
+        redirect_uri: authorization.redirect_uri.clone(),
+        scopes: authorization.scopes.clone(),
+        email: user.email.clone(),
+        code_challenge: authorization.code_challenge.clone(),
+        nonce: authorization.nonce.clone(),
+        auth_time: user.claims.auth_time,
+        amr: user.claims.amr.clone(),
+        sid: user.claims.sid.clone(),
+    };
+    let scope = grant.scope();
+
+    state
+        .authorization_code_store
+        .write()
+        .await
+        .add_code(code.clone(), grant)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    record_audit_event(
+        &state,
+        AuditEvent::OAuthConsentGranted {
+            email: user.email.as_ref().expose_secret().to_owned(),
+            client_id: authorization.client.client_id.clone(),
+            scope,
+        },
+    )
+    .await;
+
+    Ok(authorization
+        .redirect(&[("code", code.as_ref())])
+        .into_response())
+}
+
+// Issues access tokens: for an authorization code (RFC 6749 section 4.1.3) or an approved device
+// (RFC 8628 section 3.4), plus an ID token when the `openid` scope was granted, to a service
+// client for itself (RFC 6749 section 4.4), or to a service client for a user (RFC 8693)
+#[tracing::instrument(name = "Token", skip_all)]
+pub async fn token(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(request): Form<TokenRequest>,
+) -> Result<Response, OAuthError> {
+    match request.grant_type.as_deref() {
+        Some("authorization_code") => authorization_code_grant(&state, request).await,
+        Some("client_credentials") => client_credentials_grant(&state, &headers, request).await,
+        Some(DEVICE_CODE_GRANT_TYPE) => device_code_grant(&state, request).await,
+        Some(TOKEN_EXCHANGE_GRANT_TYPE) => token_exchange_grant(&state, &headers, request).await,
+        Some(_) => Err(OAuthError::new(
+            OAuthErrorCode::UnsupportedGrantType,
+            "Unsupported grant_type",
+        )),
+        None => Err(OAuthError::new(
+            OAuthErrorCode::InvalidRequest,
+            "Missing grant_type",
+        )),
+    }
+}
+
+async fn authorization_code_grant(
+    state: &AppState,
+    request: TokenRequest,
+) -> Result<Response, OAuthError> {
+    let (Some(code), Some(redirect_uri), Some(client_id), Some(code_verifier)) = (
+        request.code,
+        request.redirect_uri,
+        request.client_id,
+        request.code_verifier,
+    ) else {
+        return Err(OAuthError::new(
+            OAuthErrorCode::InvalidRequest,
+            "code, redirect_uri, client_id and code_verifier are required",
+        ));
+    };
+
+    let invalid_grant = || {
+        OAuthError::new(
+            OAuthErrorCode::InvalidGrant,
+            "The authorization code is invalid, expired or was already used",
+        )
+    };
+
+    let code = AuthorizationCode::parse(code).map_err(|_| invalid_grant())?;
+    // Taking the code up front means a failed attempt burns it, so it cannot be retried
+    let grant = state
+        .authorization_code_store
+        .write()
+        .await
+        .take_code(&code)
+        .await
+        .map_err(|e| match e {
+            AuthorizationCodeStoreError::CodeNotFound => invalid_grant(),
+            e => OAuthError::new(OAuthErrorCode::ServerError, e.to_string()),
+        })?;
+
+    if grant.client_id != client_id
+        || grant.redirect_uri != redirect_uri
+        || !verify_pkce(&code_verifier, &grant.code_challenge)
+    {
+        return Err(invalid_grant());
+    }
+
+    let scope = grant.scope();
+    let access_token = generate_access_token(
+        &grant.email,
+        &grant.client_id,
+        &scope,
+        grant.sid.as_deref(),
+        &state.settings.jwt,
+    )
+    .map_err(|e| OAuthError::new(OAuthErrorCode::ServerError, e.to_string()))?;
+    let id_token = if grant.is_openid() {
+        Some(
+            generate_id_token(
+                state,
+                &grant.client_id,
+                &grant.email,
+                grant.auth_time,
+                grant.nonce.clone(),
+                grant.amr.clone(),
+            )
+            .map_err(|e| OAuthError::new(OAuthErrorCode::ServerError, e.to_string()))?,
+        )
+    } else {
+        None
+    };
+
+    Ok(token_response(AccessTokenResponse {
+        access_token,
+        token_type: "Bearer".to_owned(),
+        expires_in: TOKEN_TTL_SECONDS,
+        scope,
+        id_token,
+    }))
+}
+
+// Service clients get tokens for themselves, limited to the scopes they were registered with.
+// Without a `scope` parameter the token carries all of them.
+async fn client_credentials_grant(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: TokenRequest,
+) -> Result<Response, OAuthError> {
+    let client = authenticate_service_client(
+        state,
+        headers,
+        request.client_id.as_deref(),
+        request.client_secret.as_deref(),
+    )
+    .await?;
+
+    let scopes = match request.scope.as_deref() {
+        Some(scope) => parse_scope(scope)
+            .ok()
+            .filter(|scopes| !scopes.is_empty() && client.allows_scopes(scopes))
+            .ok_or_else(|| {
+                OAuthError::new(
+                    OAuthErrorCode::InvalidScope,
+                    "The requested scope is invalid or not allowed for this client",
+                )
+            })?,
+        None => client.scopes.clone(),
+    };
+
+    let scope = scopes.join(" ");
+    let access_token = generate_client_token(&client.client_id, &scope, &state.settings.jwt)
+        .map_err(|e| OAuthError::new(OAuthErrorCode::ServerError, e.to_string()))?;
+
+    Ok(token_response(AccessTokenResponse {
+        access_token,
+        token_type: "Bearer".to_owned(),
+        expires_in: CLIENT_TOKEN_TTL_SECONDS,
+        scope,
+        id_token: None,
+    }))
+}
+
+// Token responses must not be cached (RFC 6749 section 5.1)
+pub(crate) fn token_response(response: AccessTokenResponse) -> Response {
+    (
+        [(CACHE_CONTROL, "no-store"), (PRAGMA, "no-cache")],
+        Json(response),
+    )
+        .into_response()
+}
+
+// Authenticates a service client with HTTP Basic, or failing that with `client_id` and
+// `client_secret` in the form body (RFC 6749 section 2.3.1). Using both at once is an error.
+pub(crate) async fn authenticate_service_client(
+    state: &AppState,
+    headers: &HeaderMap,
+    client_id: Option<&str>,
+    client_secret: Option<&str>,
+) -> Result<ServiceClient, OAuthError> {
+    let invalid_client = || {
+        OAuthError::new(
+            OAuthErrorCode::InvalidClient,
+            "Client authentication failed",
+        )
+    };
+
+    let (client_id, client_secret) = match headers.get(AUTHORIZATION) {
+        Some(_) if client_secret.is_some() => {
+            return Err(OAuthError::new(
+                OAuthErrorCode::InvalidRequest,
+                "Use only one client authentication method",
+            ))
+        }
+        Some(header) => header
+            .to_str()
+            .ok()
+            .and_then(basic_credentials)
+            .ok_or_else(invalid_client)?,
+        None => match (client_id, client_secret) {
+            (Some(client_id), Some(client_secret)) => {
+                (client_id.to_owned(), client_secret.to_owned())
+            }
+            _ => return Err(invalid_client()),
+        },
+    };
+
+    let client = state
+        .service_client_store
+        .read()
+        .await
+        .get_client(&client_id)
+        .await
+        .map_err(|e| match e {
+            ServiceClientStoreError::ClientNotFound => invalid_client(),
+            e => OAuthError::new(OAuthErrorCode::ServerError, e.to_string()),
+        })?;
+
+    if !client.verify_secret(&client_secret) {
+        return Err(invalid_client());
+    }
+
+    Ok(client)
+}
+
+// The client ID and secret are form-urlencoded before they are joined and base64 encoded
+fn basic_credentials(header: &str) -> Option<(String, String)> {
+    let (scheme, credentials) = header.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let credentials = String::from_utf8(STANDARD.decode(credentials.trim()).ok()?).ok()?;
+    let (client_id, client_secret) = credentials.split_once(':')?;
+    let decode = |value: &str| {
+        percent_encoding::percent_decode_str(&value.replace('+', " "))
+            .decode_utf8()
+            .ok()
+            .map(|value| value.into_owned())
+    };
+
+    Some((decode(client_id)?, decode(client_secret)?))
+}
+
+pub(crate) fn generate_id_token(
+    state: &AppState,
+    client_id: &str,
+    email: &Email,
+    auth_time: Option<usize>,
+    nonce: Option<String>,
+    amr: Vec<String>,
+) -> Result<String> {
+    let signer = state
+        .id_token_signer
+        .as_ref()
+        .ok_or_else(|| eyre!("no ID token signing key is configured"))?;
+    let now: usize = Utc::now().timestamp().try_into()?;
+
+    signer.sign(&IdTokenClaims {
+        iss: oidc_issuer(&state.settings),
+        sub: email.as_ref().expose_secret().to_owned(),
+        aud: client_id.to_owned(),
+        exp: now + TOKEN_TTL_SECONDS as usize,
+        iat: now,
+        auth_time,
+        nonce,
+        amr,
+    })
+}
+
+// Sends the user to log in, then back to the same request. `prompt` and `max_age` are left off
+// the way back, since the login they ask for is the one about to happen.
+fn login_redirect(request: &AuthorizationRequest) -> Redirect {
+    let mut params = form_urlencoded::Serializer::new(String::new());
+    for (name, value) in request.params() {
+        if let (false, Some(value)) = (matches!(name, "prompt" | "max_age"), value) {
+            params.append_pair(name, value);
+        }
+    }
+    let query = form_urlencoded::Serializer::new(String::new())
+        .append_pair("return_to", &format!("/authorize?{}", params.finish()))
+        .finish();
+
+    Redirect::to(&format!("/?{}", query))
+}
+
+// A request that passed every check `/authorize` makes before asking for consent
+struct ValidatedAuthorization {
+    client: OAuthClient,
+    redirect_uri: String,
+    scopes: Vec<String>,
+    state: Option<String>,
+    code_challenge: String,
+    nonce: Option<String>,
+    // `prompt=none`: fail rather than show the login or consent page
+    prompt_none: bool,
+    // `prompt=login` or `prompt=select_account`
+    force_login: bool,
+    // The most seconds since the user logged in that the client accepts
+    max_age: Option<usize>,
+}
+
+impl ValidatedAuthorization {
+    // Whether the user has to log in again before this request can be answered. Elapsed time
+    // is rounded down to whole seconds, so reaching `max_age` already counts as past it.
+    fn requires_login(&self, user: &AuthenticatedUser) -> bool {
+        let now = Utc::now().timestamp() as usize;
+        self.force_login
+            || self.max_age.is_some_and(|max_age| {
+                user.claims
+                    .auth_time
+                    .is_none_or(|auth_time| now.saturating_sub(auth_time) >= max_age)
+            })
+    }
+
+    // Sends the user back to the client with `params` and the client's `state`
+    fn redirect(&self, params: &[(&str, &str)]) -> Redirect {
+        let mut location = reqwest::Url::parse(&self.redirect_uri)
+            .expect("redirect URIs are validated before redirecting to them");
+        {
+            let mut query = location.query_pairs_mut();
+            for (name, value) in params {
+                query.append_pair(name, value);
+            }
+            if let Some(state) = &self.state {
+                query.append_pair("state", state);
+            }
+        }
+        Redirect::to(location.as_str())
+    }
+
+    fn error_redirect(&self, code: OAuthErrorCode) -> Redirect {
+        self.redirect(&[("error", code.as_str())])
+    }
+}
+
+// Why an authorization request was refused. Until the client and redirect URI check out, the
+// user must not be redirected anywhere (RFC 6749 section 4.1.2.1).
+enum AuthorizationRejection {
+    Invalid(AuthAPIError),
+    Redirect(Redirect),
+}
+
+impl IntoResponse for AuthorizationRejection {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Invalid(e) => e.into_response(),
+            Self::Redirect(redirect) => redirect.into_response(),
+        }
+    }
+}
+
+async fn validate_authorization_request(
+    state: &AppState,
+    request: &AuthorizationRequest,
+) -> Result<ValidatedAuthorization, AuthorizationRejection> {
+    let client_id = request
+        .client_id
+        .as_deref()
+        .ok_or(AuthorizationRejection::Invalid(
+            AuthAPIError::InvalidOAuthClient,
+        ))?;
+    let client = state
+        .oauth_client_store
+        .read()
+        .await
+        .get_client(client_id)
+        .await
+        .map_err(|e| {
+            AuthorizationRejection::Invalid(match e {
+                OAuthClientStoreError::ClientNotFound => AuthAPIError::InvalidOAuthClient,
+                e => AuthAPIError::UnexpectedError(e.into()),
+            })
+        })?;
+
+    let redirect_uri = request
+        .redirect_uri
+        .clone()
+        .filter(|redirect_uri| {
+            client.allows_redirect_uri(redirect_uri) && is_valid_redirect_uri(redirect_uri)
+        })
+        .ok_or(AuthorizationRejection::Invalid(
+            AuthAPIError::InvalidRedirectUri,
+        ))?;
+
+    let mut authorization = ValidatedAuthorization {
+        client,
+        redirect_uri,
+        scopes: Vec::new(),
+        state: request.state.clone(),
+        code_challenge: String::new(),
+        nonce: request.nonce.clone(),
+        prompt_none: false,
+        force_login: false,
+        max_age: None,
+    };
+    let reject = |authorization: &ValidatedAuthorization, code| {
+        AuthorizationRejection::Redirect(authorization.error_redirect(code))
+    };
+
+    if request.response_type.as_deref() != Some("code") {
+        return Err(reject(
+            &authorization,
+            OAuthErrorCode::UnsupportedResponseType,
+        ));
+    }
+
+    // PKCE is required, and only with S256 since `plain` offers no protection
+    match (
+        request.code_challenge.as_deref(),
+        request.code_challenge_method.as_deref(),
+    ) {
+        (Some(code_challenge), Some("S256")) if !code_challenge.is_empty() => {
+            authorization.code_challenge = code_challenge.to_owned();
+        }
+        _ => return Err(reject(&authorization, OAuthErrorCode::InvalidRequest)),
+    }
+
+    match parse_scope(request.scope.as_deref().unwrap_or_default()) {
+        Ok(scopes) if !scopes.is_empty() && authorization.client.allows_scopes(&scopes) => {
+            authorization.scopes = scopes;
+        }
+        _ => return Err(reject(&authorization, OAuthErrorCode::InvalidScope)),
+    }
+    // ID tokens cannot be issued without a key to sign them
+    if authorization
+        .scopes
+        .iter()
+        .any(|scope| scope == OPENID_SCOPE)
+        && state.id_token_signer.is_none()
+    {
+        return Err(reject(&authorization, OAuthErrorCode::InvalidScope));
+    }
+
+    // OpenID Connect Core section 3.1.2.1. Each session has a single account, so choosing an
+    // account means logging in again.
+    let prompts: Vec<&str> = request
+        .prompt
+        .as_deref()
+        .unwrap_or_default()
+        .split(' ')
+        .filter(|prompt| !prompt.is_empty())
+        .collect();
+    let known = prompts
+        .iter()
+        .all(|prompt| matches!(*prompt, "none" | "login" | "consent" | "select_account"));
+    if !known || (prompts.contains(&"none") && prompts.len() > 1) {
+        return Err(reject(&authorization, OAuthErrorCode::InvalidRequest));
+    }
+    authorization.prompt_none = prompts.contains(&"none");
+    authorization.force_login = prompts
+        .iter()
+        .any(|prompt| matches!(*prompt, "login" | "select_account"));
+
+    if let Some(max_age) = &request.max_age {
+        match max_age.parse() {
+            Ok(max_age) => authorization.max_age = Some(max_age),
+            Err(_) => return Err(reject(&authorization, OAuthErrorCode::InvalidRequest)),
+        }
+    }
+
+    Ok(authorization)
+}
+
+fn consent_page(
+    authorization: &ValidatedAuthorization,
+    request: &AuthorizationRequest,
+    csrf_token: &str,
+) -> String {
+    let scopes: String = authorization
+        .scopes
+        .iter()
+        .map(|scope| format!("<li><code>{}</code></li>", escape_html(scope)))
+        .collect();
+
+    // The original parameters are posted back and validated again
+    let hidden_fields: String = request
+        .params()
+        .into_iter()
+        .filter_map(|(name, value)| {
+            value.as_deref().map(|value| {
+                format!(
+                    r#"<input type="hidden" name="{}" value="{}">"#,
+                    name,
+                    escape_html(value)
+                )
+            })
+        })
+        .collect();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Authorize {name}</title>
+    <link rel="stylesheet" href="https://cdn.jsdelivr.net/npm/bootstrap@5.2.2/dist/css/bootstrap.min.css">
+</head>
+
+<body>
+    <section class="position-relative py-4 py-xl-5">
+        <div class="container">
+            <div class="row d-flex justify-content-center">
+                <div class="col-md-6 col-xl-4">
+                    <div class="card mb-5">
+                        <div class="card-body">
+                            <h2>Authorize {name}</h2>
+                            <p><strong>{name}</strong> would like to access your account with these permissions:</p>
+                            <ul>{scopes}</ul>
+                            <form method="post" action="/authorize">
+                                {hidden_fields}
+                                <input type="hidden" name="csrf_token" value="{csrf_token}">
+                                <button class="btn btn-primary" type="submit" name="decision" value="allow">Allow</button>
+                                <button class="btn btn-secondary" type="submit" name="decision" value="deny">Deny</button>
+                            </form>
+                        </div>
+                    </div>
+                </div>
+            </div>
+        </div>
+    </section>
+</body>
+
+</html>
+"#,
+        name = escape_html(&authorization.client.name),
+        scopes = scopes,
+        hidden_fields = hidden_fields,
+        csrf_token = escape_html(csrf_token),
+    )
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#x27;")
+}
+
+// Every parameter is optional here so missing ones are reported the OAuth way
+#[derive(Debug, Default, Deserialize)]
+pub struct AuthorizationRequest {
+    pub response_type: Option<String>,
+    pub client_id: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    // OpenID Connect
+    pub nonce: Option<String>,
+    pub prompt: Option<String>,
+    pub max_age: Option<String>,
+}
+
+impl AuthorizationRequest {
+    fn params(&self) -> [(&'static str, &Option<String>); 10] {
+        [
+            ("response_type", &self.response_type),
+            ("client_id", &self.client_id),
+            ("redirect_uri", &self.redirect_uri),
+            ("scope", &self.scope),
+            ("state", &self.state),
+            ("code_challenge", &self.code_challenge),
+            ("code_challenge_method", &self.code_challenge_method),
+            ("nonce", &self.nonce),
+            ("prompt", &self.prompt),
+            ("max_age", &self.max_age),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsentDecision {
+    Allow,
+    Deny,
+}
+
+#[derive(Deserialize)]
+pub struct ConsentForm {
+    #[serde(flatten)]
+    pub request: AuthorizationRequest,
+    pub csrf_token: String,
+    pub decision: ConsentDecision,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: Option<String>,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub client_id: Option<String>,
+    pub code_verifier: Option<String>,
+    pub client_secret: Option<String>,
+    pub scope: Option<String>,
+    pub device_code: Option<String>,
+    pub subject_token: Option<String>,
+    pub subject_token_type: Option<String>,
+    pub actor_token: Option<String>,
+    pub requested_token_type: Option<String>,
+    pub audience: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccessTokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub scope: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_basic_credentials() {
+        let header = format!("Basic {}", STANDARD.encode("client%3A1:s3cret+%2B"));
+        assert_eq!(
+            basic_credentials(&header),
+            Some(("client:1".to_owned(), "s3cret +".to_owned()))
+        );
+        assert_eq!(basic_credentials("Bearer token"), None);
+        assert_eq!(basic_credentials("Basic not-base64!"), None);
+        let header = format!("Basic {}", STANDARD.encode("no-separator"));
+        assert_eq!(basic_credentials(&header), None);
+    }
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(
+            escape_html(r#"<script>alert("x" & 'y')</script>"#),
+            "&lt;script&gt;alert(&quot;x&quot; &amp; &#x27;y&#x27;)&lt;/script&gt;"
+        );
+    }
+}
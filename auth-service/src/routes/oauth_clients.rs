@@ -0,0 +1,127 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::AppState,
+    domain::{
+        is_scope_token, is_valid_redirect_uri, AuthAPIError, OAuthClient, OAuthClientStoreError,
+    },
+    utils::admin::require_admin_token,
+};
+
+#[tracing::instrument(name = "Create OAuth Client", skip_all)]
+pub async fn create_oauth_client(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateOAuthClientRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_oauth_admin(&state, &headers)?;
+
+    if request.name.trim().is_empty()
+        || request.redirect_uris.is_empty()
+        || !request
+            .redirect_uris
+            .iter()
+            .all(|uri| is_valid_redirect_uri(uri))
+        || request.scopes.is_empty()
+        || !request.scopes.iter().all(|scope| is_scope_token(scope))
+    {
+        return Err(AuthAPIError::InvalidOAuthClient);
+    }
+
+    let client = OAuthClient::new(request.name, request.redirect_uris, request.scopes);
+    let response = OAuthClientResponse::from(&client);
+
+    state
+        .oauth_client_store
+        .write()
+        .await
+        .add_client(client)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+#[tracing::instrument(name = "List OAuth Clients", skip_all)]
+pub async fn list_oauth_clients(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_oauth_admin(&state, &headers)?;
+
+    let clients = state
+        .oauth_client_store
+        .read()
+        .await
+        .get_clients()
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let clients: Vec<OAuthClientResponse> = clients.iter().map(OAuthClientResponse::from).collect();
+
+    Ok(Json(clients))
+}
+
+// Tokens already issued to the client stay valid until they expire
+#[tracing::instrument(name = "Delete OAuth Client", skip_all)]
+pub async fn delete_oauth_client(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(client_id): Path<String>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_oauth_admin(&state, &headers)?;
+
+    state
+        .oauth_client_store
+        .write()
+        .await
+        .delete_client(&client_id)
+        .await
+        .map_err(|e| match e {
+            OAuthClientStoreError::ClientNotFound => AuthAPIError::OAuthClientNotFound,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn authorize_oauth_admin(state: &AppState, headers: &HeaderMap) -> Result<(), AuthAPIError> {
+    require_admin_token(headers, state.settings.oauth.admin_token.as_ref())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateOAuthClientRequest {
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthClientResponse {
+    pub client_id: String,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&OAuthClient> for OAuthClientResponse {
+    fn from(client: &OAuthClient) -> Self {
+        Self {
+            client_id: client.client_id.clone(),
+            name: client.name.clone(),
+            redirect_uris: client.redirect_uris.clone(),
+            scopes: client.scopes.clone(),
+            created_at: client.created_at,
+        }
+    }
+}
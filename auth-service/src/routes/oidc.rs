@@ -0,0 +1,171 @@
+use axum::{
+    extract::State,
+    http::header::WWW_AUTHENTICATE,
+    response::{IntoResponse, Response},
+    Json,
+};
+use jsonwebtoken::jwk::JwkSet;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+use super::{device::DEVICE_CODE_GRANT_TYPE, token_exchange::TOKEN_EXCHANGE_GRANT_TYPE};
+use crate::{
+    app_state::AppState,
+    domain::{parse_scope, AuthAPIError, Email, OPENID_SCOPE},
+    utils::{oidc_issuer, validate_token, AuthToken, TokenKind},
+};
+
+// OpenID Connect Discovery 1.0 section 3, so client libraries can configure themselves from the
+// issuer URL alone
+#[tracing::instrument(name = "OpenID Configuration", skip_all)]
+pub async fn openid_configuration(
+    State(state): State<AppState>,
+) -> Result<Json<OpenIdConfiguration>, AuthAPIError> {
+    if state.id_token_signer.is_none() {
+        return Err(AuthAPIError::OpenIdConnectDisabled);
+    }
+
+    let issuer = oidc_issuer(&state.settings);
+    let to_strings = |values: &[&str]| values.iter().map(|value| (*value).to_owned()).collect();
+
+    Ok(Json(OpenIdConfiguration {
+        authorization_endpoint: format!("{}/authorize", issuer),
+        token_endpoint: format!("{}/token", issuer),
+        introspection_endpoint: format!("{}/introspect", issuer),
+        revocation_endpoint: format!("{}/revoke", issuer),
+        device_authorization_endpoint: format!("{}/device/code", issuer),
+        userinfo_endpoint: format!("{}/userinfo", issuer),
+        jwks_uri: format!("{}/.well-known/jwks.json", issuer),
+        issuer,
+        scopes_supported: to_strings(&[OPENID_SCOPE, "profile", "email"]),
+        response_types_supported: to_strings(&["code"]),
+        grant_types_supported: to_strings(&[
+            "authorization_code",
+            "client_credentials",
+            DEVICE_CODE_GRANT_TYPE,
+            TOKEN_EXCHANGE_GRANT_TYPE,
+        ]),
+        subject_types_supported: to_strings(&["public"]),
+        id_token_signing_alg_values_supported: to_strings(&["RS256"]),
+        token_endpoint_auth_methods_supported: to_strings(&[
+            "none",
+            "client_secret_basic",
+            "client_secret_post",
+        ]),
+        code_challenge_methods_supported: to_strings(&["S256"]),
+        prompt_values_supported: to_strings(&["none", "login", "consent", "select_account"]),
+        claims_supported: to_strings(&[
+            "iss",
+            "sub",
+            "aud",
+            "exp",
+            "iat",
+            "auth_time",
+            "nonce",
+            "amr",
+            "email",
+        ]),
+    }))
+}
+
+// The public key ID tokens are signed with
+#[tracing::instrument(name = "JWKS", skip_all)]
+pub async fn jwks(State(state): State<AppState>) -> Result<Json<JwkSet>, AuthAPIError> {
+    let signer = state
+        .id_token_signer
+        .as_ref()
+        .ok_or(AuthAPIError::OpenIdConnectDisabled)?;
+
+    Ok(Json(signer.jwks()))
+}
+
+// Claims about the user behind an access token that was granted the `openid` scope (OpenID
+// Connect Core section 5.3). The email is only released with the `email` scope.
+#[tracing::instrument(name = "Userinfo", skip_all)]
+pub async fn userinfo(
+    State(state): State<AppState>,
+    token: Result<AuthToken, AuthAPIError>,
+) -> Response {
+    match user_claims(&state, token).await {
+        Ok(claims) => Json(claims).into_response(),
+        // RFC 6750 section 3
+        Err(e @ (AuthAPIError::MissingToken | AuthAPIError::InvalidToken)) => {
+            ([(WWW_AUTHENTICATE, r#"Bearer error="invalid_token""#)], e).into_response()
+        }
+        Err(e @ AuthAPIError::InsufficientScope) => (
+            [(WWW_AUTHENTICATE, r#"Bearer error="insufficient_scope""#)],
+            e,
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn user_claims(
+    state: &AppState,
+    token: Result<AuthToken, AuthAPIError>,
+) -> Result<UserInfoResponse, AuthAPIError> {
+    let token = token?;
+    let claims = validate_token(&token.token, &state.banned_token_store, &state.settings.jwt)
+        .await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+
+    // Only access tokens issued to OAuth clients for a user carry a scope; sessions and service
+    // clients' own tokens are not accepted here
+    let scopes = claims
+        .scope
+        .as_deref()
+        .filter(|_| claims.client_id.is_some() && claims.kind == TokenKind::User)
+        .ok_or(AuthAPIError::InvalidToken)?;
+    let scopes = parse_scope(scopes).map_err(|_| AuthAPIError::InvalidToken)?;
+    if !scopes.iter().any(|scope| scope == OPENID_SCOPE) {
+        return Err(AuthAPIError::InsufficientScope);
+    }
+
+    // The account may have been deleted since the token was issued
+    let email =
+        Email::parse(Secret::new(claims.sub.clone())).map_err(|_| AuthAPIError::InvalidToken)?;
+    state
+        .user_store
+        .read()
+        .await
+        .get_user(&email)
+        .await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+
+    Ok(UserInfoResponse {
+        sub: claims.sub,
+        email: scopes
+            .iter()
+            .any(|scope| scope == "email")
+            .then(|| email.as_ref().expose_secret().to_owned()),
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenIdConfiguration {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub introspection_endpoint: String,
+    pub revocation_endpoint: String,
+    pub device_authorization_endpoint: String,
+    pub userinfo_endpoint: String,
+    pub jwks_uri: String,
+    pub scopes_supported: Vec<String>,
+    pub response_types_supported: Vec<String>,
+    pub grant_types_supported: Vec<String>,
+    pub subject_types_supported: Vec<String>,
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+    pub code_challenge_methods_supported: Vec<String>,
+    pub prompt_values_supported: Vec<String>,
+    pub claims_supported: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserInfoResponse {
+    pub sub: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
@@ -0,0 +1,92 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::AppState,
+    domain::{AuditEvent, AuthAPIError, LoginAlertStoreError, LoginAlertToken, TokenBanReason},
+    services::record_audit_event,
+    utils::METRICS,
+};
+
+// Posted by the page the "this wasn't me" link in new-device emails opens, once the user
+// confirms. Mail scanners that follow the link only get the page. Revokes the reported session,
+// forgets the device and blocks password logins until the password is reset. The token stays
+// valid afterwards so the same token can be used with `/account/reset-password`.
+#[tracing::instrument(name = "Report Login", skip_all)]
+pub async fn report_login(
+    State(state): State<AppState>,
+    Json(request): Json<ReportLoginRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let token =
+        LoginAlertToken::parse(request.token).map_err(|_| AuthAPIError::InvalidLoginAlertToken)?;
+
+    let alert = match state.login_alert_store.read().await.get_alert(&token).await {
+        Ok(alert) => alert,
+        Err(LoginAlertStoreError::AlertNotFound) => {
+            return Err(AuthAPIError::InvalidLoginAlertToken)
+        }
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    state
+        .banned_token_store
+        .write()
+        .await
+        .add_banned_token(alert.session_token)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    METRICS
+        .tokens_banned
+        .with_label_values(&[TokenBanReason::ReportedLogin.as_str()])
+        .inc();
+
+    state
+        .known_device_store
+        .write()
+        .await
+        .remove_device(&alert.email, &alert.device)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .user_store
+        .write()
+        .await
+        .require_password_reset(&alert.email)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let email = alert.email.as_ref().expose_secret().to_owned();
+    record_audit_event(
+        &state,
+        AuditEvent::LoginReported {
+            email: email.clone(),
+        },
+    )
+    .await;
+    record_audit_event(
+        &state,
+        AuditEvent::TokenBanned {
+            email,
+            reason: TokenBanReason::ReportedLogin,
+        },
+    )
+    .await;
+
+    let response = ReportLoginResponse {
+        message: "The session has been signed out. Please reset your password.".to_owned(),
+    };
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+#[derive(Deserialize)]
+pub struct ReportLoginRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportLoginResponse {
+    pub message: String,
+}
@@ -0,0 +1,63 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use secrecy::{ExposeSecret, Secret};
+use serde::Deserialize;
+
+use crate::{
+    app_state::AppState,
+    domain::{AuditEvent, AuthAPIError, LoginAlertStoreError, LoginAlertToken, Password},
+    services::record_audit_event,
+};
+
+// Sets a new password using the token from a new-device email. The token is single use.
+#[tracing::instrument(name = "Reset Password", skip_all)]
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Json(request): Json<ResetPasswordRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    let token =
+        LoginAlertToken::parse(request.token).map_err(|_| AuthAPIError::InvalidLoginAlertToken)?;
+
+    let password =
+        Password::parse(request.new_password).map_err(|_| AuthAPIError::InvalidCredentials)?;
+
+    let alert = match state.login_alert_store.read().await.get_alert(&token).await {
+        Ok(alert) => alert,
+        Err(LoginAlertStoreError::AlertNotFound) => {
+            return Err(AuthAPIError::InvalidLoginAlertToken)
+        }
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+    };
+
+    state
+        .user_store
+        .write()
+        .await
+        .reset_password(&alert.email, password)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    state
+        .login_alert_store
+        .write()
+        .await
+        .remove_alert(&token)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    record_audit_event(
+        &state,
+        AuditEvent::PasswordReset {
+            email: alert.email.as_ref().expose_secret().to_owned(),
+        },
+    )
+    .await;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    #[serde(rename = "newPassword")]
+    pub new_password: Secret<String>,
+}
@@ -0,0 +1,122 @@
+use axum::{
+    extract::State,
+    http::{header::CACHE_CONTROL, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Form,
+};
+use secrecy::Secret;
+use serde::Deserialize;
+
+use super::oauth::authenticate_service_client;
+use crate::{
+    app_state::AppState,
+    domain::{AuditEvent, AuthAPIError, OAuthError, OAuthErrorCode, TokenBanReason},
+    services::record_audit_event,
+    utils::{
+        validate_token_for, AuthTokenSource, AuthenticatedUser, ExpectedAudience, TokenKind,
+        METRICS,
+    },
+};
+
+// Token revocation (RFC 7009). Service clients can revoke the tokens issued to them or restricted
+// to them as the audience, and users holding a bearer token any token of their own. Browser
+// sessions use `/logout` instead, since a cross-site form could post here with the cookie. Tokens
+// that are already invalid need no revoking, so unknown tokens get the same 200 as revoked ones.
+#[tracing::instrument(name = "Revoke", skip_all)]
+pub async fn revoke(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    user: Result<AuthenticatedUser, AuthAPIError>,
+    Form(request): Form<RevocationRequest>,
+) -> Result<Response, OAuthError> {
+    let user = user
+        .ok()
+        .filter(|user| user.session.source == AuthTokenSource::AuthorizationHeader);
+    let caller = match user {
+        Some(user) => Caller::User(user.claims.sub),
+        None => Caller::Client(
+            authenticate_service_client(
+                &state,
+                &headers,
+                request.client_id.as_deref(),
+                request.client_secret.as_deref(),
+            )
+            .await?
+            .client_id,
+        ),
+    };
+
+    let token = request
+        .token
+        .ok_or_else(|| OAuthError::new(OAuthErrorCode::InvalidRequest, "Missing token"))?;
+
+    // There are no refresh tokens, so `token_type_hint` changes nothing
+    // Ownership is checked below, so delegated tokens for any audience are looked at
+    let Ok(claims) = validate_token_for(
+        &token,
+        ExpectedAudience::Any,
+        &state.banned_token_store,
+        &state.settings.jwt,
+    )
+    .await
+    else {
+        return Ok(revoked());
+    };
+
+    let owned = match &caller {
+        Caller::User(email) => claims.kind == TokenKind::User && &claims.sub == email,
+        Caller::Client(client_id) => {
+            claims.client_id.as_ref() == Some(client_id) || claims.aud.as_ref() == Some(client_id)
+        }
+    };
+    if !owned {
+        return Err(OAuthError::new(
+            OAuthErrorCode::UnauthorizedClient,
+            "The token was not issued to the caller",
+        ));
+    }
+
+    state
+        .banned_token_store
+        .write()
+        .await
+        .add_banned_token(Secret::new(token))
+        .await
+        .map_err(|e| OAuthError::new(OAuthErrorCode::ServerError, e.to_string()))?;
+    METRICS
+        .tokens_banned
+        .with_label_values(&[TokenBanReason::Revoked.as_str()])
+        .inc();
+
+    // Client tokens have no user to record the event against
+    if claims.kind == TokenKind::User {
+        record_audit_event(
+            &state,
+            AuditEvent::TokenBanned {
+                email: claims.sub,
+                reason: TokenBanReason::Revoked,
+            },
+        )
+        .await;
+    }
+
+    Ok(revoked())
+}
+
+enum Caller {
+    // The email address of the user
+    User(String),
+    Client(String),
+}
+
+fn revoked() -> Response {
+    ([(CACHE_CONTROL, "no-store")], StatusCode::OK).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct RevocationRequest {
+    pub token: Option<String>,
+    pub token_type_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
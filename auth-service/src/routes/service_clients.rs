@@ -0,0 +1,131 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::AppState,
+    domain::{is_scope_token, AuthAPIError, ServiceClient, ServiceClientStoreError},
+    utils::admin::require_admin_token,
+};
+
+// The secret is only ever shown in this response
+#[tracing::instrument(name = "Create Service Client", skip_all)]
+pub async fn create_service_client(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateServiceClientRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_oauth_admin(&state, &headers)?;
+
+    if request.name.trim().is_empty()
+        || request.scopes.is_empty()
+        || !request.scopes.iter().all(|scope| is_scope_token(scope))
+    {
+        return Err(AuthAPIError::InvalidServiceClient);
+    }
+
+    let (client, secret) = ServiceClient::new(request.name, request.scopes);
+    let response = CreateServiceClientResponse {
+        client: ServiceClientResponse::from(&client),
+        client_secret: secret.expose_secret().to_owned(),
+    };
+
+    state
+        .service_client_store
+        .write()
+        .await
+        .add_client(client)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+#[tracing::instrument(name = "List Service Clients", skip_all)]
+pub async fn list_service_clients(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_oauth_admin(&state, &headers)?;
+
+    let clients = state
+        .service_client_store
+        .read()
+        .await
+        .get_clients()
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let clients: Vec<ServiceClientResponse> =
+        clients.iter().map(ServiceClientResponse::from).collect();
+
+    Ok(Json(clients))
+}
+
+// Tokens already issued to the client stay valid until they expire
+#[tracing::instrument(name = "Delete Service Client", skip_all)]
+pub async fn delete_service_client(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(client_id): Path<String>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_oauth_admin(&state, &headers)?;
+
+    state
+        .service_client_store
+        .write()
+        .await
+        .delete_client(&client_id)
+        .await
+        .map_err(|e| match e {
+            ServiceClientStoreError::ClientNotFound => AuthAPIError::ServiceClientNotFound,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn authorize_oauth_admin(state: &AppState, headers: &HeaderMap) -> Result<(), AuthAPIError> {
+    require_admin_token(headers, state.settings.oauth.admin_token.as_ref())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateServiceClientRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceClientResponse {
+    pub client_id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&ServiceClient> for ServiceClientResponse {
+    fn from(client: &ServiceClient) -> Self {
+        Self {
+            client_id: client.client_id.clone(),
+            name: client.name.clone(),
+            scopes: client.scopes.clone(),
+            created_at: client.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateServiceClientResponse {
+    #[serde(flatten)]
+    pub client: ServiceClientResponse,
+    pub client_secret: String,
+}
@@ -1,10 +1,11 @@
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
-use secrecy::Secret;
+use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     app_state::AppState,
-    domain::{AuthAPIError, Email, Password, User},
+    domain::{AuditEvent, AuthAPIError, Email, Password, User, UserStoreError, WebhookEventType},
+    services::{publish_webhook_event, record_audit_event},
 };
 
 #[tracing::instrument(name = "Signup instrument", skip_all)]
@@ -22,21 +23,50 @@ pub async fn signup(
 
     // Create a new `User` instance using data in the `request`
     let user = User {
-        email: email,
+        email,
         password,
         requires_2fa: request.requires_2fa,
     };
 
     let mut user_store = state.user_store.write().await;
 
-    if user_store.get_user(&user.email).await.is_ok() {
-        return Err(AuthAPIError::UserAlreadyExists);
+    // Only an address no store knows is free. A directory that cannot be reached might have it.
+    match user_store.get_user(&user.email).await {
+        Ok(_) => return Err(AuthAPIError::UserAlreadyExists),
+        Err(UserStoreError::UserNotFound) => {}
+        Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
     }
 
+    let email = user.email.as_ref().expose_secret().to_owned();
+    let requires_2fa = user.requires_2fa;
+    let event = AuditEvent::UserSignedUp {
+        email: email.clone(),
+        requires_2fa,
+    };
+
     user_store
         .add_user(user)
         .await
         .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+    drop(user_store);
+
+    record_audit_event(&state, event).await;
+
+    publish_webhook_event(
+        &state,
+        WebhookEventType::UserSignedUp,
+        serde_json::json!({ "email": email, "requires2FA": requires_2fa }),
+    )
+    .await;
+    // 2FA can only be turned on at signup today
+    if requires_2fa {
+        publish_webhook_event(
+            &state,
+            WebhookEventType::TwoFactorEnabled,
+            serde_json::json!({ "email": email }),
+        )
+        .await;
+    }
 
     let response = Json(SignupResponse {
         message: "User created successfully!".to_string(),
@@ -0,0 +1,173 @@
+use axum::{
+    http::{
+        header::{CACHE_CONTROL, PRAGMA},
+        HeaderMap,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use super::oauth::{authenticate_service_client, TokenRequest};
+use crate::{
+    app_state::AppState,
+    domain::{parse_scope, AuditEvent, OAuthError, OAuthErrorCode, ServiceClientStoreError},
+    services::record_audit_event,
+    utils::{generate_delegated_token, validate_token, TokenKind},
+};
+
+pub const TOKEN_EXCHANGE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:token-exchange";
+pub const ACCESS_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:access_token";
+const JWT_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:jwt";
+
+// Token exchange (RFC 8693), so a service calling another one on a user's behalf does not have
+// to forward the user's own token. The calling service client trades a valid user token for one
+// that only the `audience` service accepts, carrying a subset of the scopes both the caller and
+// the user's token allow, and naming the caller in its `act` claim. Without a `scope` parameter
+// the new token carries all of those.
+pub(crate) async fn token_exchange_grant(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: TokenRequest,
+) -> Result<Response, OAuthError> {
+    let client = authenticate_service_client(
+        state,
+        headers,
+        request.client_id.as_deref(),
+        request.client_secret.as_deref(),
+    )
+    .await?;
+
+    let (Some(subject_token), Some(subject_token_type), Some(audience)) = (
+        request.subject_token,
+        request.subject_token_type,
+        request.audience,
+    ) else {
+        return Err(OAuthError::new(
+            OAuthErrorCode::InvalidRequest,
+            "subject_token, subject_token_type and audience are required",
+        ));
+    };
+    // Every token this service issues is a JWT access token, and so is every token it issues here
+    if ![ACCESS_TOKEN_TYPE, JWT_TOKEN_TYPE].contains(&subject_token_type.as_str())
+        || request
+            .requested_token_type
+            .is_some_and(|token_type| token_type != ACCESS_TOKEN_TYPE)
+    {
+        return Err(OAuthError::new(
+            OAuthErrorCode::InvalidRequest,
+            "Unsupported token type",
+        ));
+    }
+    if request.actor_token.is_some() {
+        return Err(OAuthError::new(
+            OAuthErrorCode::InvalidRequest,
+            "The authenticated client is the actor, so actor_token is not supported",
+        ));
+    }
+
+    // Delegated tokens are restricted to an audience, so they cannot be exchanged again
+    let subject = validate_token(
+        &subject_token,
+        &state.banned_token_store,
+        &state.settings.jwt,
+    )
+    .await
+    .ok()
+    .filter(|claims| claims.kind == TokenKind::User)
+    .ok_or_else(|| {
+        OAuthError::new(
+            OAuthErrorCode::InvalidGrant,
+            "The subject token is invalid or does not act for a user",
+        )
+    })?;
+
+    // The audience is the service client that will accept the token
+    state
+        .service_client_store
+        .read()
+        .await
+        .get_client(&audience)
+        .await
+        .map_err(|e| match e {
+            ServiceClientStoreError::ClientNotFound => {
+                OAuthError::new(OAuthErrorCode::InvalidTarget, "Unknown audience")
+            }
+            e => OAuthError::new(OAuthErrorCode::ServerError, e.to_string()),
+        })?;
+
+    // Tokens the user granted a third-party client are limited to the scopes they granted
+    let subject_scopes = subject
+        .scope
+        .as_deref()
+        .map(|scope| parse_scope(scope).unwrap_or_default());
+    let allowed = |scope: &String| {
+        client.scopes.contains(scope)
+            && subject_scopes
+                .as_ref()
+                .is_none_or(|scopes| scopes.contains(scope))
+    };
+    let scopes = match request.scope.as_deref() {
+        Some(scope) => parse_scope(scope)
+            .ok()
+            .filter(|scopes| scopes.iter().all(allowed)),
+        None => Some(
+            client
+                .scopes
+                .iter()
+                .filter(|scope| allowed(scope))
+                .cloned()
+                .collect(),
+        ),
+    }
+    .filter(|scopes: &Vec<String>| !scopes.is_empty())
+    .ok_or_else(|| {
+        OAuthError::new(
+            OAuthErrorCode::InvalidScope,
+            "The requested scope is invalid or not allowed for this client and token",
+        )
+    })?;
+
+    let scope = scopes.join(" ");
+    let (access_token, expires_in) = generate_delegated_token(
+        &subject,
+        &client.client_id,
+        &audience,
+        &scope,
+        &state.settings.jwt,
+    )
+    .map_err(|e| OAuthError::new(OAuthErrorCode::ServerError, e.to_string()))?;
+
+    record_audit_event(
+        state,
+        AuditEvent::TokenExchanged {
+            email: subject.sub,
+            client_id: client.client_id,
+            audience,
+            scope: scope.clone(),
+        },
+    )
+    .await;
+
+    Ok((
+        [(CACHE_CONTROL, "no-store"), (PRAGMA, "no-cache")],
+        Json(TokenExchangeResponse {
+            access_token,
+            issued_token_type: ACCESS_TOKEN_TYPE.to_owned(),
+            token_type: "Bearer".to_owned(),
+            expires_in,
+            scope,
+        }),
+    )
+        .into_response())
+}
+
+// RFC 8693 section 2.2.1
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenExchangeResponse {
+    pub access_token: String,
+    pub issued_token_type: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub scope: String,
+}
@@ -1,20 +1,35 @@
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
 use axum_extra::extract::CookieJar;
-use secrecy::Secret;
+use secrecy::{ExposeSecret, Secret};
 use serde::Deserialize;
 
+use super::login::{
+    record_login_failure, record_login_success, AuthTokenResponse, LoginResponseMode,
+};
 use crate::{
     app_state::AppState,
-    domain::{AuthAPIError, Email, LoginAttemptId, TwoFACode},
-    utils::generate_auth_cookie,
+    domain::{
+        AuditEvent, AuthAPIError, ClientInfo, Email, LoginAttemptId, LoginFailureReason, TwoFACode,
+    },
+    services::record_audit_event,
+    utils::{
+        add_session_cookies, generate_auth_cookie, generate_auth_token, labels,
+        AuthenticationMethod, METRICS,
+    },
 };
 
 #[tracing::instrument(name = "Verify 2FA", skip_all)]
 pub async fn verify_2fa(
     State(state): State<AppState>,
+    client: ClientInfo,
     jar: CookieJar,
     Json(request): Json<Verify2FARequest>,
-) -> (CookieJar, Result<impl IntoResponse, AuthAPIError>) {
+) -> (CookieJar, Result<Response, AuthAPIError>) {
     // Validate the email in `request`
     let email = match Email::parse(Secret::new(request.email.to_string())) {
         Ok(email) => email,
@@ -24,13 +39,33 @@ pub async fn verify_2fa(
     // Validate the login attempt ID in `request`
     let login_attempt_id = match LoginAttemptId::parse(request.login_attempt_id) {
         Ok(id) => id,
-        Err(_) => return (jar, Err(AuthAPIError::InvalidLoginAttemptId)),
+        Err(_) => {
+            record_login_failure(
+                &state,
+                email,
+                &client,
+                LoginFailureReason::InvalidTwoFACode,
+                true,
+            )
+            .await;
+            return (jar, Err(AuthAPIError::InvalidLoginAttemptId));
+        }
     };
 
     // Validate the 2FA code in `request`
     let two_fa_code = match TwoFACode::parse(request.two_fa_code) {
         Ok(code) => code,
-        Err(_) => return (jar, Err(AuthAPIError::InvalidCredentials)),
+        Err(_) => {
+            record_login_failure(
+                &state,
+                email,
+                &client,
+                LoginFailureReason::InvalidTwoFACode,
+                true,
+            )
+            .await;
+            return (jar, Err(AuthAPIError::InvalidCredentials));
+        }
     };
 
     let mut two_fa_code_store = state.two_fa_code_store.write().await;
@@ -39,12 +74,30 @@ pub async fn verify_2fa(
     // return a `AuthAPIError::IncorrectCredentials`.
     let code_tuple = match two_fa_code_store.get_code(&email).await {
         Ok(tuple) => tuple,
-        Err(_) => return (jar, Err(AuthAPIError::IncorrectCredentials)),
+        Err(_) => {
+            record_login_failure(
+                &state,
+                email,
+                &client,
+                LoginFailureReason::IncorrectTwoFACode,
+                true,
+            )
+            .await;
+            return (jar, Err(AuthAPIError::IncorrectCredentials));
+        }
     };
 
     // Check if the login attempt ID and 2FA code in the request body matches values in the `code_tuple`.
     // If not, return a `AuthAPIError::IncorrectCredentials`.
     if code_tuple.0 != login_attempt_id || code_tuple.1 != two_fa_code {
+        record_login_failure(
+            &state,
+            email,
+            &client,
+            LoginFailureReason::IncorrectTwoFACode,
+            true,
+        )
+        .await;
         return (jar, Err(AuthAPIError::IncorrectCredentials));
     }
 
@@ -53,16 +106,47 @@ pub async fn verify_2fa(
         Ok(_) => {}
         Err(e) => return (jar, Err(AuthAPIError::UnexpectedError(e.into()))),
     }
+    drop(two_fa_code_store);
+
+    METRICS
+        .two_fa_codes
+        .with_label_values(&[labels::TWO_FA_CODE_VERIFIED])
+        .inc();
+
+    record_audit_event(
+        &state,
+        AuditEvent::TwoFAVerified {
+            email: email.as_ref().expose_secret().to_owned(),
+        },
+    )
+    .await;
+
+    // The session reports the first factor the user logged in with, not just the code
+    let method = AuthenticationMethod::two_factor(code_tuple.2);
+
+    if request.response_mode == LoginResponseMode::Token {
+        let token = match generate_auth_token(&email, method, &state.settings.jwt) {
+            Ok(token) => token,
+            Err(e) => return (jar, Err(AuthAPIError::UnexpectedError(e))),
+        };
+
+        record_login_success(&state, &email, &client, &token, true).await;
+
+        return (jar, Ok(Json(AuthTokenResponse::new(token)).into_response()));
+    }
 
     // Generate auth cookie
-    let auth_cookie = match generate_auth_cookie(&email) {
-        Ok(cookie) => cookie,
-        Err(e) => return (jar, Err(AuthAPIError::UnexpectedError(e))),
-    };
+    let auth_cookie =
+        match generate_auth_cookie(&email, method, &state.settings.jwt, &state.settings.cookie) {
+            Ok(cookie) => cookie,
+            Err(e) => return (jar, Err(AuthAPIError::UnexpectedError(e))),
+        };
+
+    record_login_success(&state, &email, &client, auth_cookie.value(), true).await;
 
-    let updated_jar = jar.add(auth_cookie);
+    let updated_jar = add_session_cookies(jar, auth_cookie, &state.settings);
 
-    (updated_jar, Ok(StatusCode::OK))
+    (updated_jar, Ok(StatusCode::OK.into_response()))
 }
 
 #[derive(Deserialize)]
@@ -72,4 +156,6 @@ pub struct Verify2FARequest {
     login_attempt_id: String,
     #[serde(rename = "2FACode")]
     two_fa_code: String,
+    #[serde(rename = "responseMode", default)]
+    response_mode: LoginResponseMode,
 }
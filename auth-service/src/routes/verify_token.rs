@@ -1,7 +1,11 @@
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use serde::{Deserialize, Serialize};
 
-use crate::{app_state::AppState, domain::AuthAPIError, utils::auth::validate_token};
+use crate::{
+    app_state::AppState,
+    domain::AuthAPIError,
+    utils::auth::{validate_token_for, Claims, ExpectedAudience, TokenKind},
+};
 
 #[tracing::instrument(name = "Verify Token", skip_all)]
 pub async fn verify_token(
@@ -9,10 +13,33 @@ pub async fn verify_token(
     Json(request): Json<VerifyTokenRequest>,
 ) -> Result<impl IntoResponse, AuthAPIError> {
     // Validate the JWT token
-    match validate_token(&request.token, &state.banned_token_store).await {
-        Ok(_) => {
+    // Delegated tokens only verify for the service they were issued to call
+    let audience = match request.audience.as_deref() {
+        Some(audience) => ExpectedAudience::Service(audience),
+        None => ExpectedAudience::Unrestricted,
+    };
+    match validate_token_for(
+        &request.token,
+        audience,
+        &state.banned_token_store,
+        &state.settings.jwt,
+    )
+    .await
+    {
+        // Callers without an audience are checking a user's session, e.g. app-service with a
+        // browser's cookie. Tokens that act for a client, or that a user granted to a third-party
+        // app, only verify for a service that names itself.
+        Ok(claims)
+            if request.audience.is_none()
+                && (claims.kind == TokenKind::Client || claims.client_id.is_some()) =>
+        {
+            Err(AuthAPIError::InvalidToken)
+        }
+        Ok(claims) => {
             let response = VerifyTokenResponse {
                 message: "Success verifying token".to_string(),
+                token_kind: claims.kind,
+                claims: request.include_claims.then_some(claims),
             };
             Ok((StatusCode::OK, Json(response)))
         }
@@ -21,11 +48,24 @@ pub async fn verify_token(
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct VerifyTokenRequest {
     pub token: String,
+    // Also return the decoded claims
+    #[serde(default)]
+    pub include_claims: bool,
+    // The service verifying the token, which accepts delegated tokens restricted to it as well
+    // as client and third-party access tokens. Without it, only user sessions verify.
+    #[serde(default)]
+    pub audience: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct VerifyTokenResponse {
     pub message: String,
+    // Whether the token acts for a user or for a service client itself
+    pub token_kind: TokenKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claims: Option<Claims>,
 }
@@ -0,0 +1,210 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    domain::{
+        AuthAPIError, WebhookDeadLetter, WebhookEventType, WebhookStoreError, WebhookSubscription,
+    },
+    utils::admin::require_admin_token,
+};
+
+#[tracing::instrument(name = "Create Webhook Subscription", skip_all)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateWebhookRequest>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_webhook_admin(&state, &headers)?;
+
+    let url =
+        reqwest::Url::parse(&request.url).map_err(|_| AuthAPIError::InvalidWebhookSubscription)?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(AuthAPIError::InvalidWebhookSubscription);
+    }
+
+    if request.secret.expose_secret().is_empty() || request.events.is_empty() {
+        return Err(AuthAPIError::InvalidWebhookSubscription);
+    }
+
+    let mut events = Vec::new();
+    for event in &request.events {
+        let event_type =
+            WebhookEventType::parse(event).map_err(|_| AuthAPIError::InvalidWebhookSubscription)?;
+        if !events.contains(&event_type) {
+            events.push(event_type);
+        }
+    }
+
+    let subscription = WebhookSubscription::new(url.to_string(), request.secret, events);
+    let response = WebhookSubscriptionResponse::from(&subscription);
+
+    state
+        .webhook_store
+        .write()
+        .await
+        .add_subscription(subscription)
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+#[tracing::instrument(name = "List Webhook Subscriptions", skip_all)]
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_webhook_admin(&state, &headers)?;
+
+    let subscriptions = state
+        .webhook_store
+        .read()
+        .await
+        .get_subscriptions()
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let subscriptions: Vec<WebhookSubscriptionResponse> = subscriptions
+        .iter()
+        .map(WebhookSubscriptionResponse::from)
+        .collect();
+
+    Ok(Json(subscriptions))
+}
+
+#[tracing::instrument(name = "Delete Webhook Subscription", skip_all)]
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_webhook_admin(&state, &headers)?;
+
+    state
+        .webhook_store
+        .write()
+        .await
+        .delete_subscription(id)
+        .await
+        .map_err(|e| match e {
+            WebhookStoreError::SubscriptionNotFound => AuthAPIError::WebhookSubscriptionNotFound,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[tracing::instrument(name = "List Webhook Dead Letters", skip_all)]
+pub async fn list_webhook_dead_letters(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_webhook_admin(&state, &headers)?;
+
+    let dead_letters = state
+        .webhook_store
+        .read()
+        .await
+        .get_dead_letters()
+        .await
+        .map_err(|e| AuthAPIError::UnexpectedError(e.into()))?;
+
+    let dead_letters: Vec<WebhookDeadLetterResponse> = dead_letters
+        .into_iter()
+        .map(WebhookDeadLetterResponse::from)
+        .collect();
+
+    Ok(Json(dead_letters))
+}
+
+// Puts a dead-lettered delivery back on the queue with a fresh set of attempts
+#[tracing::instrument(name = "Replay Webhook Delivery", skip_all)]
+pub async fn replay_webhook_delivery(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AuthAPIError> {
+    authorize_webhook_admin(&state, &headers)?;
+
+    state
+        .webhook_store
+        .write()
+        .await
+        .replay_dead_letter(id)
+        .await
+        .map_err(|e| match e {
+            WebhookStoreError::DeliveryNotFound => AuthAPIError::WebhookDeliveryNotFound,
+            e => AuthAPIError::UnexpectedError(e.into()),
+        })?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+// Webhook management is restricted to holders of the webhook admin token
+fn authorize_webhook_admin(state: &AppState, headers: &HeaderMap) -> Result<(), AuthAPIError> {
+    require_admin_token(headers, state.settings.webhooks.admin_token.as_ref())
+}
+
+#[derive(Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub secret: Secret<String>,
+    pub events: Vec<String>,
+}
+
+// The signing secret is never echoed back
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookSubscriptionResponse {
+    pub id: Uuid,
+    pub url: String,
+    pub events: Vec<WebhookEventType>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&WebhookSubscription> for WebhookSubscriptionResponse {
+    fn from(subscription: &WebhookSubscription) -> Self {
+        Self {
+            id: subscription.id,
+            url: subscription.url.clone(),
+            events: subscription.events.clone(),
+            created_at: subscription.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDeadLetterResponse {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub event_type: WebhookEventType,
+    pub payload: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub failed_at: DateTime<Utc>,
+}
+
+impl From<WebhookDeadLetter> for WebhookDeadLetterResponse {
+    fn from(dead_letter: WebhookDeadLetter) -> Self {
+        let delivery = dead_letter.delivery;
+        Self {
+            id: delivery.id,
+            subscription_id: delivery.subscription_id,
+            event_type: delivery.event_type,
+            payload: delivery.payload,
+            attempts: delivery.attempts,
+            last_error: delivery.last_error,
+            failed_at: dead_letter.failed_at,
+        }
+    }
+}
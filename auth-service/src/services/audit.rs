@@ -0,0 +1,11 @@
+use crate::{app_state::AppState, domain::AuditEvent};
+
+// Appends `event` to the audit log, extending the hash chain. The sink keeps concurrent
+// requests from forking the chain, so only a read lock is taken here. Audit failures are
+// logged rather than failing the request that triggered them.
+#[tracing::instrument(name = "Record Audit Event", skip_all)]
+pub async fn record_audit_event(state: &AppState, event: AuditEvent) {
+    if let Err(e) = state.audit_sink.read().await.record(event).await {
+        tracing::error!("Failed to append audit record: {:?}", e);
+    }
+}
@@ -0,0 +1,126 @@
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{Context, Result};
+use tokio::{fs::OpenOptions, io::AsyncWriteExt, sync::Mutex};
+
+use crate::domain::{AuditEvent, AuditRecord, AuditSink, AuditSinkError};
+
+// Appends one JSON object per line to a local file
+pub struct JsonLinesFileAuditSink {
+    path: PathBuf,
+    // Held while a record is written, so concurrent events line up one after another
+    last_record: Mutex<Option<AuditRecord>>,
+}
+
+impl JsonLinesFileAuditSink {
+    // Opens (or creates) the log and picks the chain up from its last line
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let records = read_records(&path).await?;
+
+        Ok(Self {
+            path,
+            last_record: Mutex::new(records.into_iter().last()),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditSink for JsonLinesFileAuditSink {
+    #[tracing::instrument(name = "Appending audit record to file", skip_all)]
+    async fn record(&self, event: AuditEvent) -> Result<AuditRecord, AuditSinkError> {
+        let mut last_record = self.last_record.lock().await;
+        let record = AuditRecord::chain(last_record.as_ref(), event);
+
+        let mut line = serde_json::to_string(&record)
+            .wrap_err("failed to serialize audit record")
+            .map_err(AuditSinkError::UnexpectedError)?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .wrap_err("failed to open audit log file")
+            .map_err(AuditSinkError::UnexpectedError)?;
+
+        file.write_all(line.as_bytes())
+            .await
+            .wrap_err("failed to write audit record")
+            .map_err(AuditSinkError::UnexpectedError)?;
+        file.sync_data()
+            .await
+            .wrap_err("failed to flush audit log file")
+            .map_err(AuditSinkError::UnexpectedError)?;
+
+        *last_record = Some(record.clone());
+        Ok(record)
+    }
+
+    async fn records(&self) -> Result<Vec<AuditRecord>, AuditSinkError> {
+        read_records(&self.path)
+            .await
+            .map_err(AuditSinkError::UnexpectedError)
+    }
+
+    async fn records_for(&self, email: &str) -> Result<Vec<AuditRecord>, AuditSinkError> {
+        let mut records = self.records().await?;
+        records.retain(|record| record.event.email() == email);
+        Ok(records)
+    }
+}
+
+async fn read_records(path: &Path) -> Result<Vec<AuditRecord>> {
+    let contents = match tokio::fs::read_to_string(path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).wrap_err("failed to read audit log file"),
+    };
+
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).wrap_err("failed to parse audit log line"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::verify_audit_chain;
+
+    fn temp_log_path() -> PathBuf {
+        std::env::temp_dir().join(format!("audit-{}.jsonl", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_appended_records_survive_reopening() {
+        let path = temp_log_path();
+
+        let sink = JsonLinesFileAuditSink::open(&path).await.unwrap();
+        for n in 0..2 {
+            let event = AuditEvent::LoggedOut {
+                email: format!("user{}@example.com", n),
+            };
+            sink.record(event).await.unwrap();
+        }
+
+        // A new sink continues the chain where the file left off
+        let reopened = JsonLinesFileAuditSink::open(&path).await.unwrap();
+        let event = AuditEvent::LoggedOut {
+            email: "user2@example.com".to_owned(),
+        };
+        assert_eq!(reopened.record(event).await.unwrap().sequence, 3);
+
+        let records = reopened.records().await.unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(verify_audit_chain(&records), Ok(()));
+
+        let records = reopened.records_for("user1@example.com").await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].sequence, 2);
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
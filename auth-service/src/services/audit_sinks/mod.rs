@@ -0,0 +1,7 @@
+pub mod json_lines_file_audit_sink;
+pub mod postgres_audit_sink;
+pub mod stdout_audit_sink;
+
+pub use json_lines_file_audit_sink::*;
+pub use postgres_audit_sink::*;
+pub use stdout_audit_sink::*;
@@ -0,0 +1,160 @@
+use color_eyre::eyre::{eyre, Context};
+use sqlx::PgPool;
+
+use crate::domain::{AuditEvent, AuditRecord, AuditSink, AuditSinkError};
+
+// The advisory lock appends hold, so replicas sharing the table extend the chain one at a time.
+// Nothing else in the database takes it.
+const AUDIT_LOG_LOCK_ID: i64 = 0x6175_6469_745f_6c6f;
+
+// How many times an append that loses the race for the next sequence number is tried
+const AUDIT_APPEND_ATTEMPTS: usize = 3;
+
+pub struct PostgresAuditSink {
+    pool: PgPool,
+}
+
+impl PostgresAuditSink {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    // Reads the last record and appends the next one in a single transaction. None when a
+    // writer that skipped the lock claimed the sequence number first, e.g. a replica still on
+    // an older release; the primary key on `sequence` turns that into a unique violation.
+    async fn try_append(&self, event: &AuditEvent) -> Result<Option<AuditRecord>, AuditSinkError> {
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| AuditSinkError::UnexpectedError(e.into()))?;
+
+        // Released when the transaction ends
+        sqlx::query!("SELECT pg_advisory_xact_lock($1)", AUDIT_LOG_LOCK_ID)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| AuditSinkError::UnexpectedError(e.into()))?;
+
+        let previous = sqlx::query!(
+            "SELECT sequence, occurred_at, event, previous_hash, hash FROM audit_log ORDER BY sequence DESC LIMIT 1"
+        )
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(|e| AuditSinkError::UnexpectedError(e.into()))?
+        .map(|row| {
+            Ok::<_, AuditSinkError>(AuditRecord {
+                sequence: row.sequence,
+                occurred_at: row.occurred_at,
+                event: parse_event(&row.event)?,
+                previous_hash: row.previous_hash,
+                hash: row.hash,
+            })
+        })
+        .transpose()?;
+
+        let record = AuditRecord::chain(previous.as_ref(), event.clone());
+        let event = serde_json::to_value(&record.event)
+            .wrap_err("failed to serialize audit event")
+            .map_err(AuditSinkError::UnexpectedError)?;
+        let event_type = event["type"].as_str().unwrap_or_default().to_owned();
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO audit_log (sequence, occurred_at, event_type, event, previous_hash, hash)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            record.sequence,
+            record.occurred_at,
+            event_type,
+            event.to_string(),
+            record.previous_hash,
+            record.hash
+        )
+        .execute(&mut *transaction)
+        .await;
+        match inserted {
+            Ok(_) => {}
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => return Ok(None),
+            Err(e) => return Err(AuditSinkError::UnexpectedError(e.into())),
+        }
+
+        transaction
+            .commit()
+            .await
+            .map_err(|e| AuditSinkError::UnexpectedError(e.into()))?;
+
+        Ok(Some(record))
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditSink for PostgresAuditSink {
+    #[tracing::instrument(name = "Appending audit record to PostgreSQL", skip_all)]
+    async fn record(&self, event: AuditEvent) -> Result<AuditRecord, AuditSinkError> {
+        for _ in 0..AUDIT_APPEND_ATTEMPTS {
+            if let Some(record) = self.try_append(&event).await? {
+                return Ok(record);
+            }
+        }
+
+        Err(AuditSinkError::UnexpectedError(eyre!(
+            "lost the race for the next audit sequence number {} times",
+            AUDIT_APPEND_ATTEMPTS
+        )))
+    }
+
+    #[tracing::instrument(name = "Reading audit log from PostgreSQL", skip_all)]
+    async fn records(&self) -> Result<Vec<AuditRecord>, AuditSinkError> {
+        let rows = sqlx::query!(
+            "SELECT sequence, occurred_at, event, previous_hash, hash FROM audit_log ORDER BY sequence"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AuditSinkError::UnexpectedError(e.into()))?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(AuditRecord {
+                    sequence: row.sequence,
+                    occurred_at: row.occurred_at,
+                    event: parse_event(&row.event)?,
+                    previous_hash: row.previous_hash,
+                    hash: row.hash,
+                })
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(name = "Reading user's audit records from PostgreSQL", skip_all)]
+    async fn records_for(&self, email: &str) -> Result<Vec<AuditRecord>, AuditSinkError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT sequence, occurred_at, event, previous_hash, hash FROM audit_log
+            WHERE event::jsonb ->> 'email' = $1
+            ORDER BY sequence
+            "#,
+            email
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AuditSinkError::UnexpectedError(e.into()))?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(AuditRecord {
+                    sequence: row.sequence,
+                    occurred_at: row.occurred_at,
+                    event: parse_event(&row.event)?,
+                    previous_hash: row.previous_hash,
+                    hash: row.hash,
+                })
+            })
+            .collect()
+    }
+}
+
+fn parse_event(event: &str) -> Result<AuditEvent, AuditSinkError> {
+    serde_json::from_str(event)
+        .wrap_err("failed to deserialize audit event")
+        .map_err(AuditSinkError::UnexpectedError)
+}
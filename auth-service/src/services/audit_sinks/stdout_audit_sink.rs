@@ -0,0 +1,37 @@
+use color_eyre::eyre::Context;
+use tokio::sync::Mutex;
+
+use crate::domain::{AuditEvent, AuditRecord, AuditSink, AuditSinkError};
+
+// Prints one JSON object per line to standard output, for log shippers to collect.
+// The chain only spans the lifetime of the process.
+#[derive(Default)]
+pub struct StdoutAuditSink {
+    // Held while a record is printed, so concurrent events line up one after another
+    last_record: Mutex<Option<AuditRecord>>,
+}
+
+#[async_trait::async_trait]
+impl AuditSink for StdoutAuditSink {
+    async fn record(&self, event: AuditEvent) -> Result<AuditRecord, AuditSinkError> {
+        let mut last_record = self.last_record.lock().await;
+        let record = AuditRecord::chain(last_record.as_ref(), event);
+
+        let line = serde_json::to_string(&record)
+            .wrap_err("failed to serialize audit record")
+            .map_err(AuditSinkError::UnexpectedError)?;
+
+        println!("{}", line);
+
+        *last_record = Some(record.clone());
+        Ok(record)
+    }
+
+    async fn records(&self) -> Result<Vec<AuditRecord>, AuditSinkError> {
+        Err(AuditSinkError::Unreadable)
+    }
+
+    async fn records_for(&self, _email: &str) -> Result<Vec<AuditRecord>, AuditSinkError> {
+        Err(AuditSinkError::Unreadable)
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{
+    app_state::UserStoreType,
+    domain::{Email, Password, User, UserStore, UserStoreError},
+};
+
+// Checks passwords against a directory such as `LdapUserStore` first, and against the local
+// users for anyone the directory does not know. A directory user is added locally the first time
+// they log in, so 2FA, known devices and the other per-user data work as for anyone else. The
+// random password they get is never checked while they remain in the directory.
+pub struct CompositeUserStore {
+    directory: UserStoreType,
+    local: UserStoreType,
+}
+
+impl CompositeUserStore {
+    pub fn new(directory: UserStoreType, local: UserStoreType) -> Self {
+        Self { directory, local }
+    }
+
+    async fn add_local_user(&self, email: &Email) -> Result<(), UserStoreError> {
+        let user = User {
+            email: email.clone(),
+            password: Password::random(),
+            requires_2fa: false,
+        };
+        match self.local.write().await.add_user(user).await {
+            Ok(()) | Err(UserStoreError::UserAlreadyExists) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl UserStore for CompositeUserStore {
+    // Signing up adds local users; the signup route rejects addresses the directory has, and all
+    // addresses while it cannot be reached
+    async fn add_user(&mut self, user: User) -> Result<(), UserStoreError> {
+        self.local.write().await.add_user(user).await
+    }
+
+    // The local user wins, so directory users keep their 2FA setting after the first login
+    async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
+        match self.local.read().await.get_user(email).await {
+            Err(UserStoreError::UserNotFound) => {}
+            result => return result,
+        }
+        self.directory.read().await.get_user(email).await
+    }
+
+    // A wrong directory password never falls back to a local one. Users the directory does not
+    // have, or all users while it cannot be reached, are checked locally; directory users only
+    // have a random password here, so an outage locks them out but not local users.
+    async fn validate_user(
+        &self,
+        email: &Email,
+        password: &Password,
+    ) -> Result<(), UserStoreError> {
+        match self
+            .directory
+            .read()
+            .await
+            .validate_user(email, password)
+            .await
+        {
+            Ok(()) => self.add_local_user(email).await,
+            Err(UserStoreError::UserNotFound) => {
+                self.local.read().await.validate_user(email, password).await
+            }
+            Err(UserStoreError::UnexpectedError(e)) => {
+                tracing::error!("Directory unavailable, checking local users: {:?}", e);
+                self.local.read().await.validate_user(email, password).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn require_password_reset(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        self.local.write().await.require_password_reset(email).await
+    }
+
+    async fn reset_password(
+        &mut self,
+        email: &Email,
+        password: Password,
+    ) -> Result<(), UserStoreError> {
+        self.local
+            .write()
+            .await
+            .reset_password(email, password)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use secrecy::Secret;
+    use tokio::sync::RwLock;
+
+    use super::*;
+    use crate::services::HashmapUserStore;
+
+    async fn store_with(directory: Vec<User>, local: Vec<User>) -> CompositeUserStore {
+        let mut directory_store = HashmapUserStore::default();
+        for user in directory {
+            directory_store.add_user(user).await.unwrap();
+        }
+        let mut local_store = HashmapUserStore::default();
+        for user in local {
+            local_store.add_user(user).await.unwrap();
+        }
+        CompositeUserStore::new(
+            Arc::new(RwLock::new(directory_store)),
+            Arc::new(RwLock::new(local_store)),
+        )
+    }
+
+    fn password(password: &str) -> Password {
+        Password::parse(Secret::new(password.to_owned())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn directory_users_are_added_locally_on_first_login() {
+        let user = User::new("dir@example.com", "directory1", false).unwrap();
+        let store = store_with(vec![user.clone()], vec![]).await;
+
+        assert!(store.get_user(&user.email).await.is_ok());
+        assert_eq!(
+            store
+                .local
+                .read()
+                .await
+                .get_user(&user.email)
+                .await
+                .unwrap_err(),
+            UserStoreError::UserNotFound
+        );
+
+        assert_eq!(
+            store
+                .validate_user(&user.email, &password("directory1"))
+                .await,
+            Ok(())
+        );
+        let local = store
+            .local
+            .read()
+            .await
+            .get_user(&user.email)
+            .await
+            .unwrap();
+        assert_ne!(local.password, user.password);
+
+        // Logging in again finds the local user already there
+        assert_eq!(
+            store
+                .validate_user(&user.email, &password("directory1"))
+                .await,
+            Ok(())
+        );
+    }
+
+    #[tokio::test]
+    async fn only_users_missing_from_the_directory_fall_back() {
+        let directory_user = User::new("dir@example.com", "directory1", false).unwrap();
+        let stale_local_user = User::new("dir@example.com", "oldpassword", false).unwrap();
+        let local_user = User::new("local@example.com", "localpass1", true).unwrap();
+        let store = store_with(
+            vec![directory_user.clone()],
+            vec![stale_local_user, local_user.clone()],
+        )
+        .await;
+
+        assert_eq!(
+            store
+                .validate_user(&directory_user.email, &password("oldpassword"))
+                .await,
+            Err(UserStoreError::InvalidCredentials)
+        );
+        assert_eq!(
+            store
+                .validate_user(&local_user.email, &password("localpass1"))
+                .await,
+            Ok(())
+        );
+        assert_eq!(store.get_user(&local_user.email).await, Ok(local_user));
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use crate::domain::{
+    AuthorizationCode, AuthorizationCodeStore, AuthorizationCodeStoreError, AuthorizationGrant,
+};
+
+#[derive(Default)]
+pub struct HashMapAuthorizationCodeStore {
+    codes: HashMap<String, AuthorizationGrant>,
+}
+
+#[async_trait::async_trait]
+impl AuthorizationCodeStore for HashMapAuthorizationCodeStore {
+    async fn add_code(
+        &mut self,
+        code: AuthorizationCode,
+        grant: AuthorizationGrant,
+    ) -> Result<(), AuthorizationCodeStoreError> {
+        self.codes.insert(code.as_ref().to_owned(), grant);
+        Ok(())
+    }
+
+    async fn take_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationGrant, AuthorizationCodeStoreError> {
+        self.codes
+            .remove(code.as_ref())
+            .ok_or(AuthorizationCodeStoreError::CodeNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Email;
+    use secrecy::Secret;
+
+    #[tokio::test]
+    async fn test_codes_can_only_be_taken_once() {
+        let mut store = HashMapAuthorizationCodeStore::default();
+        let code = AuthorizationCode::default();
+        let grant = AuthorizationGrant {
+            client_id: "client".to_owned(),
+            redirect_uri: "https://app.example.com/callback".to_owned(),
+            scopes: vec!["profile".to_owned()],
+            email: Email::parse(Secret::new("test@example.com".to_owned())).unwrap(),
+            code_challenge: "challenge".to_owned(),
+            nonce: None,
+            auth_time: None,
+            amr: Vec::new(),
+            sid: None,
+        };
+
+        store.add_code(code.clone(), grant.clone()).await.unwrap();
+
+        assert_eq!(store.take_code(&code).await.unwrap(), grant);
+        assert_eq!(
+            store.take_code(&code).await.unwrap_err(),
+            AuthorizationCodeStoreError::CodeNotFound
+        );
+    }
+}
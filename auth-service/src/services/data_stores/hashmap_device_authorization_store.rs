@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::domain::{
+    DeviceAuthorization, DeviceAuthorizationStore, DeviceAuthorizationStoreError, DeviceCode,
+    DevicePoll, UserCode,
+};
+
+#[derive(Default)]
+pub struct HashMapDeviceAuthorizationStore {
+    authorizations: HashMap<String, DeviceAuthorization>,
+    polls: HashMap<String, DevicePoll>,
+}
+
+#[async_trait::async_trait]
+impl DeviceAuthorizationStore for HashMapDeviceAuthorizationStore {
+    async fn add_authorization(
+        &mut self,
+        device_code: DeviceCode,
+        authorization: DeviceAuthorization,
+    ) -> Result<(), DeviceAuthorizationStoreError> {
+        self.authorizations
+            .insert(device_code.as_ref().to_owned(), authorization);
+        Ok(())
+    }
+
+    async fn get_authorization(
+        &self,
+        device_code: &DeviceCode,
+    ) -> Result<DeviceAuthorization, DeviceAuthorizationStoreError> {
+        self.authorizations
+            .get(device_code.as_ref())
+            .cloned()
+            .ok_or(DeviceAuthorizationStoreError::AuthorizationNotFound)
+    }
+
+    async fn get_device_code(
+        &self,
+        user_code: &UserCode,
+    ) -> Result<DeviceCode, DeviceAuthorizationStoreError> {
+        self.authorizations
+            .iter()
+            .find(|(_, authorization)| &authorization.user_code == user_code)
+            .and_then(|(device_code, _)| DeviceCode::parse(device_code.clone()).ok())
+            .ok_or(DeviceAuthorizationStoreError::AuthorizationNotFound)
+    }
+
+    async fn update_authorization(
+        &mut self,
+        device_code: &DeviceCode,
+        authorization: DeviceAuthorization,
+    ) -> Result<(), DeviceAuthorizationStoreError> {
+        let stored = self
+            .authorizations
+            .get_mut(device_code.as_ref())
+            .ok_or(DeviceAuthorizationStoreError::AuthorizationNotFound)?;
+        *stored = authorization;
+        Ok(())
+    }
+
+    async fn take_authorization(
+        &mut self,
+        device_code: &DeviceCode,
+    ) -> Result<DeviceAuthorization, DeviceAuthorizationStoreError> {
+        self.polls.remove(device_code.as_ref());
+        self.authorizations
+            .remove(device_code.as_ref())
+            .ok_or(DeviceAuthorizationStoreError::AuthorizationNotFound)
+    }
+
+    async fn record_poll(
+        &mut self,
+        device_code: &DeviceCode,
+        now: DateTime<Utc>,
+    ) -> Result<bool, DeviceAuthorizationStoreError> {
+        let (poll, too_soon) = DevicePoll::next(self.polls.get(device_code.as_ref()), now);
+        self.polls.insert(device_code.as_ref().to_owned(), poll);
+        Ok(too_soon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DeviceAuthorizationStatus;
+
+    fn authorization() -> DeviceAuthorization {
+        DeviceAuthorization {
+            client_id: "client".to_owned(),
+            scopes: vec!["profile".to_owned()],
+            user_code: UserCode::default(),
+            status: DeviceAuthorizationStatus::Pending,
+            expires_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_authorizations_are_found_by_user_code_and_taken_once() {
+        let mut store = HashMapDeviceAuthorizationStore::default();
+        let device_code = DeviceCode::default();
+        let mut authorization = authorization();
+        store
+            .add_authorization(device_code.clone(), authorization.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store
+                .get_device_code(&authorization.user_code)
+                .await
+                .unwrap(),
+            device_code
+        );
+        assert_eq!(
+            store
+                .get_device_code(&UserCode::default())
+                .await
+                .unwrap_err(),
+            DeviceAuthorizationStoreError::AuthorizationNotFound
+        );
+
+        authorization.status = DeviceAuthorizationStatus::Denied;
+        store
+            .update_authorization(&device_code, authorization.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            store.take_authorization(&device_code).await.unwrap(),
+            authorization
+        );
+        assert_eq!(
+            store.take_authorization(&device_code).await.unwrap_err(),
+            DeviceAuthorizationStoreError::AuthorizationNotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn test_polls_too_soon_are_reported() {
+        let mut store = HashMapDeviceAuthorizationStore::default();
+        let device_code = DeviceCode::default();
+        let now = Utc::now();
+
+        assert!(!store.record_poll(&device_code, now).await.unwrap());
+        assert!(store.record_poll(&device_code, now).await.unwrap());
+    }
+}
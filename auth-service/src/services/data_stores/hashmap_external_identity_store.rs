@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use crate::domain::{ExternalIdentity, ExternalIdentityStore, ExternalIdentityStoreError};
+
+#[derive(Default)]
+pub struct HashMapExternalIdentityStore {
+    // Keyed by issuer and subject
+    identities: HashMap<(String, String), ExternalIdentity>,
+}
+
+#[async_trait::async_trait]
+impl ExternalIdentityStore for HashMapExternalIdentityStore {
+    async fn add_identity(
+        &mut self,
+        identity: ExternalIdentity,
+    ) -> Result<(), ExternalIdentityStoreError> {
+        let key = (identity.issuer.clone(), identity.subject.clone());
+        if self.identities.contains_key(&key) {
+            return Err(ExternalIdentityStoreError::IdentityAlreadyLinked);
+        }
+        self.identities.insert(key, identity);
+        Ok(())
+    }
+
+    async fn get_identity(
+        &self,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<ExternalIdentity, ExternalIdentityStoreError> {
+        self.identities
+            .get(&(issuer.to_owned(), subject.to_owned()))
+            .cloned()
+            .ok_or(ExternalIdentityStoreError::IdentityNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use secrecy::Secret;
+
+    use super::*;
+    use crate::domain::Email;
+
+    #[tokio::test]
+    async fn test_add_and_get_identity() {
+        let mut store = HashMapExternalIdentityStore::default();
+        let identity = ExternalIdentity {
+            issuer: "https://idp.example.com".to_owned(),
+            subject: "248289761001".to_owned(),
+            email: Email::parse(Secret::new("test@example.com".to_owned())).unwrap(),
+            linked_at: Utc::now(),
+        };
+
+        store.add_identity(identity.clone()).await.unwrap();
+        assert_eq!(
+            store
+                .get_identity(&identity.issuer, &identity.subject)
+                .await
+                .unwrap(),
+            identity
+        );
+        assert_eq!(
+            store.add_identity(identity.clone()).await.unwrap_err(),
+            ExternalIdentityStoreError::IdentityAlreadyLinked
+        );
+
+        // The subject is only unique within its issuer
+        assert_eq!(
+            store
+                .get_identity("https://other.example.com", &identity.subject)
+                .await
+                .unwrap_err(),
+            ExternalIdentityStoreError::IdentityNotFound
+        );
+    }
+}
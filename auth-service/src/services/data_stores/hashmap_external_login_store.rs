@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use crate::domain::{
+    ExternalLoginState, ExternalLoginStore, ExternalLoginStoreError, PendingExternalLogin,
+};
+
+#[derive(Default)]
+pub struct HashMapExternalLoginStore {
+    logins: HashMap<String, PendingExternalLogin>,
+}
+
+#[async_trait::async_trait]
+impl ExternalLoginStore for HashMapExternalLoginStore {
+    async fn add_login(
+        &mut self,
+        state: ExternalLoginState,
+        login: PendingExternalLogin,
+    ) -> Result<(), ExternalLoginStoreError> {
+        self.logins.insert(state.as_ref().to_owned(), login);
+        Ok(())
+    }
+
+    async fn take_login(
+        &mut self,
+        state: &ExternalLoginState,
+    ) -> Result<PendingExternalLogin, ExternalLoginStoreError> {
+        self.logins
+            .remove(state.as_ref())
+            .ok_or(ExternalLoginStoreError::LoginNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_logins_can_only_be_taken_once() {
+        let mut store = HashMapExternalLoginStore::default();
+        let state = ExternalLoginState::default();
+        let login = PendingExternalLogin::new(Some("/authorize?client_id=app".to_owned()));
+
+        store.add_login(state.clone(), login.clone()).await.unwrap();
+        assert_eq!(store.take_login(&state).await.unwrap(), login);
+        assert_eq!(
+            store.take_login(&state).await.unwrap_err(),
+            ExternalLoginStoreError::LoginNotFound
+        );
+    }
+}
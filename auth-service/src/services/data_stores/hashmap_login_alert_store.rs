@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use crate::domain::{LoginAlert, LoginAlertStore, LoginAlertStoreError, LoginAlertToken};
+
+#[derive(Default)]
+pub struct HashmapLoginAlertStore {
+    alerts: HashMap<String, LoginAlert>,
+}
+
+#[async_trait::async_trait]
+impl LoginAlertStore for HashmapLoginAlertStore {
+    async fn add_alert(
+        &mut self,
+        token: LoginAlertToken,
+        alert: LoginAlert,
+    ) -> Result<(), LoginAlertStoreError> {
+        self.alerts.insert(token.as_ref().to_owned(), alert);
+        Ok(())
+    }
+
+    async fn get_alert(&self, token: &LoginAlertToken) -> Result<LoginAlert, LoginAlertStoreError> {
+        self.alerts
+            .get(token.as_ref())
+            .cloned()
+            .ok_or(LoginAlertStoreError::AlertNotFound)
+    }
+
+    async fn remove_alert(&mut self, token: &LoginAlertToken) -> Result<(), LoginAlertStoreError> {
+        self.alerts.remove(token.as_ref());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{DeviceFingerprint, Email};
+    use secrecy::Secret;
+
+    #[tokio::test]
+    async fn test_add_get_and_remove_alert() {
+        let mut store = HashmapLoginAlertStore::default();
+        let token = LoginAlertToken::default();
+        let alert = LoginAlert {
+            email: Email::parse(Secret::new("test@example.com".to_owned())).unwrap(),
+            session_token: Secret::new("session".to_owned()),
+            device: DeviceFingerprint {
+                user_agent: "test-agent".to_owned(),
+                ip_range: "10.0.0.0/24".to_owned(),
+            },
+        };
+
+        store.add_alert(token.clone(), alert.clone()).await.unwrap();
+
+        let stored = store.get_alert(&token).await.unwrap();
+        assert_eq!(stored.email, alert.email);
+        assert_eq!(stored.device, alert.device);
+
+        store.remove_alert(&token).await.unwrap();
+        assert_eq!(
+            store.get_alert(&token).await.unwrap_err(),
+            LoginAlertStoreError::AlertNotFound
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use chrono::{DateTime, Utc};
+
+use crate::domain::{Email, LoginHistoryEntry, LoginHistoryStore, LoginHistoryStoreError};
+
+#[derive(Default)]
+pub struct HashmapLoginHistoryStore {
+    entries: Vec<LoginHistoryEntry>,
+}
+
+#[async_trait::async_trait]
+impl LoginHistoryStore for HashmapLoginHistoryStore {
+    async fn add_entry(&mut self, entry: LoginHistoryEntry) -> Result<(), LoginHistoryStoreError> {
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    async fn get_entries(
+        &self,
+        email: &Email,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<LoginHistoryEntry>, LoginHistoryStoreError> {
+        let mut entries: Vec<LoginHistoryEntry> = self
+            .entries
+            .iter()
+            .rev()
+            .filter(|entry| &entry.email == email)
+            .cloned()
+            .collect();
+
+        // Newest first, matching the Postgres store. The sort is stable, so entries
+        // sharing a timestamp keep reverse insertion order.
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.occurred_at));
+
+        Ok(entries
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn count_entries(&self, email: &Email) -> Result<u64, LoginHistoryStoreError> {
+        Ok(self
+            .entries
+            .iter()
+            .filter(|entry| &entry.email == email)
+            .count() as u64)
+    }
+
+    async fn delete_entries_before(
+        &mut self,
+        cutoff: DateTime<Utc>,
+    ) -> Result<u64, LoginHistoryStoreError> {
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.occurred_at >= cutoff);
+        Ok((before - self.entries.len()) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use secrecy::Secret;
+
+    use super::*;
+    use crate::domain::{ClientInfo, LoginFailureReason, LoginOutcome};
+
+    fn parse_email(s: &str) -> Email {
+        Email::parse(Secret::new(s.to_string())).unwrap()
+    }
+
+    fn entry(email: &Email, minutes_ago: i64, outcome: LoginOutcome) -> LoginHistoryEntry {
+        let mut entry =
+            LoginHistoryEntry::new(email.clone(), &ClientInfo::default(), outcome, false);
+        entry.occurred_at = Utc::now() - Duration::minutes(minutes_ago);
+        entry
+    }
+
+    #[tokio::test]
+    async fn test_get_entries_returns_newest_first() {
+        let mut store = HashmapLoginHistoryStore::default();
+        let email = parse_email("test@example.com");
+
+        store
+            .add_entry(entry(&email, 10, LoginOutcome::Success))
+            .await
+            .unwrap();
+        store
+            .add_entry(entry(
+                &email,
+                5,
+                LoginOutcome::Failure(LoginFailureReason::IncorrectPassword),
+            ))
+            .await
+            .unwrap();
+
+        let entries = store.get_entries(&email, 10, 0).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(!entries[0].outcome.is_success());
+        assert!(entries[1].outcome.is_success());
+    }
+
+    #[tokio::test]
+    async fn test_get_entries_paginates_and_filters_by_email() {
+        let mut store = HashmapLoginHistoryStore::default();
+        let email = parse_email("test@example.com");
+        let other = parse_email("other@example.com");
+
+        for minutes_ago in 0..5 {
+            store
+                .add_entry(entry(&email, minutes_ago, LoginOutcome::Success))
+                .await
+                .unwrap();
+        }
+        store
+            .add_entry(entry(&other, 0, LoginOutcome::Success))
+            .await
+            .unwrap();
+
+        assert_eq!(store.count_entries(&email).await.unwrap(), 5);
+        assert_eq!(store.get_entries(&email, 2, 0).await.unwrap().len(), 2);
+        assert_eq!(store.get_entries(&email, 2, 4).await.unwrap().len(), 1);
+        assert_eq!(store.get_entries(&other, 10, 0).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_delete_entries_before_removes_old_entries() {
+        let mut store = HashmapLoginHistoryStore::default();
+        let email = parse_email("test@example.com");
+
+        store
+            .add_entry(entry(&email, 60 * 24 * 100, LoginOutcome::Success))
+            .await
+            .unwrap();
+        store
+            .add_entry(entry(&email, 1, LoginOutcome::Success))
+            .await
+            .unwrap();
+
+        let deleted = store
+            .delete_entries_before(Utc::now() - Duration::days(90))
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+        assert_eq!(store.count_entries(&email).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use crate::domain::{Email, MagicLinkStore, MagicLinkStoreError, MagicLinkToken};
+
+#[derive(Default)]
+pub struct HashMapMagicLinkStore {
+    links: HashMap<String, Email>,
+}
+
+#[async_trait::async_trait]
+impl MagicLinkStore for HashMapMagicLinkStore {
+    async fn add_link(
+        &mut self,
+        token: MagicLinkToken,
+        email: Email,
+    ) -> Result<(), MagicLinkStoreError> {
+        self.links.insert(token.as_ref().to_owned(), email);
+        Ok(())
+    }
+
+    async fn take_link(&mut self, token: &MagicLinkToken) -> Result<Email, MagicLinkStoreError> {
+        self.links
+            .remove(token.as_ref())
+            .ok_or(MagicLinkStoreError::LinkNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::Secret;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_links_can_only_be_taken_once() {
+        let mut store = HashMapMagicLinkStore::default();
+        let token = MagicLinkToken::default();
+        let email = Email::parse(Secret::new("test@example.com".to_owned())).unwrap();
+
+        store.add_link(token.clone(), email.clone()).await.unwrap();
+        assert_eq!(store.take_link(&token).await.unwrap(), email);
+        assert_eq!(
+            store.take_link(&token).await.unwrap_err(),
+            MagicLinkStoreError::LinkNotFound
+        );
+        assert_eq!(
+            store
+                .take_link(&MagicLinkToken::default())
+                .await
+                .unwrap_err(),
+            MagicLinkStoreError::LinkNotFound
+        );
+    }
+}
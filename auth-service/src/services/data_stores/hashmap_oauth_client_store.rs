@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use crate::domain::{OAuthClient, OAuthClientStore, OAuthClientStoreError};
+
+#[derive(Default)]
+pub struct HashMapOAuthClientStore {
+    clients: HashMap<String, OAuthClient>,
+}
+
+#[async_trait::async_trait]
+impl OAuthClientStore for HashMapOAuthClientStore {
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError> {
+        self.clients.insert(client.client_id.clone(), client);
+        Ok(())
+    }
+
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError> {
+        self.clients
+            .get(client_id)
+            .cloned()
+            .ok_or(OAuthClientStoreError::ClientNotFound)
+    }
+
+    async fn get_clients(&self) -> Result<Vec<OAuthClient>, OAuthClientStoreError> {
+        let mut clients: Vec<OAuthClient> = self.clients.values().cloned().collect();
+        clients.sort_by_key(|client| client.created_at);
+        Ok(clients)
+    }
+
+    async fn delete_client(&mut self, client_id: &str) -> Result<(), OAuthClientStoreError> {
+        self.clients
+            .remove(client_id)
+            .map(|_| ())
+            .ok_or(OAuthClientStoreError::ClientNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_add_get_and_delete_client() {
+        let mut store = HashMapOAuthClientStore::default();
+        let client = OAuthClient::new(
+            "Example".to_owned(),
+            vec!["https://app.example.com/callback".to_owned()],
+            vec!["profile".to_owned()],
+        );
+
+        store.add_client(client.clone()).await.unwrap();
+        assert_eq!(store.get_client(&client.client_id).await.unwrap(), client);
+        assert_eq!(store.get_clients().await.unwrap(), vec![client.clone()]);
+
+        store.delete_client(&client.client_id).await.unwrap();
+        assert_eq!(
+            store.get_client(&client.client_id).await.unwrap_err(),
+            OAuthClientStoreError::ClientNotFound
+        );
+        assert_eq!(
+            store.delete_client(&client.client_id).await.unwrap_err(),
+            OAuthClientStoreError::ClientNotFound
+        );
+    }
+}
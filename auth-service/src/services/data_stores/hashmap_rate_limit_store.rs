@@ -0,0 +1,66 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::domain::{RateLimitStore, RateLimitStoreError};
+
+#[derive(Default)]
+pub struct HashmapRateLimitStore {
+    // Maps a key to the number of hits and the instant its window started
+    windows: HashMap<String, (u64, Instant)>,
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for HashmapRateLimitStore {
+    async fn record_hit(
+        &mut self,
+        key: &str,
+        window_seconds: u64,
+    ) -> Result<u64, RateLimitStoreError> {
+        let now = Instant::now();
+        let window = Duration::from_secs(window_seconds);
+
+        let entry = self.windows.entry(key.to_owned()).or_insert((0, now));
+
+        // Start a fresh window once the previous one has expired
+        if now.duration_since(entry.1) >= window {
+            *entry = (0, now);
+        }
+
+        entry.0 += 1;
+        Ok(entry.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_hit_counts_hits_in_window() {
+        let mut store = HashmapRateLimitStore::default();
+
+        assert_eq!(store.record_hit("key", 60).await.unwrap(), 1);
+        assert_eq!(store.record_hit("key", 60).await.unwrap(), 2);
+        assert_eq!(store.record_hit("key", 60).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_record_hit_tracks_keys_separately() {
+        let mut store = HashmapRateLimitStore::default();
+
+        assert_eq!(store.record_hit("first", 60).await.unwrap(), 1);
+        assert_eq!(store.record_hit("second", 60).await.unwrap(), 1);
+        assert_eq!(store.record_hit("first", 60).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_record_hit_resets_after_window() {
+        let mut store = HashmapRateLimitStore::default();
+
+        assert_eq!(store.record_hit("key", 0).await.unwrap(), 1);
+        // A zero-second window has always expired by the next hit
+        assert_eq!(store.record_hit("key", 0).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use crate::domain::{ServiceClient, ServiceClientStore, ServiceClientStoreError};
+
+#[derive(Default)]
+pub struct HashMapServiceClientStore {
+    clients: HashMap<String, ServiceClient>,
+}
+
+#[async_trait::async_trait]
+impl ServiceClientStore for HashMapServiceClientStore {
+    async fn add_client(&mut self, client: ServiceClient) -> Result<(), ServiceClientStoreError> {
+        self.clients.insert(client.client_id.clone(), client);
+        Ok(())
+    }
+
+    async fn get_client(&self, client_id: &str) -> Result<ServiceClient, ServiceClientStoreError> {
+        self.clients
+            .get(client_id)
+            .cloned()
+            .ok_or(ServiceClientStoreError::ClientNotFound)
+    }
+
+    async fn get_clients(&self) -> Result<Vec<ServiceClient>, ServiceClientStoreError> {
+        let mut clients: Vec<ServiceClient> = self.clients.values().cloned().collect();
+        clients.sort_by_key(|client| client.created_at);
+        Ok(clients)
+    }
+
+    async fn delete_client(&mut self, client_id: &str) -> Result<(), ServiceClientStoreError> {
+        self.clients
+            .remove(client_id)
+            .map(|_| ())
+            .ok_or(ServiceClientStoreError::ClientNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_add_get_and_delete_client() {
+        let mut store = HashMapServiceClientStore::default();
+        let (client, _) = ServiceClient::new("Nightly job".to_owned(), vec!["read".to_owned()]);
+
+        store.add_client(client.clone()).await.unwrap();
+        assert_eq!(store.get_client(&client.client_id).await.unwrap(), client);
+        assert_eq!(store.get_clients().await.unwrap(), vec![client.clone()]);
+
+        store.delete_client(&client.client_id).await.unwrap();
+        assert_eq!(
+            store.get_client(&client.client_id).await.unwrap_err(),
+            ServiceClientStoreError::ClientNotFound
+        );
+        assert_eq!(
+            store.delete_client(&client.client_id).await.unwrap_err(),
+            ServiceClientStoreError::ClientNotFound
+        );
+    }
+}
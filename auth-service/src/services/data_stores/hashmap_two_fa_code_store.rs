@@ -1,13 +1,13 @@
 use std::collections::HashMap;
 
 use crate::domain::{
-    data_stores::{LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError},
+    data_stores::{FirstFactor, LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError},
     email::Email,
 };
 
 #[derive(Default)]
 pub struct HashmapTwoFACodeStore {
-    codes: HashMap<Email, (LoginAttemptId, TwoFACode)>,
+    codes: HashMap<Email, (LoginAttemptId, TwoFACode, FirstFactor)>,
 }
 
 // TODO: implement TwoFACodeStore for HashmapTwoFACodeStore
@@ -19,8 +19,10 @@ impl TwoFACodeStore for HashmapTwoFACodeStore {
         email: Email,
         login_attempt_id: LoginAttemptId,
         code: TwoFACode,
+        first_factor: FirstFactor,
     ) -> Result<(), TwoFACodeStoreError> {
-        self.codes.insert(email, (login_attempt_id, code));
+        self.codes
+            .insert(email, (login_attempt_id, code, first_factor));
         Ok(())
     }
     async fn remove_code(&mut self, email: &Email) -> Result<(), TwoFACodeStoreError> {
@@ -32,7 +34,7 @@ impl TwoFACodeStore for HashmapTwoFACodeStore {
     async fn get_code(
         &self,
         email: &Email,
-    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+    ) -> Result<(LoginAttemptId, TwoFACode, FirstFactor), TwoFACodeStoreError> {
         self.codes
             .get(email)
             .cloned()
@@ -54,7 +56,12 @@ mod tests {
         let login_attempt_id = LoginAttemptId::default();
         let code = TwoFACode::default();
         assert!(store
-            .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+            .add_code(
+                email.clone(),
+                login_attempt_id.clone(),
+                code.clone(),
+                FirstFactor::Password,
+            )
             .await
             .is_ok());
         assert!(store.codes.contains_key(&email));
@@ -69,7 +76,12 @@ mod tests {
         let login_attempt_id = LoginAttemptId::default();
         let code = TwoFACode::default();
         store
-            .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+            .add_code(
+                email.clone(),
+                login_attempt_id.clone(),
+                code.clone(),
+                FirstFactor::Password,
+            )
             .await
             .unwrap();
         assert!(store.remove_code(&email).await.is_ok());
@@ -83,12 +95,17 @@ mod tests {
         let login_attempt_id = LoginAttemptId::default();
         let code = TwoFACode::default();
         store
-            .add_code(email.clone(), login_attempt_id.clone(), code.clone())
+            .add_code(
+                email.clone(),
+                login_attempt_id.clone(),
+                code.clone(),
+                FirstFactor::MagicLink,
+            )
             .await
             .unwrap();
         assert_eq!(
             store.get_code(&email).await.unwrap(),
-            (login_attempt_id, code)
+            (login_attempt_id, code, FirstFactor::MagicLink)
         );
     }
 
@@ -1,10 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::domain::{Email, Password, User, UserStore, UserStoreError};
 
 #[derive(Default)]
 pub struct HashmapUserStore {
     users: HashMap<Email, User>,
+    password_reset_required: HashSet<Email>,
 }
 
 #[async_trait::async_trait]
@@ -33,12 +34,36 @@ impl UserStore for HashmapUserStore {
         password: &Password,
     ) -> Result<(), UserStoreError> {
         let user = self.get_user(email).await?;
-        if &user.password == password {
-            Ok(())
-        } else {
+        if &user.password != password {
             Err(UserStoreError::InvalidCredentials)
+        } else if self.password_reset_required.contains(email) {
+            Err(UserStoreError::PasswordResetRequired)
+        } else {
+            Ok(())
         }
     }
+
+    async fn require_password_reset(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        if !self.users.contains_key(email) {
+            return Err(UserStoreError::UserNotFound);
+        }
+        self.password_reset_required.insert(email.clone());
+        Ok(())
+    }
+
+    async fn reset_password(
+        &mut self,
+        email: &Email,
+        password: Password,
+    ) -> Result<(), UserStoreError> {
+        let user = self
+            .users
+            .get_mut(email)
+            .ok_or(UserStoreError::UserNotFound)?;
+        user.password = password;
+        self.password_reset_required.remove(email);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -91,4 +116,31 @@ mod tests {
             Err(UserStoreError::InvalidCredentials)
         );
     }
+
+    #[tokio::test]
+    async fn test_password_reset() {
+        let mut store = HashmapUserStore::default();
+        let user = User::new("test@example.com", "pasword123", false).unwrap();
+        store.add_user(user.clone()).await.unwrap();
+
+        store.require_password_reset(&user.email).await.unwrap();
+        assert_eq!(
+            store.validate_user(&user.email, &user.password).await,
+            Err(UserStoreError::PasswordResetRequired)
+        );
+
+        let new_password = Password::parse(Secret::new("newpasword123".to_string())).unwrap();
+        store
+            .reset_password(&user.email, new_password.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            store.validate_user(&user.email, &new_password).await,
+            Ok(())
+        );
+        assert_eq!(
+            store.validate_user(&user.email, &user.password).await,
+            Err(UserStoreError::InvalidCredentials)
+        );
+    }
 }
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::domain::{
+    WebhookDeadLetter, WebhookDelivery, WebhookStore, WebhookStoreError, WebhookSubscription,
+};
+
+#[derive(Default)]
+pub struct HashMapWebhookStore {
+    subscriptions: HashMap<Uuid, WebhookSubscription>,
+    deliveries: HashMap<Uuid, WebhookDelivery>,
+    dead_letters: HashMap<Uuid, WebhookDeadLetter>,
+}
+
+#[async_trait::async_trait]
+impl WebhookStore for HashMapWebhookStore {
+    async fn add_subscription(
+        &mut self,
+        subscription: WebhookSubscription,
+    ) -> Result<(), WebhookStoreError> {
+        self.subscriptions.insert(subscription.id, subscription);
+        Ok(())
+    }
+
+    async fn get_subscription(&self, id: Uuid) -> Result<WebhookSubscription, WebhookStoreError> {
+        self.subscriptions
+            .get(&id)
+            .cloned()
+            .ok_or(WebhookStoreError::SubscriptionNotFound)
+    }
+
+    async fn get_subscriptions(&self) -> Result<Vec<WebhookSubscription>, WebhookStoreError> {
+        let mut subscriptions: Vec<WebhookSubscription> =
+            self.subscriptions.values().cloned().collect();
+        subscriptions.sort_by_key(|subscription| subscription.created_at);
+        Ok(subscriptions)
+    }
+
+    async fn delete_subscription(&mut self, id: Uuid) -> Result<(), WebhookStoreError> {
+        self.subscriptions
+            .remove(&id)
+            .ok_or(WebhookStoreError::SubscriptionNotFound)?;
+        self.deliveries
+            .retain(|_, delivery| delivery.subscription_id != id);
+        self.dead_letters
+            .retain(|_, dead_letter| dead_letter.delivery.subscription_id != id);
+        Ok(())
+    }
+
+    async fn enqueue_delivery(
+        &mut self,
+        delivery: WebhookDelivery,
+    ) -> Result<(), WebhookStoreError> {
+        if !self.subscriptions.contains_key(&delivery.subscription_id) {
+            return Err(WebhookStoreError::SubscriptionNotFound);
+        }
+        self.deliveries.insert(delivery.id, delivery);
+        Ok(())
+    }
+
+    async fn claim_due_deliveries(
+        &mut self,
+        limit: u32,
+        lease: std::time::Duration,
+    ) -> Result<Vec<WebhookDelivery>, WebhookStoreError> {
+        let now = Utc::now();
+        let lease = chrono::Duration::from_std(lease)
+            .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))?;
+
+        let mut due: Vec<&mut WebhookDelivery> = self
+            .deliveries
+            .values_mut()
+            .filter(|delivery| delivery.next_attempt_at <= now)
+            .collect();
+        due.sort_by_key(|delivery| delivery.next_attempt_at);
+
+        let claimed = due
+            .into_iter()
+            .take(limit as usize)
+            .map(|delivery| {
+                let claimed = delivery.clone();
+                delivery.next_attempt_at = now + lease;
+                claimed
+            })
+            .collect();
+
+        Ok(claimed)
+    }
+
+    async fn remove_delivery(&mut self, id: Uuid) -> Result<(), WebhookStoreError> {
+        self.deliveries.remove(&id);
+        Ok(())
+    }
+
+    async fn schedule_retry(
+        &mut self,
+        delivery: &WebhookDelivery,
+    ) -> Result<(), WebhookStoreError> {
+        let queued = self
+            .deliveries
+            .get_mut(&delivery.id)
+            .ok_or(WebhookStoreError::DeliveryNotFound)?;
+        queued.attempts = delivery.attempts;
+        queued.next_attempt_at = delivery.next_attempt_at;
+        queued.last_error = delivery.last_error.clone();
+        Ok(())
+    }
+
+    async fn dead_letter(&mut self, delivery: WebhookDelivery) -> Result<(), WebhookStoreError> {
+        self.deliveries.remove(&delivery.id);
+        self.dead_letters.insert(
+            delivery.id,
+            WebhookDeadLetter {
+                delivery,
+                failed_at: Utc::now(),
+            },
+        );
+        Ok(())
+    }
+
+    async fn get_dead_letters(&self) -> Result<Vec<WebhookDeadLetter>, WebhookStoreError> {
+        let mut dead_letters: Vec<WebhookDeadLetter> =
+            self.dead_letters.values().cloned().collect();
+        dead_letters.sort_by_key(|dead_letter| dead_letter.failed_at);
+        Ok(dead_letters)
+    }
+
+    async fn replay_dead_letter(&mut self, id: Uuid) -> Result<(), WebhookStoreError> {
+        let dead_letter = self
+            .dead_letters
+            .remove(&id)
+            .ok_or(WebhookStoreError::DeliveryNotFound)?;
+
+        let delivery = WebhookDelivery {
+            attempts: 0,
+            next_attempt_at: Utc::now(),
+            last_error: None,
+            ..dead_letter.delivery
+        };
+        self.deliveries.insert(delivery.id, delivery);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::WebhookEventType;
+    use secrecy::Secret;
+    use std::time::Duration;
+
+    fn subscription() -> WebhookSubscription {
+        WebhookSubscription::new(
+            "https://example.com/hooks".to_owned(),
+            Secret::new("secret".to_owned()),
+            vec![WebhookEventType::UserSignedUp],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_claimed_deliveries_are_hidden_until_the_lease_expires() {
+        let mut store = HashMapWebhookStore::default();
+        let subscription = subscription();
+        let delivery = WebhookDelivery::new(
+            subscription.id,
+            WebhookEventType::UserSignedUp,
+            "{}".to_owned(),
+        );
+        store.add_subscription(subscription).await.unwrap();
+        store.enqueue_delivery(delivery.clone()).await.unwrap();
+
+        let claimed = store
+            .claim_due_deliveries(10, Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(claimed, vec![delivery]);
+
+        let claimed = store
+            .claim_due_deliveries(10, Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert!(claimed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_can_be_replayed() {
+        let mut store = HashMapWebhookStore::default();
+        let subscription = subscription();
+        let mut delivery = WebhookDelivery::new(
+            subscription.id,
+            WebhookEventType::UserSignedUp,
+            "{}".to_owned(),
+        );
+        store.add_subscription(subscription).await.unwrap();
+        store.enqueue_delivery(delivery.clone()).await.unwrap();
+
+        delivery.attempts = 3;
+        delivery.last_error = Some("HTTP 500".to_owned());
+        store.dead_letter(delivery.clone()).await.unwrap();
+
+        assert!(store
+            .claim_due_deliveries(10, Duration::from_secs(60))
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(store.get_dead_letters().await.unwrap().len(), 1);
+
+        store.replay_dead_letter(delivery.id).await.unwrap();
+
+        assert!(store.get_dead_letters().await.unwrap().is_empty());
+        let claimed = store
+            .claim_due_deliveries(10, Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].attempts, 0);
+        assert_eq!(claimed[0].last_error, None);
+        assert_eq!(
+            store.replay_dead_letter(delivery.id).await,
+            Err(WebhookStoreError::DeliveryNotFound)
+        );
+    }
+}
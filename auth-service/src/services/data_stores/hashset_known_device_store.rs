@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+
+use crate::domain::{DeviceFingerprint, Email, KnownDeviceStore, KnownDeviceStoreError};
+
+#[derive(Default)]
+pub struct HashSetKnownDeviceStore {
+    devices: HashSet<(Email, DeviceFingerprint)>,
+}
+
+#[async_trait::async_trait]
+impl KnownDeviceStore for HashSetKnownDeviceStore {
+    async fn add_device(
+        &mut self,
+        email: &Email,
+        device: &DeviceFingerprint,
+    ) -> Result<(), KnownDeviceStoreError> {
+        self.devices.insert((email.clone(), device.clone()));
+        Ok(())
+    }
+
+    async fn is_known_device(
+        &self,
+        email: &Email,
+        device: &DeviceFingerprint,
+    ) -> Result<bool, KnownDeviceStoreError> {
+        Ok(self.devices.contains(&(email.clone(), device.clone())))
+    }
+
+    async fn has_known_devices(&self, email: &Email) -> Result<bool, KnownDeviceStoreError> {
+        Ok(self
+            .devices
+            .iter()
+            .any(|(known_email, _)| known_email == email))
+    }
+
+    async fn remove_device(
+        &mut self,
+        email: &Email,
+        device: &DeviceFingerprint,
+    ) -> Result<(), KnownDeviceStoreError> {
+        self.devices.remove(&(email.clone(), device.clone()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::Secret;
+
+    fn device(ip_range: &str) -> DeviceFingerprint {
+        DeviceFingerprint {
+            user_agent: "test-agent".to_owned(),
+            ip_range: ip_range.to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_and_check_device() {
+        let mut store = HashSetKnownDeviceStore::default();
+        let email = Email::parse(Secret::new("test@example.com".to_owned())).unwrap();
+
+        assert!(!store.has_known_devices(&email).await.unwrap());
+
+        store
+            .add_device(&email, &device("10.0.0.0/24"))
+            .await
+            .unwrap();
+
+        assert!(store.has_known_devices(&email).await.unwrap());
+        assert!(store
+            .is_known_device(&email, &device("10.0.0.0/24"))
+            .await
+            .unwrap());
+        assert!(!store
+            .is_known_device(&email, &device("10.0.1.0/24"))
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_remove_device() {
+        let mut store = HashSetKnownDeviceStore::default();
+        let email = Email::parse(Secret::new("test@example.com".to_owned())).unwrap();
+
+        store
+            .add_device(&email, &device("10.0.0.0/24"))
+            .await
+            .unwrap();
+        store
+            .remove_device(&email, &device("10.0.0.0/24"))
+            .await
+            .unwrap();
+
+        assert!(!store.has_known_devices(&email).await.unwrap());
+    }
+}
@@ -0,0 +1,196 @@
+use color_eyre::eyre::{eyre, Context, Report, Result};
+use ldap3::{ldap_escape, Ldap, LdapConnAsync, LdapConnSettings, Scope, SearchEntry};
+use secrecy::ExposeSecret;
+
+use crate::{
+    domain::{
+        data_stores::{UserStore, UserStoreError},
+        Email, Password, User,
+    },
+    utils::LdapSettings,
+};
+
+// invalidCredentials (RFC 4511 appendix A.1)
+const LDAP_INVALID_CREDENTIALS: u32 = 49;
+
+// Users kept in an LDAP or Active Directory server. Passwords are checked with search-then-bind:
+// the user's entry is found as the service account, then bound to with the password given. The
+// directory is only read, so users are added and reset there rather than through this store.
+pub struct LdapUserStore {
+    settings: LdapSettings,
+}
+
+impl LdapUserStore {
+    pub fn new(settings: LdapSettings) -> Self {
+        Self { settings }
+    }
+
+    // The configured user filter for `email`, narrowed to members of the required groups
+    fn search_filter(&self, email: &Email) -> String {
+        let filter = self
+            .settings
+            .user_filter
+            .replace("{email}", &ldap_escape(email.as_ref().expose_secret()));
+        if self.settings.required_groups.is_empty() {
+            return filter;
+        }
+
+        let groups: String = self
+            .settings
+            .required_groups
+            .iter()
+            .map(|group| format!("(memberOf={})", ldap_escape(group)))
+            .collect();
+        format!("(&{}(|{}))", filter, groups)
+    }
+
+    async fn connect(&self) -> Result<Ldap> {
+        let url = self
+            .settings
+            .url
+            .as_deref()
+            .ok_or_else(|| eyre!("ldap.url is not set"))?;
+        let conn_settings = LdapConnSettings::new()
+            .set_conn_timeout(self.settings.timeout())
+            .set_starttls(self.settings.starttls);
+
+        let (conn, ldap) = LdapConnAsync::with_settings(conn_settings, url)
+            .await
+            .wrap_err("failed to connect to the directory")?;
+        ldap3::drive!(conn);
+        Ok(ldap)
+    }
+
+    // The DN of the user's entry, searched for as the service account
+    async fn find_user_dn(&self, ldap: &mut Ldap, email: &Email) -> Result<Option<String>> {
+        if let (Some(bind_dn), Some(bind_password)) =
+            (&self.settings.bind_dn, &self.settings.bind_password)
+        {
+            ldap.simple_bind(bind_dn, bind_password.expose_secret())
+                .await?
+                .success()
+                .wrap_err("the directory refused the service account")?;
+        }
+
+        let (entries, _) = ldap
+            .search(
+                &self.settings.user_base_dn,
+                Scope::Subtree,
+                &self.search_filter(email),
+                // No attributes, only the DN
+                vec!["1.1"],
+            )
+            .await?
+            .success()
+            .wrap_err("failed to search the directory")?;
+        let mut dns: Vec<String> = entries
+            .into_iter()
+            .filter(|entry| !entry.is_ref())
+            .map(|entry| SearchEntry::construct(entry).dn)
+            .collect();
+
+        // More than one match means the filter does not identify users; log nobody in
+        if dns.len() > 1 {
+            return Err(eyre!("{} directory entries match one user", dns.len()));
+        }
+        Ok(dns.pop())
+    }
+
+    #[tracing::instrument(name = "Looking up user in LDAP", skip_all)]
+    async fn lookup(&self, email: &Email) -> Result<Option<String>> {
+        let mut ldap = self.connect().await?;
+        let dn = tokio::time::timeout(self.settings.timeout(), self.find_user_dn(&mut ldap, email))
+            .await
+            .wrap_err("the directory timed out")?;
+        let _ = ldap.unbind().await;
+        dn
+    }
+
+    #[tracing::instrument(name = "Binding as user in LDAP", skip_all)]
+    async fn authenticate(&self, email: &Email, password: &Password) -> Result<(), UserStoreError> {
+        let password = password.as_ref().expose_secret();
+        // An empty password would make this an unauthenticated bind, which servers accept
+        // (RFC 4513 section 5.1.2)
+        if password.is_empty() {
+            return Err(UserStoreError::InvalidCredentials);
+        }
+
+        let mut ldap = self
+            .connect()
+            .await
+            .map_err(UserStoreError::UnexpectedError)?;
+        let result = tokio::time::timeout(self.settings.timeout(), async {
+            let dn = self
+                .find_user_dn(&mut ldap, email)
+                .await
+                .map_err(UserStoreError::UnexpectedError)?
+                .ok_or(UserStoreError::UserNotFound)?;
+
+            let result = ldap
+                .simple_bind(&dn, password)
+                .await
+                .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+            match result.rc {
+                0 => Ok(()),
+                LDAP_INVALID_CREDENTIALS => Err(UserStoreError::InvalidCredentials),
+                _ => Err(UserStoreError::UnexpectedError(Report::new(
+                    ldap3::LdapError::from(result),
+                ))),
+            }
+        })
+        .await
+        .unwrap_or_else(|_| {
+            Err(UserStoreError::UnexpectedError(eyre!(
+                "the directory timed out"
+            )))
+        });
+        let _ = ldap.unbind().await;
+        result
+    }
+}
+
+#[async_trait::async_trait]
+impl UserStore for LdapUserStore {
+    async fn add_user(&mut self, _user: User) -> Result<(), UserStoreError> {
+        Err(UserStoreError::UnexpectedError(eyre!(
+            "Users are added in the directory"
+        )))
+    }
+
+    // Directory users have no password here and start without 2FA
+    async fn get_user(&self, email: &Email) -> Result<User, UserStoreError> {
+        match self.lookup(email).await {
+            Ok(Some(_)) => Ok(User {
+                email: email.clone(),
+                password: Password::random(),
+                requires_2fa: false,
+            }),
+            Ok(None) => Err(UserStoreError::UserNotFound),
+            Err(e) => Err(UserStoreError::UnexpectedError(e)),
+        }
+    }
+
+    async fn validate_user(
+        &self,
+        email: &Email,
+        password: &Password,
+    ) -> Result<(), UserStoreError> {
+        self.authenticate(email, password).await
+    }
+
+    async fn require_password_reset(&mut self, _email: &Email) -> Result<(), UserStoreError> {
+        Err(UserStoreError::UnexpectedError(eyre!(
+            "Passwords are reset in the directory"
+        )))
+    }
+
+    async fn reset_password(
+        &mut self,
+        _email: &Email,
+        _password: Password,
+    ) -> Result<(), UserStoreError> {
+        Err(UserStoreError::UnexpectedError(eyre!(
+            "Passwords are reset in the directory"
+        )))
+    }
+}
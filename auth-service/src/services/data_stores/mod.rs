@@ -1,13 +1,65 @@
+pub mod composite_user_store;
+pub mod hashmap_authorization_code_store;
+pub mod hashmap_device_authorization_store;
+pub mod hashmap_external_identity_store;
+pub mod hashmap_external_login_store;
+pub mod hashmap_login_alert_store;
+pub mod hashmap_login_history_store;
+pub mod hashmap_magic_link_store;
+pub mod hashmap_oauth_client_store;
+pub mod hashmap_rate_limit_store;
+pub mod hashmap_service_client_store;
 pub mod hashmap_two_fa_code_store;
 pub mod hashmap_user_store;
+pub mod hashmap_webhook_store;
 pub mod hashset_banned_token_store;
+pub mod hashset_known_device_store;
+pub mod ldap_user_store;
+pub mod postgres_external_identity_store;
+pub mod postgres_known_device_store;
+pub mod postgres_login_history_store;
+pub mod postgres_oauth_client_store;
+pub mod postgres_service_client_store;
 pub mod postgres_user_store;
+pub mod postgres_webhook_store;
+pub mod redis_authorization_code_store;
 pub mod redis_banned_token_store;
+pub mod redis_device_authorization_store;
+pub mod redis_external_login_store;
+pub mod redis_login_alert_store;
+pub mod redis_magic_link_store;
+pub mod redis_rate_limit_store;
 pub mod redis_two_fa_code_store;
 
+pub use composite_user_store::*;
+pub use hashmap_authorization_code_store::*;
+pub use hashmap_device_authorization_store::*;
+pub use hashmap_external_identity_store::*;
+pub use hashmap_external_login_store::*;
+pub use hashmap_login_alert_store::*;
+pub use hashmap_login_history_store::*;
+pub use hashmap_magic_link_store::*;
+pub use hashmap_oauth_client_store::*;
+pub use hashmap_rate_limit_store::*;
+pub use hashmap_service_client_store::*;
 pub use hashmap_two_fa_code_store::*;
 pub use hashmap_user_store::*;
+pub use hashmap_webhook_store::*;
 pub use hashset_banned_token_store::*;
+pub use hashset_known_device_store::*;
+pub use ldap_user_store::*;
+pub use postgres_external_identity_store::*;
+pub use postgres_known_device_store::*;
+pub use postgres_login_history_store::*;
+pub use postgres_oauth_client_store::*;
+pub use postgres_service_client_store::*;
 pub use postgres_user_store::*;
+pub use postgres_webhook_store::*;
+pub use redis_authorization_code_store::*;
 pub use redis_banned_token_store::*;
+pub use redis_device_authorization_store::*;
+pub use redis_external_login_store::*;
+pub use redis_login_alert_store::*;
+pub use redis_magic_link_store::*;
+pub use redis_rate_limit_store::*;
 pub use redis_two_fa_code_store::*;
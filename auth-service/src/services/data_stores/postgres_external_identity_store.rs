@@ -0,0 +1,79 @@
+use color_eyre::eyre::{eyre, Result};
+use secrecy::{ExposeSecret, Secret};
+use sqlx::PgPool;
+
+use crate::domain::{
+    data_stores::{ExternalIdentityStore, ExternalIdentityStoreError},
+    Email, ExternalIdentity,
+};
+
+pub struct PostgresExternalIdentityStore {
+    pool: PgPool,
+}
+
+impl PostgresExternalIdentityStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ExternalIdentityStore for PostgresExternalIdentityStore {
+    #[tracing::instrument(name = "Adding external identity to PostgreSQL", skip_all)]
+    async fn add_identity(
+        &mut self,
+        identity: ExternalIdentity,
+    ) -> Result<(), ExternalIdentityStoreError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO external_identities (issuer, subject, email, linked_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (issuer, subject) DO NOTHING
+            "#,
+            identity.issuer,
+            identity.subject,
+            identity.email.as_ref().expose_secret() as &str,
+            identity.linked_at
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ExternalIdentityStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(ExternalIdentityStoreError::IdentityAlreadyLinked);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving external identity from PostgreSQL", skip_all)]
+    async fn get_identity(
+        &self,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<ExternalIdentity, ExternalIdentityStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT issuer, subject, email, linked_at
+            FROM external_identities
+            WHERE issuer = $1 AND subject = $2
+            "#,
+            issuer,
+            subject
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| ExternalIdentityStoreError::UnexpectedError(e.into()))?
+        .ok_or(ExternalIdentityStoreError::IdentityNotFound)?;
+
+        let email = Email::parse(Secret::new(row.email))
+            .map_err(|e| ExternalIdentityStoreError::UnexpectedError(eyre!("{}", e)))?;
+
+        Ok(ExternalIdentity {
+            issuer: row.issuer,
+            subject: row.subject,
+            email,
+            linked_at: row.linked_at,
+        })
+    }
+}
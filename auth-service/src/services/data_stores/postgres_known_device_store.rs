@@ -0,0 +1,94 @@
+use secrecy::ExposeSecret;
+use sqlx::PgPool;
+
+use crate::domain::{
+    data_stores::{KnownDeviceStore, KnownDeviceStoreError},
+    DeviceFingerprint, Email,
+};
+
+pub struct PostgresKnownDeviceStore {
+    pool: PgPool,
+}
+
+impl PostgresKnownDeviceStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl KnownDeviceStore for PostgresKnownDeviceStore {
+    #[tracing::instrument(name = "Adding known device to PostgreSQL", skip_all)]
+    async fn add_device(
+        &mut self,
+        email: &Email,
+        device: &DeviceFingerprint,
+    ) -> Result<(), KnownDeviceStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO known_devices (email, user_agent, ip_range)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (email, user_agent, ip_range) DO UPDATE SET last_seen_at = NOW()
+            "#,
+            email.as_ref().expose_secret() as &str,
+            device.user_agent,
+            device.ip_range
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| KnownDeviceStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Checking known device in PostgreSQL", skip_all)]
+    async fn is_known_device(
+        &self,
+        email: &Email,
+        device: &DeviceFingerprint,
+    ) -> Result<bool, KnownDeviceStoreError> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM known_devices WHERE email = $1 AND user_agent = $2 AND ip_range = $3
+            ) as "exists!"
+            "#,
+            email.as_ref().expose_secret() as &str,
+            device.user_agent,
+            device.ip_range
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| KnownDeviceStoreError::UnexpectedError(e.into()))
+    }
+
+    #[tracing::instrument(name = "Checking for any known devices in PostgreSQL", skip_all)]
+    async fn has_known_devices(&self, email: &Email) -> Result<bool, KnownDeviceStoreError> {
+        sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM known_devices WHERE email = $1) as "exists!""#,
+            email.as_ref().expose_secret() as &str
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| KnownDeviceStoreError::UnexpectedError(e.into()))
+    }
+
+    #[tracing::instrument(name = "Removing known device from PostgreSQL", skip_all)]
+    async fn remove_device(
+        &mut self,
+        email: &Email,
+        device: &DeviceFingerprint,
+    ) -> Result<(), KnownDeviceStoreError> {
+        sqlx::query!(
+            "DELETE FROM known_devices WHERE email = $1 AND user_agent = $2 AND ip_range = $3",
+            email.as_ref().expose_secret() as &str,
+            device.user_agent,
+            device.ip_range
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| KnownDeviceStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+}
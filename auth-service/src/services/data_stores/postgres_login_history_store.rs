@@ -0,0 +1,124 @@
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::Context;
+use secrecy::{ExposeSecret, Secret};
+use sqlx::PgPool;
+
+use crate::domain::{
+    data_stores::{LoginHistoryStore, LoginHistoryStoreError},
+    Email, LoginFailureReason, LoginHistoryEntry, LoginOutcome,
+};
+
+pub struct PostgresLoginHistoryStore {
+    pool: PgPool,
+}
+
+impl PostgresLoginHistoryStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl LoginHistoryStore for PostgresLoginHistoryStore {
+    #[tracing::instrument(name = "Adding login history entry to PostgreSQL", skip_all)]
+    async fn add_entry(&mut self, entry: LoginHistoryEntry) -> Result<(), LoginHistoryStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO login_history (email, occurred_at, ip_address, user_agent, success, failure_reason, used_2fa)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            entry.email.as_ref().expose_secret() as &str,
+            entry.occurred_at,
+            entry.ip_address,
+            entry.user_agent,
+            entry.outcome.is_success(),
+            entry.outcome.failure_reason().map(|reason| reason.as_str()),
+            entry.used_2fa
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| LoginHistoryStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving login history from PostgreSQL", skip_all)]
+    async fn get_entries(
+        &self,
+        email: &Email,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<LoginHistoryEntry>, LoginHistoryStoreError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT email, occurred_at, ip_address, user_agent, success, failure_reason, used_2fa
+            FROM login_history
+            WHERE email = $1
+            ORDER BY occurred_at DESC, id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            email.as_ref().expose_secret() as &str,
+            i64::from(limit),
+            i64::from(offset)
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| LoginHistoryStoreError::UnexpectedError(e.into()))?;
+
+        rows.into_iter()
+            .map(|row| {
+                let outcome = match (row.success, row.failure_reason) {
+                    (true, _) => LoginOutcome::Success,
+                    (false, Some(reason)) => LoginOutcome::Failure(
+                        LoginFailureReason::parse(&reason)
+                            .map_err(LoginHistoryStoreError::UnexpectedError)?,
+                    ),
+                    (false, None) => {
+                        return Err(LoginHistoryStoreError::UnexpectedError(
+                            color_eyre::eyre::eyre!("Failed login is missing a failure reason"),
+                        ))
+                    }
+                };
+
+                Ok(LoginHistoryEntry {
+                    email: Email::parse(Secret::new(row.email))
+                        .map_err(LoginHistoryStoreError::UnexpectedError)?,
+                    occurred_at: row.occurred_at,
+                    ip_address: row.ip_address,
+                    user_agent: row.user_agent,
+                    outcome,
+                    used_2fa: row.used_2fa,
+                })
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(name = "Counting login history entries in PostgreSQL", skip_all)]
+    async fn count_entries(&self, email: &Email) -> Result<u64, LoginHistoryStoreError> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM login_history WHERE email = $1"#,
+            email.as_ref().expose_secret() as &str
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| LoginHistoryStoreError::UnexpectedError(e.into()))?;
+
+        count
+            .try_into()
+            .wrap_err("failed to cast login history count to u64")
+            .map_err(LoginHistoryStoreError::UnexpectedError)
+    }
+
+    #[tracing::instrument(name = "Pruning login history in PostgreSQL", skip_all)]
+    async fn delete_entries_before(
+        &mut self,
+        cutoff: DateTime<Utc>,
+    ) -> Result<u64, LoginHistoryStoreError> {
+        let result = sqlx::query!("DELETE FROM login_history WHERE occurred_at < $1", cutoff)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| LoginHistoryStoreError::UnexpectedError(e.into()))?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,86 @@
+use color_eyre::eyre::Result;
+use sqlx::PgPool;
+
+use crate::domain::{
+    data_stores::{OAuthClientStore, OAuthClientStoreError},
+    OAuthClient,
+};
+
+pub struct PostgresOAuthClientStore {
+    pool: PgPool,
+}
+
+impl PostgresOAuthClientStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl OAuthClientStore for PostgresOAuthClientStore {
+    #[tracing::instrument(name = "Adding OAuth client to PostgreSQL", skip_all)]
+    async fn add_client(&mut self, client: OAuthClient) -> Result<(), OAuthClientStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO oauth_clients (client_id, name, redirect_uris, scopes, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            client.client_id,
+            client.name,
+            &client.redirect_uris,
+            &client.scopes,
+            client.created_at
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| OAuthClientStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving OAuth client from PostgreSQL", skip_all)]
+    async fn get_client(&self, client_id: &str) -> Result<OAuthClient, OAuthClientStoreError> {
+        sqlx::query_as!(
+            OAuthClient,
+            r#"
+            SELECT client_id, name, redirect_uris, scopes, created_at
+            FROM oauth_clients
+            WHERE client_id = $1
+            "#,
+            client_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| OAuthClientStoreError::UnexpectedError(e.into()))?
+        .ok_or(OAuthClientStoreError::ClientNotFound)
+    }
+
+    #[tracing::instrument(name = "Retrieving OAuth clients from PostgreSQL", skip_all)]
+    async fn get_clients(&self) -> Result<Vec<OAuthClient>, OAuthClientStoreError> {
+        sqlx::query_as!(
+            OAuthClient,
+            r#"
+            SELECT client_id, name, redirect_uris, scopes, created_at
+            FROM oauth_clients
+            ORDER BY created_at, client_id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| OAuthClientStoreError::UnexpectedError(e.into()))
+    }
+
+    #[tracing::instrument(name = "Deleting OAuth client from PostgreSQL", skip_all)]
+    async fn delete_client(&mut self, client_id: &str) -> Result<(), OAuthClientStoreError> {
+        let result = sqlx::query!("DELETE FROM oauth_clients WHERE client_id = $1", client_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| OAuthClientStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(OAuthClientStoreError::ClientNotFound);
+        }
+
+        Ok(())
+    }
+}
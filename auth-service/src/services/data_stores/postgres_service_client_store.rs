@@ -0,0 +1,89 @@
+use color_eyre::eyre::Result;
+use sqlx::PgPool;
+
+use crate::domain::{
+    data_stores::{ServiceClientStore, ServiceClientStoreError},
+    ServiceClient,
+};
+
+pub struct PostgresServiceClientStore {
+    pool: PgPool,
+}
+
+impl PostgresServiceClientStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ServiceClientStore for PostgresServiceClientStore {
+    #[tracing::instrument(name = "Adding service client to PostgreSQL", skip_all)]
+    async fn add_client(&mut self, client: ServiceClient) -> Result<(), ServiceClientStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO service_clients (client_id, name, scopes, secret_hash, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            client.client_id,
+            client.name,
+            &client.scopes,
+            client.secret_hash,
+            client.created_at
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ServiceClientStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving service client from PostgreSQL", skip_all)]
+    async fn get_client(&self, client_id: &str) -> Result<ServiceClient, ServiceClientStoreError> {
+        sqlx::query_as!(
+            ServiceClient,
+            r#"
+            SELECT client_id, name, scopes, secret_hash, created_at
+            FROM service_clients
+            WHERE client_id = $1
+            "#,
+            client_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| ServiceClientStoreError::UnexpectedError(e.into()))?
+        .ok_or(ServiceClientStoreError::ClientNotFound)
+    }
+
+    #[tracing::instrument(name = "Retrieving service clients from PostgreSQL", skip_all)]
+    async fn get_clients(&self) -> Result<Vec<ServiceClient>, ServiceClientStoreError> {
+        sqlx::query_as!(
+            ServiceClient,
+            r#"
+            SELECT client_id, name, scopes, secret_hash, created_at
+            FROM service_clients
+            ORDER BY created_at, client_id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| ServiceClientStoreError::UnexpectedError(e.into()))
+    }
+
+    #[tracing::instrument(name = "Deleting service client from PostgreSQL", skip_all)]
+    async fn delete_client(&mut self, client_id: &str) -> Result<(), ServiceClientStoreError> {
+        let result = sqlx::query!(
+            "DELETE FROM service_clients WHERE client_id = $1",
+            client_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| ServiceClientStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(ServiceClientStoreError::ClientNotFound);
+        }
+
+        Ok(())
+    }
+}
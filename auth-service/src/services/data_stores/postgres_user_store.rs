@@ -3,13 +3,15 @@ use argon2::{
     PasswordVerifier, Version,
 };
 use secrecy::{ExposeSecret, Secret};
-use std::error::Error;
 
 use sqlx::PgPool;
 
-use crate::domain::{
-    data_stores::{UserStore, UserStoreError},
-    Email, Password, User,
+use crate::{
+    domain::{
+        data_stores::{UserStore, UserStoreError},
+        Email, Password, User,
+    },
+    utils::{labels, METRICS},
 };
 
 pub struct PostgresUserStore {
@@ -22,7 +24,7 @@ impl PostgresUserStore {
     }
 }
 
-use color_eyre::eyre::{eyre, Context, Result};
+use color_eyre::eyre::Result;
 
 #[async_trait::async_trait]
 impl UserStore for PostgresUserStore {
@@ -73,10 +75,10 @@ impl UserStore for PostgresUserStore {
         match user_row {
             Some(row) => {
                 let user_email = Email::parse(Secret::new(row.email.to_string()))
-                    .map_err(|e| UserStoreError::UnexpectedError(e))?;
+                    .map_err(UserStoreError::UnexpectedError)?;
 
                 let password = Password::parse(Secret::new(row.password_hash))
-                    .map_err(|e| UserStoreError::UnexpectedError(e))?;
+                    .map_err(UserStoreError::UnexpectedError)?;
 
                 Ok(User {
                     email: user_email,
@@ -96,25 +98,70 @@ impl UserStore for PostgresUserStore {
     ) -> Result<(), UserStoreError> {
         // Get the stored password hash from the database
         let user_row = sqlx::query!(
-            "SELECT password_hash FROM users WHERE email = $1",
+            "SELECT password_hash, requires_password_reset FROM users WHERE email = $1",
             email.as_ref().expose_secret() as &str
         )
         .fetch_optional(&self.pool)
         .await
         .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
 
-        let stored_password_hash = match user_row {
-            Some(row) => row.password_hash,
+        let row = match user_row {
+            Some(row) => row,
             None => return Err(UserStoreError::UserNotFound),
         };
 
         // Verify the provided password against the stored hash
-        verify_password_hash(
-            Secret::new(stored_password_hash),
-            password.as_ref().to_owned(),
+        verify_password_hash(Secret::new(row.password_hash), password.as_ref().to_owned())
+            .await
+            .map_err(|_| UserStoreError::InvalidCredentials)?;
+
+        // Only reveal a pending reset to someone who knows the current password
+        if row.requires_password_reset {
+            return Err(UserStoreError::PasswordResetRequired);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Requiring password reset in PostgreSQL", skip_all)]
+    async fn require_password_reset(&mut self, email: &Email) -> Result<(), UserStoreError> {
+        let result = sqlx::query!(
+            "UPDATE users SET requires_password_reset = TRUE WHERE email = $1",
+            email.as_ref().expose_secret() as &str
         )
+        .execute(&self.pool)
         .await
-        .map_err(|_| UserStoreError::InvalidCredentials)?;
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Resetting password in PostgreSQL", skip_all)]
+    async fn reset_password(
+        &mut self,
+        email: &Email,
+        password: Password,
+    ) -> Result<(), UserStoreError> {
+        let password_hash = compute_password_hash(password.as_ref().to_owned())
+            .await
+            .map_err(UserStoreError::UnexpectedError)?;
+
+        let result = sqlx::query!(
+            "UPDATE users SET password_hash = $2, requires_password_reset = FALSE WHERE email = $1",
+            email.as_ref().expose_secret() as &str,
+            &password_hash.expose_secret()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| UserStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(UserStoreError::UserNotFound);
+        }
 
         Ok(())
     }
@@ -135,6 +182,10 @@ async fn verify_password_hash(
         // This is especially useful for tracing operations that are performed in a different thread or task, such as within tokio::task::spawn_blocking.
         current_span.in_scope(|| {
             // New!
+            let _timer = METRICS
+                .password_hash_duration
+                .with_label_values(&[labels::PASSWORD_VERIFY])
+                .start_timer();
             let expected_password_hash: PasswordHash<'_> =
                 PasswordHash::new(expected_password_hash.expose_secret())?;
 
@@ -164,6 +215,10 @@ async fn compute_password_hash(password: Secret<String>) -> Result<Secret<String
         // This is especially useful for tracing operations that are performed in a different thread or task, such as within tokio::task::spawn_blocking.
         current_span.in_scope(|| {
             // New!
+            let _timer = METRICS
+                .password_hash_duration
+                .with_label_values(&[labels::PASSWORD_HASH])
+                .start_timer();
             let salt: SaltString = SaltString::generate(&mut rand::thread_rng());
             let password_hash = Argon2::new(
                 Algorithm::Argon2id,
@@ -0,0 +1,329 @@
+use color_eyre::eyre::Result;
+use secrecy::{ExposeSecret, Secret};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::domain::{
+    data_stores::{WebhookStore, WebhookStoreError},
+    WebhookDeadLetter, WebhookDelivery, WebhookEventType, WebhookSubscription,
+};
+
+pub struct PostgresWebhookStore {
+    pool: PgPool,
+}
+
+impl PostgresWebhookStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl WebhookStore for PostgresWebhookStore {
+    #[tracing::instrument(name = "Adding webhook subscription to PostgreSQL", skip_all)]
+    async fn add_subscription(
+        &mut self,
+        subscription: WebhookSubscription,
+    ) -> Result<(), WebhookStoreError> {
+        let events: Vec<String> = subscription
+            .events
+            .iter()
+            .map(|event_type| event_type.as_str().to_owned())
+            .collect();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO webhook_subscriptions (id, url, secret, events, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            subscription.id,
+            subscription.url,
+            subscription.secret.expose_secret(),
+            &events,
+            subscription.created_at
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Retrieving webhook subscription from PostgreSQL", skip_all)]
+    async fn get_subscription(&self, id: Uuid) -> Result<WebhookSubscription, WebhookStoreError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, url, secret, events, created_at
+            FROM webhook_subscriptions
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))?
+        .ok_or(WebhookStoreError::SubscriptionNotFound)?;
+
+        Ok(WebhookSubscription {
+            id: row.id,
+            url: row.url,
+            secret: Secret::new(row.secret),
+            events: parse_event_types(&row.events).map_err(WebhookStoreError::UnexpectedError)?,
+            created_at: row.created_at,
+        })
+    }
+
+    #[tracing::instrument(name = "Retrieving webhook subscriptions from PostgreSQL", skip_all)]
+    async fn get_subscriptions(&self) -> Result<Vec<WebhookSubscription>, WebhookStoreError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, url, secret, events, created_at
+            FROM webhook_subscriptions
+            ORDER BY created_at, id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(WebhookSubscription {
+                    id: row.id,
+                    url: row.url,
+                    secret: Secret::new(row.secret),
+                    events: parse_event_types(&row.events)
+                        .map_err(WebhookStoreError::UnexpectedError)?,
+                    created_at: row.created_at,
+                })
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(name = "Deleting webhook subscription from PostgreSQL", skip_all)]
+    async fn delete_subscription(&mut self, id: Uuid) -> Result<(), WebhookStoreError> {
+        let result = sqlx::query!("DELETE FROM webhook_subscriptions WHERE id = $1", id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(WebhookStoreError::SubscriptionNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Queueing webhook delivery in PostgreSQL", skip_all)]
+    async fn enqueue_delivery(
+        &mut self,
+        delivery: WebhookDelivery,
+    ) -> Result<(), WebhookStoreError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO webhook_deliveries (id, subscription_id, event_type, payload, attempts, next_attempt_at, last_error)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            delivery.id,
+            delivery.subscription_id,
+            delivery.event_type.as_str(),
+            delivery.payload,
+            delivery.attempts,
+            delivery.next_attempt_at,
+            delivery.last_error
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Claiming due webhook deliveries in PostgreSQL", skip_all)]
+    async fn claim_due_deliveries(
+        &mut self,
+        limit: u32,
+        lease: std::time::Duration,
+    ) -> Result<Vec<WebhookDelivery>, WebhookStoreError> {
+        // Pushing next_attempt_at past the lease claims the rows; SKIP LOCKED lets several
+        // service instances drain the queue without sending the same delivery twice.
+        // RETURNING sees the updated row, so the original due time is carried out separately.
+        let rows = sqlx::query!(
+            r#"
+            WITH due AS (
+                SELECT id, next_attempt_at
+                FROM webhook_deliveries
+                WHERE next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            UPDATE webhook_deliveries
+            SET next_attempt_at = NOW() + make_interval(secs => $2)
+            FROM due
+            WHERE webhook_deliveries.id = due.id
+            RETURNING webhook_deliveries.id, subscription_id, event_type, payload, attempts,
+                due.next_attempt_at as "due_at!", last_error
+            "#,
+            i64::from(limit),
+            lease.as_secs_f64()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))?;
+
+        let mut deliveries = rows
+            .into_iter()
+            .map(|row| {
+                Ok(WebhookDelivery {
+                    id: row.id,
+                    subscription_id: row.subscription_id,
+                    event_type: WebhookEventType::parse(&row.event_type)
+                        .map_err(WebhookStoreError::UnexpectedError)?,
+                    payload: row.payload,
+                    attempts: row.attempts,
+                    next_attempt_at: row.due_at,
+                    last_error: row.last_error,
+                })
+            })
+            .collect::<Result<Vec<_>, WebhookStoreError>>()?;
+        deliveries.sort_by_key(|delivery| delivery.next_attempt_at);
+
+        Ok(deliveries)
+    }
+
+    #[tracing::instrument(name = "Removing webhook delivery from PostgreSQL", skip_all)]
+    async fn remove_delivery(&mut self, id: Uuid) -> Result<(), WebhookStoreError> {
+        sqlx::query!("DELETE FROM webhook_deliveries WHERE id = $1", id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Scheduling webhook delivery retry in PostgreSQL", skip_all)]
+    async fn schedule_retry(
+        &mut self,
+        delivery: &WebhookDelivery,
+    ) -> Result<(), WebhookStoreError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET attempts = $2, next_attempt_at = $3, last_error = $4
+            WHERE id = $1
+            "#,
+            delivery.id,
+            delivery.attempts,
+            delivery.next_attempt_at,
+            delivery.last_error
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(WebhookStoreError::DeliveryNotFound);
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Dead-lettering webhook delivery in PostgreSQL", skip_all)]
+    async fn dead_letter(&mut self, delivery: WebhookDelivery) -> Result<(), WebhookStoreError> {
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))?;
+
+        sqlx::query!("DELETE FROM webhook_deliveries WHERE id = $1", delivery.id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO webhook_dead_letters (id, subscription_id, event_type, payload, attempts, last_error)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            delivery.id,
+            delivery.subscription_id,
+            delivery.event_type.as_str(),
+            delivery.payload,
+            delivery.attempts,
+            delivery.last_error
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))?;
+
+        transaction
+            .commit()
+            .await
+            .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))
+    }
+
+    #[tracing::instrument(name = "Retrieving webhook dead letters from PostgreSQL", skip_all)]
+    async fn get_dead_letters(&self) -> Result<Vec<WebhookDeadLetter>, WebhookStoreError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, subscription_id, event_type, payload, attempts, last_error, failed_at
+            FROM webhook_dead_letters
+            ORDER BY failed_at, id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(WebhookDeadLetter {
+                    delivery: WebhookDelivery {
+                        id: row.id,
+                        subscription_id: row.subscription_id,
+                        event_type: WebhookEventType::parse(&row.event_type)
+                            .map_err(WebhookStoreError::UnexpectedError)?,
+                        payload: row.payload,
+                        attempts: row.attempts,
+                        next_attempt_at: row.failed_at,
+                        last_error: row.last_error,
+                    },
+                    failed_at: row.failed_at,
+                })
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(name = "Replaying webhook dead letter in PostgreSQL", skip_all)]
+    async fn replay_dead_letter(&mut self, id: Uuid) -> Result<(), WebhookStoreError> {
+        let result = sqlx::query!(
+            r#"
+            WITH replayed AS (
+                DELETE FROM webhook_dead_letters WHERE id = $1
+                RETURNING id, subscription_id, event_type, payload
+            )
+            INSERT INTO webhook_deliveries (id, subscription_id, event_type, payload)
+            SELECT id, subscription_id, event_type, payload FROM replayed
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| WebhookStoreError::UnexpectedError(e.into()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(WebhookStoreError::DeliveryNotFound);
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_event_types(events: &[String]) -> Result<Vec<WebhookEventType>> {
+    events
+        .iter()
+        .map(|event_type| WebhookEventType::parse(event_type))
+        .collect()
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use color_eyre::eyre::{eyre, Context};
+use redis::{Commands, Connection};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::domain::{
+    data_stores::{AuthorizationCodeStore, AuthorizationCodeStoreError},
+    AuthorizationCode, AuthorizationGrant, Email,
+};
+
+pub struct RedisAuthorizationCodeStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisAuthorizationCodeStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl AuthorizationCodeStore for RedisAuthorizationCodeStore {
+    #[tracing::instrument(name = "Add Authorization Code", skip_all)]
+    async fn add_code(
+        &mut self,
+        code: AuthorizationCode,
+        grant: AuthorizationGrant,
+    ) -> Result<(), AuthorizationCodeStoreError> {
+        let stored_grant = StoredAuthorizationGrant {
+            client_id: grant.client_id,
+            redirect_uri: grant.redirect_uri,
+            scopes: grant.scopes,
+            email: grant.email.as_ref().expose_secret().to_owned(),
+            code_challenge: grant.code_challenge,
+            nonce: grant.nonce,
+            auth_time: grant.auth_time,
+            amr: grant.amr,
+            sid: grant.sid,
+        };
+
+        let serialized_data = serde_json::to_string(&stored_grant)
+            .wrap_err("failed to serialize authorization grant")
+            .map_err(AuthorizationCodeStoreError::UnexpectedError)?;
+
+        let _: () = self
+            .conn
+            .write()
+            .await
+            .set_ex(
+                get_key(&code),
+                serialized_data,
+                AUTHORIZATION_CODE_TTL_SECONDS,
+            )
+            .wrap_err("failed to set authorization code in Redis")
+            .map_err(AuthorizationCodeStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    // GETDEL makes redeeming atomic, even across replicas sharing the Redis instance
+    #[tracing::instrument(name = "Take Authorization Code", skip_all)]
+    async fn take_code(
+        &mut self,
+        code: &AuthorizationCode,
+    ) -> Result<AuthorizationGrant, AuthorizationCodeStoreError> {
+        let value: Option<String> = self
+            .conn
+            .write()
+            .await
+            .get_del(get_key(code))
+            .wrap_err("failed to take authorization code from Redis")
+            .map_err(AuthorizationCodeStoreError::UnexpectedError)?;
+
+        let value = value.ok_or(AuthorizationCodeStoreError::CodeNotFound)?;
+
+        let stored_grant: StoredAuthorizationGrant = serde_json::from_str(&value)
+            .wrap_err("failed to deserialize authorization grant")
+            .map_err(AuthorizationCodeStoreError::UnexpectedError)?;
+
+        let email = Email::parse(Secret::new(stored_grant.email))
+            .map_err(|e| AuthorizationCodeStoreError::UnexpectedError(eyre!("{}", e)))?;
+
+        Ok(AuthorizationGrant {
+            client_id: stored_grant.client_id,
+            redirect_uri: stored_grant.redirect_uri,
+            scopes: stored_grant.scopes,
+            email,
+            code_challenge: stored_grant.code_challenge,
+            nonce: stored_grant.nonce,
+            auth_time: stored_grant.auth_time,
+            amr: stored_grant.amr,
+            sid: stored_grant.sid,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredAuthorizationGrant {
+    client_id: String,
+    redirect_uri: String,
+    scopes: Vec<String>,
+    email: String,
+    code_challenge: String,
+    nonce: Option<String>,
+    auth_time: Option<usize>,
+    amr: Vec<String>,
+    #[serde(default)]
+    sid: Option<String>,
+}
+
+// Clients redeem codes right after the redirect; RFC 6749 caps their lifetime at 10 minutes
+const AUTHORIZATION_CODE_TTL_SECONDS: u64 = 60;
+const AUTHORIZATION_CODE_PREFIX: &str = "authorization_code:";
+
+fn get_key(code: &AuthorizationCode) -> String {
+    format!("{}{}", AUTHORIZATION_CODE_PREFIX, code.as_ref())
+}
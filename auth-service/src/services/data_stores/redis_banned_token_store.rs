@@ -1,4 +1,4 @@
-use color_eyre::eyre::{eyre, Context, Report, Result};
+use color_eyre::eyre::{Context, Result};
 use redis::{Commands, Connection};
 use secrecy::{ExposeSecret, Secret};
 use std::sync::Arc;
@@ -0,0 +1,299 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{eyre, Context};
+use redis::{Commands, Connection};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::domain::{
+    data_stores::{DeviceAuthorizationStore, DeviceAuthorizationStoreError},
+    DeviceApproval, DeviceAuthorization, DeviceAuthorizationStatus, DeviceCode, DevicePoll, Email,
+    UserCode, DEVICE_CODE_TTL_SECONDS,
+};
+
+pub struct RedisDeviceAuthorizationStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisDeviceAuthorizationStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+
+    // Writes the request under its device code, and the device code under the user code, both
+    // expiring with the request
+    async fn set_authorization(
+        &mut self,
+        device_code: &DeviceCode,
+        authorization: DeviceAuthorization,
+    ) -> Result<(), DeviceAuthorizationStoreError> {
+        let ttl = (authorization.expires_at - Utc::now()).num_seconds().max(1) as u64;
+        let user_code_key = get_user_code_key(&authorization.user_code);
+        let serialized_data =
+            serde_json::to_string(&StoredDeviceAuthorization::from(authorization))
+                .wrap_err("failed to serialize device authorization")
+                .map_err(DeviceAuthorizationStoreError::UnexpectedError)?;
+
+        let mut conn = self.conn.write().await;
+        let _: () = conn
+            .set_ex(get_key(device_code), serialized_data, ttl)
+            .wrap_err("failed to set device authorization in Redis")
+            .map_err(DeviceAuthorizationStoreError::UnexpectedError)?;
+        let _: () = conn
+            .set_ex(user_code_key, device_code.as_ref(), ttl)
+            .wrap_err("failed to set device user code in Redis")
+            .map_err(DeviceAuthorizationStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl DeviceAuthorizationStore for RedisDeviceAuthorizationStore {
+    #[tracing::instrument(name = "Add Device Authorization", skip_all)]
+    async fn add_authorization(
+        &mut self,
+        device_code: DeviceCode,
+        authorization: DeviceAuthorization,
+    ) -> Result<(), DeviceAuthorizationStoreError> {
+        self.set_authorization(&device_code, authorization).await
+    }
+
+    #[tracing::instrument(name = "Get Device Authorization", skip_all)]
+    async fn get_authorization(
+        &self,
+        device_code: &DeviceCode,
+    ) -> Result<DeviceAuthorization, DeviceAuthorizationStoreError> {
+        let value: Option<String> = self
+            .conn
+            .write()
+            .await
+            .get(get_key(device_code))
+            .wrap_err("failed to get device authorization from Redis")
+            .map_err(DeviceAuthorizationStoreError::UnexpectedError)?;
+
+        parse_authorization(value)
+    }
+
+    #[tracing::instrument(name = "Get Device Code", skip_all)]
+    async fn get_device_code(
+        &self,
+        user_code: &UserCode,
+    ) -> Result<DeviceCode, DeviceAuthorizationStoreError> {
+        let value: Option<String> = self
+            .conn
+            .write()
+            .await
+            .get(get_user_code_key(user_code))
+            .wrap_err("failed to get device user code from Redis")
+            .map_err(DeviceAuthorizationStoreError::UnexpectedError)?;
+
+        let value = value.ok_or(DeviceAuthorizationStoreError::AuthorizationNotFound)?;
+        DeviceCode::parse(value).map_err(DeviceAuthorizationStoreError::UnexpectedError)
+    }
+
+    #[tracing::instrument(name = "Update Device Authorization", skip_all)]
+    async fn update_authorization(
+        &mut self,
+        device_code: &DeviceCode,
+        authorization: DeviceAuthorization,
+    ) -> Result<(), DeviceAuthorizationStoreError> {
+        // Requests that expired in the meantime stay gone
+        self.get_authorization(device_code).await?;
+        self.set_authorization(device_code, authorization).await
+    }
+
+    // GETDEL makes redeeming atomic, even across replicas sharing the Redis instance
+    #[tracing::instrument(name = "Take Device Authorization", skip_all)]
+    async fn take_authorization(
+        &mut self,
+        device_code: &DeviceCode,
+    ) -> Result<DeviceAuthorization, DeviceAuthorizationStoreError> {
+        let mut conn = self.conn.write().await;
+        let value: Option<String> = conn
+            .get_del(get_key(device_code))
+            .wrap_err("failed to take device authorization from Redis")
+            .map_err(DeviceAuthorizationStoreError::UnexpectedError)?;
+        let authorization = parse_authorization(value)?;
+
+        let _: () = conn
+            .del(&[
+                get_user_code_key(&authorization.user_code),
+                get_poll_key(device_code),
+            ])
+            .wrap_err("failed to delete device user code from Redis")
+            .map_err(DeviceAuthorizationStoreError::UnexpectedError)?;
+
+        Ok(authorization)
+    }
+
+    // Polls are kept under their own key, so recording one cannot overwrite an approval that
+    // lands at the same time
+    #[tracing::instrument(name = "Record Device Poll", skip_all)]
+    async fn record_poll(
+        &mut self,
+        device_code: &DeviceCode,
+        now: DateTime<Utc>,
+    ) -> Result<bool, DeviceAuthorizationStoreError> {
+        let mut conn = self.conn.write().await;
+        let value: Option<String> = conn
+            .get(get_poll_key(device_code))
+            .wrap_err("failed to get device poll from Redis")
+            .map_err(DeviceAuthorizationStoreError::UnexpectedError)?;
+        let previous = value
+            .map(|value| serde_json::from_str::<StoredDevicePoll>(&value))
+            .transpose()
+            .wrap_err("failed to deserialize device poll")
+            .map_err(DeviceAuthorizationStoreError::UnexpectedError)?
+            .map(DevicePoll::from);
+
+        let (poll, too_soon) = DevicePoll::next(previous.as_ref(), now);
+        let serialized_data = serde_json::to_string(&StoredDevicePoll::from(poll))
+            .wrap_err("failed to serialize device poll")
+            .map_err(DeviceAuthorizationStoreError::UnexpectedError)?;
+        let _: () = conn
+            .set_ex(
+                get_poll_key(device_code),
+                serialized_data,
+                DEVICE_CODE_TTL_SECONDS,
+            )
+            .wrap_err("failed to set device poll in Redis")
+            .map_err(DeviceAuthorizationStoreError::UnexpectedError)?;
+
+        Ok(too_soon)
+    }
+}
+
+fn parse_authorization(
+    value: Option<String>,
+) -> Result<DeviceAuthorization, DeviceAuthorizationStoreError> {
+    let value = value.ok_or(DeviceAuthorizationStoreError::AuthorizationNotFound)?;
+
+    let stored: StoredDeviceAuthorization = serde_json::from_str(&value)
+        .wrap_err("failed to deserialize device authorization")
+        .map_err(DeviceAuthorizationStoreError::UnexpectedError)?;
+
+    stored.try_into()
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredDeviceAuthorization {
+    client_id: String,
+    scopes: Vec<String>,
+    user_code: String,
+    #[serde(flatten)]
+    status: StoredDeviceAuthorizationStatus,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum StoredDeviceAuthorizationStatus {
+    Pending,
+    Approved {
+        email: String,
+        auth_time: Option<usize>,
+        amr: Vec<String>,
+        sid: Option<String>,
+    },
+    Denied,
+}
+
+impl From<DeviceAuthorization> for StoredDeviceAuthorization {
+    fn from(authorization: DeviceAuthorization) -> Self {
+        let status = match authorization.status {
+            DeviceAuthorizationStatus::Pending => StoredDeviceAuthorizationStatus::Pending,
+            DeviceAuthorizationStatus::Approved(approval) => {
+                StoredDeviceAuthorizationStatus::Approved {
+                    email: approval.email.as_ref().expose_secret().to_owned(),
+                    auth_time: approval.auth_time,
+                    amr: approval.amr,
+                    sid: approval.sid,
+                }
+            }
+            DeviceAuthorizationStatus::Denied => StoredDeviceAuthorizationStatus::Denied,
+        };
+
+        Self {
+            client_id: authorization.client_id,
+            scopes: authorization.scopes,
+            user_code: authorization.user_code.as_ref().to_owned(),
+            status,
+            expires_at: authorization.expires_at,
+        }
+    }
+}
+
+impl TryFrom<StoredDeviceAuthorization> for DeviceAuthorization {
+    type Error = DeviceAuthorizationStoreError;
+
+    fn try_from(stored: StoredDeviceAuthorization) -> Result<Self, Self::Error> {
+        let status = match stored.status {
+            StoredDeviceAuthorizationStatus::Pending => DeviceAuthorizationStatus::Pending,
+            StoredDeviceAuthorizationStatus::Approved {
+                email,
+                auth_time,
+                amr,
+                sid,
+            } => DeviceAuthorizationStatus::Approved(DeviceApproval {
+                email: Email::parse(Secret::new(email))
+                    .map_err(|e| DeviceAuthorizationStoreError::UnexpectedError(eyre!("{}", e)))?,
+                auth_time,
+                amr,
+                sid,
+            }),
+            StoredDeviceAuthorizationStatus::Denied => DeviceAuthorizationStatus::Denied,
+        };
+
+        Ok(Self {
+            client_id: stored.client_id,
+            scopes: stored.scopes,
+            user_code: UserCode::parse(&stored.user_code)
+                .map_err(DeviceAuthorizationStoreError::UnexpectedError)?,
+            status,
+            expires_at: stored.expires_at,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredDevicePoll {
+    polled_at: DateTime<Utc>,
+    interval: u64,
+}
+
+impl From<DevicePoll> for StoredDevicePoll {
+    fn from(poll: DevicePoll) -> Self {
+        Self {
+            polled_at: poll.polled_at,
+            interval: poll.interval,
+        }
+    }
+}
+
+impl From<StoredDevicePoll> for DevicePoll {
+    fn from(stored: StoredDevicePoll) -> Self {
+        Self {
+            polled_at: stored.polled_at,
+            interval: stored.interval,
+        }
+    }
+}
+
+const DEVICE_CODE_PREFIX: &str = "device_code:";
+const DEVICE_USER_CODE_PREFIX: &str = "device_user_code:";
+const DEVICE_POLL_PREFIX: &str = "device_poll:";
+
+fn get_key(device_code: &DeviceCode) -> String {
+    format!("{}{}", DEVICE_CODE_PREFIX, device_code.as_ref())
+}
+
+fn get_user_code_key(user_code: &UserCode) -> String {
+    format!("{}{}", DEVICE_USER_CODE_PREFIX, user_code.as_ref())
+}
+
+fn get_poll_key(device_code: &DeviceCode) -> String {
+    format!("{}{}", DEVICE_POLL_PREFIX, device_code.as_ref())
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use color_eyre::eyre::Context;
+use redis::{Commands, Connection};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::domain::{
+    data_stores::{ExternalLoginStore, ExternalLoginStoreError},
+    ExternalLoginState, PendingExternalLogin, EXTERNAL_LOGIN_TTL_SECONDS,
+};
+
+pub struct RedisExternalLoginStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisExternalLoginStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl ExternalLoginStore for RedisExternalLoginStore {
+    #[tracing::instrument(name = "Add External Login", skip_all)]
+    async fn add_login(
+        &mut self,
+        state: ExternalLoginState,
+        login: PendingExternalLogin,
+    ) -> Result<(), ExternalLoginStoreError> {
+        let stored_login = StoredExternalLogin {
+            nonce: login.nonce,
+            code_verifier: login.code_verifier,
+            return_to: login.return_to,
+        };
+
+        let serialized_data = serde_json::to_string(&stored_login)
+            .wrap_err("failed to serialize external login")
+            .map_err(ExternalLoginStoreError::UnexpectedError)?;
+
+        let _: () = self
+            .conn
+            .write()
+            .await
+            .set_ex(get_key(&state), serialized_data, EXTERNAL_LOGIN_TTL_SECONDS)
+            .wrap_err("failed to set external login in Redis")
+            .map_err(ExternalLoginStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    // GETDEL makes each redirect back usable once, even across replicas
+    #[tracing::instrument(name = "Take External Login", skip_all)]
+    async fn take_login(
+        &mut self,
+        state: &ExternalLoginState,
+    ) -> Result<PendingExternalLogin, ExternalLoginStoreError> {
+        let value: Option<String> = self
+            .conn
+            .write()
+            .await
+            .get_del(get_key(state))
+            .wrap_err("failed to take external login from Redis")
+            .map_err(ExternalLoginStoreError::UnexpectedError)?;
+
+        let value = value.ok_or(ExternalLoginStoreError::LoginNotFound)?;
+
+        let stored_login: StoredExternalLogin = serde_json::from_str(&value)
+            .wrap_err("failed to deserialize external login")
+            .map_err(ExternalLoginStoreError::UnexpectedError)?;
+
+        Ok(PendingExternalLogin {
+            nonce: stored_login.nonce,
+            code_verifier: stored_login.code_verifier,
+            return_to: stored_login.return_to,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredExternalLogin {
+    nonce: String,
+    code_verifier: String,
+    return_to: Option<String>,
+}
+
+const EXTERNAL_LOGIN_PREFIX: &str = "external_login:";
+
+fn get_key(state: &ExternalLoginState) -> String {
+    format!("{}{}", EXTERNAL_LOGIN_PREFIX, state.as_ref())
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use color_eyre::eyre::{eyre, Context};
+use redis::{Commands, Connection};
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::domain::{
+    data_stores::{LoginAlertStore, LoginAlertStoreError},
+    DeviceFingerprint, Email, LoginAlert, LoginAlertToken,
+};
+
+pub struct RedisLoginAlertStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisLoginAlertStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl LoginAlertStore for RedisLoginAlertStore {
+    #[tracing::instrument(name = "Add Login Alert", skip_all)]
+    async fn add_alert(
+        &mut self,
+        token: LoginAlertToken,
+        alert: LoginAlert,
+    ) -> Result<(), LoginAlertStoreError> {
+        let stored_alert = StoredLoginAlert {
+            email: alert.email.as_ref().expose_secret().to_owned(),
+            session_token: alert.session_token.expose_secret().to_owned(),
+            user_agent: alert.device.user_agent,
+            ip_range: alert.device.ip_range,
+        };
+
+        let serialized_data = serde_json::to_string(&stored_alert)
+            .wrap_err("failed to serialize login alert")
+            .map_err(LoginAlertStoreError::UnexpectedError)?;
+
+        let _: () = self
+            .conn
+            .write()
+            .await
+            .set_ex(get_key(&token), serialized_data, LOGIN_ALERT_TTL_SECONDS)
+            .wrap_err("failed to set login alert in Redis")
+            .map_err(LoginAlertStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Get Login Alert", skip_all)]
+    async fn get_alert(&self, token: &LoginAlertToken) -> Result<LoginAlert, LoginAlertStoreError> {
+        let value: Option<String> = self
+            .conn
+            .write()
+            .await
+            .get(get_key(token))
+            .wrap_err("failed to get login alert from Redis")
+            .map_err(LoginAlertStoreError::UnexpectedError)?;
+
+        let value = value.ok_or(LoginAlertStoreError::AlertNotFound)?;
+
+        let stored_alert: StoredLoginAlert = serde_json::from_str(&value)
+            .wrap_err("failed to deserialize login alert")
+            .map_err(LoginAlertStoreError::UnexpectedError)?;
+
+        let email = Email::parse(Secret::new(stored_alert.email))
+            .map_err(|e| LoginAlertStoreError::UnexpectedError(eyre!("{}", e)))?;
+
+        Ok(LoginAlert {
+            email,
+            session_token: Secret::new(stored_alert.session_token),
+            device: DeviceFingerprint {
+                user_agent: stored_alert.user_agent,
+                ip_range: stored_alert.ip_range,
+            },
+        })
+    }
+
+    #[tracing::instrument(name = "Remove Login Alert", skip_all)]
+    async fn remove_alert(&mut self, token: &LoginAlertToken) -> Result<(), LoginAlertStoreError> {
+        let _: () = self
+            .conn
+            .write()
+            .await
+            .del(get_key(token))
+            .wrap_err("failed to delete login alert from Redis")
+            .map_err(LoginAlertStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredLoginAlert {
+    email: String,
+    session_token: String,
+    user_agent: String,
+    ip_range: String,
+}
+
+// Links in new-device emails stay usable for a week
+const LOGIN_ALERT_TTL_SECONDS: u64 = 7 * 24 * 60 * 60;
+const LOGIN_ALERT_PREFIX: &str = "login_alert:";
+
+fn get_key(token: &LoginAlertToken) -> String {
+    format!("{}{}", LOGIN_ALERT_PREFIX, token.as_ref())
+}
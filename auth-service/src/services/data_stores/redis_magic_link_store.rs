@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use color_eyre::eyre::Context;
+use redis::{Commands, Connection};
+use secrecy::{ExposeSecret, Secret};
+use tokio::sync::RwLock;
+
+use crate::domain::{
+    data_stores::{MagicLinkStore, MagicLinkStoreError},
+    Email, MagicLinkToken, MAGIC_LINK_TTL_SECONDS,
+};
+
+pub struct RedisMagicLinkStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisMagicLinkStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl MagicLinkStore for RedisMagicLinkStore {
+    #[tracing::instrument(name = "Add Magic Link", skip_all)]
+    async fn add_link(
+        &mut self,
+        token: MagicLinkToken,
+        email: Email,
+    ) -> Result<(), MagicLinkStoreError> {
+        let _: () = self
+            .conn
+            .write()
+            .await
+            .set_ex(
+                get_key(&token),
+                email.as_ref().expose_secret(),
+                MAGIC_LINK_TTL_SECONDS,
+            )
+            .wrap_err("failed to set magic link in Redis")
+            .map_err(MagicLinkStoreError::UnexpectedError)?;
+
+        Ok(())
+    }
+
+    // GETDEL makes each link usable once, even across replicas
+    #[tracing::instrument(name = "Take Magic Link", skip_all)]
+    async fn take_link(&mut self, token: &MagicLinkToken) -> Result<Email, MagicLinkStoreError> {
+        let value: Option<String> = self
+            .conn
+            .write()
+            .await
+            .get_del(get_key(token))
+            .wrap_err("failed to take magic link from Redis")
+            .map_err(MagicLinkStoreError::UnexpectedError)?;
+
+        let value = value.ok_or(MagicLinkStoreError::LinkNotFound)?;
+
+        Email::parse(Secret::new(value))
+            .wrap_err("failed to parse magic link email")
+            .map_err(MagicLinkStoreError::UnexpectedError)
+    }
+}
+
+const MAGIC_LINK_PREFIX: &str = "magic_link:";
+
+fn get_key(token: &MagicLinkToken) -> String {
+    format!("{}{}", MAGIC_LINK_PREFIX, token.as_ref())
+}
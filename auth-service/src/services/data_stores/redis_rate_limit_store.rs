@@ -0,0 +1,55 @@
+use color_eyre::eyre::Context;
+use redis::Connection;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::domain::data_stores::{RateLimitStore, RateLimitStoreError};
+
+pub struct RedisRateLimitStore {
+    conn: Arc<RwLock<Connection>>,
+}
+
+impl RedisRateLimitStore {
+    pub fn new(conn: Arc<RwLock<Connection>>) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+    #[tracing::instrument(name = "Record Rate Limit Hit", skip_all)]
+    async fn record_hit(
+        &mut self,
+        key: &str,
+        window_seconds: u64,
+    ) -> Result<u64, RateLimitStoreError> {
+        let key = get_key(key);
+        let ttl: i64 = window_seconds
+            .try_into()
+            .wrap_err("failed to cast window_seconds to i64")
+            .map_err(RateLimitStoreError::UnexpectedError)?;
+        let mut conn = self.conn.write().await;
+
+        // Both commands run in one transaction, and NX only sets the expiry when the key has
+        // none, so the first hit opens the window and a counter can never be left without one
+        let (hits,): (u64,) = redis::pipe()
+            .atomic()
+            .incr(&key, 1)
+            .cmd("EXPIRE")
+            .arg(&key)
+            .arg(ttl)
+            .arg("NX")
+            .ignore()
+            .query(&mut *conn)
+            .wrap_err("failed to record rate limit hit in Redis")
+            .map_err(RateLimitStoreError::UnexpectedError)?;
+
+        Ok(hits)
+    }
+}
+
+const RATE_LIMIT_KEY_PREFIX: &str = "rate_limit:";
+
+fn get_key(key: &str) -> String {
+    format!("{}{}", RATE_LIMIT_KEY_PREFIX, key)
+}
@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 
 use crate::domain::{
-    data_stores::{LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError},
+    data_stores::{FirstFactor, LoginAttemptId, TwoFACode, TwoFACodeStore, TwoFACodeStoreError},
     Email,
 };
 
@@ -23,19 +23,20 @@ impl RedisTwoFACodeStore {
 
 #[async_trait::async_trait]
 impl TwoFACodeStore for RedisTwoFACodeStore {
-    
     #[tracing::instrument(name = "Add 2FA Code", skip_all)]
     async fn add_code(
         &mut self,
         email: Email,
         login_attempt_id: LoginAttemptId,
         code: TwoFACode,
+        first_factor: FirstFactor,
     ) -> Result<(), TwoFACodeStoreError> {
         let key = get_key(&email);
 
         let two_fa_tuple = TwoFATuple(
             login_attempt_id.as_ref().to_string(),
             code.as_ref().to_string(),
+            first_factor,
         );
 
         let serialized_data = serde_json::to_string(&two_fa_tuple)
@@ -72,7 +73,7 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
     async fn get_code(
         &self,
         email: &Email,
-    ) -> Result<(LoginAttemptId, TwoFACode), TwoFACodeStoreError> {
+    ) -> Result<(LoginAttemptId, TwoFACode, FirstFactor), TwoFACodeStoreError> {
         let key = get_key(email);
 
         match self.conn.write().await.get::<_, String>(&key) {
@@ -87,7 +88,7 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
                 let email_code = TwoFACode::parse(data.1)
                     .map_err(|e| TwoFACodeStoreError::UnexpectedError(eyre!("{}", e)))?; // Updated!
 
-                Ok((login_attempt_id, email_code))
+                Ok((login_attempt_id, email_code, data.2))
             }
             Err(_) => Err(TwoFACodeStoreError::LoginAttemptIdNotFound),
         }
@@ -95,7 +96,12 @@ impl TwoFACodeStore for RedisTwoFACodeStore {
 }
 
 #[derive(Serialize, Deserialize)]
-struct TwoFATuple(pub String, pub String);
+struct TwoFATuple(
+    pub String,
+    pub String,
+    // Codes stored before the first factor was recorded came from a password login
+    #[serde(default)] pub FirstFactor,
+);
 
 const TEN_MINUTES_IN_SECONDS: u64 = 600;
 const TWO_FA_CODE_PREFIX: &str = "two_fa_code:";
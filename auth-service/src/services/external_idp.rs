@@ -0,0 +1,206 @@
+use color_eyre::eyre::{eyre, Context, Result};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use reqwest::{Client, Url};
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+
+use crate::utils::{ExternalIdpSettings, EXTERNAL_IDP_TIMEOUT};
+
+// The relying party side of OpenID Connect, for logging users in through an external identity
+// provider with the authorization code flow and PKCE
+pub struct ExternalIdpClient {
+    http_client: Client,
+    settings: ExternalIdpSettings,
+}
+
+impl ExternalIdpClient {
+    // None while no provider is configured
+    pub fn from_settings(settings: &ExternalIdpSettings) -> Option<Self> {
+        settings.issuer_url.as_ref()?;
+        let http_client = Client::builder()
+            .timeout(EXTERNAL_IDP_TIMEOUT)
+            .build()
+            .ok()?;
+
+        Some(Self {
+            http_client,
+            settings: settings.clone(),
+        })
+    }
+
+    fn issuer(&self) -> &str {
+        self.settings.issuer().unwrap_or_default()
+    }
+
+    // OpenID Connect Discovery 1.0 section 4. The document must name the configured issuer, or
+    // anyone able to serve it could vouch for other providers' tokens.
+    #[tracing::instrument(name = "Discover External IdP", skip_all)]
+    pub async fn discover(&self) -> Result<ProviderMetadata> {
+        let url = format!("{}/.well-known/openid-configuration", self.issuer());
+        let metadata: ProviderMetadata = self
+            .http_client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .wrap_err("failed to fetch the identity provider's configuration")?
+            .json()
+            .await
+            .wrap_err("invalid identity provider configuration")?;
+
+        if metadata.issuer.trim_end_matches('/') != self.issuer() {
+            return Err(eyre!(
+                "identity provider configuration is for issuer `{}`",
+                metadata.issuer
+            ));
+        }
+
+        Ok(metadata)
+    }
+
+    // Where to send the user to log in
+    pub fn authorization_url(
+        &self,
+        metadata: &ProviderMetadata,
+        redirect_uri: &str,
+        state: &str,
+        nonce: &str,
+        code_challenge: &str,
+    ) -> Result<Url> {
+        Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", self.settings.client_id.as_str()),
+                ("redirect_uri", redirect_uri),
+                ("scope", self.settings.scopes.join(" ").as_str()),
+                ("state", state),
+                ("nonce", nonce),
+                ("code_challenge", code_challenge),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .wrap_err("invalid authorization endpoint")
+    }
+
+    // Redeems the code the provider redirected back with, returning the ID token
+    #[tracing::instrument(name = "Redeem External IdP Code", skip_all)]
+    pub async fn redeem_code(
+        &self,
+        metadata: &ProviderMetadata,
+        code: &str,
+        redirect_uri: &str,
+        code_verifier: &str,
+    ) -> Result<String> {
+        let mut request = self.http_client.post(&metadata.token_endpoint).form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("client_id", self.settings.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ]);
+        if let Some(client_secret) = &self.settings.client_secret {
+            request = request.basic_auth(
+                &self.settings.client_id,
+                Some(client_secret.expose_secret()),
+            );
+        }
+
+        let response: TokenResponse = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .wrap_err("the identity provider refused the authorization code")?
+            .json()
+            .await
+            .wrap_err("invalid token response from the identity provider")?;
+
+        response
+            .id_token
+            .ok_or_else(|| eyre!("the identity provider returned no ID token"))
+    }
+
+    // OpenID Connect Core section 3.1.3.7: signed by one of the provider's published keys,
+    // issued by it to this client, unexpired, and carrying the nonce this login sent
+    #[tracing::instrument(name = "Verify External ID Token", skip_all)]
+    pub async fn verify_id_token(
+        &self,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<ExternalIdTokenClaims> {
+        let header = decode_header(id_token).wrap_err("malformed ID token")?;
+        // Only asymmetric signatures, so the algorithm cannot be switched to HS256
+        if !ID_TOKEN_ALGORITHMS.contains(&header.alg) {
+            return Err(eyre!("ID token signed with {:?}", header.alg));
+        }
+
+        let jwks: JwkSet = self
+            .http_client
+            .get(&metadata.jwks_uri)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .wrap_err("failed to fetch the identity provider's keys")?
+            .json()
+            .await
+            .wrap_err("invalid identity provider key set")?;
+        let jwk = match &header.kid {
+            Some(kid) => jwks.find(kid),
+            None if jwks.keys.len() == 1 => jwks.keys.first(),
+            None => None,
+        }
+        .ok_or_else(|| eyre!("ID token signed with an unknown key"))?;
+        let key = DecodingKey::from_jwk(jwk).wrap_err("unusable identity provider key")?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[self.issuer(), metadata.issuer.as_str()]);
+        validation.set_audience(&[&self.settings.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let claims = decode::<ExternalIdTokenClaims>(id_token, &key, &validation)
+            .wrap_err("invalid ID token")?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(eyre!("ID token nonce does not match"));
+        }
+
+        Ok(claims)
+    }
+}
+
+const ID_TOKEN_ALGORITHMS: [Algorithm; 6] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::ES256,
+    Algorithm::ES384,
+];
+
+// The parts of the provider's discovery document the login flow uses
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExternalIdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    #[serde(default)]
+    pub nonce: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    // Only verified addresses are trusted to link or create accounts
+    #[serde(default)]
+    pub email_verified: bool,
+}
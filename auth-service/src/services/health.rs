@@ -0,0 +1,194 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use color_eyre::eyre::{eyre, Result};
+use serde::Serialize;
+use tokio::time::Instant;
+
+use crate::{
+    app_state::{AppState, EmailClientType, RedisConnectionType},
+    utils::{
+        HEALTH_CHECK_EMAIL_PROVIDER_TIMEOUT, HEALTH_CHECK_POSTGRES_TIMEOUT,
+        HEALTH_CHECK_REDIS_TIMEOUT,
+    },
+};
+
+// What the readiness probe checks beyond the required Postgres and Redis dependencies, and
+// whether the service is draining for shutdown
+#[derive(Debug, Default)]
+pub struct Readiness {
+    // The email provider is reported but never fails readiness: logins without 2FA still work
+    pub check_email_provider: bool,
+    draining: AtomicBool,
+}
+
+impl Readiness {
+    pub fn new(check_email_provider: bool) -> Self {
+        Self {
+            check_email_provider,
+            draining: AtomicBool::new(false),
+        }
+    }
+
+    // Fails readiness from now on so load balancers stop routing new traffic here
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessStatus {
+    Ready,
+    NotReady,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyHealth {
+    pub status: DependencyStatus,
+    pub required: bool,
+    pub latency_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessReport {
+    pub status: ReadinessStatus,
+    pub draining: bool,
+    pub checks: BTreeMap<&'static str, DependencyHealth>,
+}
+
+// Probes every dependency concurrently, each bounded by its own timeout
+#[tracing::instrument(name = "Check Readiness", skip_all)]
+pub async fn check_readiness(state: &AppState) -> ReadinessReport {
+    let (postgres, redis, email_provider) = tokio::join!(
+        check_dependency(
+            true,
+            HEALTH_CHECK_POSTGRES_TIMEOUT,
+            check_postgres(&state.pg_pool)
+        ),
+        check_dependency(
+            true,
+            HEALTH_CHECK_REDIS_TIMEOUT,
+            check_redis(state.redis_conn.clone())
+        ),
+        async {
+            if state.readiness.check_email_provider {
+                Some(
+                    check_dependency(
+                        false,
+                        HEALTH_CHECK_EMAIL_PROVIDER_TIMEOUT,
+                        check_email_provider(&state.email_client),
+                    )
+                    .await,
+                )
+            } else {
+                None
+            }
+        }
+    );
+
+    let mut checks = BTreeMap::from([("postgres", postgres), ("redis", redis)]);
+    if let Some(email_provider) = email_provider {
+        checks.insert("emailProvider", email_provider);
+    }
+
+    let draining = state.readiness.is_draining();
+    let ready = !draining
+        && checks
+            .values()
+            .all(|check| !check.required || check.status == DependencyStatus::Up);
+
+    ReadinessReport {
+        status: if ready {
+            ReadinessStatus::Ready
+        } else {
+            ReadinessStatus::NotReady
+        },
+        draining,
+        checks,
+    }
+}
+
+async fn check_dependency(
+    required: bool,
+    timeout: Duration,
+    check: impl Future<Output = Result<()>>,
+) -> DependencyHealth {
+    let start = Instant::now();
+    let outcome = match tokio::time::timeout(timeout, check).await {
+        Ok(outcome) => outcome,
+        Err(_) => Err(eyre!("timed out after {}ms", timeout.as_millis())),
+    };
+    let latency_ms = start.elapsed().as_millis();
+
+    match outcome {
+        Ok(()) => DependencyHealth {
+            status: DependencyStatus::Up,
+            required,
+            latency_ms,
+            error: None,
+        },
+        Err(e) => {
+            tracing::warn!("Health check failed: {:?}", e);
+            DependencyHealth {
+                status: DependencyStatus::Down,
+                required,
+                latency_ms,
+                error: Some(e.to_string()),
+            }
+        }
+    }
+}
+
+async fn check_postgres(pg_pool: &sqlx::PgPool) -> Result<()> {
+    sqlx::query("SELECT 1").execute(pg_pool).await?;
+    Ok(())
+}
+
+async fn check_redis(redis_conn: RedisConnectionType) -> Result<()> {
+    // The Redis connection is synchronous, so ping it off the async runtime. If the ping hangs
+    // past the timeout the blocking task is abandoned rather than stalling the probe.
+    tokio::task::spawn_blocking(move || {
+        let mut conn = redis_conn.blocking_write();
+        redis::cmd("PING").query::<String>(&mut *conn)
+    })
+    .await??;
+    Ok(())
+}
+
+async fn check_email_provider(email_client: &EmailClientType) -> Result<()> {
+    email_client.read().await.check_health().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draining_is_one_way() {
+        let readiness = Readiness::new(false);
+        assert!(!readiness.is_draining());
+
+        readiness.start_draining();
+        readiness.start_draining();
+        assert!(readiness.is_draining());
+    }
+}
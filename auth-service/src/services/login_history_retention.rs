@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use color_eyre::eyre::{eyre, Result};
+use tokio::task::JoinHandle;
+
+use crate::app_state::LoginHistoryStoreType;
+
+// Periodically removes login history older than the retention period
+pub fn spawn_login_history_retention(
+    login_history_store: LoginHistoryStoreType,
+    retention_days: i64,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            match prune_login_history(&login_history_store, retention_days).await {
+                Ok(deleted) => tracing::info!("Pruned {} expired login history entries", deleted),
+                Err(e) => tracing::error!("Failed to prune login history: {:?}", e),
+            }
+        }
+    })
+}
+
+#[tracing::instrument(name = "Prune Login History", skip_all)]
+pub async fn prune_login_history(
+    login_history_store: &LoginHistoryStoreType,
+    retention_days: i64,
+) -> Result<u64> {
+    let retention = chrono::Duration::try_days(retention_days).ok_or(eyre!(
+        "invalid login history retention: {} days",
+        retention_days
+    ))?;
+
+    let cutoff = Utc::now()
+        .checked_sub_signed(retention)
+        .ok_or(eyre!("failed to compute login history retention cutoff"))?;
+
+    let deleted = login_history_store
+        .write()
+        .await
+        .delete_entries_before(cutoff)
+        .await?;
+
+    Ok(deleted)
+}
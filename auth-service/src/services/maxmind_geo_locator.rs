@@ -0,0 +1,41 @@
+use std::{net::IpAddr, path::Path};
+
+use color_eyre::eyre::{Context, Result};
+use maxminddb::{geoip2, Reader};
+
+use crate::domain::{GeoLocator, Location};
+
+// Looks addresses up in a local MaxMind (GeoLite2/GeoIP2 City) database file
+pub struct MaxMindGeoLocator {
+    reader: Reader<Vec<u8>>,
+}
+
+impl MaxMindGeoLocator {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let reader = Reader::open_readfile(path.as_ref()).wrap_err_with(|| {
+            format!(
+                "failed to open GeoIP database at {}",
+                path.as_ref().display()
+            )
+        })?;
+        Ok(Self { reader })
+    }
+}
+
+impl GeoLocator for MaxMindGeoLocator {
+    #[tracing::instrument(name = "GeoIP lookup", skip_all)]
+    fn locate(&self, ip: IpAddr) -> Option<Location> {
+        let city: geoip2::City = self.reader.lookup(ip).ok()?;
+
+        let english_name = |names: Option<std::collections::BTreeMap<&str, &str>>| {
+            names.and_then(|names| names.get("en").map(|name| name.to_string()))
+        };
+
+        let location = Location {
+            city: english_name(city.city.and_then(|city| city.names)),
+            country: english_name(city.country.and_then(|country| country.names)),
+        };
+
+        (location != Location::default()).then_some(location)
+    }
+}
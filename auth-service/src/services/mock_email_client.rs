@@ -19,4 +19,8 @@ impl EmailClient for MockEmailClient {
 
         Ok(())
     }
+
+    async fn check_health(&self) -> Result<()> {
+        Ok(())
+    }
 }
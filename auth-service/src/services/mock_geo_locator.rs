@@ -0,0 +1,15 @@
+use std::net::IpAddr;
+
+use crate::domain::{GeoLocator, Location};
+
+// Stands in when no GeoIP database is configured: every address resolves to `location`
+#[derive(Default)]
+pub struct MockGeoLocator {
+    pub location: Option<Location>,
+}
+
+impl GeoLocator for MockGeoLocator {
+    fn locate(&self, _ip: IpAddr) -> Option<Location> {
+        self.location.clone()
+    }
+}
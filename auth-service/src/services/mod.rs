@@ -1,7 +1,25 @@
+pub mod audit;
+pub mod audit_sinks;
 pub mod data_stores;
+pub mod external_idp;
+pub mod health;
+pub mod login_history_retention;
+pub mod maxmind_geo_locator;
 pub mod mock_email_client;
+pub mod mock_geo_locator;
+pub mod new_device_alert;
 pub mod postmark_email_client;
+pub mod webhooks;
 
+pub use audit::*;
+pub use audit_sinks::*;
 pub use data_stores::*;
+pub use external_idp::*;
+pub use health::*;
+pub use login_history_retention::*;
+pub use maxmind_geo_locator::*;
 pub use mock_email_client::*;
+pub use mock_geo_locator::*;
+pub use new_device_alert::*;
 pub use postmark_email_client::*;
+pub use webhooks::*;
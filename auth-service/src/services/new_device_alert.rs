@@ -0,0 +1,113 @@
+use std::net::IpAddr;
+
+use chrono::Utc;
+use color_eyre::eyre::Result;
+use secrecy::Secret;
+
+use crate::{
+    app_state::AppState,
+    domain::{ClientInfo, DeviceFingerprint, Email, Location, LoginAlert, LoginAlertToken},
+};
+
+// Emails the account owner when a successful login comes from a device we have not seen
+// for them before. The very first login only records the device, since there is nothing
+// to compare it against yet.
+#[tracing::instrument(name = "Notify If New Device", skip_all)]
+pub async fn notify_if_new_device(
+    state: &AppState,
+    email: &Email,
+    client: &ClientInfo,
+    session_token: &str,
+) -> Result<()> {
+    let device = DeviceFingerprint::from_client_info(client);
+
+    {
+        let mut known_device_store = state.known_device_store.write().await;
+
+        if known_device_store.is_known_device(email, &device).await? {
+            // Refresh when the device was last seen
+            known_device_store.add_device(email, &device).await?;
+            return Ok(());
+        }
+
+        let is_first_device = !known_device_store.has_known_devices(email).await?;
+        known_device_store.add_device(email, &device).await?;
+
+        if is_first_device {
+            return Ok(());
+        }
+    }
+
+    let token = LoginAlertToken::default();
+    state
+        .login_alert_store
+        .write()
+        .await
+        .add_alert(
+            token.clone(),
+            LoginAlert {
+                email: email.clone(),
+                session_token: Secret::new(session_token.to_owned()),
+                device,
+            },
+        )
+        .await?;
+
+    let location = match client
+        .ip_address
+        .as_deref()
+        .and_then(|ip| ip.parse::<IpAddr>().ok())
+    {
+        Some(ip) => state
+            .geo_locator
+            .read()
+            .await
+            .locate(ip)
+            .unwrap_or_default(),
+        None => Location::default(),
+    };
+
+    state
+        .email_client
+        .read()
+        .await
+        .send_email(
+            email,
+            NEW_DEVICE_EMAIL_SUBJECT,
+            &new_device_email_content(
+                &state.settings.application.public_url,
+                client,
+                &location,
+                &token,
+            ),
+        )
+        .await
+}
+
+const NEW_DEVICE_EMAIL_SUBJECT: &str = "New sign-in to your account";
+
+// The report link opens a page that asks before signing anything out, so mail scanners that
+// follow every link do no harm. The token is in the fragment, which keeps it out of server logs.
+fn new_device_email_content(
+    public_url: &str,
+    client: &ClientInfo,
+    location: &Location,
+    token: &LoginAlertToken,
+) -> String {
+    format!(
+        "We noticed a new sign-in to your account.\n\n\
+         Time: {}\n\
+         Approximate location: {}\n\
+         Device: {}\n\
+         IP address: {}\n\n\
+         If this was you, there is nothing else to do.\n\n\
+         If this wasn't you, use the link below to sign that session out and reset your password:\n\
+         {}/account/report-login#token={}",
+        Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+        location,
+        client.user_agent.as_deref().unwrap_or("Unknown device"),
+        client.ip_address.as_deref().unwrap_or("Unknown"),
+        public_url.trim_end_matches('/'),
+        token.as_ref()
+    )
+}
@@ -2,7 +2,10 @@ use color_eyre::eyre::Result; // For improved error handling and reporting
 use reqwest::{Client, Url}; // For making HTTP requests
 use secrecy::{ExposeSecret, Secret}; // For securely handling sensitive data
 
-use crate::domain::{Email, EmailClient}; // Import domain-specific modules
+use crate::{
+    domain::{Email, EmailClient},
+    utils::METRICS,
+}; // Import domain-specific modules
 
 // Define the PostmarkEmailClient struct
 pub struct PostmarkEmailClient {
@@ -58,7 +61,33 @@ impl EmailClient for PostmarkEmailClient {
             .json(&request_body);
 
         // Send the request and handle the response
-        request.send().await?.error_for_status()?;
+        if let Err(e) = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+        {
+            METRICS.email_send_failures.inc();
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Checking email provider health", skip_all)]
+    async fn check_health(&self) -> Result<()> {
+        // The server details endpoint is authenticated, so this also validates the token
+        let url = Url::parse(&self.base_url)?.join("/server")?;
+
+        self.http_client
+            .get(url)
+            .header(
+                POSTMARK_AUTH_HEADER,
+                self.authorization_token.expose_secret(),
+            )
+            .header(reqwest::header::ACCEPT, "application/json")
+            .send()
+            .await?
+            .error_for_status()?;
 
         Ok(())
     }
@@ -83,7 +112,6 @@ struct SendEmailRequest<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::utils::constants::test;
 
     use super::*;
     use fake::faker::internet::en::SafeEmail;
@@ -112,7 +140,7 @@ mod tests {
     // Helper function to create a test email client
     fn email_client(base_url: String) -> PostmarkEmailClient {
         let http_client = Client::builder()
-            .timeout(test::email_client::TIMEOUT)
+            .timeout(std::time::Duration::from_millis(200))
             .build()
             .unwrap();
         PostmarkEmailClient::new(base_url, email(), Secret::new(Faker.fake()), http_client)
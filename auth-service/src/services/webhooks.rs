@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use color_eyre::eyre::{eyre, Result};
+use reqwest::Client;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::{
+    app_state::{AppState, WebhookStoreType},
+    domain::{
+        sign_webhook_payload, WebhookDelivery, WebhookDeliveryPolicy, WebhookEventType,
+        WebhookPayload, WebhookStoreError,
+    },
+    utils::{WEBHOOK_DELIVERY_BATCH_SIZE, WEBHOOK_DELIVERY_LEASE},
+};
+
+pub const WEBHOOK_EVENT_HEADER: &str = "X-Webhook-Event";
+pub const WEBHOOK_TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+// Queues `data` for every subscription that wants `event_type`. The delivery worker sends it
+// later, so webhook failures never slow down or fail the request that triggered them.
+#[tracing::instrument(name = "Publish Webhook Event", skip_all)]
+pub async fn publish_webhook_event(
+    state: &AppState,
+    event_type: WebhookEventType,
+    data: serde_json::Value,
+) {
+    if let Err(e) = enqueue_webhook_deliveries(&state.webhook_store, event_type, data).await {
+        tracing::error!("Failed to queue {} webhooks: {:?}", event_type.as_str(), e);
+    }
+}
+
+async fn enqueue_webhook_deliveries(
+    webhook_store: &WebhookStoreType,
+    event_type: WebhookEventType,
+    data: serde_json::Value,
+) -> Result<()> {
+    let mut webhook_store = webhook_store.write().await;
+
+    let subscriptions: Vec<_> = webhook_store
+        .get_subscriptions()
+        .await?
+        .into_iter()
+        .filter(|subscription| subscription.wants(event_type))
+        .collect();
+
+    if subscriptions.is_empty() {
+        return Ok(());
+    }
+
+    // Every subscriber gets the same event id so they can correlate and deduplicate
+    let payload = serde_json::to_string(&WebhookPayload {
+        id: Uuid::new_v4(),
+        event_type,
+        occurred_at: Utc::now(),
+        data,
+    })?;
+
+    for subscription in subscriptions {
+        webhook_store
+            .enqueue_delivery(WebhookDelivery::new(
+                subscription.id,
+                event_type,
+                payload.clone(),
+            ))
+            .await?;
+    }
+
+    Ok(())
+}
+
+// Polls the delivery queue until the process exits
+pub fn spawn_webhook_worker(
+    webhook_store: WebhookStoreType,
+    http_client: Client,
+    policy: WebhookDeliveryPolicy,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            if let Err(e) = deliver_due_webhooks(&webhook_store, &http_client, &policy).await {
+                tracing::error!("Failed to deliver webhooks: {:?}", e);
+            }
+        }
+    })
+}
+
+// Sends every delivery that is due once. Failures are rescheduled with exponential backoff
+// and moved to the dead-letter table after the policy's last attempt. Returns how many
+// deliveries were attempted.
+#[tracing::instrument(name = "Deliver Due Webhooks", skip_all)]
+pub async fn deliver_due_webhooks(
+    webhook_store: &WebhookStoreType,
+    http_client: &Client,
+    policy: &WebhookDeliveryPolicy,
+) -> Result<usize> {
+    let deliveries = webhook_store
+        .write()
+        .await
+        .claim_due_deliveries(WEBHOOK_DELIVERY_BATCH_SIZE, WEBHOOK_DELIVERY_LEASE)
+        .await?;
+    let attempted = deliveries.len();
+
+    for mut delivery in deliveries {
+        // The store lock is not held while waiting on the subscriber
+        let subscription = match webhook_store
+            .read()
+            .await
+            .get_subscription(delivery.subscription_id)
+            .await
+        {
+            Ok(subscription) => subscription,
+            // Deleted since the claim; its deliveries went with it
+            Err(WebhookStoreError::SubscriptionNotFound) => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        let outcome = send_delivery(
+            http_client,
+            &subscription.url,
+            &subscription.secret,
+            &delivery,
+        )
+        .await;
+
+        let mut webhook_store = webhook_store.write().await;
+
+        match outcome {
+            Ok(()) => webhook_store.remove_delivery(delivery.id).await?,
+            Err(e) => {
+                delivery.attempts += 1;
+                delivery.last_error = Some(e.to_string());
+
+                if policy.is_exhausted(delivery.attempts) {
+                    tracing::warn!(
+                        "Webhook delivery {} failed {} times; moving it to the dead-letter queue",
+                        delivery.id,
+                        delivery.attempts
+                    );
+                    webhook_store.dead_letter(delivery).await?;
+                } else {
+                    delivery.next_attempt_at =
+                        Utc::now() + chrono::Duration::from_std(policy.backoff(delivery.attempts))?;
+                    webhook_store.schedule_retry(&delivery).await?;
+                }
+            }
+        }
+    }
+
+    Ok(attempted)
+}
+
+async fn send_delivery(
+    http_client: &Client,
+    url: &str,
+    secret: &secrecy::Secret<String>,
+    delivery: &WebhookDelivery,
+) -> Result<()> {
+    let timestamp = Utc::now().timestamp();
+    let signature = sign_webhook_payload(secret, timestamp, &delivery.payload);
+
+    let response = http_client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(WEBHOOK_EVENT_HEADER, delivery.event_type.as_str())
+        .header(WEBHOOK_TIMESTAMP_HEADER, timestamp.to_string())
+        .header(WEBHOOK_SIGNATURE_HEADER, format!("sha256={}", signature))
+        .body(delivery.payload.clone())
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(eyre!(
+            "Subscriber responded with HTTP {}",
+            response.status()
+        ));
+    }
+
+    Ok(())
+}
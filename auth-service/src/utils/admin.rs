@@ -0,0 +1,27 @@
+use axum::http::{header::AUTHORIZATION, HeaderMap};
+use secrecy::{ExposeSecret, Secret};
+use sha2::{Digest, Sha256};
+
+use crate::domain::AuthAPIError;
+
+// Admin routes are restricted to holders of a configured bearer token. Without one configured
+// every request is rejected.
+pub fn require_admin_token(
+    headers: &HeaderMap,
+    admin_token: Option<&Secret<String>>,
+) -> Result<(), AuthAPIError> {
+    let presented = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(AuthAPIError::MissingToken)?;
+
+    let expected = admin_token.ok_or(AuthAPIError::InvalidToken)?;
+
+    // Comparing digests keeps the comparison time independent of how much of the token matched
+    if Sha256::digest(presented.as_bytes()) != Sha256::digest(expected.expose_secret().as_bytes()) {
+        return Err(AuthAPIError::InvalidToken);
+    }
+
+    Ok(())
+}
@@ -1,4 +1,7 @@
-use crate::{app_state::BannedTokenStoreType, domain::email::Email};
+use crate::{
+    app_state::BannedTokenStoreType,
+    domain::{email::Email, FirstFactor},
+};
 use axum_extra::extract::cookie::{Cookie, SameSite};
 use chrono::Utc;
 use color_eyre::eyre::{eyre, Context, ContextCompat, Result};
@@ -6,24 +9,63 @@ use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Validation};
 use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Serialize};
 
-use super::constants::{JWT_COOKIE_NAME, JWT_SECRET};
+use super::settings::{CookieSameSite, CookieSettings, JwtSettings};
 
 // Create cookie with a new JWT auth token
 #[tracing::instrument(name = "Generate Auth Cookie", skip_all)]
-pub fn generate_auth_cookie(email: &Email) -> Result<Cookie<'static>> {
-    let token = generate_auth_token(email)?;
-    Ok(create_auth_cookie(token))
+pub fn generate_auth_cookie(
+    email: &Email,
+    method: AuthenticationMethod,
+    jwt: &JwtSettings,
+    cookie: &CookieSettings,
+) -> Result<Cookie<'static>> {
+    let token = generate_auth_token(email, method, jwt)?;
+    Ok(create_auth_cookie(token, cookie))
 }
 
 // Create cookie and set the value to the passed-in token string
 #[tracing::instrument(name = "Create Auth Cookie", skip_all)]
-fn create_auth_cookie(token: String) -> Cookie<'static> {
-    let cookie = Cookie::build((JWT_COOKIE_NAME, token))
+fn create_auth_cookie(token: String, settings: &CookieSettings) -> Cookie<'static> {
+    let mut cookie = build_auth_cookie(token, settings);
+    // The browser drops the cookie when the token inside it expires
+    cookie.set_max_age(time::Duration::seconds(TOKEN_TTL_SECONDS));
+
+    cookie
+}
+
+// An auth cookie to pass to `CookieJar::remove`. Browsers only replace a cookie with the same
+// name, domain and path, so this carries exactly the attributes it was set with.
+pub fn removal_auth_cookie(settings: &CookieSettings) -> Cookie<'static> {
+    build_auth_cookie(String::new(), settings)
+}
+
+fn build_auth_cookie(value: String, settings: &CookieSettings) -> Cookie<'static> {
+    let mut cookie = build_session_cookie(settings.cookie_name(), value, settings);
+    cookie.set_http_only(true);
+
+    cookie
+}
+
+// The attributes every cookie of a browser session is set with
+pub fn build_session_cookie(
+    name: String,
+    value: String,
+    settings: &CookieSettings,
+) -> Cookie<'static> {
+    let mut cookie = Cookie::build((name, value))
         .path("/")
-        .http_only(true)
-        .same_site(SameSite::Lax)
+        .secure(settings.secure)
+        .same_site(match settings.same_site {
+            CookieSameSite::Strict => SameSite::Strict,
+            CookieSameSite::Lax => SameSite::Lax,
+            CookieSameSite::None => SameSite::None,
+        })
         .build();
 
+    if let Some(domain) = &settings.domain {
+        cookie.set_domain(domain.clone());
+    }
+
     cookie
 }
 
@@ -37,35 +79,209 @@ pub enum TokenError {
 // This value determines how long the JWT auth token is valid for
 pub const TOKEN_TTL_SECONDS: i64 = 600; // 10 minutes
 
+// Service clients can ask for a new token whenever they need one, so theirs are kept short
+pub const CLIENT_TOKEN_TTL_SECONDS: i64 = 300; // 5 minutes
+
+// How a session was authenticated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuthenticationMethod {
+    Password,
+    // A password followed by the emailed 2FA code
+    TwoFactor,
+    // Logged in at the external identity provider
+    External,
+    // Followed a single-use login link sent by email
+    MagicLink,
+    // A login link followed by the emailed 2FA code
+    MagicLinkTwoFactor,
+}
+
+impl AuthenticationMethod {
+    // The method of a login finished with the 2FA code
+    pub fn two_factor(first_factor: FirstFactor) -> Self {
+        match first_factor {
+            FirstFactor::Password => Self::TwoFactor,
+            FirstFactor::MagicLink => Self::MagicLinkTwoFactor,
+        }
+    }
+
+    // Authentication method references (RFC 8176), as reported in OpenID Connect ID tokens
+    pub fn amr(&self) -> Vec<String> {
+        let values: &[&str] = match self {
+            Self::Password => &["pwd"],
+            Self::TwoFactor => &["pwd", "otp", "mfa"],
+            Self::External => &["fed"],
+            Self::MagicLink => &["otp"],
+            Self::MagicLinkTwoFactor => &["otp", "mfa"],
+        };
+        values.iter().map(|value| (*value).to_owned()).collect()
+    }
+}
+
 // Create JWT auth token
 #[tracing::instrument(name = "Generate Auth Token", skip_all)]
-pub fn generate_auth_token(email: &Email) -> Result<String> {
-    let delta = chrono::Duration::try_seconds(TOKEN_TTL_SECONDS)
-        .wrap_err("failed to create 10 minute time delta")?;
+pub fn generate_auth_token(
+    email: &Email,
+    method: AuthenticationMethod,
+    jwt: &JwtSettings,
+) -> Result<String> {
+    let iat = issued_at()?;
+    let claims = Claims {
+        sub: email.as_ref().expose_secret().to_owned(),
+        exp: token_expiry(TOKEN_TTL_SECONDS)?,
+        iat: Some(iat),
+        auth_time: Some(iat),
+        amr: method.amr(),
+        // Every login starts a new session
+        sid: Some(uuid::Uuid::new_v4().to_string()),
+        client_id: None,
+        scope: None,
+        aud: None,
+        act: None,
+        kind: TokenKind::User,
+    };
+
+    create_token(&claims, jwt)
+}
+
+// Create an access token for an OAuth client, limited to the scopes the user granted it. `sid`
+// is the session the user approved the client from.
+#[tracing::instrument(name = "Generate Access Token", skip_all)]
+pub fn generate_access_token(
+    email: &Email,
+    client_id: &str,
+    scope: &str,
+    sid: Option<&str>,
+    jwt: &JwtSettings,
+) -> Result<String> {
+    let claims = Claims {
+        sub: email.as_ref().expose_secret().to_owned(),
+        exp: token_expiry(TOKEN_TTL_SECONDS)?,
+        iat: Some(issued_at()?),
+        auth_time: None,
+        amr: Vec::new(),
+        sid: sid.map(str::to_owned),
+        client_id: Some(client_id.to_owned()),
+        scope: Some(scope.to_owned()),
+        aud: None,
+        act: None,
+        kind: TokenKind::User,
+    };
+
+    create_token(&claims, jwt)
+}
+
+// Create a token for a service client acting on its own behalf, with the client as the subject
+#[tracing::instrument(name = "Generate Client Token", skip_all)]
+pub fn generate_client_token(client_id: &str, scope: &str, jwt: &JwtSettings) -> Result<String> {
+    let claims = Claims {
+        sub: client_id.to_owned(),
+        exp: token_expiry(CLIENT_TOKEN_TTL_SECONDS)?,
+        iat: Some(issued_at()?),
+        auth_time: None,
+        amr: Vec::new(),
+        sid: None,
+        client_id: Some(client_id.to_owned()),
+        scope: Some(scope.to_owned()),
+        aud: None,
+        act: None,
+        kind: TokenKind::Client,
+    };
+
+    create_token(&claims, jwt)
+}
+
+// Create a token for a service client to call `audience` on behalf of the subject token's user
+// (RFC 8693). It never outlives the subject token. Returns the token and its lifetime in seconds.
+#[tracing::instrument(name = "Generate Delegated Token", skip_all)]
+pub fn generate_delegated_token(
+    subject: &Claims,
+    actor: &str,
+    audience: &str,
+    scope: &str,
+    jwt: &JwtSettings,
+) -> Result<(String, i64)> {
+    let iat = issued_at()?;
+    let exp = token_expiry(CLIENT_TOKEN_TTL_SECONDS)?.min(subject.exp);
+    let claims = Claims {
+        sub: subject.sub.clone(),
+        exp,
+        iat: Some(iat),
+        auth_time: None,
+        amr: Vec::new(),
+        sid: subject.sid.clone(),
+        client_id: Some(actor.to_owned()),
+        scope: Some(scope.to_owned()),
+        aud: Some(audience.to_owned()),
+        act: Some(Actor {
+            sub: actor.to_owned(),
+        }),
+        kind: TokenKind::User,
+    };
+
+    Ok((create_token(&claims, jwt)?, exp.saturating_sub(iat) as i64))
+}
+
+fn issued_at() -> Result<usize> {
+    Utc::now()
+        .timestamp()
+        .try_into()
+        .wrap_err("invalid issue time")
+}
+
+fn token_expiry(ttl_seconds: i64) -> Result<usize> {
+    let delta = chrono::Duration::try_seconds(ttl_seconds).wrap_err(format!(
+        "failed to create {} second time delta",
+        ttl_seconds
+    ))?;
 
     // Create JWT expiration time
     let exp = Utc::now()
         .checked_add_signed(delta)
-        .ok_or(eyre!("failed to add 10 minutes to current time"))?
+        .ok_or(eyre!(
+            "failed to add {} seconds to current time",
+            ttl_seconds
+        ))?
         .timestamp();
 
-    let exp: usize = exp.try_into().wrap_err(format!(
+    exp.try_into().wrap_err(format!(
         "failed to cast exp time to usize. exp time: {}",
         exp
-    ))?;
-
-    let sub = email.as_ref().expose_secret().to_owned();
+    ))
+}
 
-    let claims = Claims { sub, exp };
+// Check if JWT auth token is valid by decoding it using the JWT secret. Tokens restricted to an
+// audience are only valid for that audience, so they are rejected here.
+pub async fn validate_token(
+    token: &str,
+    banned_token_store: &BannedTokenStoreType,
+    jwt: &JwtSettings,
+) -> Result<Claims> {
+    validate_token_for(
+        token,
+        ExpectedAudience::Unrestricted,
+        banned_token_store,
+        jwt,
+    )
+    .await
+}
 
-    create_token(&claims)
+// Which audience-restricted tokens a caller accepts. Tokens without an audience are always valid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExpectedAudience<'a> {
+    Unrestricted,
+    // Tokens restricted to this service client
+    Service(&'a str),
+    // Every audience, for callers that check the claims themselves
+    Any,
 }
 
-// Check if JWT auth token is valid by decoding it using the JWT secret
 #[tracing::instrument(name = "Validate Token", skip_all)]
-pub async fn validate_token(
+pub async fn validate_token_for(
     token: &str,
+    audience: ExpectedAudience<'_>,
     banned_token_store: &BannedTokenStoreType,
+    jwt: &JwtSettings,
 ) -> Result<Claims> {
     //check if the token is banned
     let banned_token_store_guard = banned_token_store.read().await;
@@ -78,21 +294,28 @@ pub async fn validate_token(
         return Err(eyre!("Token is banned"));
     }
 
+    let mut validation = Validation::default();
+    match audience {
+        ExpectedAudience::Unrestricted => {}
+        ExpectedAudience::Service(audience) => validation.set_audience(&[audience]),
+        ExpectedAudience::Any => validation.validate_aud = false,
+    }
+
     decode::<Claims>(
         token,
-        &DecodingKey::from_secret(JWT_SECRET.expose_secret().as_bytes()),
-        &Validation::default(),
+        &DecodingKey::from_secret(jwt.secret.expose_secret().as_bytes()),
+        &validation,
     )
     .map(|data| data.claims)
     .map_err(|e| eyre!("Failed to validate token: {}", e))
 }
 
 #[tracing::instrument(name = "Create Token", skip_all)]
-fn create_token(claims: &Claims) -> Result<String> {
+fn create_token(claims: &Claims, jwt: &JwtSettings) -> Result<String> {
     encode(
         &jsonwebtoken::Header::default(),
         &claims,
-        &EncodingKey::from_secret(JWT_SECRET.expose_secret().as_bytes()),
+        &EncodingKey::from_secret(jwt.secret.expose_secret().as_bytes()),
     )
     .wrap_err("failed to create token")
 }
@@ -101,6 +324,54 @@ fn create_token(claims: &Claims) -> Result<String> {
 pub struct Claims {
     pub sub: String,
     pub exp: usize,
+    // Absent from tokens issued before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<usize>,
+    // Set on session tokens: when the user logged in and how. A session outlives neither, since
+    // its token is never refreshed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_time: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub amr: Vec<String>,
+    // The login a session token belongs to, also carried by access tokens the user granted from it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    // Set on access tokens issued to OAuth clients: who the token was issued to, and the
+    // space-delimited scopes the user granted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    // Set on delegated tokens from a token exchange: the one service that accepts the token, and
+    // the service client acting for the user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,
+    // Only set on tokens issued to service clients, so every token without it acts for a user
+    #[serde(default, skip_serializing_if = "TokenKind::is_user")]
+    pub kind: TokenKind,
+}
+
+// The party acting on the subject's behalf (RFC 8693 section 4.1)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Actor {
+    pub sub: String,
+}
+
+// Who a token acts for: a user, or a service client on its own behalf
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenKind {
+    #[default]
+    User,
+    Client,
+}
+
+impl TokenKind {
+    fn is_user(&self) -> bool {
+        *self == Self::User
+    }
 }
 
 #[cfg(test)]
@@ -114,43 +385,101 @@ mod tests {
 
     use super::*;
 
+    fn jwt_settings() -> JwtSettings {
+        JwtSettings {
+            secret: Secret::new("test-jwt-secret".to_owned()),
+        }
+    }
+
+    fn cookie_settings() -> CookieSettings {
+        CookieSettings {
+            name: "jwt".to_owned(),
+            domain: None,
+            secure: true,
+            same_site: CookieSameSite::Lax,
+            host_prefix: false,
+        }
+    }
+
     #[tokio::test]
     async fn test_generate_auth_cookie() {
         let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
-        let cookie = generate_auth_cookie(&email).unwrap();
-        assert_eq!(cookie.name(), JWT_COOKIE_NAME);
+        let cookie = generate_auth_cookie(
+            &email,
+            AuthenticationMethod::Password,
+            &jwt_settings(),
+            &cookie_settings(),
+        )
+        .unwrap();
+        assert_eq!(cookie.name(), "jwt");
         assert_eq!(cookie.value().split('.').count(), 3);
         assert_eq!(cookie.path(), Some("/"));
         assert_eq!(cookie.http_only(), Some(true));
         assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+        assert_eq!(
+            cookie.max_age(),
+            Some(time::Duration::seconds(TOKEN_TTL_SECONDS))
+        );
     }
 
     #[tokio::test]
     async fn test_create_auth_cookie() {
         let token = "test_token".to_owned();
-        let cookie = create_auth_cookie(token.clone());
-        assert_eq!(cookie.name(), JWT_COOKIE_NAME);
+        let cookie = create_auth_cookie(token.clone(), &cookie_settings());
+        assert_eq!(cookie.name(), "jwt");
         assert_eq!(cookie.value(), token);
         assert_eq!(cookie.path(), Some("/"));
         assert_eq!(cookie.http_only(), Some(true));
+        assert_eq!(cookie.secure(), Some(true));
         assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+        assert_eq!(cookie.domain(), None);
+    }
+
+    #[tokio::test]
+    async fn test_create_auth_cookie_with_configured_attributes() {
+        let settings = CookieSettings {
+            domain: Some("example.com".to_owned()),
+            same_site: CookieSameSite::Strict,
+            ..cookie_settings()
+        };
+        let cookie = create_auth_cookie("test_token".to_owned(), &settings);
+        assert_eq!(cookie.domain(), Some("example.com"));
+        assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+
+        let settings = CookieSettings {
+            host_prefix: true,
+            ..cookie_settings()
+        };
+        let cookie = create_auth_cookie("test_token".to_owned(), &settings);
+        assert_eq!(cookie.name(), "__Host-jwt");
+
+        // The removal cookie must match the name, domain and path of the one it replaces
+        let removal = removal_auth_cookie(&settings);
+        assert_eq!(removal.name(), cookie.name());
+        assert_eq!(removal.domain(), cookie.domain());
+        assert_eq!(removal.path(), cookie.path());
+        assert_eq!(removal.secure(), cookie.secure());
     }
 
     #[tokio::test]
     async fn test_generate_auth_token() {
         let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
-        let result = generate_auth_token(&email).unwrap();
+        let result =
+            generate_auth_token(&email, AuthenticationMethod::Password, &jwt_settings()).unwrap();
         assert_eq!(result.split('.').count(), 3);
     }
 
     #[tokio::test]
     async fn test_validate_token_with_valid_token() {
         let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
-        let token = generate_auth_token(&email).unwrap();
+        let token =
+            generate_auth_token(&email, AuthenticationMethod::Password, &jwt_settings()).unwrap();
 
         let banned_token_store =
             Arc::new(RwLock::new(HashSetBannedTokenStore::default())) as BannedTokenStoreType;
-        let result = validate_token(&token, &banned_token_store).await.unwrap();
+        let result = validate_token(&token, &banned_token_store, &jwt_settings())
+            .await
+            .unwrap();
         assert_eq!(result.sub, "test@example.com");
 
         let exp = Utc::now()
@@ -161,13 +490,123 @@ mod tests {
         assert!(result.exp > exp as usize);
     }
 
+    #[tokio::test]
+    async fn test_session_tokens_record_how_the_user_logged_in() {
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let token =
+            generate_auth_token(&email, AuthenticationMethod::TwoFactor, &jwt_settings()).unwrap();
+
+        let banned_token_store =
+            Arc::new(RwLock::new(HashSetBannedTokenStore::default())) as BannedTokenStoreType;
+        let claims = validate_token(&token, &banned_token_store, &jwt_settings())
+            .await
+            .unwrap();
+        assert_eq!(claims.amr, vec!["pwd", "otp", "mfa"]);
+        let auth_time = claims.auth_time.expect("Session tokens carry auth_time");
+        assert!(auth_time.abs_diff(Utc::now().timestamp() as usize) <= 5);
+        assert_eq!(claims.iat, Some(auth_time));
+
+        // Each login is a session of its own
+        let other_token =
+            generate_auth_token(&email, AuthenticationMethod::TwoFactor, &jwt_settings()).unwrap();
+        let other_claims = validate_token(&other_token, &banned_token_store, &jwt_settings())
+            .await
+            .unwrap();
+        assert!(claims.sid.is_some());
+        assert_ne!(claims.sid, other_claims.sid);
+    }
+
+    #[tokio::test]
+    async fn test_access_tokens_carry_the_client_and_scope() {
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let token = generate_access_token(
+            &email,
+            "client",
+            "profile email",
+            Some("session"),
+            &jwt_settings(),
+        )
+        .unwrap();
+
+        let banned_token_store =
+            Arc::new(RwLock::new(HashSetBannedTokenStore::default())) as BannedTokenStoreType;
+        let claims = validate_token(&token, &banned_token_store, &jwt_settings())
+            .await
+            .unwrap();
+        assert_eq!(claims.sub, "test@example.com");
+        assert_eq!(claims.client_id.as_deref(), Some("client"));
+        assert_eq!(claims.scope.as_deref(), Some("profile email"));
+        assert_eq!(claims.sid.as_deref(), Some("session"));
+
+        // First-party tokens carry neither claim
+        let token =
+            generate_auth_token(&email, AuthenticationMethod::Password, &jwt_settings()).unwrap();
+        let claims = validate_token(&token, &banned_token_store, &jwt_settings())
+            .await
+            .unwrap();
+        assert_eq!(claims.client_id, None);
+        assert_eq!(claims.scope, None);
+        assert_eq!(claims.kind, TokenKind::User);
+    }
+
+    #[tokio::test]
+    async fn test_client_tokens_have_the_client_as_subject() {
+        let token = generate_client_token("client", "reports:read", &jwt_settings()).unwrap();
+
+        let banned_token_store =
+            Arc::new(RwLock::new(HashSetBannedTokenStore::default())) as BannedTokenStoreType;
+        let claims = validate_token(&token, &banned_token_store, &jwt_settings())
+            .await
+            .unwrap();
+        assert_eq!(claims.kind, TokenKind::Client);
+        assert_eq!(claims.sub, "client");
+        assert_eq!(claims.client_id.as_deref(), Some("client"));
+        assert_eq!(claims.scope.as_deref(), Some("reports:read"));
+        assert!(claims.exp as i64 <= Utc::now().timestamp() + CLIENT_TOKEN_TTL_SECONDS);
+    }
+
+    #[tokio::test]
+    async fn test_delegated_tokens_are_only_valid_for_their_audience() {
+        let email = Email::parse(Secret::new("test@example.com".to_string())).unwrap();
+        let token =
+            generate_auth_token(&email, AuthenticationMethod::Password, &jwt_settings()).unwrap();
+        let banned_token_store =
+            Arc::new(RwLock::new(HashSetBannedTokenStore::default())) as BannedTokenStoreType;
+        let subject = validate_token(&token, &banned_token_store, &jwt_settings())
+            .await
+            .unwrap();
+
+        let (token, expires_in) =
+            generate_delegated_token(&subject, "app", "reports", "read", &jwt_settings()).unwrap();
+        assert!(expires_in <= CLIENT_TOKEN_TTL_SECONDS);
+
+        assert!(validate_token(&token, &banned_token_store, &jwt_settings())
+            .await
+            .is_err());
+        let jwt = jwt_settings();
+        let validate = |audience| validate_token_for(&token, audience, &banned_token_store, &jwt);
+        assert!(validate(ExpectedAudience::Service("app")).await.is_err());
+        assert!(validate(ExpectedAudience::Any).await.is_ok());
+
+        let claims = validate(ExpectedAudience::Service("reports"))
+            .await
+            .unwrap();
+        assert_eq!(claims.sub, "test@example.com");
+        assert_eq!(claims.aud.as_deref(), Some("reports"));
+        assert_eq!(claims.act.map(|actor| actor.sub).as_deref(), Some("app"));
+        assert_eq!(claims.client_id.as_deref(), Some("app"));
+        assert_eq!(claims.scope.as_deref(), Some("read"));
+        assert_eq!(claims.sid, subject.sid);
+        assert!(claims.exp <= subject.exp);
+    }
+
     #[tokio::test]
     async fn test_validate_token_with_invalid_token() {
         let token = "invalid_token".to_owned();
 
         let banned_token_store =
             Arc::new(RwLock::new(HashSetBannedTokenStore::default())) as BannedTokenStoreType;
-        let result = validate_token(&token, &banned_token_store).await;
+        let result = validate_token(&token, &banned_token_store, &jwt_settings()).await;
         assert!(result.is_err());
     }
 }
@@ -0,0 +1,75 @@
+use axum::{
+    extract::FromRequestParts,
+    http::{header::AUTHORIZATION, request::Parts},
+};
+use axum_extra::extract::CookieJar;
+
+use crate::{app_state::AppState, domain::AuthAPIError};
+
+// Where the client sent its auth token from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuthTokenSource {
+    AuthorizationHeader,
+    Cookie,
+}
+
+// The raw, unvalidated auth token of a request
+#[derive(Debug, Clone)]
+pub struct AuthToken {
+    pub token: String,
+    pub source: AuthTokenSource,
+}
+
+// Reads `Authorization: Bearer <token>` for non-browser clients, falling back to the auth
+// cookie. A bearer header wins when both are present.
+impl FromRequestParts<AppState> for AuthToken {
+    type Rejection = AuthAPIError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        if let Some(value) = parts.headers.get(AUTHORIZATION) {
+            if let Some(token) =
+                bearer_token(value.to_str().map_err(|_| AuthAPIError::InvalidToken)?)
+            {
+                if token.is_empty() {
+                    return Err(AuthAPIError::InvalidToken);
+                }
+                return Ok(AuthToken {
+                    token: token.to_owned(),
+                    source: AuthTokenSource::AuthorizationHeader,
+                });
+            }
+        }
+
+        let jar = CookieJar::from_headers(&parts.headers);
+        jar.get(&state.settings.cookie.cookie_name())
+            .map(|cookie| AuthToken {
+                token: cookie.value().to_owned(),
+                source: AuthTokenSource::Cookie,
+            })
+            .ok_or(AuthAPIError::MissingToken)
+    }
+}
+
+// The auth scheme is case-insensitive (RFC 7235); other schemes are ignored
+fn bearer_token(authorization: &str) -> Option<&str> {
+    let (scheme, token) = authorization.split_once(' ').unwrap_or((authorization, ""));
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bearer_tokens() {
+        assert_eq!(bearer_token("Bearer abc.def.ghi"), Some("abc.def.ghi"));
+        assert_eq!(bearer_token("bearer  abc.def.ghi "), Some("abc.def.ghi"));
+        assert_eq!(bearer_token("Bearer "), Some(""));
+        assert_eq!(bearer_token("Basic dXNlcjpwYXNz"), None);
+        assert_eq!(bearer_token("Bearer"), Some(""));
+        assert_eq!(bearer_token("Bearerabc"), None);
+    }
+}
@@ -0,0 +1,96 @@
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::request::Parts,
+    middleware::Next,
+    response::Response,
+};
+use secrecy::Secret;
+
+use super::{
+    auth::{validate_token, Claims},
+    auth_token::AuthToken,
+};
+use crate::{
+    app_state::AppState,
+    domain::{AuthAPIError, Email, UserStoreError},
+};
+
+// The account behind a request carrying a valid, unbanned auth token
+#[derive(Clone)]
+pub struct AuthenticatedUser {
+    pub email: Email,
+    pub claims: Claims,
+    // The token the request authenticated with, e.g. to ban it on logout
+    pub session: AuthToken,
+}
+
+// Rejects with AuthAPIError::MissingToken when no token was sent and
+// AuthAPIError::InvalidToken when it does not validate
+impl FromRequestParts<AppState> for AuthenticatedUser {
+    type Rejection = AuthAPIError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        // Routes behind `require_authentication` were already checked by the layer
+        if let Some(user) = parts.extensions.get::<AuthenticatedUser>() {
+            return Ok(user.clone());
+        }
+
+        let session = AuthToken::from_request_parts(parts, state).await?;
+
+        let claims = validate_token(
+            &session.token,
+            &state.banned_token_store,
+            &state.settings.jwt,
+        )
+        .await
+        .map_err(|_| AuthAPIError::InvalidToken)?;
+
+        // Tokens issued to OAuth clients only carry the scopes the user granted, not the
+        // user's own session
+        if claims.client_id.is_some() {
+            return Err(AuthAPIError::InvalidToken);
+        }
+
+        // The token's subject is the account's email address
+        let email = Email::parse(Secret::new(claims.sub.clone()))
+            .map_err(|_| AuthAPIError::InvalidToken)?;
+
+        Ok(AuthenticatedUser {
+            email,
+            claims,
+            session,
+        })
+    }
+}
+
+impl AuthenticatedUser {
+    // Whether the session belongs to a finished login. For users with 2FA that means the code
+    // was verified, or the external provider logged them in in our place.
+    pub async fn completed_login(&self, state: &AppState) -> Result<bool, AuthAPIError> {
+        let user = match state.user_store.read().await.get_user(&self.email).await {
+            Ok(user) => user,
+            Err(UserStoreError::UserNotFound) => return Err(AuthAPIError::InvalidToken),
+            Err(e) => return Err(AuthAPIError::UnexpectedError(e.into())),
+        };
+        Ok(!user.requires_2fa
+            || self
+                .claims
+                .amr
+                .iter()
+                .any(|method| method == "mfa" || method == "fed"))
+    }
+}
+
+// Guards every route of a router, for use with `middleware::from_fn_with_state`. Handlers
+// behind it can still take `AuthenticatedUser` without validating the token again.
+pub async fn require_authentication(
+    user: AuthenticatedUser,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    request.extensions_mut().insert(user);
+    next.run(request).await
+}
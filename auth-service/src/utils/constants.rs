@@ -1,87 +1,57 @@
-use dotenvy::dotenv;
-use lazy_static::lazy_static;
-use secrecy::Secret;
-use std::env as std_env;
+// Tunables that are not worth exposing as settings. Deployment-specific values live in
+// `Settings`, loaded from the files in `configuration/`.
 
-use crate::utils::test::DEFAULT_REDIS_HOSTNAME;
+// How many account exports a user may request within one rate limit window
+pub const ACCOUNT_EXPORT_MAX_REQUESTS: u64 = 3;
+pub const ACCOUNT_EXPORT_WINDOW_SECONDS: u64 = 3600; // 1 hour
 
-// Define a lazily evaluated static. lazy_static is needed because std_env::var is not a const function.
-lazy_static! {
-    pub static ref JWT_SECRET: Secret<String> = set_token();
-    pub static ref DATABASE_URL: Secret<String> = get_database_url();
-    pub static ref REDIS_HOST_NAME: String = set_redis_host();
-    pub static ref POSTMARK_AUTH_TOKEN: Secret<String> = set_postmark_auth_token();
-}
-
-fn set_token() -> Secret<String> {
-    dotenv().ok(); // Load environment variables
-    let secret = std_env::var(env::JWT_SECRET_ENV_VAR).expect("JWT_SECRET must be set.");
-    if secret.is_empty() {
-        panic!("JWT_SECRET must not be empty.");
-    }
-    Secret::new(secret)
-}
+// How many login links may be emailed to one address within one rate limit window
+pub const MAGIC_LINK_MAX_REQUESTS: u64 = 3;
+pub const MAGIC_LINK_WINDOW_SECONDS: u64 = 900; // 15 minutes
 
-fn get_database_url() -> Secret<String> {
-    dotenv().ok(); // Load environment variables
-    let url = std_env::var(env::DATABASE_URL_ENV_VAR).expect("DATABASE_URL must be set.");
-    if url.is_empty() {
-        panic!("DATABASE_URL must not be empty.");
-    }
-    Secret::new(url)
-}
+// How many user codes one user may look up on the device verification page within one rate
+// limit window. User codes are short enough to guess otherwise (RFC 8628 section 5.1).
+pub const DEVICE_VERIFICATION_MAX_ATTEMPTS: u64 = 10;
+pub const DEVICE_VERIFICATION_WINDOW_SECONDS: u64 = 900; // 15 minutes
 
-fn set_redis_host() -> String {
-    dotenv().ok();
-    std_env::var(env::REDIS_HOST_NAME_ENV_VAR).unwrap_or(DEFAULT_REDIS_HOSTNAME.to_owned())
-}
+// Login history older than the retention period is pruned on this interval
+pub const LOGIN_HISTORY_PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
 
-fn set_postmark_auth_token() -> Secret<String> {
-    dotenv().ok();
-    let token = std_env::var(env::POSTMARK_AUTH_TOKEN_ENV_VAR)
-        .expect("POSTMARK_AUTH_TOKEN must be set.")
-        .trim()
-        .to_string();
-    if token.is_empty() {
-        panic!("POSTMARK_AUTH_TOKEN must not be empty.");
-    }
-    Secret::new(token)
-}
-pub mod env {
-    pub const JWT_SECRET_ENV_VAR: &str = "JWT_SECRET";
-    pub const DATABASE_URL_ENV_VAR: &str = "DATABASE_URL";
-    pub const REDIS_HOST_NAME_ENV_VAR: &str = "REDIS_HOST_NAME";
-    pub const POSTMARK_AUTH_TOKEN_ENV_VAR: &str = "POSTMARK_AUTH_TOKEN";
-}
+// Page size bounds for the login history route
+pub const LOGIN_HISTORY_DEFAULT_PAGE_SIZE: u32 = 20;
+pub const LOGIN_HISTORY_MAX_PAGE_SIZE: u32 = 100;
 
-pub const JWT_COOKIE_NAME: &str = "jwt";
+// Webhook deliveries claimed per worker pass, and how long a claimed delivery stays hidden
+// from other workers. The lease must outlast a batch of request timeouts.
+pub const WEBHOOK_DELIVERY_BATCH_SIZE: u32 = 50;
+pub const WEBHOOK_DELIVERY_LEASE: std::time::Duration = std::time::Duration::from_secs(15 * 60);
 
-pub mod prod {
-    pub const APP_ADDRESS: &str = "0.0.0.0:3000";
+// How long the readiness probe waits on each dependency
+pub const HEALTH_CHECK_POSTGRES_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+pub const HEALTH_CHECK_REDIS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+pub const HEALTH_CHECK_EMAIL_PROVIDER_TIMEOUT: std::time::Duration =
+    std::time::Duration::from_secs(3);
 
-    pub mod email_client {
-        use std::time::Duration;
+// TLS handshakes that take longer than this are dropped, and at most this many finished
+// handshakes wait for the server to pick them up
+pub const TLS_HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+pub const TLS_PENDING_CONNECTIONS: usize = 128;
 
-        pub const BASE_URL: &str = "https://api.postmarkapp.com/email";
-        // If you created your own Postmark account, make sure to use your email address!
-        pub const SENDER: &str = "bogdan@codeiron.io";
-        pub const TIMEOUT: Duration = std::time::Duration::from_secs(10);
-    }
-}
+// How long each request to the external identity provider may take
+pub const EXTERNAL_IDP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
 
-pub mod test {
-    pub const APP_ADDRESS: &str = "127.0.0.1:0";
-    pub const DEFAULT_REDIS_HOSTNAME: &str = "127.0.0.1"; // New!
+// The readable cookie carrying a cookie session's CSRF token, and the header browsers echo it
+// back in on state-changing requests
+pub const CSRF_COOKIE_NAME: &str = "csrf_token";
+pub const CSRF_HEADER_NAME: &str = "x-csrf-token";
 
-    pub mod email_client {
-        use std::time::Duration;
+// The cookie that ties an external login to the browser that started it
+pub const EXTERNAL_LOGIN_COOKIE_NAME: &str = "external_login";
 
-        pub const SENDER: &str = "test@email.com";
-        pub const TIMEOUT: Duration = std::time::Duration::from_millis(200);
+// Fixture data shared by the unit and integration tests
+pub mod test {
+    pub mod geo_locator {
+        pub const CITY: &str = "Springfield";
+        pub const COUNTRY: &str = "United States";
     }
 }
-
-pub mod localhost {
-    pub const AUTH_SERVICE_LOCAL_URL: &str = "http://localhost:8000";
-    pub const AUTH_SERVICE_DROPLET_URL: &str = "http://174.138.41.161:8000";
-}
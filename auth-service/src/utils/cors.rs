@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use axum::http::{request::Parts, HeaderName, HeaderValue, Method};
+use color_eyre::eyre::{eyre, Result};
+use reqwest::Url;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use super::settings::CorsSettings;
+
+// One entry of `cors.allowed_origins`: an exact origin, a wildcard subdomain pattern such as
+// "https://*.example.com", or "*" for any origin
+#[derive(Debug, Clone, PartialEq)]
+pub enum OriginPattern {
+    Any,
+    Exact(String),
+    Subdomain {
+        scheme: String,
+        // Includes the leading dot, e.g. ".example.com"
+        host_suffix: String,
+        port: Option<u16>,
+    },
+}
+
+impl OriginPattern {
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let pattern = pattern.trim();
+        if pattern == "*" {
+            return Ok(Self::Any);
+        }
+
+        if let Some((scheme, rest)) = pattern.split_once("://*.") {
+            if rest.is_empty() || rest.starts_with('.') || rest.contains('*') {
+                return Err(eyre!("invalid wildcard pattern"));
+            }
+            // Parse with a placeholder label so the URL parser validates the rest of the host
+            let url = parse_origin(&format!("{}://wildcard.{}", scheme, rest))?;
+            let host = url.host_str().ok_or_else(|| eyre!("missing host"))?;
+            let host_suffix = host
+                .strip_prefix("wildcard")
+                .ok_or_else(|| eyre!("invalid wildcard host"))?;
+
+            return Ok(Self::Subdomain {
+                scheme: url.scheme().to_owned(),
+                host_suffix: host_suffix.to_owned(),
+                port: url.port(),
+            });
+        }
+
+        // Wildcards are only supported as the leftmost label
+        if pattern.contains('*') {
+            return Err(eyre!("invalid wildcard pattern"));
+        }
+        let url = parse_origin(pattern)?;
+        Ok(Self::Exact(url.origin().ascii_serialization()))
+    }
+
+    pub fn matches(&self, origin: &str) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Exact(expected) => expected.eq_ignore_ascii_case(origin),
+            Self::Subdomain {
+                scheme,
+                host_suffix,
+                port,
+            } => {
+                let Ok(url) = Url::parse(origin) else {
+                    return false;
+                };
+                let Some(host) = url.host_str() else {
+                    return false;
+                };
+
+                // At least one label must come before the suffix, so the bare domain and
+                // look-alikes such as "evil-example.com" do not match
+                url.scheme() == scheme
+                    && url.port() == *port
+                    && host.len() > host_suffix.len()
+                    && host.ends_with(host_suffix.as_str())
+            }
+        }
+    }
+}
+
+// Origins are a scheme, host and optional port, with nothing after them
+fn parse_origin(origin: &str) -> Result<Url> {
+    let url = Url::parse(origin)?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(eyre!("must be an http(s) origin"));
+    }
+    if url.path() != "/" || url.query().is_some() || url.fragment().is_some() {
+        return Err(eyre!("must not include a path, query or fragment"));
+    }
+    Ok(url)
+}
+
+pub fn build_cors_layer(settings: &CorsSettings) -> Result<CorsLayer> {
+    let patterns = settings
+        .allowed_origins
+        .iter()
+        .map(|origin| OriginPattern::parse(origin))
+        .collect::<Result<Vec<_>>>()?;
+
+    let allow_origin = if patterns.contains(&OriginPattern::Any) {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::predicate(move |origin: &HeaderValue, _: &Parts| {
+            origin
+                .to_str()
+                .is_ok_and(|origin| patterns.iter().any(|pattern| pattern.matches(origin)))
+        })
+    };
+
+    let methods = settings
+        .allowed_methods
+        .iter()
+        .map(|method| method.trim().to_uppercase().parse::<Method>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let headers = settings
+        .allowed_headers
+        .iter()
+        .map(|header| header.trim().parse::<HeaderName>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut cors = CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(methods)
+        .allow_headers(headers)
+        .allow_credentials(settings.allow_credentials);
+
+    if let Some(max_age_secs) = settings.max_age_secs {
+        cors = cors.max_age(Duration::from_secs(max_age_secs));
+    }
+
+    Ok(cors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_origins_are_normalised() {
+        let pattern = OriginPattern::parse("http://localhost:8000/").unwrap();
+        assert_eq!(
+            pattern,
+            OriginPattern::Exact("http://localhost:8000".to_owned())
+        );
+        assert!(pattern.matches("http://localhost:8000"));
+        assert!(!pattern.matches("http://localhost:8001"));
+        assert!(!pattern.matches("https://localhost:8000"));
+    }
+
+    #[test]
+    fn wildcard_patterns_match_subdomains_only() {
+        let pattern = OriginPattern::parse("https://*.example.com").unwrap();
+
+        assert!(pattern.matches("https://app.example.com"));
+        assert!(pattern.matches("https://eu.app.example.com"));
+        assert!(!pattern.matches("https://example.com"));
+        assert!(!pattern.matches("https://evil-example.com"));
+        assert!(!pattern.matches("https://app.example.com.evil.com"));
+        assert!(!pattern.matches("http://app.example.com"));
+        assert!(!pattern.matches("https://app.example.com:8443"));
+    }
+
+    #[test]
+    fn wildcard_patterns_can_pin_a_port() {
+        let pattern = OriginPattern::parse("http://*.localhost:8000").unwrap();
+
+        assert!(pattern.matches("http://app.localhost:8000"));
+        assert!(!pattern.matches("http://app.localhost"));
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        for pattern in [
+            "localhost:8000",
+            "ftp://example.com",
+            "https://example.com/app",
+            "https://*.",
+            "https://app.*.example.com",
+        ] {
+            assert!(
+                OriginPattern::parse(pattern).is_err(),
+                "Accepted {}",
+                pattern
+            );
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use axum::{
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::extract::{cookie::Cookie, CookieJar};
+use hmac::{Hmac, Mac};
+use secrecy::ExposeSecret;
+use sha2::Sha256;
+
+use super::{
+    auth::{build_session_cookie, TOKEN_TTL_SECONDS},
+    auth_token::AuthTokenSource,
+    authenticated_user::AuthenticatedUser,
+    constants::CSRF_HEADER_NAME,
+    settings::{JwtSettings, Settings},
+};
+use crate::{app_state::AppState, domain::AuthAPIError};
+
+// Signed double-submit tokens: the CSRF token is an HMAC of the auth token it was issued with.
+// A sibling origin that can write cookies for our domain still cannot mint a token matching
+// the HttpOnly session cookie, and no server-side state is needed to check it.
+pub fn csrf_token(session_token: &str, jwt: &JwtSettings) -> String {
+    hex::encode(csrf_mac(session_token, jwt).finalize().into_bytes())
+}
+
+// Compares in constant time
+pub fn verify_csrf_token(session_token: &str, candidate: &str, jwt: &JwtSettings) -> bool {
+    hex::decode(candidate)
+        .map(|candidate| {
+            csrf_mac(session_token, jwt)
+                .verify_slice(&candidate)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+fn csrf_mac(session_token: &str, jwt: &JwtSettings) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(jwt.secret.expose_secret().as_bytes())
+        .expect("HMAC can take a key of any size");
+    // Keeps these MACs distinct from anything else keyed with the JWT secret
+    mac.update(b"csrf.");
+    mac.update(session_token.as_bytes());
+    mac
+}
+
+// Adds the auth cookie and the CSRF cookie that goes with it. The CSRF cookie is readable by
+// scripts so the client can echo it back in the `X-CSRF-Token` header.
+pub fn add_session_cookies(
+    jar: CookieJar,
+    auth_cookie: Cookie<'static>,
+    settings: &Settings,
+) -> CookieJar {
+    let mut csrf_cookie = build_session_cookie(
+        settings.cookie.csrf_cookie_name(),
+        csrf_token(auth_cookie.value(), &settings.jwt),
+        &settings.cookie,
+    );
+    csrf_cookie.set_max_age(time::Duration::seconds(TOKEN_TTL_SECONDS));
+
+    jar.add(csrf_cookie).add(auth_cookie)
+}
+
+// Removes the CSRF cookie along with the auth cookie on logout
+pub fn removal_csrf_cookie(settings: &Settings) -> Cookie<'static> {
+    build_session_cookie(
+        settings.cookie.csrf_cookie_name(),
+        String::new(),
+        &settings.cookie,
+    )
+}
+
+// Rejects state-changing requests that authenticate with the auth cookie unless they carry the
+// session's CSRF token. Bearer tokens are never sent automatically by browsers, so requests
+// using them are exempt. Runs behind `require_authentication`.
+pub async fn require_csrf_token(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    request: Request,
+    next: Next,
+) -> Result<Response, AuthAPIError> {
+    let safe_method = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+
+    if !safe_method && user.session.source == AuthTokenSource::Cookie {
+        let valid = request
+            .headers()
+            .get(CSRF_HEADER_NAME)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|candidate| {
+                verify_csrf_token(&user.session.token, candidate, &state.settings.jwt)
+            });
+        if !valid {
+            return Err(AuthAPIError::InvalidCsrfToken);
+        }
+    }
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::Secret;
+
+    use super::*;
+
+    fn jwt(secret: &str) -> JwtSettings {
+        JwtSettings {
+            secret: Secret::new(secret.to_owned()),
+        }
+    }
+
+    #[test]
+    fn tokens_only_verify_for_their_session() {
+        let jwt = jwt("secret");
+        let token = csrf_token("session-a", &jwt);
+
+        assert!(verify_csrf_token("session-a", &token, &jwt));
+        assert!(!verify_csrf_token("session-b", &token, &jwt));
+        assert!(!verify_csrf_token(
+            "session-a",
+            &token,
+            &self::jwt("other-secret")
+        ));
+        assert!(!verify_csrf_token("session-a", "", &jwt));
+        assert!(!verify_csrf_token("session-a", "not hex", &jwt));
+        assert!(!verify_csrf_token("session-a", &token[..32], &jwt));
+    }
+}
@@ -0,0 +1,187 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use color_eyre::eyre::Result;
+use lazy_static::lazy_static;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use sqlx::PgPool;
+
+use crate::app_state::RedisConnectionType;
+
+// Metrics are process-wide so code without access to `AppState` (such as password hashing
+// inside the user store) can record them too
+lazy_static! {
+    pub static ref METRICS: Metrics = Metrics::new().expect("Failed to register metrics");
+}
+
+// Label values used by the counters below
+pub mod labels {
+    pub const LOGIN_SUCCESS: &str = "success";
+    pub const LOGIN_TWO_FA_REQUIRED: &str = "two_fa_required";
+    pub const TWO_FA_CODE_SENT: &str = "sent";
+    pub const TWO_FA_CODE_VERIFIED: &str = "verified";
+    pub const TWO_FA_CODE_FAILED: &str = "failed";
+    pub const PASSWORD_HASH: &str = "hash";
+    pub const PASSWORD_VERIFY: &str = "verify";
+    pub const POOL_IDLE: &str = "idle";
+    pub const POOL_IN_USE: &str = "in_use";
+    // Never expected, since the metrics layer only wraps matched routes
+    pub const UNMATCHED_ROUTE: &str = "unmatched";
+}
+
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    // Labelled with "success", "two_fa_required" or a login failure reason
+    pub login_attempts: IntCounterVec,
+    pub two_fa_codes: IntCounterVec,
+    pub tokens_banned: IntCounterVec,
+    pub email_send_failures: IntCounter,
+    pub password_hash_duration: HistogramVec,
+    pub postgres_pool_connections: IntGaugeVec,
+    pub postgres_pool_max_connections: IntGauge,
+    // The service shares a single Redis connection; this is 1 while a request holds it
+    pub redis_connection_busy: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some("auth".to_owned()), None)?;
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )?;
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency by route and status",
+            ),
+            &["method", "route", "status"],
+        )?;
+        let login_attempts = IntCounterVec::new(
+            Opts::new("login_attempts_total", "Login attempts by outcome"),
+            &["outcome"],
+        )?;
+        let two_fa_codes = IntCounterVec::new(
+            Opts::new("two_fa_codes_total", "2FA codes sent, verified and failed"),
+            &["result"],
+        )?;
+        let tokens_banned = IntCounterVec::new(
+            Opts::new(
+                "tokens_banned_total",
+                "JWTs added to the ban list by reason",
+            ),
+            &["reason"],
+        )?;
+        let email_send_failures = IntCounter::new(
+            "email_send_failures_total",
+            "Emails the email provider failed to accept",
+        )?;
+        let password_hash_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "password_hash_duration_seconds",
+                "Time spent hashing and verifying passwords with Argon2",
+            ),
+            &["operation"],
+        )?;
+        let postgres_pool_connections = IntGaugeVec::new(
+            Opts::new(
+                "postgres_pool_connections",
+                "Open Postgres connections by state",
+            ),
+            &["state"],
+        )?;
+        let postgres_pool_max_connections = IntGauge::new(
+            "postgres_pool_max_connections",
+            "Maximum size of the Postgres connection pool",
+        )?;
+        let redis_connection_busy = IntGauge::new(
+            "redis_connection_busy",
+            "Whether the shared Redis connection is currently in use",
+        )?;
+
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_request_duration.clone()))?;
+        registry.register(Box::new(login_attempts.clone()))?;
+        registry.register(Box::new(two_fa_codes.clone()))?;
+        registry.register(Box::new(tokens_banned.clone()))?;
+        registry.register(Box::new(email_send_failures.clone()))?;
+        registry.register(Box::new(password_hash_duration.clone()))?;
+        registry.register(Box::new(postgres_pool_connections.clone()))?;
+        registry.register(Box::new(postgres_pool_max_connections.clone()))?;
+        registry.register(Box::new(redis_connection_busy.clone()))?;
+
+        Ok(Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            login_attempts,
+            two_fa_codes,
+            tokens_banned,
+            email_send_failures,
+            password_hash_duration,
+            postgres_pool_connections,
+            postgres_pool_max_connections,
+            redis_connection_busy,
+        })
+    }
+
+    // Pool gauges are sampled when scraped rather than tracked continuously
+    pub fn record_pool_stats(&self, pg_pool: &PgPool, redis_conn: &RedisConnectionType) {
+        let size = i64::from(pg_pool.size());
+        let idle = pg_pool.num_idle() as i64;
+
+        self.postgres_pool_connections
+            .with_label_values(&[labels::POOL_IDLE])
+            .set(idle);
+        self.postgres_pool_connections
+            .with_label_values(&[labels::POOL_IN_USE])
+            .set((size - idle).max(0));
+        self.postgres_pool_max_connections
+            .set(i64::from(pg_pool.options().get_max_connections()));
+        self.redis_connection_busy
+            .set(i64::from(redis_conn.try_write().is_err()));
+    }
+
+    // Renders every metric in the Prometheus text exposition format
+    pub fn encode(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+// Counts requests and records their latency per matched route. Applied as a route layer so
+// the route template (e.g. "/admin/webhooks/{id}") is known.
+pub async fn track_http_metrics(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| labels::UNMATCHED_ROUTE.to_owned());
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let latency = start.elapsed();
+
+    let status = response.status().as_u16().to_string();
+    let label_values = [method.as_str(), route.as_str(), status.as_str()];
+
+    METRICS.http_requests.with_label_values(&label_values).inc();
+    METRICS
+        .http_request_duration
+        .with_label_values(&label_values)
+        .observe(latency.as_secs_f64());
+
+    response
+}
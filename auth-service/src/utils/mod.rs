@@ -1,7 +1,28 @@
+pub mod admin;
 pub mod auth;
+pub mod auth_token;
+pub mod authenticated_user;
+pub mod client_info;
 pub mod constants;
+pub mod cors;
+pub mod csrf;
+pub mod metrics;
+pub mod oidc;
+pub mod settings;
+pub mod shutdown;
+pub mod tls;
 pub mod tracing;
 
+pub use admin::*;
 pub use auth::*;
+pub use auth_token::*;
+pub use authenticated_user::*;
 pub use constants::*;
+pub use cors::*;
+pub use csrf::*;
+pub use metrics::*;
+pub use oidc::*;
+pub use settings::*;
+pub use shutdown::*;
+pub use tls::*;
 pub use tracing::*;
@@ -0,0 +1,133 @@
+use color_eyre::eyre::{Context, Result};
+use jsonwebtoken::{
+    encode,
+    jwk::{Jwk, JwkSet, PublicKeyUse, ThumbprintHash},
+    Algorithm, EncodingKey, Header,
+};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+
+use super::settings::Settings;
+
+// Signs OpenID Connect ID tokens with the configured RSA key. Unlike access tokens, clients
+// verify these themselves, against the public key published at `/.well-known/jwks.json`.
+pub struct IdTokenSigner {
+    key: EncodingKey,
+    jwk: Jwk,
+}
+
+impl IdTokenSigner {
+    // None while no signing key is configured, which leaves OpenID Connect off
+    pub fn from_settings(settings: &Settings) -> Result<Option<Self>> {
+        settings
+            .oauth
+            .id_token_signing_key
+            .as_ref()
+            .map(|pem| Self::from_pem(pem.expose_secret()))
+            .transpose()
+    }
+
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        let key = EncodingKey::from_rsa_pem(pem.as_bytes())
+            .wrap_err("ID token signing key is not an RSA private key")?;
+        let mut jwk = Jwk::from_encoding_key(&key, Algorithm::RS256)
+            .wrap_err("failed to derive the ID token signing JWK")?;
+        // The key ID is the key's thumbprint (RFC 7638), so it changes whenever the key does
+        jwk.common.key_id = Some(jwk.thumbprint(ThumbprintHash::SHA256));
+        jwk.common.public_key_use = Some(PublicKeyUse::Signature);
+
+        Ok(Self { key, jwk })
+    }
+
+    #[tracing::instrument(name = "Sign ID Token", skip_all)]
+    pub fn sign(&self, claims: &IdTokenClaims) -> Result<String> {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = self.jwk.common.key_id.clone();
+
+        encode(&header, claims, &self.key).wrap_err("failed to sign ID token")
+    }
+
+    // The public half only
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: vec![self.jwk.clone()],
+        }
+    }
+}
+
+// `iss` in every ID token, and the base of every URL in the discovery document
+pub fn oidc_issuer(settings: &Settings) -> String {
+    settings
+        .application
+        .public_url
+        .trim_end_matches('/')
+        .to_owned()
+}
+
+// OpenID Connect Core section 2
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    // The client the token was issued to
+    pub aud: String,
+    pub exp: usize,
+    pub iat: usize,
+    // When the user last logged in. Absent for sessions started before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_time: Option<usize>,
+    // Echoed from the authorization request so the client can detect replays
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub amr: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
+
+    use super::*;
+
+    const TEST_KEY: &str = include_str!("../../configuration/test.toml");
+
+    fn test_pem() -> &'static str {
+        let start = TEST_KEY
+            .find("-----BEGIN")
+            .expect("test.toml has a signing key");
+        let end = TEST_KEY.find("-----END PRIVATE KEY-----").unwrap() + 25;
+        &TEST_KEY[start..end]
+    }
+
+    #[test]
+    fn id_tokens_verify_against_the_published_key() {
+        let signer = IdTokenSigner::from_pem(test_pem()).unwrap();
+        let claims = IdTokenClaims {
+            iss: "https://auth.example.com".to_owned(),
+            sub: "test@example.com".to_owned(),
+            aud: "client".to_owned(),
+            exp: 4_102_444_800,
+            iat: 1_700_000_000,
+            auth_time: Some(1_700_000_000),
+            nonce: Some("n-0S6_WzA2Mj".to_owned()),
+            amr: vec!["pwd".to_owned()],
+        };
+
+        let token = signer.sign(&claims).unwrap();
+        let jwks = signer.jwks();
+        let jwk = &jwks.keys[0];
+        assert_eq!(decode_header(&token).unwrap().kid, jwk.common.key_id);
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&["client"]);
+        validation.set_issuer(&["https://auth.example.com"]);
+        let decoded =
+            decode::<IdTokenClaims>(&token, &DecodingKey::from_jwk(jwk).unwrap(), &validation)
+                .unwrap()
+                .claims;
+        assert_eq!(decoded.nonce, claims.nonce);
+        assert_eq!(decoded.amr, claims.amr);
+
+        assert!(IdTokenSigner::from_pem("not a key").is_err());
+    }
+}
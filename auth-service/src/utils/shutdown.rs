@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+// Triggers a graceful shutdown of a running `Application`. `main` fires it on SIGTERM/SIGINT;
+// tests fire it directly.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl ShutdownHandle {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self {
+            sender: Arc::new(sender),
+        }
+    }
+
+    pub fn shutdown(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    // Resolves once `shutdown` has been called, immediately if it already has
+    pub async fn wait(&self) {
+        let mut receiver = self.sender.subscribe();
+        // The handle owns the sender, so the channel cannot close while we wait on it
+        let _ = receiver.wait_for(|shutting_down| *shutting_down).await;
+    }
+}
+
+impl Default for ShutdownHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Resolves on the first SIGINT (Ctrl+C) or, on Unix, SIGTERM
+pub async fn wait_for_shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install SIGINT handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received SIGINT"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn wait_resolves_for_handles_cloned_before_and_after_shutdown() {
+        let handle = ShutdownHandle::new();
+        let before = handle.clone();
+        let waiter = tokio::spawn(async move { before.wait().await });
+
+        assert!(!handle.is_shutting_down());
+        handle.shutdown();
+        assert!(handle.is_shutting_down());
+
+        waiter.await.unwrap();
+        handle.clone().wait().await;
+    }
+}
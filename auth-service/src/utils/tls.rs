@@ -0,0 +1,330 @@
+use std::{
+    io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use axum::{
+    extract::State,
+    http::{header, uri::Authority, HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+    serve::Listener,
+    Router,
+};
+use color_eyre::eyre::{eyre, Context, Result};
+use rustls::{
+    crypto::{ring, CryptoProvider},
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+    ServerConfig,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+use tokio_rustls::{server::TlsStream, TlsAcceptor};
+
+use super::{
+    constants::{TLS_HANDSHAKE_TIMEOUT, TLS_PENDING_CONNECTIONS},
+    shutdown::ShutdownHandle,
+};
+
+// The certificate served to new handshakes. Reloading swaps it without touching connections
+// that are already established.
+#[derive(Debug)]
+pub struct ReloadableCertificate {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    provider: Arc<CryptoProvider>,
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ReloadableCertificate {
+    pub fn load(cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Result<Self> {
+        let cert_path = cert_path.into();
+        let key_path = key_path.into();
+        let provider = Arc::new(ring::default_provider());
+        let current = load_certified_key(&cert_path, &key_path, &provider)?;
+
+        Ok(Self {
+            cert_path,
+            key_path,
+            provider,
+            current: RwLock::new(Arc::new(current)),
+        })
+    }
+
+    // Keeps serving the previous certificate if the new files are missing or invalid
+    pub fn reload(&self) -> Result<()> {
+        let certified_key = load_certified_key(&self.cert_path, &self.key_path, &self.provider)?;
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(certified_key);
+        Ok(())
+    }
+
+    pub fn current(&self) -> Arc<CertifiedKey> {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn modified(&self) -> (Option<SystemTime>, Option<SystemTime>) {
+        let modified = |path: &Path| path.metadata().and_then(|m| m.modified()).ok();
+        (modified(&self.cert_path), modified(&self.key_path))
+    }
+}
+
+impl ResolvesServerCert for ReloadableCertificate {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current())
+    }
+}
+
+fn load_certified_key(
+    cert_path: &Path,
+    key_path: &Path,
+    provider: &CryptoProvider,
+) -> Result<CertifiedKey> {
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .wrap_err_with(|| format!("failed to read certificates from {}", cert_path.display()))?;
+    if certs.is_empty() {
+        return Err(eyre!("no certificates found in {}", cert_path.display()));
+    }
+
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .wrap_err_with(|| format!("failed to read private key from {}", key_path.display()))?;
+
+    CertifiedKey::from_der(certs, key, provider)
+        .wrap_err("certificate chain does not match the private key")
+}
+
+pub fn tls_server_config(certificate: Arc<ReloadableCertificate>) -> Result<Arc<ServerConfig>> {
+    let mut config = ServerConfig::builder_with_provider(certificate.provider.clone())
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_cert_resolver(certificate);
+    // The server only speaks HTTP/1.1
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(Arc::new(config))
+}
+
+// Polls the certificate files and reloads them when either one changes, until shutdown
+pub fn spawn_certificate_reloader(
+    certificate: Arc<ReloadableCertificate>,
+    interval: Duration,
+    shutdown: ShutdownHandle,
+) {
+    tokio::spawn(async move {
+        let mut last_modified = certificate.modified();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        ticker.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.wait() => return,
+            }
+
+            let modified = certificate.modified();
+            if modified == last_modified {
+                continue;
+            }
+            // A half-written pair fails to load and is retried on the next change
+            last_modified = modified;
+
+            match certificate.reload() {
+                Ok(()) => tracing::info!("Reloaded TLS certificate"),
+                Err(e) => tracing::warn!(
+                    "Failed to reload TLS certificate, keeping the current one: {:?}",
+                    e
+                ),
+            }
+        }
+    });
+}
+
+pub trait ServerIo: AsyncRead + AsyncWrite + Unpin + Send + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + 'static> ServerIo for T {}
+
+// The main listener, serving either plain HTTP or HTTPS on `application.address`
+pub enum ServerListener {
+    Plain(TcpListener),
+    Tls {
+        local_addr: SocketAddr,
+        connections: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    },
+}
+
+impl ServerListener {
+    pub fn tls(listener: TcpListener, config: Arc<ServerConfig>) -> io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let (sender, connections) = mpsc::channel(TLS_PENDING_CONNECTIONS);
+        tokio::spawn(accept_tls_connections(
+            listener,
+            TlsAcceptor::from(config),
+            sender,
+        ));
+
+        Ok(Self::Tls {
+            local_addr,
+            connections,
+        })
+    }
+}
+
+impl Listener for ServerListener {
+    type Io = Box<dyn ServerIo>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self {
+            Self::Plain(listener) => {
+                let (stream, addr) = Listener::accept(listener).await;
+                (Box::new(stream), addr)
+            }
+            Self::Tls { connections, .. } => match connections.recv().await {
+                Some((stream, addr)) => (Box::new(stream), addr),
+                // The accept loop only stops once this receiver is dropped
+                None => std::future::pending().await,
+            },
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        match self {
+            Self::Plain(listener) => listener.local_addr(),
+            Self::Tls { local_addr, .. } => Ok(*local_addr),
+        }
+    }
+}
+
+async fn accept_tls_connections(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    connections: mpsc::Sender<(TlsStream<TcpStream>, SocketAddr)>,
+) {
+    loop {
+        let (stream, addr) = tokio::select! {
+            // The server stopped and dropped the listener, which also frees the port
+            _ = connections.closed() => return,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::warn!("Failed to accept connection: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            },
+        };
+
+        // Handshakes run in their own tasks so a slow client cannot hold up the others
+        let acceptor = acceptor.clone();
+        let connections = connections.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => {
+                    let _ = connections.send((stream, addr)).await;
+                }
+                Ok(Err(e)) => tracing::debug!("TLS handshake with {} failed: {}", addr, e),
+                Err(_) => tracing::debug!("TLS handshake with {} timed out", addr),
+            }
+        });
+    }
+}
+
+// Served on `tls.redirect_address`: sends every request to the same path over HTTPS
+pub fn https_redirect_router(https_port: u16) -> Router {
+    Router::new()
+        .fallback(redirect_to_https)
+        .with_state(https_port)
+}
+
+async fn redirect_to_https(
+    State(https_port): State<u16>,
+    headers: HeaderMap,
+    uri: Uri,
+) -> Response {
+    let location = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .and_then(|host| https_location(host, https_port, &uri));
+
+    match location {
+        Some(location) => Redirect::permanent(&location).into_response(),
+        None => StatusCode::BAD_REQUEST.into_response(),
+    }
+}
+
+fn https_location(host: &str, https_port: u16, uri: &Uri) -> Option<String> {
+    // Drop the plain-HTTP port from the Host header
+    let host = host.parse::<Authority>().ok()?.host().to_owned();
+    let path_and_query = uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+
+    Some(if https_port == 443 {
+        format!("https://{}{}", host, path_and_query)
+    } else {
+        format!("https://{}:{}{}", host, https_port, path_and_query)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_certificate(dir: &Path) -> CertificateDer<'static> {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+        std::fs::write(dir.join("cert.pem"), certified.cert.pem()).unwrap();
+        std::fs::write(dir.join("key.pem"), certified.key_pair.serialize_pem()).unwrap();
+        certified.cert.der().clone()
+    }
+
+    #[test]
+    fn reload_swaps_the_certificate_and_keeps_it_on_failure() {
+        let dir = std::env::temp_dir().join(format!("auth-tls-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let first = write_certificate(&dir);
+        let certificate =
+            ReloadableCertificate::load(dir.join("cert.pem"), dir.join("key.pem")).unwrap();
+        assert_eq!(certificate.current().cert[0], first);
+
+        let second = write_certificate(&dir);
+        certificate.reload().unwrap();
+        assert_eq!(certificate.current().cert[0], second);
+
+        // A key that does not belong to the certificate is rejected
+        let other = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+        std::fs::write(dir.join("key.pem"), other.key_pair.serialize_pem()).unwrap();
+        assert!(certificate.reload().is_err());
+        assert_eq!(certificate.current().cert[0], second);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn redirects_keep_the_host_path_and_query() {
+        let uri: Uri = "/account/login-history?page=2".parse().unwrap();
+
+        assert_eq!(
+            https_location("auth.example.com:80", 443, &uri).as_deref(),
+            Some("https://auth.example.com/account/login-history?page=2")
+        );
+        assert_eq!(
+            https_location("localhost:8080", 8443, &uri).as_deref(),
+            Some("https://localhost:8443/account/login-history?page=2")
+        );
+        assert_eq!(
+            https_location("[::1]:80", 443, &"/".parse().unwrap()).as_deref(),
+            Some("https://[::1]/")
+        );
+        assert_eq!(https_location("bad host", 443, &uri), None);
+    }
+}
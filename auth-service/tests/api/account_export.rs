@@ -0,0 +1,209 @@
+use std::io::{Cursor, Read};
+
+use auth_service::{
+    domain::AuditEvent, routes::AccountExport, utils::constants::ACCOUNT_EXPORT_MAX_REQUESTS,
+    ErrorResponse,
+};
+use reqwest::Url;
+
+use crate::helpers::{get_random_email, TestApp};
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app.get_account_export(None).await;
+    assert_eq!(response.status(), 400);
+
+    let error_response: ErrorResponse = response
+        .json()
+        .await
+        .expect("Failed to parse error response");
+    assert_eq!(error_response.error, "Missing token");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_401_if_invalid_token() {
+    let app = TestApp::new().await;
+
+    app.cookie_jar.add_cookie_str(
+        &format!(
+            "{}=invalid; HttpOnly; SameSite=Lax; Secure; Path=/",
+            app.auth_cookie_name()
+        ),
+        &Url::parse("http://127.0.0.1").expect("Failed to parse URL"),
+    );
+
+    let response = app.get_account_export(None).await;
+    assert_eq!(response.status(), 401);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_200_with_account_data() {
+    let app = TestApp::new().await;
+
+    // Another account's events must stay out of the export
+    let other_signup_body = serde_json::json!({
+        "email": get_random_email(),
+        "password": "pasword123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&other_signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "requires2FA": false
+    });
+
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+    });
+
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.get_account_export(None).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let disposition = response
+        .headers()
+        .get("content-disposition")
+        .expect("No content-disposition header")
+        .to_str()
+        .unwrap()
+        .to_owned();
+    assert!(disposition.starts_with("attachment"));
+
+    let body = response.text().await.expect("Failed to read response body");
+
+    // Secrets such as the password hash must never be part of an export
+    assert!(!body.contains("password"));
+    assert!(!body.contains("$argon2"));
+
+    let export: AccountExport =
+        serde_json::from_str(&body).expect("Could not deserialize response body to AccountExport");
+    assert_eq!(export.profile.email, random_email);
+    assert!(!export.two_factor.enabled);
+    assert!(export.current_session.expires_at > export.generated_at);
+
+    // The user's own audit trail, and nobody else's
+    assert!(export.audit_events.iter().any(|entry| entry.event
+        == AuditEvent::UserSignedUp {
+            email: random_email.clone(),
+            requires_2fa: false,
+        }));
+    assert!(export
+        .audit_events
+        .iter()
+        .any(|entry| matches!(entry.event, AuditEvent::LoginSucceeded { .. })));
+    assert!(export
+        .audit_events
+        .iter()
+        .all(|entry| entry.event.email() == random_email));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_zip_archive_if_requested() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "requires2FA": false
+    });
+
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+    });
+
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.get_account_export(Some("zip")).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/zip"
+    );
+
+    let bytes = response
+        .bytes()
+        .await
+        .expect("Failed to read response body");
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(bytes.to_vec())).expect("Response is not a zip archive");
+    assert_eq!(archive.len(), 1);
+
+    let mut contents = String::new();
+    archive
+        .by_name("account-export.json")
+        .expect("No export entry in archive")
+        .read_to_string(&mut contents)
+        .unwrap();
+
+    let export: AccountExport = serde_json::from_str(&contents)
+        .expect("Could not deserialize archive entry to AccountExport");
+    assert_eq!(export.profile.email, random_email);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_429_if_exports_are_spammed() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "requires2FA": false
+    });
+
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+    });
+
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    for _ in 0..ACCOUNT_EXPORT_MAX_REQUESTS {
+        let response = app.get_account_export(None).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    let response = app.get_account_export(None).await;
+    assert_eq!(response.status().as_u16(), 429);
+
+    let error_response: ErrorResponse = response
+        .json()
+        .await
+        .expect("Failed to parse error response");
+    assert_eq!(error_response.error, "Too many requests");
+
+    app.clean_up().await;
+}
@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use auth_service::{
+    domain::{verify_audit_chain, AuditEvent, AuditSink, TokenBanReason},
+    routes::AuditLogVerificationResponse,
+    services::PostgresAuditSink,
+};
+use tokio::task::JoinSet;
+
+use crate::helpers::{get_random_email, TestApp};
+
+#[tokio::test]
+async fn should_record_chained_audit_events_for_account_activity() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "requires2FA": false
+    });
+
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let wrong_login_body = serde_json::json!({
+        "email": random_email,
+        "password": "wrongpassword",
+    });
+
+    let response = app.post_login(&wrong_login_body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+    });
+
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let records = app
+        .audit_sink
+        .read()
+        .await
+        .records()
+        .await
+        .expect("Failed to read audit log");
+
+    assert_eq!(verify_audit_chain(&records), Ok(()));
+
+    let events: Vec<AuditEvent> = records.into_iter().map(|record| record.event).collect();
+    assert_eq!(
+        events,
+        vec![
+            AuditEvent::UserSignedUp {
+                email: random_email.clone(),
+                requires_2fa: false,
+            },
+            AuditEvent::LoginFailed {
+                email: random_email.clone(),
+                reason: "incorrect_password".to_owned(),
+                ip_address: Some("127.0.0.1".to_owned()),
+                user_agent: None,
+            },
+            AuditEvent::LoginSucceeded {
+                email: random_email.clone(),
+                used_2fa: false,
+                ip_address: Some("127.0.0.1".to_owned()),
+                user_agent: None,
+            },
+            AuditEvent::TokenBanned {
+                email: random_email.clone(),
+                reason: TokenBanReason::Logout,
+            },
+            AuditEvent::LoggedOut {
+                email: random_email.clone(),
+            },
+        ]
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_let_admins_verify_the_audit_chain() {
+    let app = TestApp::new().await;
+
+    for _ in 0..2 {
+        let signup_body = serde_json::json!({
+            "email": get_random_email(),
+            "password": "pasword123",
+            "requires2FA": false
+        });
+        let response = app.post_signup(&signup_body).await;
+        assert_eq!(response.status().as_u16(), 201);
+    }
+
+    assert_eq!(app.get_audit_verify(None).await.status().as_u16(), 400);
+    assert_eq!(
+        app.get_audit_verify(Some("wrong-token"))
+            .await
+            .status()
+            .as_u16(),
+        401
+    );
+
+    let response = app.get_audit_verify(Some("test-audit-admin-token")).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let verification: AuditLogVerificationResponse = response.json().await.unwrap();
+    assert_eq!(verification.records, 2);
+    assert!(verification.valid);
+    assert!(verification.error.is_none());
+
+    // Editing a stored event breaks the chain at that record
+    sqlx::query(
+        "UPDATE audit_log SET event = replace(event, 'false', 'true') \
+         WHERE sequence = 1",
+    )
+    .execute(&app.pg_pool)
+    .await
+    .unwrap();
+
+    let response = app.get_audit_verify(Some("test-audit-admin-token")).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let verification: AuditLogVerificationResponse = response.json().await.unwrap();
+    assert!(!verification.valid);
+    assert_eq!(
+        verification.error.as_deref(),
+        Some("Audit record 1 has been modified")
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_keep_a_single_chain_when_replicas_append_at_once() {
+    let app = TestApp::new().await;
+
+    // Two sinks on the same table stand in for two replicas
+    let replicas = [
+        Arc::new(PostgresAuditSink::new(app.pg_pool.clone())),
+        Arc::new(PostgresAuditSink::new(app.pg_pool.clone())),
+    ];
+    let mut appends = JoinSet::new();
+    for n in 0..20 {
+        let sink = replicas[n % 2].clone();
+        appends.spawn(async move {
+            let event = AuditEvent::LoggedOut {
+                email: format!("user{}@example.com", n),
+            };
+            sink.record(event).await
+        });
+    }
+    while let Some(result) = appends.join_next().await {
+        result.unwrap().expect("Failed to append audit record");
+    }
+
+    // Every event made it in, one after another
+    let records = replicas[0].records().await.unwrap();
+    assert_eq!(records.len(), 20);
+    assert_eq!(verify_audit_chain(&records), Ok(()));
+
+    app.clean_up().await;
+}
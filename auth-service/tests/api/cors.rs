@@ -0,0 +1,85 @@
+use reqwest::{header, Method};
+
+use crate::helpers::TestApp;
+
+impl TestApp {
+    async fn preflight_login(&self, origin: &str) -> reqwest::Response {
+        self.http_client
+            .request(Method::OPTIONS, format!("{}/login", &self.address))
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+}
+
+#[tokio::test]
+async fn should_allow_preflight_from_configured_origin() {
+    let app = TestApp::new().await;
+
+    let response = app.preflight_login("http://localhost:8000").await;
+
+    assert_eq!(response.status().as_u16(), 200);
+    let headers = response.headers();
+    assert_eq!(
+        headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        "http://localhost:8000"
+    );
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+    assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "3600");
+
+    let methods = headers[header::ACCESS_CONTROL_ALLOW_METHODS]
+        .to_str()
+        .unwrap();
+    assert!(methods.contains("GET") && methods.contains("POST"));
+    let allowed_headers = headers[header::ACCESS_CONTROL_ALLOW_HEADERS]
+        .to_str()
+        .unwrap();
+    assert!(allowed_headers.contains("content-type") && allowed_headers.contains("x-csrf-token"));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_allow_preflight_from_wildcard_subdomain() {
+    let app = TestApp::new().await;
+
+    for origin in ["https://app.example.com", "https://eu.app.example.com"] {
+        let response = app.preflight_login(origin).await;
+
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            origin
+        );
+    }
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_not_allow_preflight_from_other_origins() {
+    let app = TestApp::new().await;
+
+    for origin in [
+        "https://example.com",
+        "https://evil-example.com",
+        "https://app.example.com.evil.com",
+        "http://app.example.com",
+        "http://localhost:8001",
+    ] {
+        let response = app.preflight_login(origin).await;
+
+        assert!(
+            response
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none(),
+            "Allowed {}",
+            origin
+        );
+    }
+
+    app.clean_up().await;
+}
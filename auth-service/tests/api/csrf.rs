@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use auth_service::ErrorResponse;
+use secrecy::Secret;
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn sign_up_and_log_in(app: &TestApp) -> reqwest::Response {
+    let email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+}
+
+#[tokio::test]
+async fn should_issue_a_readable_csrf_cookie_with_the_auth_cookie() {
+    let app = TestApp::new().await;
+
+    let response = sign_up_and_log_in(&app).await;
+
+    let csrf_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == "csrf_token")
+        .expect("No CSRF cookie found");
+    assert!(!csrf_cookie.value().is_empty());
+    // Scripts have to read it to echo it back
+    assert!(!csrf_cookie.http_only());
+    assert!(csrf_cookie.secure());
+    assert!(csrf_cookie.same_site_lax());
+    assert_eq!(csrf_cookie.max_age(), Some(Duration::from_secs(600)));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_403_for_cookie_logout_without_a_valid_csrf_token() {
+    let app = TestApp::new().await;
+
+    let response = sign_up_and_log_in(&app).await;
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == app.auth_cookie_name())
+        .expect("No auth cookie found");
+
+    for csrf_token in [None, Some(""), Some("not hex"), Some(&"00".repeat(32))] {
+        let response = app.post_logout_with_csrf_token(csrf_token).await;
+        assert_eq!(response.status().as_u16(), 403);
+        let error_response: ErrorResponse = response
+            .json()
+            .await
+            .expect("Failed to parse error response");
+        assert_eq!(error_response.error, "Invalid CSRF token");
+    }
+
+    // The rejected requests did not log the user out
+    assert!(!app
+        .banned_token_store
+        .read()
+        .await
+        .is_token_banned(&Secret::new(auth_cookie.value().to_owned()))
+        .await
+        .unwrap());
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let removal_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == "csrf_token")
+        .expect("Logout did not clear the CSRF cookie");
+    assert!(removal_cookie.value().is_empty());
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_a_csrf_token_issued_for_another_session() {
+    let app = TestApp::new().await;
+
+    sign_up_and_log_in(&app).await;
+    let first_csrf_token = app.csrf_token().expect("No CSRF cookie found");
+
+    // Logging in again replaces both cookies
+    sign_up_and_log_in(&app).await;
+    assert_ne!(app.csrf_token().as_ref(), Some(&first_csrf_token));
+
+    let response = app
+        .post_logout_with_csrf_token(Some(&first_csrf_token))
+        .await;
+    assert_eq!(response.status().as_u16(), 403);
+
+    app.clean_up().await;
+}
@@ -0,0 +1,283 @@
+use auth_service::{
+    domain::Email,
+    routes::{
+        AccessTokenResponse, DeviceAuthorizationResponse, DeviceVerificationResponse,
+        OAuthClientResponse, UserInfoResponse,
+    },
+    utils::{generate_auth_token, AuthenticationMethod},
+    ErrorResponse,
+};
+use secrecy::Secret;
+
+use crate::helpers::{get_random_email, TestApp};
+
+const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+async fn register_client(app: &TestApp) -> OAuthClientResponse {
+    let response = app
+        .post_oauth_client(&serde_json::json!({
+            "name": "Example CLI",
+            "redirectUris": ["https://client.example.com/callback"],
+            "scopes": ["openid", "email", "profile"]
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    response.json().await.unwrap()
+}
+
+async fn sign_up_and_log_in(app: &TestApp) -> String {
+    let email = get_random_email();
+    let body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 201);
+    let body = serde_json::json!({ "email": email, "password": "password123" });
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 200);
+    email
+}
+
+async fn request_device_code(
+    app: &TestApp,
+    client_id: &str,
+    scope: &str,
+) -> DeviceAuthorizationResponse {
+    let response = app
+        .post_device_code(&[("client_id", client_id), ("scope", scope)])
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["cache-control"], "no-store");
+    response.json().await.unwrap()
+}
+
+async fn poll(app: &TestApp, client_id: &str, device_code: &str) -> reqwest::Response {
+    app.post_token(&[
+        ("grant_type", DEVICE_CODE_GRANT_TYPE),
+        ("device_code", device_code),
+        ("client_id", client_id),
+    ])
+    .await
+}
+
+async fn poll_error(app: &TestApp, client_id: &str, device_code: &str) -> String {
+    let response = poll(app, client_id, device_code).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let body: serde_json::Value = response.json().await.unwrap();
+    body["error"].as_str().unwrap().to_owned()
+}
+
+#[tokio::test]
+async fn should_issue_tokens_once_the_user_approves_the_device() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+    let email = sign_up_and_log_in(&app).await;
+
+    let device = request_device_code(&app, &client.client_id, "openid email").await;
+    let issuer = &app.settings.application.public_url;
+    assert_eq!(device.verification_uri, format!("{}/device", issuer));
+    assert_eq!(
+        device.verification_uri_complete,
+        format!("{}/device?user_code={}", issuer, device.user_code)
+    );
+    assert_eq!(device.user_code.len(), 9);
+    assert_eq!(device.interval, 5);
+
+    // Users may type the code without the dash and in lower case
+    let typed = device.user_code.replace('-', "").to_lowercase();
+    let response = app.get_device_verify(&typed).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let request: DeviceVerificationResponse = response.json().await.unwrap();
+    assert_eq!(request.client_name, "Example CLI");
+    assert_eq!(request.user_code, device.user_code);
+    assert_eq!(request.scopes, ["openid", "email"]);
+
+    let response = app
+        .post_device_verify(&serde_json::json!({
+            "userCode": device.user_code,
+            "decision": "allow"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = poll(&app, &client.client_id, &device.device_code).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let token: AccessTokenResponse = response.json().await.unwrap();
+    assert_eq!(token.scope, "openid email");
+    assert!(token.id_token.is_some());
+    let userinfo: UserInfoResponse = app
+        .get_userinfo(&token.access_token)
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(userinfo.sub, email);
+
+    // The device code is single use, and the approved user code cannot be decided again
+    assert_eq!(
+        poll_error(&app, &client.client_id, &device.device_code).await,
+        "expired_token"
+    );
+    let response = app.get_device_verify(&device.user_code).await;
+    assert_eq!(response.status().as_u16(), 404);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_tell_devices_to_keep_waiting_and_to_slow_down() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+    let device = request_device_code(&app, &client.client_id, "profile").await;
+
+    assert_eq!(
+        poll_error(&app, &client.client_id, &device.device_code).await,
+        "authorization_pending"
+    );
+    // Polling again before the interval has passed
+    assert_eq!(
+        poll_error(&app, &client.client_id, &device.device_code).await,
+        "slow_down"
+    );
+
+    // Only the client the code was issued to can redeem it
+    assert_eq!(
+        poll_error(&app, "another-client", &device.device_code).await,
+        "invalid_grant"
+    );
+    assert_eq!(
+        poll_error(&app, &client.client_id, "not-a-device-code").await,
+        "invalid_grant"
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_deny_the_device_when_the_user_does() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+    sign_up_and_log_in(&app).await;
+    let device = request_device_code(&app, &client.client_id, "profile").await;
+
+    let body = serde_json::json!({
+        "userCode": device.user_code,
+        "decision": "deny"
+    });
+    assert_eq!(app.post_device_verify(&body).await.status().as_u16(), 204);
+    assert_eq!(app.post_device_verify(&body).await.status().as_u16(), 404);
+
+    assert_eq!(
+        poll_error(&app, &client.client_id, &device.device_code).await,
+        "access_denied"
+    );
+    assert_eq!(
+        poll_error(&app, &client.client_id, &device.device_code).await,
+        "expired_token"
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_unknown_clients_scopes_and_anonymous_users() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+
+    let response = app
+        .post_device_code(&[("client_id", "unknown"), ("scope", "profile")])
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "invalid_client");
+
+    for scope in ["", "admin"] {
+        let response = app
+            .post_device_code(&[("client_id", client.client_id.as_str()), ("scope", scope)])
+            .await;
+        assert_eq!(response.status().as_u16(), 400);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"], "invalid_scope");
+    }
+
+    // The verification page needs a logged-in user
+    let device = request_device_code(&app, &client.client_id, "profile").await;
+    let response = app.get_device_verify(&device.user_code).await;
+    assert!(matches!(response.status().as_u16(), 400 | 401));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_limit_how_many_user_codes_a_user_can_try() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+    sign_up_and_log_in(&app).await;
+    let device = request_device_code(&app, &client.client_id, "profile").await;
+
+    for _ in 0..10 {
+        let response = app.get_device_verify("BCDF-GHJK").await;
+        assert_eq!(response.status().as_u16(), 404);
+    }
+    // Not even the right code gets through once the guesses are used up
+    let response = app.get_device_verify(&device.user_code).await;
+    assert_eq!(response.status().as_u16(), 429);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_refuse_users_who_have_not_entered_their_2fa_code() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+    let email = get_random_email();
+    let body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": true
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 201);
+    let device = request_device_code(&app, &client.client_id, "profile").await;
+
+    // A password-only session, as issued before the user turned 2FA on
+    let token = generate_auth_token(
+        &Email::parse(Secret::new(email)).unwrap(),
+        AuthenticationMethod::Password,
+        &app.settings.jwt,
+    )
+    .unwrap();
+    let url = format!("{}/device/verify", &app.address);
+    let responses = [
+        app.http_client
+            .get(&url)
+            .query(&[("user_code", &device.user_code)])
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap(),
+        app.http_client
+            .post(&url)
+            .json(&serde_json::json!({
+                "userCode": device.user_code,
+                "decision": "allow"
+            }))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap(),
+    ];
+    for response in responses {
+        assert_eq!(response.status().as_u16(), 403);
+        assert_eq!(
+            response.json::<ErrorResponse>().await.unwrap().error,
+            "2FA required"
+        );
+    }
+
+    assert_eq!(
+        poll_error(&app, &client.client_id, &device.device_code).await,
+        "authorization_pending"
+    );
+
+    app.clean_up().await;
+}
@@ -0,0 +1,369 @@
+use auth_service::{routes::ExternalLoginProviderResponse, utils::IdTokenSigner};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use reqwest::Url;
+use secrecy::ExposeSecret;
+use wiremock::{
+    matchers::{body_string_contains, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+use crate::helpers::{get_random_email, TestApp};
+
+const CLIENT_ID: &str = "auth-service";
+
+// A stand-in OpenID Connect provider: discovery, keys, and a token endpoint that hands out
+// whatever ID token the test asks for. It signs with the test profile's ID token key.
+struct MockIdp {
+    server: MockServer,
+    key: EncodingKey,
+    kid: Option<String>,
+}
+
+impl MockIdp {
+    async fn start(server: MockServer, app: &TestApp) -> Self {
+        let pem = app.settings.oauth.id_token_signing_key.as_ref().unwrap();
+        let signer = IdTokenSigner::from_pem(pem.expose_secret()).unwrap();
+        let jwks = signer.jwks();
+        let kid = jwks.keys[0].common.key_id.clone();
+
+        let issuer = server.uri();
+        Mock::given(method("GET"))
+            .and(path("/.well-known/openid-configuration"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "issuer": issuer,
+                "authorization_endpoint": format!("{}/authorize", issuer),
+                "token_endpoint": format!("{}/token", issuer),
+                "jwks_uri": format!("{}/jwks", issuer),
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/jwks"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&jwks))
+            .mount(&server)
+            .await;
+
+        Self {
+            server,
+            key: EncodingKey::from_rsa_pem(pem.expose_secret().as_bytes()).unwrap(),
+            kid,
+        }
+    }
+
+    fn issuer(&self) -> String {
+        self.server.uri()
+    }
+
+    fn sign(&self, claims: serde_json::Value) -> String {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = self.kid.clone();
+        encode(&header, &claims, &self.key).unwrap()
+    }
+
+    // Answers the next redemption of `code` with an ID token carrying `claims`
+    async fn issue(&self, code: &str, claims: serde_json::Value) {
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains(format!("code={}", code)))
+            .and(body_string_contains("code_verifier="))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "idp-access-token",
+                "token_type": "Bearer",
+                "id_token": self.sign(claims),
+            })))
+            .up_to_n_times(1)
+            .mount(&self.server)
+            .await;
+    }
+
+    fn id_token_claims(&self, subject: &str, email: &str, nonce: &str) -> serde_json::Value {
+        serde_json::json!({
+            "iss": self.issuer(),
+            "sub": subject,
+            "aud": CLIENT_ID,
+            "exp": chrono::Utc::now().timestamp() + 300,
+            "iat": chrono::Utc::now().timestamp(),
+            "nonce": nonce,
+            "email": email,
+            "email_verified": true,
+        })
+    }
+}
+
+async fn start(
+    configure: impl FnOnce(&mut auth_service::utils::ExternalIdpSettings),
+) -> (TestApp, MockIdp) {
+    let server = MockServer::start().await;
+    let issuer = server.uri();
+    let app = TestApp::with_settings(|settings| {
+        settings.external_idp.issuer_url = Some(issuer);
+        settings.external_idp.client_id = CLIENT_ID.to_owned();
+        settings.external_idp.display_name = "Example SSO".to_owned();
+        configure(&mut settings.external_idp);
+    })
+    .await;
+    let idp = MockIdp::start(server, &app).await;
+    (app, idp)
+}
+
+// Starts a login and returns the `state` and `nonce` sent to the provider
+async fn begin_login(app: &TestApp, idp: &MockIdp, query: &[(&str, &str)]) -> (String, String) {
+    let response = app.get_external_login(query).await;
+    assert_eq!(response.status().as_u16(), 303);
+    let cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == app.settings.cookie.external_login_cookie_name())
+        .expect("No external login cookie found");
+    assert!(cookie.http_only() && cookie.same_site_lax());
+
+    let location = Url::parse(response.headers()["location"].to_str().unwrap()).unwrap();
+    assert!(location
+        .as_str()
+        .starts_with(&format!("{}/authorize?", idp.issuer())));
+    let param = |name: &str| {
+        location
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    assert_eq!(param("client_id").as_deref(), Some(CLIENT_ID));
+    assert_eq!(param("response_type").as_deref(), Some("code"));
+    assert_eq!(param("code_challenge_method").as_deref(), Some("S256"));
+    assert_eq!(
+        param("redirect_uri").unwrap(),
+        format!(
+            "{}/login/external/callback",
+            app.settings.application.public_url
+        )
+    );
+
+    (param("state").unwrap(), param("nonce").unwrap())
+}
+
+async fn finish_login(app: &TestApp, code: &str, state: &str) -> reqwest::Response {
+    app.get_external_login_callback(&[("code", code), ("state", state)])
+        .await
+}
+
+#[tokio::test]
+async fn should_only_offer_external_login_when_configured() {
+    let app = TestApp::new().await;
+    assert_eq!(
+        app.get_external_login_provider().await.status().as_u16(),
+        404
+    );
+    assert_eq!(app.get_external_login(&[]).await.status().as_u16(), 404);
+    app.clean_up().await;
+
+    let (app, _idp) = start(|_| {}).await;
+    let response = app.get_external_login_provider().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let provider: ExternalLoginProviderResponse = response.json().await.unwrap();
+    assert_eq!(provider.display_name, "Example SSO");
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_provision_and_log_in_a_new_user() {
+    let (app, idp) = start(|settings| settings.jit_provisioning = true).await;
+    let email = get_random_email();
+
+    let (state, nonce) = begin_login(&app, &idp, &[("return_to", "/device")]).await;
+    idp.issue("code-1", idp.id_token_claims("user-1", &email, &nonce))
+        .await;
+    let response = finish_login(&app, "code-1", &state).await;
+    assert_eq!(response.status().as_u16(), 303);
+    assert_eq!(response.headers()["location"], "/device");
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == app.auth_cookie_name())
+        .expect("No auth cookie found");
+    assert!(!auth_cookie.value().is_empty());
+
+    // The session works like any other
+    assert_eq!(app.get_account_export(None).await.status().as_u16(), 200);
+
+    // The state is spent
+    assert_eq!(
+        finish_login(&app, "code-1", &state).await.status().as_u16(),
+        400
+    );
+
+    // The provisioned user's password is random, so they cannot log in with one
+    let body = serde_json::json!({ "email": email, "password": "password123" });
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 401);
+
+    // Later logins find the linked account, even once the provider reports a new address
+    let (state, nonce) =
+        begin_login(&app, &idp, &[("return_to", "https://evil.example.com/")]).await;
+    let claims = idp.id_token_claims("user-1", &get_random_email(), &nonce);
+    idp.issue("code-2", claims).await;
+    let response = finish_login(&app, "code-2", &state).await;
+    assert_eq!(response.status().as_u16(), 303);
+    assert_eq!(response.headers()["location"], "/");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_only_finish_logins_in_the_browser_that_started_them() {
+    let (app, idp) = start(|settings| settings.jit_provisioning = true).await;
+
+    let (state, nonce) = begin_login(&app, &idp, &[]).await;
+    let claims = idp.id_token_claims("attacker", &get_random_email(), &nonce);
+    idp.issue("code-1", claims).await;
+
+    // Someone else following the callback URL is not logged in as whoever started the login
+    let victim = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let response = victim
+        .get(format!("{}/login/external/callback", app.address))
+        .query(&[("code", "code-1"), ("state", state.as_str())])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 400);
+    assert!(!response
+        .cookies()
+        .any(|cookie| cookie.name() == app.auth_cookie_name()));
+
+    // The browser that started it still can, once
+    let response = finish_login(&app, "code-1", &state).await;
+    assert_eq!(response.status().as_u16(), 303);
+    assert!(response.cookies().any(|cookie| {
+        cookie.name() == app.settings.cookie.external_login_cookie_name()
+            && cookie.value().is_empty()
+    }));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_link_existing_users_only_when_allowed() {
+    let (app, idp) = start(|_| {}).await;
+    let email = get_random_email();
+    let body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": true
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 201);
+
+    let (state, nonce) = begin_login(&app, &idp, &[]).await;
+    idp.issue("code-1", idp.id_token_claims("user-1", &email, &nonce))
+        .await;
+    assert_eq!(
+        finish_login(&app, "code-1", &state).await.status().as_u16(),
+        403
+    );
+    app.clean_up().await;
+
+    let (app, idp) = start(|settings| settings.link_existing_users = true).await;
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 201);
+
+    // Unverified addresses are never trusted to pick an account
+    let (state, nonce) = begin_login(&app, &idp, &[]).await;
+    let mut claims = idp.id_token_claims("user-1", &email, &nonce);
+    claims["email_verified"] = serde_json::json!(false);
+    idp.issue("code-1", claims).await;
+    assert_eq!(
+        finish_login(&app, "code-1", &state).await.status().as_u16(),
+        403
+    );
+
+    // The provider's login stands in for ours, 2FA included
+    let (state, nonce) = begin_login(&app, &idp, &[]).await;
+    idp.issue("code-2", idp.id_token_claims("user-1", &email, &nonce))
+        .await;
+    let response = finish_login(&app, "code-2", &state).await;
+    assert_eq!(response.status().as_u16(), 303);
+    assert!(response
+        .cookies()
+        .any(|cookie| cookie.name() == app.auth_cookie_name()));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_accounts_outside_the_allowed_domains() {
+    let (app, idp) = start(|settings| {
+        settings.jit_provisioning = true;
+        settings.allowed_domains = vec!["corp.example.com".to_owned()];
+    })
+    .await;
+
+    let (state, nonce) = begin_login(&app, &idp, &[]).await;
+    idp.issue(
+        "code-1",
+        idp.id_token_claims("user-1", &get_random_email(), &nonce),
+    )
+    .await;
+    assert_eq!(
+        finish_login(&app, "code-1", &state).await.status().as_u16(),
+        403
+    );
+
+    let (state, nonce) = begin_login(&app, &idp, &[]).await;
+    let claims = idp.id_token_claims("user-2", "someone@corp.example.com", &nonce);
+    idp.issue("code-2", claims).await;
+    assert_eq!(
+        finish_login(&app, "code-2", &state).await.status().as_u16(),
+        303
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_id_tokens_that_do_not_verify() {
+    let (app, idp) = start(|settings| settings.jit_provisioning = true).await;
+    let email = get_random_email();
+
+    let (state, _) = begin_login(&app, &idp, &[]).await;
+    idp.issue(
+        "code-1",
+        idp.id_token_claims("user-1", &email, "another-nonce"),
+    )
+    .await;
+    assert_eq!(
+        finish_login(&app, "code-1", &state).await.status().as_u16(),
+        400
+    );
+
+    let (state, nonce) = begin_login(&app, &idp, &[]).await;
+    let mut claims = idp.id_token_claims("user-1", &email, &nonce);
+    claims["aud"] = serde_json::json!("another-client");
+    idp.issue("code-2", claims).await;
+    assert_eq!(
+        finish_login(&app, "code-2", &state).await.status().as_u16(),
+        400
+    );
+
+    let (state, nonce) = begin_login(&app, &idp, &[]).await;
+    let mut claims = idp.id_token_claims("user-1", &email, &nonce);
+    claims["iss"] = serde_json::json!("https://idp.example.com");
+    idp.issue("code-3", claims).await;
+    assert_eq!(
+        finish_login(&app, "code-3", &state).await.status().as_u16(),
+        400
+    );
+
+    // Codes the provider does not recognise, and states we never issued
+    let (state, _) = begin_login(&app, &idp, &[]).await;
+    assert_eq!(
+        finish_login(&app, "unknown-code", &state)
+            .await
+            .status()
+            .as_u16(),
+        400
+    );
+    let state = "A".repeat(43);
+    assert_eq!(
+        finish_login(&app, "code-4", &state).await.status().as_u16(),
+        400
+    );
+
+    app.clean_up().await;
+}
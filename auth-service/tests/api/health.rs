@@ -0,0 +1,84 @@
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::TestApp;
+
+#[tokio::test]
+async fn should_return_200_when_alive() {
+    let app = TestApp::new().await;
+
+    let response = app.get_health_live().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "ok");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_200_when_all_dependencies_are_up() {
+    let app = TestApp::new().await;
+
+    Mock::given(path("/server"))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app.get_health_ready().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "ready");
+    for dependency in ["postgres", "redis", "emailProvider"] {
+        assert_eq!(body["checks"][dependency]["status"], "up", "{}", dependency);
+    }
+    assert_eq!(body["checks"]["postgres"]["required"], true);
+    assert_eq!(body["checks"]["emailProvider"]["required"], false);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_stay_ready_when_the_email_provider_is_down() {
+    let app = TestApp::new().await;
+
+    Mock::given(path("/server"))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(401))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app.get_health_ready().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "ready");
+    assert_eq!(body["checks"]["emailProvider"]["status"], "down");
+    assert!(body["checks"]["emailProvider"]["error"].is_string());
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_503_when_postgres_is_down() {
+    let app = TestApp::new().await;
+
+    // A closed pool refuses every query, just like an unreachable database
+    app.pg_pool.close().await;
+
+    let response = app.get_health_ready().await;
+    assert_eq!(response.status().as_u16(), 503);
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "not_ready");
+    assert_eq!(body["checks"]["postgres"]["status"], "down");
+    assert_eq!(body["checks"]["redis"]["status"], "up");
+
+    app.clean_up().await;
+}
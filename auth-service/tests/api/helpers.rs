@@ -1,72 +1,176 @@
 use auth_service::{
     app_state::{
-        AppState, BannedTokenStoreType, EmailClientType, TwoFACodeStoreType, UserStoreType,
+        AppState, AppStores, AuditSinkType, AuthorizationCodeStoreType, BannedTokenStoreType,
+        DeviceAuthorizationStoreType, EmailClientType, ExternalIdentityStoreType,
+        ExternalLoginStoreType, GeoLocatorType, IdTokenSignerType, KnownDeviceStoreType,
+        LoginAlertStoreType, LoginHistoryStoreType, MagicLinkStoreType, OAuthClientStoreType,
+        RateLimitStoreType, ServiceClientStoreType, SettingsType, TwoFACodeStoreType,
+        UserStoreType, WebhookStoreType,
     },
-    domain::Email,
+    domain::Location,
     get_postgres_pool, get_redis_client,
     services::{
-        redis_banned_token_store::RedisBannedTokenStore,
-        redis_two_fa_code_store::RedisTwoFACodeStore, HashSetBannedTokenStore, MockEmailClient,
-        PostgresUserStore, PostmarkEmailClient,
+        deliver_due_webhooks, redis_banned_token_store::RedisBannedTokenStore,
+        redis_login_alert_store::RedisLoginAlertStore, redis_rate_limit_store::RedisRateLimitStore,
+        redis_two_fa_code_store::RedisTwoFACodeStore, CompositeUserStore, LdapUserStore,
+        MockGeoLocator, PostgresAuditSink, PostgresExternalIdentityStore, PostgresKnownDeviceStore,
+        PostgresLoginHistoryStore, PostgresOAuthClientStore, PostgresServiceClientStore,
+        PostgresUserStore, PostgresWebhookStore, PostmarkEmailClient, Readiness,
+        RedisAuthorizationCodeStore, RedisDeviceAuthorizationStore, RedisExternalLoginStore,
+        RedisMagicLinkStore,
     },
-    utils::{test, DATABASE_URL},
+    utils::{test, IdTokenSigner, Profile, Settings, ShutdownHandle},
     Application,
 };
-use reqwest::{cookie::Jar, Client};
+use reqwest::{
+    cookie::{CookieStore, Jar},
+    Client, Url,
+};
 use secrecy::{ExposeSecret, Secret};
-use sqlx::{postgres::PgConnectOptions, Connection, Executor, PgConnection};
+use sqlx::{postgres::PgConnectOptions, Connection, PgConnection};
 use sqlx::{postgres::PgPoolOptions, PgPool};
 use std::{cell::Cell, str::FromStr, sync::Arc};
-use tokio::sync::RwLock;
+use tokio::{sync::RwLock, task::JoinHandle};
 use uuid::Uuid;
 use wiremock::MockServer;
 
 pub struct TestApp {
     pub address: String,
+    pub redirect_address: Option<String>,
     pub cookie_jar: Arc<Jar>,
     pub http_client: reqwest::Client,
+    // Shares the cookie jar but hands redirects back, e.g. to read OAuth redirects
+    pub no_redirect_client: reqwest::Client,
     pub banned_token_store: BannedTokenStoreType,
     pub two_fa_code_store: TwoFACodeStoreType,
+    pub audit_sink: Arc<RwLock<PostgresAuditSink>>,
+    pub webhook_store: WebhookStoreType,
+    pub pg_pool: PgPool,
     pub email_server: MockServer,
     pub db_name: String,
+    pub settings: SettingsType,
+    pub shutdown_handle: ShutdownHandle,
+    server: Option<JoinHandle<Result<(), std::io::Error>>>,
     clean_up_called: Cell<bool>,
 }
 
 impl TestApp {
     pub async fn new() -> Self {
-        let (pg_pool, db_name) = configure_postgresql().await;
-        let redis_conn = configure_redis();
+        Self::with_settings(|_| {}).await
+    }
+
+    // Starts an app with the test profile adjusted by `configure`
+    pub async fn with_settings(configure: impl FnOnce(&mut Settings)) -> Self {
+        let mut settings =
+            Settings::load_profile(Profile::Test).expect("Failed to load test settings");
+        configure(&mut settings);
+        let settings: SettingsType = Arc::new(settings);
+        let (pg_pool, db_name) = configure_postgresql(&settings).await;
+        let redis_conn = configure_redis(&settings);
         let shared_redis_conn = Arc::new(RwLock::new(redis_conn));
 
-        let user_store = Arc::new(RwLock::new(PostgresUserStore::new(pg_pool))) as UserStoreType;
+        let user_store = configure_user_store(&settings, pg_pool.clone());
 
         let banned_token_store = Arc::new(RwLock::new(RedisBannedTokenStore::new(
             shared_redis_conn.clone(),
         ))) as BannedTokenStoreType;
-        let two_fa_code_store = Arc::new(RwLock::new(RedisTwoFACodeStore::new(shared_redis_conn)))
-            as TwoFACodeStoreType;
+        let two_fa_code_store = Arc::new(RwLock::new(RedisTwoFACodeStore::new(
+            shared_redis_conn.clone(),
+        ))) as TwoFACodeStoreType;
+        let rate_limit_store = Arc::new(RwLock::new(RedisRateLimitStore::new(
+            shared_redis_conn.clone(),
+        ))) as RateLimitStoreType;
+        let login_alert_store = Arc::new(RwLock::new(RedisLoginAlertStore::new(
+            shared_redis_conn.clone(),
+        ))) as LoginAlertStoreType;
+        let login_history_store =
+            Arc::new(RwLock::new(PostgresLoginHistoryStore::new(pg_pool.clone())))
+                as LoginHistoryStoreType;
+        let known_device_store =
+            Arc::new(RwLock::new(PostgresKnownDeviceStore::new(pg_pool.clone())))
+                as KnownDeviceStoreType;
+        let webhook_store =
+            Arc::new(RwLock::new(PostgresWebhookStore::new(pg_pool.clone()))) as WebhookStoreType;
+        let oauth_client_store =
+            Arc::new(RwLock::new(PostgresOAuthClientStore::new(pg_pool.clone())))
+                as OAuthClientStoreType;
+        let authorization_code_store = Arc::new(RwLock::new(RedisAuthorizationCodeStore::new(
+            shared_redis_conn.clone(),
+        ))) as AuthorizationCodeStoreType;
+        let service_client_store = Arc::new(RwLock::new(PostgresServiceClientStore::new(
+            pg_pool.clone(),
+        ))) as ServiceClientStoreType;
+        let device_authorization_store = Arc::new(RwLock::new(RedisDeviceAuthorizationStore::new(
+            shared_redis_conn.clone(),
+        ))) as DeviceAuthorizationStoreType;
+        let external_identity_store = Arc::new(RwLock::new(PostgresExternalIdentityStore::new(
+            pg_pool.clone(),
+        ))) as ExternalIdentityStoreType;
+        let external_login_store = Arc::new(RwLock::new(RedisExternalLoginStore::new(
+            shared_redis_conn.clone(),
+        ))) as ExternalLoginStoreType;
+        let magic_link_store = Arc::new(RwLock::new(RedisMagicLinkStore::new(
+            shared_redis_conn.clone(),
+        ))) as MagicLinkStoreType;
+        let audit_sink = Arc::new(RwLock::new(PostgresAuditSink::new(pg_pool.clone())));
+        let geo_locator = Arc::new(RwLock::new(MockGeoLocator {
+            location: Some(Location {
+                city: Some(test::geo_locator::CITY.to_owned()),
+                country: Some(test::geo_locator::COUNTRY.to_owned()),
+            }),
+        })) as GeoLocatorType;
 
         let email_server = MockServer::start().await; // New!
         let base_url = email_server.uri(); // New!
-        let email_client =
-            Arc::new(RwLock::new(configure_postmark_email_client(base_url))) as EmailClientType;
+        let email_client = Arc::new(RwLock::new(configure_postmark_email_client(
+            base_url, &settings,
+        ))) as EmailClientType;
 
         let app_state = AppState::new(
-            user_store,
-            banned_token_store.clone(),
-            two_fa_code_store.clone(),
-            email_client,
+            AppStores {
+                user_store,
+                banned_token_store: banned_token_store.clone(),
+                two_fa_code_store: two_fa_code_store.clone(),
+                email_client,
+                rate_limit_store,
+                login_history_store,
+                known_device_store,
+                login_alert_store,
+                geo_locator,
+                audit_sink: audit_sink.clone() as AuditSinkType,
+                webhook_store: webhook_store.clone(),
+                oauth_client_store,
+                authorization_code_store,
+                service_client_store,
+                device_authorization_store,
+                external_identity_store,
+                external_login_store,
+                magic_link_store,
+            },
+            pg_pool.clone(),
+            shared_redis_conn,
+            Arc::new(Readiness::new(settings.health.check_email_provider)),
+            IdTokenSigner::from_settings(&settings)
+                .expect("Failed to load the ID token signing key")
+                .map(|signer| Arc::new(signer) as IdTokenSignerType),
+            settings.clone(),
         );
-        let app = Application::build(app_state, test::APP_ADDRESS)
+        let app = Application::build(app_state)
             .await
             .expect("Failed to build app");
 
-        let address = format!("http://{}", app.address.clone());
+        let scheme = if settings.tls.enabled {
+            "https"
+        } else {
+            "http"
+        };
+        let address = format!("{}://{}", scheme, app.address.clone());
+        let redirect_address = app.redirect_address.clone();
+        let shutdown_handle = app.shutdown_handle();
 
         // Run the auth service in a separate async task
         // to avoid blocking the main test thread.
-        #[allow(clippy::let_underscore_future)]
-        let _ = tokio::spawn(app.run());
+        let server = tokio::spawn(app.run());
 
         // Create a Reqwest http client instance with a cookie jar
         let cookie_jar = Arc::new(Jar::default());
@@ -74,29 +178,76 @@ impl TestApp {
             .cookie_provider(cookie_jar.clone())
             .build()
             .unwrap();
+        let no_redirect_client = reqwest::Client::builder()
+            .cookie_provider(cookie_jar.clone())
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
 
         Self {
             address,
+            redirect_address,
             cookie_jar,
             http_client,
+            no_redirect_client,
             banned_token_store,
             two_fa_code_store,
+            audit_sink,
+            webhook_store,
+            pg_pool,
             email_server,
             db_name,
+            settings,
+            shutdown_handle,
+            server: Some(server),
             clean_up_called: Cell::new(false),
         }
     }
 
     // New!
 
+    // Triggers a graceful shutdown and waits for the server to finish draining
+    pub async fn shutdown(&mut self) -> Result<(), std::io::Error> {
+        self.shutdown_handle.shutdown();
+        self.server
+            .take()
+            .expect("Server was already shut down")
+            .await
+            .expect("Server task panicked")
+    }
+
     pub async fn clean_up(&self) {
-        delete_database(&self.db_name).await;
+        delete_database(&self.settings, &self.db_name).await;
         self.clean_up_called.set(true);
     }
 
     pub async fn get_root(&self) -> reqwest::Response {
         self.http_client
-            .get(&format!("{}/", &self.address))
+            .get(format!("{}/", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_health_live(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/health/live", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_health_ready(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/health/ready", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_metrics(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/metrics", &self.address))
             .send()
             .await
             .expect("Failed to execute request.")
@@ -107,7 +258,7 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/signup", &self.address))
+            .post(format!("{}/signup", &self.address))
             .json(body)
             .send()
             .await
@@ -119,16 +270,49 @@ impl TestApp {
         Body: serde::Serialize,
     {
         self.http_client
-            .post(&format!("{}/login", &self.address))
+            .post(format!("{}/login", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Logs in while presenting a specific User-Agent, so tests can act as different devices
+    pub async fn post_login_with_user_agent<Body>(
+        &self,
+        body: &Body,
+        user_agent: &str,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/login", &self.address))
+            .header(reqwest::header::USER_AGENT, user_agent)
             .json(body)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
+    // Sends the CSRF token from the cookie jar, as a browser client would
     pub async fn post_logout(&self) -> reqwest::Response {
+        self.post_logout_with_csrf_token(self.csrf_token().as_deref())
+            .await
+    }
+
+    pub async fn post_logout_with_csrf_token(&self, csrf_token: Option<&str>) -> reqwest::Response {
+        let mut request = self.http_client.post(format!("{}/logout", &self.address));
+        if let Some(csrf_token) = csrf_token {
+            request = request.header("x-csrf-token", csrf_token);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn post_logout_with_bearer(&self, token: &str) -> reqwest::Response {
         self.http_client
-            .post(&format!("{}/logout", &self.address))
+            .post(format!("{}/logout", &self.address))
+            .bearer_auth(token)
             .send()
             .await
             .expect("Failed to execute request.")
@@ -158,6 +342,487 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    pub async fn get_account_export(&self, format: Option<&str>) -> reqwest::Response {
+        let mut request = self
+            .http_client
+            .get(format!("{}/account/export", &self.address));
+
+        if let Some(format) = format {
+            request = request.query(&[("format", format)]);
+        }
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_login_history(
+        &self,
+        page: Option<u32>,
+        per_page: Option<u32>,
+    ) -> reqwest::Response {
+        let mut request = self
+            .http_client
+            .get(format!("{}/account/login-history", &self.address));
+
+        if let Some(page) = page {
+            request = request.query(&[("page", page)]);
+        }
+        if let Some(per_page) = per_page {
+            request = request.query(&[("perPage", per_page)]);
+        }
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    // What a mail scanner following the emailed link fetches; the token stays in the fragment
+    pub async fn get_report_login_page(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/account/report-login", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_report_login(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/account/report-login", &self.address))
+            .json(&serde_json::json!({ "token": token }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_reset_password_page(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/account/reset-password", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_reset_password<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/account/reset-password", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_webhook<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.post_webhook_with_token(body, Some(self.webhook_admin_token()))
+            .await
+    }
+
+    pub fn auth_cookie_name(&self) -> String {
+        self.settings.cookie.cookie_name()
+    }
+
+    // The CSRF cookie's value for the app's address, if a cookie login set one
+    pub fn csrf_token(&self) -> Option<String> {
+        let prefix = format!("{}=", self.settings.cookie.csrf_cookie_name());
+        let cookies = self
+            .cookie_jar
+            .cookies(&Url::parse(&self.address).expect("Failed to parse URL"))?;
+        let token = cookies
+            .to_str()
+            .ok()?
+            .split("; ")
+            .find_map(|cookie| cookie.strip_prefix(&prefix))?
+            .to_owned();
+        Some(token)
+    }
+
+    fn webhook_admin_token(&self) -> &str {
+        self.settings
+            .webhooks
+            .admin_token
+            .as_ref()
+            .expect("The test profile sets a webhook admin token")
+            .expose_secret()
+    }
+
+    pub async fn post_webhook_with_token<Body>(
+        &self,
+        body: &Body,
+        token: Option<&str>,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let mut request = self
+            .http_client
+            .post(format!("{}/admin/webhooks", &self.address))
+            .json(body);
+
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_audit_verify(&self, token: Option<&str>) -> reqwest::Response {
+        let mut request = self
+            .http_client
+            .get(format!("{}/admin/audit/verify", &self.address));
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_webhooks(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/admin/webhooks", &self.address))
+            .bearer_auth(self.webhook_admin_token())
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_webhook(&self, id: &str) -> reqwest::Response {
+        self.http_client
+            .delete(format!("{}/admin/webhooks/{}", &self.address, id))
+            .bearer_auth(self.webhook_admin_token())
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_webhook_dead_letters(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/admin/webhooks/dead-letters", &self.address))
+            .bearer_auth(self.webhook_admin_token())
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_replay_webhook_delivery(&self, id: &str) -> reqwest::Response {
+        self.http_client
+            .post(format!(
+                "{}/admin/webhooks/dead-letters/{}/replay",
+                &self.address, id
+            ))
+            .bearer_auth(self.webhook_admin_token())
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    fn oauth_admin_token(&self) -> &str {
+        self.settings
+            .oauth
+            .admin_token
+            .as_ref()
+            .expect("The test profile sets an OAuth admin token")
+            .expose_secret()
+    }
+
+    pub async fn post_oauth_client<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.post_oauth_client_with_token(body, Some(self.oauth_admin_token()))
+            .await
+    }
+
+    pub async fn post_oauth_client_with_token<Body>(
+        &self,
+        body: &Body,
+        token: Option<&str>,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let mut request = self
+            .http_client
+            .post(format!("{}/admin/oauth/clients", &self.address))
+            .json(body);
+
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_oauth_clients(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/admin/oauth/clients", &self.address))
+            .bearer_auth(self.oauth_admin_token())
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_oauth_client(&self, client_id: &str) -> reqwest::Response {
+        self.http_client
+            .delete(format!(
+                "{}/admin/oauth/clients/{}",
+                &self.address, client_id
+            ))
+            .bearer_auth(self.oauth_admin_token())
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_service_client<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/admin/oauth/service-clients", &self.address))
+            .bearer_auth(self.oauth_admin_token())
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_service_clients(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/admin/oauth/service-clients", &self.address))
+            .bearer_auth(self.oauth_admin_token())
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn delete_service_client(&self, client_id: &str) -> reqwest::Response {
+        self.http_client
+            .delete(format!(
+                "{}/admin/oauth/service-clients/{}",
+                &self.address, client_id
+            ))
+            .bearer_auth(self.oauth_admin_token())
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Redirects are returned rather than followed
+    pub async fn get_authorize(&self, query: &[(&str, &str)]) -> reqwest::Response {
+        self.no_redirect_client
+            .get(format!("{}/authorize", &self.address))
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_authorize(&self, form: &[(&str, &str)]) -> reqwest::Response {
+        self.no_redirect_client
+            .post(format!("{}/authorize", &self.address))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_token(&self, form: &[(&str, &str)]) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/token", &self.address))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_token_with_basic_auth(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+        form: &[(&str, &str)],
+    ) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/token", &self.address))
+            .basic_auth(client_id, Some(client_secret))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_introspect(
+        &self,
+        credentials: Option<(&str, &str)>,
+        form: &[(&str, &str)],
+    ) -> reqwest::Response {
+        let mut request = self
+            .http_client
+            .post(format!("{}/introspect", &self.address))
+            .form(form);
+
+        if let Some((client_id, client_secret)) = credentials {
+            request = request.basic_auth(client_id, Some(client_secret));
+        }
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn post_revoke(
+        &self,
+        credentials: Option<(&str, &str)>,
+        form: &[(&str, &str)],
+    ) -> reqwest::Response {
+        let mut request = self
+            .http_client
+            .post(format!("{}/revoke", &self.address))
+            .form(form);
+
+        if let Some((client_id, client_secret)) = credentials {
+            request = request.basic_auth(client_id, Some(client_secret));
+        }
+
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn post_revoke_with_bearer(
+        &self,
+        bearer_token: &str,
+        form: &[(&str, &str)],
+    ) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/revoke", &self.address))
+            .bearer_auth(bearer_token)
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_device_code(&self, form: &[(&str, &str)]) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/device/code", &self.address))
+            .form(form)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_device_verify(&self, user_code: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/device/verify", &self.address))
+            .query(&[("user_code", user_code)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Sends the CSRF token from the cookie jar, as the verification page does
+    pub async fn post_device_verify<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let mut request = self
+            .http_client
+            .post(format!("{}/device/verify", &self.address))
+            .json(body);
+        if let Some(csrf_token) = self.csrf_token() {
+            request = request.header("x-csrf-token", csrf_token);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    pub async fn get_userinfo(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/userinfo", &self.address))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_external_login_provider(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/login/external/provider", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Redirects are returned rather than followed, to read where the identity provider is
+    pub async fn get_external_login(&self, query: &[(&str, &str)]) -> reqwest::Response {
+        self.no_redirect_client
+            .get(format!("{}/login/external", &self.address))
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_external_login_callback(&self, query: &[(&str, &str)]) -> reqwest::Response {
+        self.no_redirect_client
+            .get(format!("{}/login/external/callback", &self.address))
+            .query(query)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_magic_link<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.http_client
+            .post(format!("{}/login/magic-link", &self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_magic_link_callback(&self, token: &str) -> reqwest::Response {
+        self.http_client
+            .post(format!("{}/login/magic-link/callback", &self.address))
+            .json(&serde_json::json!({ "token": token }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_openid_configuration(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!(
+                "{}/.well-known/openid-configuration",
+                &self.address
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_jwks(&self) -> reqwest::Response {
+        self.http_client
+            .get(format!("{}/.well-known/jwks.json", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    // Runs one pass of the webhook delivery worker, which tests drive by hand
+    pub async fn deliver_webhooks(&self) -> usize {
+        let http_client = Client::builder()
+            .timeout(self.settings.webhooks.timeout())
+            .build()
+            .expect("Failed to build HTTP client");
+
+        let policy = self.settings.webhooks.delivery_policy();
+
+        deliver_due_webhooks(&self.webhook_store, &http_client, &policy)
+            .await
+            .expect("Failed to deliver webhooks")
+    }
+
     // Implement helper functions for all other routes (signup, login, logout, verify-2fa, and verify-token)
 }
 
@@ -176,15 +841,26 @@ pub fn get_random_email() -> String {
     format!("{}@example.com", Uuid::new_v4())
 }
 
-fn configure_redis() -> redis::Connection {
-    get_redis_client(test::DEFAULT_REDIS_HOSTNAME.to_owned())
+// Directory users are checked first when a test points `ldap.url` at a stand-in server
+fn configure_user_store(settings: &Settings, pg_pool: PgPool) -> UserStoreType {
+    let local = Arc::new(RwLock::new(PostgresUserStore::new(pg_pool))) as UserStoreType;
+    if settings.ldap.url.is_none() {
+        return local;
+    }
+
+    let directory = Arc::new(RwLock::new(LdapUserStore::new(settings.ldap.clone())));
+    Arc::new(RwLock::new(CompositeUserStore::new(directory, local)))
+}
+
+fn configure_redis(settings: &Settings) -> redis::Connection {
+    get_redis_client(settings.redis.host_name.clone())
         .expect("Failed to get Redis client")
         .get_connection()
         .expect("Failed to get Redis connection")
 }
 
-async fn configure_postgresql() -> (PgPool, String) {
-    let mut postgresql_conn_url = DATABASE_URL.clone();
+async fn configure_postgresql(settings: &Settings) -> (PgPool, String) {
+    let mut postgresql_conn_url = settings.database.url.clone();
 
     // Replace 'db' hostname with 'localhost' for local testing
     postgresql_conn_url = Secret::new(
@@ -206,9 +882,12 @@ async fn configure_postgresql() -> (PgPool, String) {
         format!("{}/{}", postgresql_conn_url.expose_secret(), db_name);
 
     // Create a new connection pool and return it along with the database name
-    let pool = get_postgres_pool(Secret::new(postgresql_conn_url_with_db))
-        .await
-        .expect("Failed to create Postgres connection pool!");
+    let pool = get_postgres_pool(
+        Secret::new(postgresql_conn_url_with_db),
+        settings.database.max_connections,
+    )
+    .await
+    .expect("Failed to create Postgres connection pool!");
     (pool, db_name)
 }
 
@@ -245,8 +924,8 @@ async fn configure_database(db_conn_string: &str, db_name: &str) {
         .expect("Failed to migrate the database");
 }
 
-async fn delete_database(db_name: &str) {
-    let mut postgresql_conn_url = DATABASE_URL.to_owned();
+async fn delete_database(settings: &Settings, db_name: &str) {
+    let mut postgresql_conn_url = settings.database.url.clone();
 
     // Replace 'db' hostname with 'localhost' for local testing
     postgresql_conn_url = Secret::new(
@@ -287,13 +966,13 @@ async fn delete_database(db_name: &str) {
         .expect("Failed to drop the database.");
 }
 
-fn configure_postmark_email_client(base_url: String) -> PostmarkEmailClient {
-    let postmark_auth_token = Secret::new("auth_token".to_owned());
+fn configure_postmark_email_client(base_url: String, settings: &Settings) -> PostmarkEmailClient {
+    let postmark_auth_token = settings.email_client.auth_token.clone();
 
-    let sender = Email::parse(Secret::new(test::email_client::SENDER.to_owned())).unwrap();
+    let sender = settings.email_client.sender().unwrap();
 
     let http_client = Client::builder()
-        .timeout(test::email_client::TIMEOUT)
+        .timeout(settings.email_client.timeout())
         .build()
         .expect("Failed to build HTTP client");
 
@@ -0,0 +1,196 @@
+use auth_service::{
+    routes::{
+        AccessTokenResponse, AuthTokenResponse, CreateServiceClientResponse, IntrospectionResponse,
+        VerifyTokenResponse,
+    },
+    utils::TokenKind,
+};
+use chrono::Utc;
+use reqwest::header;
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn register_service_client(app: &TestApp) -> CreateServiceClientResponse {
+    let response = app
+        .post_service_client(&serde_json::json!({
+            "name": "Resource Server",
+            "scopes": ["reports:read"]
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    response.json().await.unwrap()
+}
+
+// Logs a new user in and returns the session token
+async fn log_in(app: &TestApp, email: &str) -> String {
+    let body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 201);
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": email,
+            "password": "password123",
+            "responseMode": "token"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json::<AuthTokenResponse>()
+        .await
+        .unwrap()
+        .access_token
+}
+
+async fn introspect(
+    app: &TestApp,
+    client: &CreateServiceClientResponse,
+    token: &str,
+) -> IntrospectionResponse {
+    let response = app
+        .post_introspect(
+            Some((&client.client.client_id, &client.client_secret)),
+            &[("token", token)],
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+    response.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn should_describe_active_tokens() {
+    let app = TestApp::new().await;
+    let client = register_service_client(&app).await;
+    let email = get_random_email();
+    let session_token = log_in(&app, &email).await;
+
+    let introspection = introspect(&app, &client, &session_token).await;
+    assert!(introspection.active);
+    assert_eq!(introspection.sub.as_deref(), Some(email.as_str()));
+    assert!(introspection.sid.is_some());
+    assert_eq!(introspection.client_id, None);
+    let now = Utc::now().timestamp() as usize;
+    assert!(introspection.iat.unwrap().abs_diff(now) <= 60);
+    assert!(introspection.exp.unwrap() > now);
+
+    // A service client's own token
+    let response = app
+        .post_token_with_basic_auth(
+            &client.client.client_id,
+            &client.client_secret,
+            &[("grant_type", "client_credentials")],
+        )
+        .await;
+    let client_token: AccessTokenResponse = response.json().await.unwrap();
+    let introspection = introspect(&app, &client, &client_token.access_token).await;
+    assert!(introspection.active);
+    assert_eq!(introspection.sub.as_ref(), Some(&client.client.client_id));
+    assert_eq!(
+        introspection.client_id.as_ref(),
+        Some(&client.client.client_id)
+    );
+    assert_eq!(introspection.scope.as_deref(), Some("reports:read"));
+    assert_eq!(introspection.sid, None);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_report_invalid_and_revoked_tokens_as_inactive() {
+    let app = TestApp::new().await;
+    let client = register_service_client(&app).await;
+    let session_token = log_in(&app, &get_random_email()).await;
+
+    assert_eq!(
+        app.post_logout_with_bearer(&session_token)
+            .await
+            .status()
+            .as_u16(),
+        200
+    );
+
+    for token in [session_token.as_str(), "not-a-token"] {
+        let response = app
+            .post_introspect(
+                Some((&client.client.client_id, &client.client_secret)),
+                &[("token", token), ("token_type_hint", "access_token")],
+            )
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+        // Nothing is said about inactive tokens beyond that
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body, serde_json::json!({ "active": false }));
+    }
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_only_answer_authenticated_clients() {
+    let app = TestApp::new().await;
+    let client = register_service_client(&app).await;
+    let session_token = log_in(&app, &get_random_email()).await;
+
+    for credentials in [
+        None,
+        Some((client.client.client_id.as_str(), "wrong-secret")),
+    ] {
+        let response = app
+            .post_introspect(credentials, &[("token", &session_token)])
+            .await;
+        assert_eq!(response.status().as_u16(), 401);
+        let error: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(error["error"], "invalid_client");
+    }
+
+    // Users cannot introspect with their own token
+    let response = app
+        .http_client
+        .post(format!("{}/introspect", &app.address))
+        .bearer_auth(&session_token)
+        .form(&[("token", &session_token)])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app
+        .post_introspect(Some((&client.client.client_id, &client.client_secret)), &[])
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_claims_from_verify_token_on_request() {
+    let app = TestApp::new().await;
+    let email = get_random_email();
+    let session_token = log_in(&app, &email).await;
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": session_token }))
+        .await;
+    let verified: VerifyTokenResponse = response.json().await.unwrap();
+    assert!(verified.claims.is_none());
+
+    let response = app
+        .post_verify_token(&serde_json::json!({
+            "token": session_token,
+            "includeClaims": true
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let verified: VerifyTokenResponse = response.json().await.unwrap();
+    assert_eq!(verified.token_kind, TokenKind::User);
+    let claims = verified.claims.expect("No claims returned");
+    assert_eq!(claims.sub, email);
+    assert_eq!(claims.amr, ["pwd"]);
+    assert!(claims.sid.is_some());
+
+    app.clean_up().await;
+}
@@ -0,0 +1,362 @@
+use std::sync::{Arc, Mutex};
+
+use auth_service::{
+    domain::{Email, User, UserStore, UserStoreError},
+    services::PostgresUserStore,
+};
+use bytes::BytesMut;
+use ldap3::asn1::{parse_tag, write, StructureTag, TagClass, PL};
+use secrecy::Secret;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::helpers::TestApp;
+
+const BASE_DN: &str = "ou=people,dc=example,dc=com";
+const SERVICE_DN: &str = "cn=auth-service,ou=services,dc=example,dc=com";
+const SERVICE_PASSWORD: &str = "service-password";
+const STAFF_GROUP: &str = "cn=staff,ou=groups,dc=example,dc=com";
+
+struct DirectoryEntry {
+    dn: String,
+    password: String,
+    attributes: Vec<(&'static str, String)>,
+}
+
+impl DirectoryEntry {
+    fn person(uid: &str, email: &str, password: &str, groups: &[&str]) -> Self {
+        let mut attributes = vec![
+            ("objectClass", "person".to_owned()),
+            ("mail", email.to_owned()),
+        ];
+        attributes.extend(groups.iter().map(|group| ("memberOf", (*group).to_owned())));
+        Self {
+            dn: format!("uid={},{}", uid, BASE_DN),
+            password: password.to_owned(),
+            attributes,
+        }
+    }
+
+    fn has(&self, attribute: &str, value: &str) -> bool {
+        self.attributes
+            .iter()
+            .any(|(name, v)| name.eq_ignore_ascii_case(attribute) && v.eq_ignore_ascii_case(value))
+    }
+}
+
+// An in-process stand-in for an LDAP server, speaking just enough of RFC 4511 for the user store:
+// simple binds, subtree searches with and/or/not/equality/presence filters, and unbind. Searches
+// are only answered for the service account, and every bind is recorded.
+struct LdapStandIn {
+    url: String,
+    binds: Arc<Mutex<Vec<String>>>,
+}
+
+impl LdapStandIn {
+    async fn start(entries: Vec<DirectoryEntry>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ldap://{}", listener.local_addr().unwrap());
+        let binds = Arc::new(Mutex::new(Vec::new()));
+        let entries = Arc::new(entries);
+
+        let recorded = binds.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, entries.clone(), recorded.clone()));
+            }
+        });
+
+        Self { url, binds }
+    }
+
+    fn binds(&self) -> Vec<String> {
+        std::mem::take(&mut self.binds.lock().unwrap())
+    }
+}
+
+async fn serve(
+    mut stream: TcpStream,
+    entries: Arc<Vec<DirectoryEntry>>,
+    binds: Arc<Mutex<Vec<String>>>,
+) {
+    let mut buffer = Vec::new();
+    let mut bound_as: Option<String> = None;
+
+    loop {
+        let (consumed, message) = match parse_tag(&buffer) {
+            Ok((rest, message)) => (buffer.len() - rest.len(), message),
+            Err(_) => {
+                let mut chunk = [0u8; 4096];
+                match stream.read(&mut chunk).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(n) => {
+                        buffer.extend_from_slice(&chunk[..n]);
+                        continue;
+                    }
+                }
+            }
+        };
+        buffer.drain(..consumed);
+
+        // LDAPMessage ::= SEQUENCE { messageID, protocolOp, controls OPTIONAL }
+        let parts = children(&message);
+        let message_id = parts[0].clone();
+        let operation = &parts[1];
+        let mut responses = Vec::new();
+
+        match operation.id {
+            // BindRequest ::= [APPLICATION 0] SEQUENCE { version, name, simple [0] }
+            0 => {
+                let fields = children(operation);
+                let name = octets(&fields[1]);
+                let password = octets(&fields[2]);
+                binds.lock().unwrap().push(name.clone());
+
+                let valid = !password.is_empty()
+                    && ((name == SERVICE_DN && password == SERVICE_PASSWORD)
+                        || entries
+                            .iter()
+                            .any(|entry| entry.dn == name && entry.password == password));
+                bound_as = valid.then_some(name);
+                responses.push(ldap_result(1, if valid { 0 } else { 49 }));
+            }
+            // UnbindRequest
+            2 => return,
+            // SearchRequest ::= [APPLICATION 3] SEQUENCE { baseObject, scope, derefAliases,
+            //     sizeLimit, timeLimit, typesOnly, filter, attributes }
+            3 => {
+                if bound_as.as_deref() != Some(SERVICE_DN) {
+                    // insufficientAccessRights
+                    responses.push(ldap_result(5, 50));
+                } else {
+                    let fields = children(operation);
+                    let base = octets(&fields[0]).to_lowercase();
+                    for entry in entries.iter() {
+                        let in_base = entry.dn.to_lowercase().ends_with(&format!(",{}", base));
+                        if in_base && matches(&fields[6], entry) {
+                            // SearchResultEntry ::= [APPLICATION 4] SEQUENCE { objectName,
+                            //     attributes }
+                            responses.push(constructed(
+                                TagClass::Application,
+                                4,
+                                vec![
+                                    octet_string(&entry.dn),
+                                    constructed(TagClass::Universal, 16, vec![]),
+                                ],
+                            ));
+                        }
+                    }
+                    responses.push(ldap_result(5, 0));
+                }
+            }
+            _ => return,
+        }
+
+        for response in responses {
+            let envelope = constructed(TagClass::Universal, 16, vec![message_id.clone(), response]);
+            let mut bytes = BytesMut::new();
+            write::encode_into(&mut bytes, envelope).unwrap();
+            if stream.write_all(&bytes).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+// Filter ::= CHOICE { and [0], or [1], not [2], equalityMatch [3], ..., present [7], ... }
+fn matches(filter: &StructureTag, entry: &DirectoryEntry) -> bool {
+    match filter.id {
+        0 => children(filter).iter().all(|f| matches(f, entry)),
+        1 => children(filter).iter().any(|f| matches(f, entry)),
+        2 => !matches(&children(filter)[0], entry),
+        3 => {
+            let assertion = children(filter);
+            entry.has(&octets(&assertion[0]), &octets(&assertion[1]))
+        }
+        7 => {
+            let attribute = octets(filter);
+            entry
+                .attributes
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case(&attribute))
+        }
+        _ => false,
+    }
+}
+
+fn children(tag: &StructureTag) -> &[StructureTag] {
+    match &tag.payload {
+        PL::C(children) => children,
+        PL::P(_) => &[],
+    }
+}
+
+fn octets(tag: &StructureTag) -> String {
+    match &tag.payload {
+        PL::P(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+        PL::C(_) => String::new(),
+    }
+}
+
+fn octet_string(value: &str) -> StructureTag {
+    StructureTag {
+        class: TagClass::Universal,
+        id: 4,
+        payload: PL::P(value.as_bytes().to_vec()),
+    }
+}
+
+fn constructed(class: TagClass, id: u64, children: Vec<StructureTag>) -> StructureTag {
+    StructureTag {
+        class,
+        id,
+        payload: PL::C(children),
+    }
+}
+
+// LDAPResult ::= SEQUENCE { resultCode ENUMERATED, matchedDN, diagnosticMessage }
+fn ldap_result(operation: u64, code: u8) -> StructureTag {
+    let result_code = StructureTag {
+        class: TagClass::Universal,
+        id: 10,
+        payload: PL::P(vec![code]),
+    };
+    constructed(
+        TagClass::Application,
+        operation,
+        vec![result_code, octet_string(""), octet_string("")],
+    )
+}
+
+async fn app_with_directory(url: &str) -> TestApp {
+    let url = url.to_owned();
+    TestApp::with_settings(|settings| {
+        settings.ldap.url = Some(url);
+        settings.ldap.bind_dn = Some(SERVICE_DN.to_owned());
+        settings.ldap.bind_password = Some(Secret::new(SERVICE_PASSWORD.to_owned()));
+        settings.ldap.user_base_dn = BASE_DN.to_owned();
+        settings.ldap.required_groups = vec![STAFF_GROUP.to_owned()];
+    })
+    .await
+}
+
+#[tokio::test]
+async fn should_log_in_directory_users_with_their_directory_password() {
+    let directory = LdapStandIn::start(vec![DirectoryEntry::person(
+        "jane",
+        "jane@example.com",
+        "directory-password",
+        &[STAFF_GROUP],
+    )])
+    .await;
+    let app = app_with_directory(&directory.url).await;
+
+    let body = serde_json::json!({ "email": "jane@example.com", "password": "directory-password" });
+    let response = app.post_login(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response
+        .cookies()
+        .any(|cookie| cookie.name() == app.auth_cookie_name()));
+
+    // Searched for as the service account, then bound to as herself
+    let binds = directory.binds();
+    assert_eq!(binds.last().unwrap(), &format!("uid=jane,{}", BASE_DN));
+    assert!(binds[..binds.len() - 1].iter().all(|dn| dn == SERVICE_DN));
+
+    let body = serde_json::json!({ "email": "jane@example.com", "password": "wrong-password" });
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 401);
+
+    // She now has a local user, so the address cannot be signed up with
+    let body = serde_json::json!({
+        "email": "jane@example.com",
+        "password": "password123",
+        "requires2FA": false
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 409);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_only_let_members_of_the_required_groups_log_in() {
+    let directory = LdapStandIn::start(vec![
+        DirectoryEntry::person("john", "john@example.com", "directory-password", &[]),
+        DirectoryEntry::person(
+            "eve",
+            "eve@example.com",
+            "directory-password",
+            &["cn=contractors,ou=groups,dc=example,dc=com"],
+        ),
+    ])
+    .await;
+    let app = app_with_directory(&directory.url).await;
+
+    for email in ["john@example.com", "eve@example.com"] {
+        let body = serde_json::json!({ "email": email, "password": "directory-password" });
+        assert_eq!(app.post_login(&body).await.status().as_u16(), 400);
+    }
+    // Never bound to as either of them
+    assert!(directory.binds().iter().all(|dn| dn == SERVICE_DN));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_fall_back_to_local_users() {
+    let directory = LdapStandIn::start(vec![]).await;
+    let app = app_with_directory(&directory.url).await;
+
+    let body = serde_json::json!({
+        "email": "local@example.com",
+        "password": "password123",
+        "requires2FA": false
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 201);
+    let body = serde_json::json!({ "email": "local@example.com", "password": "password123" });
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 200);
+    app.clean_up().await;
+
+    // Local users can still log in while the directory is down
+    let app = app_with_directory(&unreachable_url().await).await;
+    PostgresUserStore::new(app.pg_pool.clone())
+        .add_user(User::new("local@example.com", "password123", false).unwrap())
+        .await
+        .unwrap();
+
+    let body = serde_json::json!({ "email": "local@example.com", "password": "password123" });
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 200);
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_refuse_signups_while_the_directory_is_down() {
+    let app = app_with_directory(&unreachable_url().await).await;
+
+    // The address may belong to a directory user, who would otherwise log in with this password
+    // for as long as the outage lasts
+    let body = serde_json::json!({
+        "email": "jane@example.com",
+        "password": "password123",
+        "requires2FA": false
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 500);
+
+    let email = Email::parse(Secret::new("jane@example.com".to_owned())).unwrap();
+    assert_eq!(
+        PostgresUserStore::new(app.pg_pool.clone())
+            .get_user(&email)
+            .await
+            .unwrap_err(),
+        UserStoreError::UserNotFound
+    );
+
+    app.clean_up().await;
+}
+
+// An address nothing listens on
+async fn unreachable_url() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    format!("ldap://{}", listener.local_addr().unwrap())
+}
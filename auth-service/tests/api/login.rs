@@ -1,7 +1,6 @@
 use auth_service::{
-    domain::{Email, LoginAttemptId},
-    routes::TwoFactorAuthResponse,
-    utils::JWT_COOKIE_NAME,
+    domain::Email,
+    routes::{AuthTokenResponse, TwoFactorAuthResponse},
 };
 use secrecy::Secret;
 use wiremock::{
@@ -131,7 +130,7 @@ async fn should_return_200_if_valid_credentials_and_2fa_disabled() {
 
     let auth_cookie = response
         .cookies()
-        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .find(|cookie| cookie.name() == app.auth_cookie_name())
         .expect("No auth cookie found");
 
     assert!(!auth_cookie.value().is_empty());
@@ -173,13 +172,10 @@ async fn should_return_206_if_valid_credentials_and_2fa_enabled() {
 
     assert_eq!(response.status().as_u16(), 206);
 
-    // Check cookies before consuming the response
-    let auth_cookie = response
+    // No session until the code is verified
+    assert!(!response
         .cookies()
-        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
-        .expect("No auth cookie found");
-
-    assert!(!auth_cookie.value().is_empty());
+        .any(|cookie| cookie.name() == app.auth_cookie_name()));
 
     // Now check the response body and extract login_attempt_id
     let two_fa_response = response
@@ -201,3 +197,43 @@ async fn should_return_206_if_valid_credentials_and_2fa_enabled() {
 
     app.clean_up().await;
 }
+
+#[tokio::test]
+async fn should_return_token_in_body_in_token_mode() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "responseMode": "token"
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(
+        response.cookies().next().is_none(),
+        "Token mode must not set the auth cookie"
+    );
+
+    let body = response
+        .json::<AuthTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to AuthTokenResponse");
+    assert_eq!(body.token_type, "Bearer");
+    assert_eq!(body.expires_in, 600);
+
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": body.access_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await;
+}
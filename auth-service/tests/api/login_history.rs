@@ -0,0 +1,289 @@
+use auth_service::{
+    domain::Email,
+    routes::{AuthTokenResponse, LoginHistoryResponse, TwoFactorAuthResponse},
+    ErrorResponse,
+};
+use secrecy::Secret;
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::{get_random_email, TestApp};
+
+#[tokio::test]
+async fn should_return_400_if_jwt_cookie_missing() {
+    let app = TestApp::new().await;
+
+    let response = app.get_login_history(None, None).await;
+    assert_eq!(response.status(), 400);
+
+    let error_response: ErrorResponse = response
+        .json()
+        .await
+        .expect("Failed to parse error response");
+    assert_eq!(error_response.error, "Missing token");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_record_failed_and_successful_logins() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "requires2FA": false
+    });
+
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let wrong_login_body = serde_json::json!({
+        "email": random_email,
+        "password": "wrongpassword",
+    });
+
+    let response = app.post_login(&wrong_login_body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+    });
+
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.get_login_history(None, None).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let history: LoginHistoryResponse = response
+        .json()
+        .await
+        .expect("Could not deserialize response body to LoginHistoryResponse");
+
+    assert_eq!(history.total, 2);
+    assert_eq!(history.page, 1);
+    assert_eq!(history.entries.len(), 2);
+
+    // Newest entries come first
+    let success = &history.entries[0];
+    assert!(success.success);
+    assert!(!success.used_2fa);
+    assert_eq!(success.failure_reason, None);
+    assert_eq!(success.ip_address.as_deref(), Some("127.0.0.1"));
+
+    let failure = &history.entries[1];
+    assert!(!failure.success);
+    assert_eq!(
+        failure.failure_reason.as_deref(),
+        Some("incorrect_password")
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_paginate_login_history() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "requires2FA": false
+    });
+
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+    });
+
+    for _ in 0..3 {
+        let response = app.post_login(&login_body).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    let response = app.get_login_history(Some(2), Some(2)).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let history: LoginHistoryResponse = response
+        .json()
+        .await
+        .expect("Could not deserialize response body to LoginHistoryResponse");
+
+    assert_eq!(history.total, 3);
+    assert_eq!(history.page, 2);
+    assert_eq!(history.per_page, 2);
+    assert_eq!(history.entries.len(), 1);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_record_2fa_logins() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "requires2FA": true
+    });
+
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+    });
+
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let two_fa_response = response
+        .json::<TwoFactorAuthResponse>()
+        .await
+        .expect("Could not deserialize response body to TwoFactorAuthResponse");
+
+    let email = Email::parse(Secret::new(random_email.clone())).unwrap();
+    let (_, two_fa_code, _) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&email)
+        .await
+        .expect("Could not get 2FA code from store");
+
+    let verify_body = serde_json::json!({
+        "email": random_email,
+        "loginAttemptId": two_fa_response.login_attempt_id,
+        "2FACode": two_fa_code.as_ref(),
+    });
+
+    let response = app.post_verify_2fa(&verify_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.get_login_history(None, None).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let history: LoginHistoryResponse = response
+        .json()
+        .await
+        .expect("Could not deserialize response body to LoginHistoryResponse");
+
+    // Only the completed 2FA login is recorded, not the pending first step
+    assert_eq!(history.total, 1);
+    assert!(history.entries[0].success);
+    assert!(history.entries[0].used_2fa);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_pagination() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "requires2FA": false
+    });
+
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+    });
+
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    for (page, per_page) in [(Some(0), None), (None, Some(0)), (None, Some(1000))] {
+        let response = app.get_login_history(page, per_page).await;
+        assert_eq!(response.status().as_u16(), 400);
+
+        let error_response: ErrorResponse = response
+            .json()
+            .await
+            .expect("Failed to parse error response");
+        assert_eq!(error_response.error, "Invalid pagination");
+    }
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_accept_a_bearer_token() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "responseMode": "token"
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let token = response
+        .json::<AuthTokenResponse>()
+        .await
+        .unwrap()
+        .access_token;
+
+    let response = app
+        .http_client
+        .get(format!("{}/account/login-history", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let history: LoginHistoryResponse = response.json().await.unwrap();
+    assert_eq!(history.total, 1);
+
+    // Once the token is banned the guard rejects it
+    let response = app.post_logout_with_bearer(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .http_client
+        .get(format!("{}/account/login-history", &app.address))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await;
+}
@@ -1,4 +1,6 @@
-use auth_service::{utils::constants::JWT_COOKIE_NAME, ErrorResponse};
+use auth_service::{routes::AuthTokenResponse, ErrorResponse};
+use std::time::Duration;
+
 use reqwest::Url;
 use secrecy::Secret;
 
@@ -32,7 +34,7 @@ async fn should_return_401_if_invalid_token() {
     app.cookie_jar.add_cookie_str(
         &format!(
             "{}=invalid; HttpOnly; SameSite=Lax; Secure; Path=/",
-            JWT_COOKIE_NAME
+            app.auth_cookie_name()
         ),
         &Url::parse("http://127.0.0.1").expect("Failed to parse URL"),
     );
@@ -81,7 +83,7 @@ async fn should_return_200_if_valid_jwt_cookie() {
     // Verify we got a valid auth cookie
     let auth_cookie = response
         .cookies()
-        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .find(|cookie| cookie.name() == app.auth_cookie_name())
         .expect("No auth cookie found");
     assert!(!auth_cookie.value().is_empty());
 
@@ -101,6 +103,53 @@ async fn should_return_200_if_valid_jwt_cookie() {
     app.clean_up().await;
 }
 
+#[tokio::test]
+async fn should_clear_the_cookie_with_the_attributes_it_was_set_with() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == app.auth_cookie_name())
+        .expect("No auth cookie found");
+    // The cookie lives exactly as long as the token inside it
+    assert_eq!(auth_cookie.max_age(), Some(Duration::from_secs(600)));
+    assert!(auth_cookie.secure());
+    assert!(auth_cookie.http_only());
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let removal_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == app.auth_cookie_name())
+        .expect("Logout did not clear the auth cookie");
+    assert!(removal_cookie.value().is_empty());
+    assert_eq!(removal_cookie.max_age(), Some(Duration::ZERO));
+    assert_eq!(removal_cookie.path(), auth_cookie.path());
+    assert_eq!(removal_cookie.domain(), auth_cookie.domain());
+    assert_eq!(removal_cookie.secure(), auth_cookie.secure());
+    assert_eq!(removal_cookie.http_only(), auth_cookie.http_only());
+    assert_eq!(removal_cookie.same_site_lax(), auth_cookie.same_site_lax());
+
+    app.clean_up().await;
+}
+
 #[tokio::test]
 async fn should_return_400_if_logout_called_twice_in_a_row() {
     let app = TestApp::new().await;
@@ -135,7 +184,7 @@ async fn should_return_400_if_logout_called_twice_in_a_row() {
     assert_eq!(response.status(), 400);
 
     // Parse and verify error response
-    let error_response: ErrorResponse = response
+    let _error_response: ErrorResponse = response
         .json()
         .await
         .expect("Failed to parse error response");
@@ -143,3 +192,62 @@ async fn should_return_400_if_logout_called_twice_in_a_row() {
 
     app.clean_up().await;
 }
+
+#[tokio::test]
+async fn should_logout_with_a_bearer_token() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "responseMode": "token"
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let token = response
+        .json::<AuthTokenResponse>()
+        .await
+        .unwrap()
+        .access_token;
+
+    let response = app.post_logout_with_bearer(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    // Bearer clients have no cookie to clear
+    assert!(response.cookies().next().is_none());
+
+    assert!(app
+        .banned_token_store
+        .read()
+        .await
+        .is_token_banned(&Secret::new(token.clone()))
+        .await
+        .unwrap());
+
+    // The banned token can no longer be used
+    let response = app.post_logout_with_bearer(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_401_if_bearer_token_is_invalid() {
+    let app = TestApp::new().await;
+
+    let response = app.post_logout_with_bearer("invalid").await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = app.post_logout_with_bearer("").await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await;
+}
@@ -0,0 +1,241 @@
+use std::time::{Duration, Instant};
+
+use auth_service::{
+    domain::Email,
+    routes::{MagicLinkResponse, TwoFactorAuthResponse},
+    utils::Claims,
+    ErrorResponse,
+};
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use reqwest::Url;
+use secrecy::{ExposeSecret, Secret};
+use serde_json::Value;
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn signup(app: &TestApp, requires_2fa: bool) -> String {
+    let email = get_random_email();
+    let body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": requires_2fa
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 201);
+    email
+}
+
+async fn accept_emails(app: &TestApp) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+}
+
+async fn request_link(app: &TestApp, email: &str) -> reqwest::Response {
+    app.post_magic_link(&serde_json::json!({ "email": email }))
+        .await
+}
+
+// The signed token and address from the login link in the only email sent so far. Links are
+// emailed after the response, so this waits for the email to arrive.
+async fn sent_link(app: &TestApp) -> (String, String) {
+    let mut requests = Vec::new();
+    for _ in 0..500 {
+        requests = app
+            .email_server
+            .received_requests()
+            .await
+            .expect("Request recording is disabled");
+        if !requests.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(requests.len(), 1);
+
+    let body: Value = serde_json::from_slice(&requests[0].body).expect("Invalid email request");
+    let prefix = format!("{}/#", app.settings.application.public_url);
+    let link = body["TextBody"]
+        .as_str()
+        .expect("Email has no text body")
+        .split_whitespace()
+        .find(|word| word.starts_with(&prefix))
+        .expect("Email has no login link");
+
+    let link = Url::parse(link).unwrap();
+    let fragment = link.fragment().expect("Login link has no fragment");
+    let param = |name: &str| {
+        form_urlencoded::parse(fragment.as_bytes())
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+            .unwrap()
+    };
+    (param("magic_link"), param("email"))
+}
+
+#[tokio::test]
+async fn should_log_in_once_with_an_emailed_link() {
+    let app = TestApp::new().await;
+    let email = signup(&app, false).await;
+    accept_emails(&app).await;
+
+    let response = request_link(&app, &email).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let (token, linked_email) = sent_link(&app).await;
+    assert_eq!(linked_email, email);
+
+    let response = app.post_magic_link_callback(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == app.auth_cookie_name())
+        .expect("No auth cookie found");
+    assert!(!auth_cookie.value().is_empty());
+    assert_eq!(app.get_account_export(None).await.status().as_u16(), 200);
+
+    // The link is spent
+    let response = app.post_magic_link_callback(&token).await;
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        response.json::<ErrorResponse>().await.unwrap().error,
+        "Invalid magic link"
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_ask_users_with_2fa_for_their_code() {
+    let app = TestApp::new().await;
+    let email = signup(&app, true).await;
+    accept_emails(&app).await;
+
+    assert_eq!(request_link(&app, &email).await.status().as_u16(), 200);
+    let (token, _) = sent_link(&app).await;
+
+    let response = app.post_magic_link_callback(&token).await;
+    assert_eq!(response.status().as_u16(), 206);
+    assert!(!response
+        .cookies()
+        .any(|cookie| cookie.name() == app.auth_cookie_name()));
+    let two_fa_response = response.json::<TwoFactorAuthResponse>().await.unwrap();
+
+    let (_, code, _) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&Email::parse(Secret::new(email.clone())).unwrap())
+        .await
+        .unwrap();
+    let body = serde_json::json!({
+        "email": email,
+        "loginAttemptId": two_fa_response.login_attempt_id,
+        "2FACode": code.as_ref(),
+    });
+    let response = app.post_verify_2fa(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let auth_cookie = response
+        .cookies()
+        .find(|cookie| cookie.name() == app.auth_cookie_name())
+        .expect("No auth cookie found");
+
+    // The session was opened with the link and the code, no password
+    let claims = decode::<Claims>(
+        auth_cookie.value(),
+        &DecodingKey::from_secret(app.settings.jwt.secret.expose_secret().as_bytes()),
+        &Validation::default(),
+    )
+    .unwrap()
+    .claims;
+    assert_eq!(claims.amr, ["otp", "mfa"]);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_answer_the_same_for_unknown_addresses() {
+    let app = TestApp::new().await;
+    let email = signup(&app, false).await;
+    accept_emails(&app).await;
+
+    let known = request_link(&app, &email).await;
+    assert_eq!(known.status().as_u16(), 200);
+    let unknown = request_link(&app, &get_random_email()).await;
+    assert_eq!(unknown.status().as_u16(), 200);
+    assert_eq!(
+        known.json::<MagicLinkResponse>().await.unwrap().message,
+        unknown.json::<MagicLinkResponse>().await.unwrap().message
+    );
+    // Only the user was emailed
+    sent_link(&app).await;
+
+    assert_eq!(
+        request_link(&app, "not-an-email").await.status().as_u16(),
+        400
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_not_wait_for_the_email_provider() {
+    let app = TestApp::new().await;
+    let email = signup(&app, false).await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(2)))
+        .mount(&app.email_server)
+        .await;
+
+    // A slow provider would otherwise tell addresses with accounts apart from the rest
+    let started = Instant::now();
+    assert_eq!(request_link(&app, &email).await.status().as_u16(), 200);
+    assert!(started.elapsed() < Duration::from_secs(1));
+    sent_link(&app).await;
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_limit_how_many_links_are_sent() {
+    let app = TestApp::new().await;
+    let email = signup(&app, false).await;
+    accept_emails(&app).await;
+
+    for _ in 0..3 {
+        assert_eq!(request_link(&app, &email).await.status().as_u16(), 200);
+    }
+    assert_eq!(request_link(&app, &email).await.status().as_u16(), 429);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_links_we_did_not_sign() {
+    let app = TestApp::new().await;
+    let email = signup(&app, false).await;
+    accept_emails(&app).await;
+
+    assert_eq!(request_link(&app, &email).await.status().as_u16(), 200);
+    let (token, _) = sent_link(&app).await;
+    let (id, signature) = token.split_once('.').unwrap();
+
+    let tampered = format!("{}.{}", id, "0".repeat(signature.len()));
+    for token in [id, tampered.as_str(), "", "not-a-token"] {
+        let response = app.post_magic_link_callback(token).await;
+        assert_eq!(response.status().as_u16(), 400);
+    }
+
+    // Failed attempts do not spend the real link
+    assert_eq!(
+        app.post_magic_link_callback(&token).await.status().as_u16(),
+        200
+    );
+
+    app.clean_up().await;
+}
@@ -1,7 +1,28 @@
+mod account_export;
+mod audit_log;
+mod cors;
+mod csrf;
+mod device;
+mod external_login;
+mod health;
 mod helpers;
+mod introspect;
+mod ldap;
 mod login;
+mod login_history;
 mod logout;
+mod magic_link;
+mod metrics;
+mod oauth;
+mod oidc;
+mod report_login;
+mod revoke;
 mod root;
+mod service_clients;
+mod shutdown;
 mod signup;
+mod tls;
+mod token_exchange;
 mod verify_2fa;
 mod verify_token;
+mod webhooks;
@@ -0,0 +1,149 @@
+use auth_service::routes::TwoFactorAuthResponse;
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::{get_random_email, TestApp};
+
+// Metrics are process-wide and tests run concurrently, so only lower bounds can be asserted
+fn metric_value(body: &str, series: &str) -> f64 {
+    body.lines()
+        .find_map(|line| line.strip_prefix(series)?.trim().parse().ok())
+        .unwrap_or_else(|| panic!("Metric {} not found in:\n{}", series, body))
+}
+
+#[tokio::test]
+async fn should_expose_prometheus_metrics() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.post_logout().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app.get_metrics().await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain"));
+
+    let body = response.text().await.unwrap();
+
+    assert!(
+        metric_value(
+            &body,
+            r#"auth_http_requests_total{method="POST",route="/login",status="200"}"#
+        ) >= 1.0
+    );
+    assert!(
+        metric_value(
+            &body,
+            r#"auth_http_request_duration_seconds_count{method="POST",route="/signup",status="201"}"#
+        ) >= 1.0
+    );
+    assert!(metric_value(&body, r#"auth_login_attempts_total{outcome="success"}"#) >= 1.0);
+    assert!(metric_value(&body, r#"auth_tokens_banned_total{reason="logout"}"#) >= 1.0);
+    assert!(
+        metric_value(
+            &body,
+            r#"auth_password_hash_duration_seconds_count{operation="hash"}"#
+        ) >= 1.0
+    );
+    assert!(
+        metric_value(
+            &body,
+            r#"auth_password_hash_duration_seconds_count{operation="verify"}"#
+        ) >= 1.0
+    );
+    assert!(metric_value(&body, "auth_postgres_pool_max_connections") >= 1.0);
+    metric_value(&body, r#"auth_postgres_pool_connections{state="idle"}"#);
+    metric_value(&body, "auth_redis_connection_busy");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_count_2fa_codes_and_login_failures() {
+    let app = TestApp::new().await;
+
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+        "requires2FA": true
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let wrong_login_body = serde_json::json!({
+        "email": random_email,
+        "password": "wrongpassword",
+    });
+    let response = app.post_login(&wrong_login_body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "password123",
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+
+    let login_attempt_id = response
+        .json::<TwoFactorAuthResponse>()
+        .await
+        .expect("Could not deserialize response body to TwoFactorAuthResponse")
+        .login_attempt_id;
+
+    let verify_body = serde_json::json!({
+        "email": random_email,
+        "loginAttemptId": login_attempt_id,
+        "2FACode": "123456",
+    });
+    let response = app.post_verify_2fa(&verify_body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let body = app.get_metrics().await.text().await.unwrap();
+
+    assert!(
+        metric_value(
+            &body,
+            r#"auth_login_attempts_total{outcome="incorrect_password"}"#
+        ) >= 1.0
+    );
+    assert!(
+        metric_value(
+            &body,
+            r#"auth_login_attempts_total{outcome="two_fa_required"}"#
+        ) >= 1.0
+    );
+    assert!(metric_value(&body, r#"auth_two_fa_codes_total{result="sent"}"#) >= 1.0);
+    assert!(metric_value(&body, r#"auth_two_fa_codes_total{result="failed"}"#) >= 1.0);
+
+    app.clean_up().await;
+}
@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+
+use auth_service::{
+    domain::{Email, OAuthErrorCode},
+    routes::{AccessTokenResponse, OAuthClientResponse},
+    utils::{generate_auth_token, AuthenticationMethod, Claims},
+    ErrorResponse, OAuthErrorResponse,
+};
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use reqwest::{header, Url};
+use secrecy::{ExposeSecret, Secret};
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::{get_random_email, TestApp};
+
+const REDIRECT_URI: &str = "https://client.example.com/callback";
+// The example pair from RFC 7636 appendix B
+const CODE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+const CODE_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+async fn register_client(app: &TestApp) -> OAuthClientResponse {
+    let response = app
+        .post_oauth_client(&serde_json::json!({
+            "name": "Example App",
+            "redirectUris": [REDIRECT_URI],
+            "scopes": ["profile", "email"]
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    response.json().await.unwrap()
+}
+
+async fn log_in(app: &TestApp) -> String {
+    let email = get_random_email();
+    let body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 201);
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 200);
+    email
+}
+
+fn authorization_request(client_id: &str) -> Vec<(&'static str, &str)> {
+    vec![
+        ("response_type", "code"),
+        ("client_id", client_id),
+        ("redirect_uri", REDIRECT_URI),
+        ("scope", "profile"),
+        ("state", "xyz"),
+        ("code_challenge", CODE_CHALLENGE),
+        ("code_challenge_method", "S256"),
+    ]
+}
+
+async fn consent(app: &TestApp, client_id: &str, decision: &str) -> reqwest::Response {
+    let csrf_token = app.csrf_token().expect("No CSRF cookie found");
+    let mut form = authorization_request(client_id);
+    form.push(("csrf_token", &csrf_token));
+    form.push(("decision", decision));
+    app.post_authorize(&form).await
+}
+
+// The query parameters of a redirect back to the client
+fn redirect_params(response: &reqwest::Response) -> HashMap<String, String> {
+    assert_eq!(response.status().as_u16(), 303);
+    let location = Url::parse(
+        response.headers()[header::LOCATION]
+            .to_str()
+            .expect("Invalid location header"),
+    )
+    .expect("Redirect location is not absolute");
+    assert!(location.as_str().starts_with(REDIRECT_URI));
+    location.query_pairs().into_owned().collect()
+}
+
+async fn authorization_code(app: &TestApp, client_id: &str) -> String {
+    let response = consent(app, client_id, "allow").await;
+    let params = redirect_params(&response);
+    assert_eq!(params["state"], "xyz");
+    params["code"].clone()
+}
+
+async fn redeem(app: &TestApp, client_id: &str, code: &str, verifier: &str) -> reqwest::Response {
+    app.post_token(&[
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", REDIRECT_URI),
+        ("client_id", client_id),
+        ("code_verifier", verifier),
+    ])
+    .await
+}
+
+#[tokio::test]
+async fn should_issue_a_scoped_token_for_a_code_with_pkce() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+    let email = log_in(&app).await;
+
+    let response = app
+        .get_authorize(&authorization_request(&client.client_id))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()[header::X_FRAME_OPTIONS], "DENY");
+    let page = response.text().await.unwrap();
+    assert!(page.contains("Example App"));
+    assert!(page.contains("<code>profile</code>"));
+
+    let code = authorization_code(&app, &client.client_id).await;
+    let response = redeem(&app, &client.client_id, &code, CODE_VERIFIER).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+
+    let token: AccessTokenResponse = response.json().await.unwrap();
+    assert_eq!(token.token_type, "Bearer");
+    assert_eq!(token.scope, "profile");
+    let claims = decode::<Claims>(
+        &token.access_token,
+        &DecodingKey::from_secret(app.settings.jwt.secret.expose_secret().as_bytes()),
+        &Validation::default(),
+    )
+    .unwrap()
+    .claims;
+    assert_eq!(claims.sub, email);
+    assert_eq!(claims.client_id.as_deref(), Some(client.client_id.as_str()));
+    assert_eq!(claims.scope.as_deref(), Some("profile"));
+
+    // Codes are single use
+    let response = redeem(&app, &client.client_id, &code, CODE_VERIFIER).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let error: OAuthErrorResponse = response.json().await.unwrap();
+    assert_eq!(error.error, OAuthErrorCode::InvalidGrant);
+
+    // A client's token is not a first-party session
+    let response = app
+        .http_client
+        .get(format!("{}/account/login-history", &app.address))
+        .bearer_auth(&token.access_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": token.access_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_a_code_redeemed_with_the_wrong_verifier_or_redirect_uri() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+    log_in(&app).await;
+
+    let code = authorization_code(&app, &client.client_id).await;
+    let response = redeem(&app, &client.client_id, &code, &"a".repeat(43)).await;
+    assert_eq!(response.status().as_u16(), 400);
+    // The failed attempt used the code up
+    let response = redeem(&app, &client.client_id, &code, CODE_VERIFIER).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let code = authorization_code(&app, &client.client_id).await;
+    let response = app
+        .post_token(&[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("redirect_uri", "https://client.example.com/other"),
+            ("client_id", &client.client_id),
+            ("code_verifier", CODE_VERIFIER),
+        ])
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app.post_token(&[("grant_type", "password")]).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let error: OAuthErrorResponse = response.json().await.unwrap();
+    assert_eq!(error.error, OAuthErrorCode::UnsupportedGrantType);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_send_users_who_are_not_logged_in_to_the_login_page() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+
+    let response = app
+        .get_authorize(&authorization_request(&client.client_id))
+        .await;
+
+    assert_eq!(response.status().as_u16(), 303);
+    let location = response.headers()[header::LOCATION].to_str().unwrap();
+    assert!(location.starts_with("/?return_to=%2Fauthorize%3Fresponse_type%3Dcode"));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_refuse_users_who_have_not_entered_their_2fa_code() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    let email = get_random_email();
+    let body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": true
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 201);
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 206);
+
+    let request = authorization_request(&client.client_id);
+    let response = app.get_authorize(&request).await;
+    assert_eq!(response.status().as_u16(), 303);
+    let location = response.headers()[header::LOCATION].to_str().unwrap();
+    assert!(location.starts_with("/?return_to="));
+
+    // Nor does a session from before 2FA was turned on count
+    let token = generate_auth_token(
+        &Email::parse(Secret::new(email)).unwrap(),
+        AuthenticationMethod::Password,
+        &app.settings.jwt,
+    )
+    .unwrap();
+    let response = app
+        .no_redirect_client
+        .get(format!("{}/authorize", &app.address))
+        .query(&request)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 303);
+    let location = response.headers()[header::LOCATION].to_str().unwrap();
+    assert!(location.starts_with("/?return_to="));
+
+    // Bearer requests need no CSRF token
+    let mut form = request.clone();
+    form.push(("csrf_token", ""));
+    form.push(("decision", "allow"));
+    let response = app
+        .no_redirect_client
+        .post(format!("{}/authorize", &app.address))
+        .form(&form)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 403);
+    assert_eq!(
+        response.json::<ErrorResponse>().await.unwrap().error,
+        "2FA required"
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_not_redirect_to_unregistered_clients_or_redirect_uris() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+    log_in(&app).await;
+
+    let mut request = authorization_request(&client.client_id);
+    request[2] = ("redirect_uri", "https://attacker.example.com/callback");
+    let response = app.get_authorize(&request).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let error: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(error.error, "Invalid redirect URI");
+
+    let response = app
+        .get_authorize(&authorization_request("unknown-client"))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+    let error: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(error.error, "Invalid OAuth client");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_redirect_errors_back_to_the_client() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+    log_in(&app).await;
+
+    let test_cases = [
+        // PKCE is required, and only with S256
+        (6, ("code_challenge_method", "plain"), "invalid_request"),
+        (3, ("scope", "profile admin"), "invalid_scope"),
+        (0, ("response_type", "token"), "unsupported_response_type"),
+    ];
+    for (index, replacement, expected_error) in test_cases {
+        let mut request = authorization_request(&client.client_id);
+        request[index] = replacement;
+        let response = app.get_authorize(&request).await;
+        let params = redirect_params(&response);
+        assert_eq!(params["error"], expected_error);
+        assert_eq!(params["state"], "xyz");
+    }
+
+    let response = consent(&app, &client.client_id, "deny").await;
+    assert_eq!(redirect_params(&response)["error"], "access_denied");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_require_the_csrf_token_to_approve() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+    log_in(&app).await;
+
+    let mut form = authorization_request(&client.client_id);
+    form.push(("csrf_token", "forged"));
+    form.push(("decision", "allow"));
+    let response = app.post_authorize(&form).await;
+
+    assert_eq!(response.status().as_u16(), 403);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_manage_clients_with_the_admin_token() {
+    let app = TestApp::new().await;
+    let body = serde_json::json!({
+        "name": "Example App",
+        "redirectUris": [REDIRECT_URI],
+        "scopes": ["profile"]
+    });
+
+    let response = app.post_oauth_client_with_token(&body, None).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let response = app
+        .post_oauth_client_with_token(&body, Some("wrong-token"))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let invalid_bodies = [
+        serde_json::json!({ "name": "", "redirectUris": [REDIRECT_URI], "scopes": ["profile"] }),
+        serde_json::json!({ "name": "App", "redirectUris": [], "scopes": ["profile"] }),
+        serde_json::json!({ "name": "App", "redirectUris": ["/callback"], "scopes": ["profile"] }),
+        serde_json::json!({ "name": "App", "redirectUris": [REDIRECT_URI], "scopes": ["a b"] }),
+    ];
+    for invalid_body in invalid_bodies {
+        let response = app.post_oauth_client(&invalid_body).await;
+        assert_eq!(response.status().as_u16(), 400);
+    }
+
+    let client = register_client(&app).await;
+    let clients: Vec<OAuthClientResponse> = app.get_oauth_clients().await.json().await.unwrap();
+    assert_eq!(clients.len(), 1);
+    assert_eq!(clients[0].client_id, client.client_id);
+
+    let response = app.delete_oauth_client(&client.client_id).await;
+    assert_eq!(response.status().as_u16(), 204);
+    let response = app.delete_oauth_client(&client.client_id).await;
+    assert_eq!(response.status().as_u16(), 404);
+
+    app.clean_up().await;
+}
@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+
+use auth_service::{
+    domain::Email,
+    routes::{
+        AccessTokenResponse, AuthTokenResponse, OAuthClientResponse, OpenIdConfiguration,
+        UserInfoResponse,
+    },
+    utils::IdTokenClaims,
+    ErrorResponse,
+};
+use chrono::Utc;
+use jsonwebtoken::{decode, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use reqwest::{header, Url};
+use secrecy::Secret;
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::{get_random_email, TestApp};
+
+const REDIRECT_URI: &str = "https://client.example.com/callback";
+// The example pair from RFC 7636 appendix B
+const CODE_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+const CODE_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+async fn register_client(app: &TestApp) -> OAuthClientResponse {
+    let response = app
+        .post_oauth_client(&serde_json::json!({
+            "name": "Example App",
+            "redirectUris": [REDIRECT_URI],
+            "scopes": ["openid", "email", "profile"]
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    response.json().await.unwrap()
+}
+
+async fn sign_up(app: &TestApp, requires_2fa: bool) -> String {
+    let email = get_random_email();
+    let body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": requires_2fa
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 201);
+    email
+}
+
+async fn log_in(app: &TestApp, email: &str) {
+    let body = serde_json::json!({ "email": email, "password": "password123" });
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 200);
+}
+
+fn authorization_request<'a>(
+    client_id: &'a str,
+    scope: &'a str,
+    extra: &[(&'static str, &'a str)],
+) -> Vec<(&'static str, &'a str)> {
+    let mut request = vec![
+        ("response_type", "code"),
+        ("client_id", client_id),
+        ("redirect_uri", REDIRECT_URI),
+        ("scope", scope),
+        ("state", "xyz"),
+        ("code_challenge", CODE_CHALLENGE),
+        ("code_challenge_method", "S256"),
+    ];
+    request.extend_from_slice(extra);
+    request
+}
+
+// The query parameters of a redirect back to the client
+fn redirect_params(response: &reqwest::Response) -> HashMap<String, String> {
+    assert_eq!(response.status().as_u16(), 303);
+    let location = Url::parse(response.headers()[header::LOCATION].to_str().unwrap())
+        .expect("Redirect location is not absolute");
+    assert!(location.as_str().starts_with(REDIRECT_URI));
+    location.query_pairs().into_owned().collect()
+}
+
+// Approves the request on the consent page and redeems the code
+async fn authorize(app: &TestApp, request: &[(&str, &str)]) -> AccessTokenResponse {
+    let csrf_token = app.csrf_token().expect("No CSRF cookie found");
+    let mut form = request.to_vec();
+    form.push(("csrf_token", &csrf_token));
+    form.push(("decision", "allow"));
+    let code = redirect_params(&app.post_authorize(&form).await)["code"].clone();
+    let client_id = request
+        .iter()
+        .find(|(name, _)| *name == "client_id")
+        .unwrap()
+        .1;
+
+    let response = app
+        .post_token(&[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("redirect_uri", REDIRECT_URI),
+            ("client_id", client_id),
+            ("code_verifier", CODE_VERIFIER),
+        ])
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    response.json().await.unwrap()
+}
+
+async fn verify_id_token(app: &TestApp, id_token: &str, client_id: &str) -> IdTokenClaims {
+    let jwks: JwkSet = app.get_jwks().await.json().await.unwrap();
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.set_audience(&[client_id]);
+    validation.set_issuer(&[&app.settings.application.public_url]);
+
+    decode::<IdTokenClaims>(
+        id_token,
+        &DecodingKey::from_jwk(&jwks.keys[0]).unwrap(),
+        &validation,
+    )
+    .expect("ID token does not verify against the published key")
+    .claims
+}
+
+#[tokio::test]
+async fn should_publish_the_provider_configuration() {
+    let app = TestApp::new().await;
+
+    let response = app.get_openid_configuration().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let configuration: OpenIdConfiguration = response.json().await.unwrap();
+    let issuer = &app.settings.application.public_url;
+    assert_eq!(&configuration.issuer, issuer);
+    assert_eq!(
+        configuration.authorization_endpoint,
+        format!("{}/authorize", issuer)
+    );
+    assert_eq!(configuration.token_endpoint, format!("{}/token", issuer));
+    assert_eq!(
+        configuration.userinfo_endpoint,
+        format!("{}/userinfo", issuer)
+    );
+    assert_eq!(
+        configuration.jwks_uri,
+        format!("{}/.well-known/jwks.json", issuer)
+    );
+    assert_eq!(
+        configuration.id_token_signing_alg_values_supported,
+        ["RS256"]
+    );
+
+    let jwks: JwkSet = app.get_jwks().await.json().await.unwrap();
+    assert_eq!(jwks.keys.len(), 1);
+    assert!(jwks.keys[0].common.key_id.is_some());
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_refuse_openid_without_a_signing_key() {
+    let app = TestApp::with_settings(|settings| settings.oauth.id_token_signing_key = None).await;
+    let client = register_client(&app).await;
+    let email = sign_up(&app, false).await;
+    log_in(&app, &email).await;
+
+    let response = app.get_openid_configuration().await;
+    assert_eq!(response.status().as_u16(), 404);
+    assert_eq!(app.get_jwks().await.status().as_u16(), 404);
+
+    let response = app
+        .get_authorize(&authorization_request(&client.client_id, "openid", &[]))
+        .await;
+    assert_eq!(redirect_params(&response)["error"], "invalid_scope");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_issue_an_id_token_for_the_openid_scope() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+    let email = sign_up(&app, false).await;
+    log_in(&app, &email).await;
+
+    let request = authorization_request(
+        &client.client_id,
+        "openid email",
+        &[("nonce", "n-0S6_WzA2Mj")],
+    );
+    let token = authorize(&app, &request).await;
+
+    let id_token = token.id_token.expect("No ID token issued");
+    let claims = verify_id_token(&app, &id_token, &client.client_id).await;
+    assert_eq!(claims.sub, email);
+    assert_eq!(claims.nonce.as_deref(), Some("n-0S6_WzA2Mj"));
+    assert_eq!(claims.amr, ["pwd"]);
+    let auth_time = claims.auth_time.expect("No auth_time in the ID token");
+    assert!(auth_time.abs_diff(Utc::now().timestamp() as usize) <= 60);
+
+    let response = app.get_userinfo(&token.access_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let userinfo: UserInfoResponse = response.json().await.unwrap();
+    assert_eq!(userinfo.sub, email);
+    assert_eq!(userinfo.email.as_deref(), Some(email.as_str()));
+
+    // Without the openid scope there is no ID token, and userinfo is off limits
+    let token = authorize(
+        &app,
+        &authorization_request(&client.client_id, "profile", &[]),
+    )
+    .await;
+    assert!(token.id_token.is_none());
+    let response = app.get_userinfo(&token.access_token).await;
+    assert_eq!(response.status().as_u16(), 403);
+    assert_eq!(
+        response.headers()[header::WWW_AUTHENTICATE],
+        r#"Bearer error="insufficient_scope""#
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_record_two_factor_logins_in_amr() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+    let email = sign_up(&app, true).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+    let body = serde_json::json!({ "email": email, "password": "password123" });
+    assert_eq!(app.post_login(&body).await.status().as_u16(), 206);
+    let (login_attempt_id, two_fa_code, _) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&Email::parse(Secret::new(email.clone())).unwrap())
+        .await
+        .unwrap();
+    let response = app
+        .post_verify_2fa(&serde_json::json!({
+            "email": email,
+            "loginAttemptId": login_attempt_id.as_ref(),
+            "2FACode": two_fa_code.as_ref(),
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let token = authorize(
+        &app,
+        &authorization_request(&client.client_id, "openid", &[]),
+    )
+    .await;
+    let claims = verify_id_token(&app, &token.id_token.unwrap(), &client.client_id).await;
+    assert_eq!(claims.amr, ["pwd", "otp", "mfa"]);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_send_users_to_log_in_again_for_prompt_and_max_age() {
+    let app = TestApp::new().await;
+    let client = register_client(&app).await;
+    let email = sign_up(&app, false).await;
+
+    // prompt=none never shows a page
+    let request = authorization_request(&client.client_id, "openid", &[("prompt", "none")]);
+    let response = app.get_authorize(&request).await;
+    assert_eq!(redirect_params(&response)["error"], "login_required");
+
+    log_in(&app, &email).await;
+    let response = app.get_authorize(&request).await;
+    assert_eq!(redirect_params(&response)["error"], "consent_required");
+
+    for extra in [("prompt", "login"), ("max_age", "0")] {
+        let response = app
+            .get_authorize(&authorization_request(
+                &client.client_id,
+                "openid",
+                &[extra],
+            ))
+            .await;
+        assert_eq!(response.status().as_u16(), 303);
+        let location = response.headers()[header::LOCATION].to_str().unwrap();
+        assert!(location.starts_with("/?return_to=%2Fauthorize%3F"));
+        // The way back does not ask for another login
+        let return_to: HashMap<String, String> = form_urlencoded::parse(&location.as_bytes()[2..])
+            .into_owned()
+            .collect();
+        assert!(!return_to["return_to"].contains(extra.0));
+    }
+
+    // A recent enough login is accepted
+    let response = app
+        .get_authorize(&authorization_request(
+            &client.client_id,
+            "openid",
+            &[("max_age", "3600")],
+        ))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .get_authorize(&authorization_request(
+            &client.client_id,
+            "openid",
+            &[("prompt", "none login")],
+        ))
+        .await;
+    assert_eq!(redirect_params(&response)["error"], "invalid_request");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_only_accept_access_tokens_at_userinfo() {
+    let app = TestApp::new().await;
+    let email = sign_up(&app, false).await;
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": email,
+            "password": "password123",
+            "responseMode": "token"
+        }))
+        .await;
+    let session_token = response
+        .json::<AuthTokenResponse>()
+        .await
+        .unwrap()
+        .access_token;
+
+    for token in [session_token.as_str(), "not-a-token"] {
+        let response = app.get_userinfo(token).await;
+        assert_eq!(response.status().as_u16(), 401);
+        assert_eq!(
+            response.headers()[header::WWW_AUTHENTICATE],
+            r#"Bearer error="invalid_token""#
+        );
+        let error: ErrorResponse = response.json().await.unwrap();
+        assert_eq!(error.error, "Invalid token");
+    }
+
+    app.clean_up().await;
+}
@@ -0,0 +1,225 @@
+use auth_service::{utils::constants::test, ErrorResponse};
+use serde_json::Value;
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::{get_random_email, TestApp};
+
+const PASSWORD: &str = "pasword123";
+
+async fn signup(app: &TestApp) -> String {
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": PASSWORD,
+        "requires2FA": false
+    });
+
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    random_email
+}
+
+fn login_body(email: &str, password: &str) -> Value {
+    serde_json::json!({
+        "email": email,
+        "password": password,
+    })
+}
+
+// Pulls the body of the only email the mock Postmark server received
+async fn sent_email_body(app: &TestApp) -> String {
+    let requests = app
+        .email_server
+        .received_requests()
+        .await
+        .expect("Request recording is disabled");
+    assert_eq!(requests.len(), 1);
+
+    let body: Value = serde_json::from_slice(&requests[0].body).expect("Invalid email request");
+    body["TextBody"]
+        .as_str()
+        .expect("Email has no text body")
+        .to_owned()
+}
+
+fn report_token(email_body: &str) -> String {
+    email_body
+        .split("token=")
+        .nth(1)
+        .expect("Email has no report link")
+        .trim()
+        .to_owned()
+}
+
+#[tokio::test]
+async fn should_not_email_on_first_login_or_known_device() {
+    let app = TestApp::new().await;
+    let email = signup(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    for _ in 0..2 {
+        let response = app
+            .post_login_with_user_agent(&login_body(&email, PASSWORD), "device-a")
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_email_owner_on_login_from_new_device() {
+    let app = TestApp::new().await;
+    let email = signup(&app).await;
+
+    let response = app
+        .post_login_with_user_agent(&login_body(&email, PASSWORD), "device-a")
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app
+        .post_login_with_user_agent(&login_body(&email, PASSWORD), "device-b")
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body = sent_email_body(&app).await;
+    assert!(body.contains("device-b"));
+    assert!(body.contains(&format!(
+        "{}, {}",
+        test::geo_locator::CITY,
+        test::geo_locator::COUNTRY
+    )));
+    assert!(body.contains("/account/report-login#token="));
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_revoke_session_and_force_password_reset_when_reported() {
+    let app = TestApp::new().await;
+    let email = signup(&app).await;
+
+    let response = app
+        .post_login_with_user_agent(&login_body(&email, PASSWORD), "device-a")
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let response = app
+        .post_login_with_user_agent(&login_body(&email, PASSWORD), "device-b")
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let session_token = response
+        .cookies()
+        .find(|cookie| cookie.name() == app.auth_cookie_name())
+        .expect("No auth cookie found")
+        .value()
+        .to_owned();
+
+    let token = report_token(&sent_email_body(&app).await);
+
+    // Opening the link, as mail scanners do, only serves the page
+    let response = app.get_report_login_page().await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["content-type"], "text/html");
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": session_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Confirming on the page reports the login
+    let response = app.post_report_login(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // The reported session no longer verifies
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": session_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // Password logins are blocked until the password is reset
+    let response = app
+        .post_login_with_user_agent(&login_body(&email, PASSWORD), "device-a")
+        .await;
+    assert_eq!(response.status().as_u16(), 403);
+
+    let error_response: ErrorResponse = response
+        .json()
+        .await
+        .expect("Failed to parse error response");
+    assert_eq!(error_response.error, "Password reset required");
+
+    // The page the report sends the user on to
+    let response = app.get_reset_password_page().await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["content-type"], "text/html");
+
+    let response = app
+        .post_reset_password(&serde_json::json!({
+            "token": token,
+            "newPassword": "newpasword123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_login_with_user_agent(&login_body(&email, "newpasword123"), "device-a")
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // The reset token is single use
+    let response = app
+        .post_reset_password(&serde_json::json!({
+            "token": token,
+            "newPassword": "anotherpasword123",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_400_if_invalid_report_token() {
+    let app = TestApp::new().await;
+
+    let tokens = ["not-a-token", "1b4e28ba-2fa1-11d2-883f-0016d3cca427"];
+
+    for token in tokens {
+        let response = app.post_report_login(token).await;
+        assert_eq!(response.status().as_u16(), 400);
+
+        let error_response: ErrorResponse = response
+            .json()
+            .await
+            .expect("Failed to parse error response");
+        assert_eq!(error_response.error, "Invalid login alert token");
+    }
+
+    app.clean_up().await;
+}
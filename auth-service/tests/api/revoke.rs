@@ -0,0 +1,165 @@
+use auth_service::routes::{AccessTokenResponse, AuthTokenResponse, CreateServiceClientResponse};
+
+use crate::helpers::{get_random_email, TestApp};
+
+async fn sign_up(app: &TestApp) -> String {
+    let email = get_random_email();
+    let body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 201);
+    email
+}
+
+// A new session token for the user
+async fn log_in(app: &TestApp, email: &str) -> String {
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": email,
+            "password": "password123",
+            "responseMode": "token"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json::<AuthTokenResponse>()
+        .await
+        .unwrap()
+        .access_token
+}
+
+async fn register_service_client(app: &TestApp) -> CreateServiceClientResponse {
+    let response = app
+        .post_service_client(&serde_json::json!({
+            "name": "Nightly Reports",
+            "scopes": ["reports:read"]
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    response.json().await.unwrap()
+}
+
+async fn client_token(app: &TestApp, client: &CreateServiceClientResponse) -> String {
+    let response = app
+        .post_token_with_basic_auth(
+            &client.client.client_id,
+            &client.client_secret,
+            &[("grant_type", "client_credentials")],
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    response
+        .json::<AccessTokenResponse>()
+        .await
+        .unwrap()
+        .access_token
+}
+
+// Asks as a named service, so client tokens verify as well as sessions
+async fn is_valid(app: &TestApp, token: &str) -> bool {
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": token, "audience": "revoke-tests" }))
+        .await;
+    response.status().as_u16() == 200
+}
+
+#[tokio::test]
+async fn should_let_users_revoke_their_own_tokens() {
+    let app = TestApp::new().await;
+    let email = sign_up(&app).await;
+    let session = log_in(&app, &email).await;
+    let other_session = log_in(&app, &email).await;
+
+    let response = app
+        .post_revoke_with_bearer(
+            &session,
+            &[
+                ("token", &other_session),
+                ("token_type_hint", "access_token"),
+            ],
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(!is_valid(&app, &other_session).await);
+    assert!(is_valid(&app, &session).await);
+
+    // Unknown and already revoked tokens are not an error
+    for token in ["not-a-token", other_session.as_str()] {
+        let response = app
+            .post_revoke_with_bearer(&session, &[("token", token)])
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    // Another user's token is off limits
+    let someone_else = log_in(&app, &sign_up(&app).await).await;
+    let response = app
+        .post_revoke_with_bearer(&session, &[("token", &someone_else)])
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+    let error: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(error["error"], "unauthorized_client");
+    assert!(is_valid(&app, &someone_else).await);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_let_service_clients_revoke_tokens_issued_to_them() {
+    let app = TestApp::new().await;
+    let client = register_service_client(&app).await;
+    let credentials = Some((
+        client.client.client_id.as_str(),
+        client.client_secret.as_str(),
+    ));
+    let token = client_token(&app, &client).await;
+
+    let response = app.post_revoke(credentials, &[("token", &token)]).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(!is_valid(&app, &token).await);
+
+    let response = app
+        .post_revoke(credentials, &[("token", "not-a-token")])
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Neither a user's session nor another client's token was issued to this client
+    let session = log_in(&app, &sign_up(&app).await).await;
+    let other_client = register_service_client(&app).await;
+    let other_token = client_token(&app, &other_client).await;
+    for token in [&session, &other_token] {
+        let response = app.post_revoke(credentials, &[("token", token)]).await;
+        assert_eq!(response.status().as_u16(), 400);
+        assert!(is_valid(&app, token).await);
+    }
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_unauthenticated_callers() {
+    let app = TestApp::new().await;
+    let email = sign_up(&app).await;
+    let session = log_in(&app, &email).await;
+
+    // A cookie session is not enough; browsers use /logout
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "password123" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    for credentials in [None, Some(("unknown-client", "secret"))] {
+        let response = app.post_revoke(credentials, &[("token", &session)]).await;
+        assert_eq!(response.status().as_u16(), 401);
+        let error: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(error["error"], "invalid_client");
+    }
+    assert!(is_valid(&app, &session).await);
+
+    let response = app.post_revoke_with_bearer(&session, &[]).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    app.clean_up().await;
+}
@@ -0,0 +1,179 @@
+use auth_service::{
+    routes::{
+        AccessTokenResponse, CreateServiceClientResponse, ServiceClientResponse,
+        VerifyTokenResponse,
+    },
+    utils::TokenKind,
+    ErrorResponse,
+};
+use reqwest::header;
+
+use crate::helpers::TestApp;
+
+async fn register_service_client(app: &TestApp) -> CreateServiceClientResponse {
+    let response = app
+        .post_service_client(&serde_json::json!({
+            "name": "Nightly Reports",
+            "scopes": ["reports:read", "reports:write"]
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    response.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn should_manage_service_clients_without_revealing_secrets() {
+    let app = TestApp::new().await;
+
+    let client = register_service_client(&app).await;
+    assert!(!client.client_secret.is_empty());
+
+    let response = app.get_service_clients().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.text().await.unwrap();
+    assert!(!body.contains(&client.client_secret));
+    let clients: Vec<ServiceClientResponse> = serde_json::from_str(&body).unwrap();
+    assert_eq!(clients.len(), 1);
+    assert_eq!(clients[0].client_id, client.client.client_id);
+    assert_eq!(clients[0].scopes, ["reports:read", "reports:write"]);
+
+    let invalid_requests = [
+        serde_json::json!({ "name": " ", "scopes": ["reports:read"] }),
+        serde_json::json!({ "name": "Job", "scopes": [] }),
+        serde_json::json!({ "name": "Job", "scopes": ["bad scope"] }),
+    ];
+    for body in invalid_requests {
+        let response = app.post_service_client(&body).await;
+        assert_eq!(response.status().as_u16(), 400);
+        let error: ErrorResponse = response.json().await.unwrap();
+        assert_eq!(error.error, "Invalid service client");
+    }
+
+    let response = app.delete_service_client(&client.client.client_id).await;
+    assert_eq!(response.status().as_u16(), 204);
+    let response = app.delete_service_client(&client.client.client_id).await;
+    assert_eq!(response.status().as_u16(), 404);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_issue_client_tokens_for_client_credentials() {
+    let app = TestApp::new().await;
+    let client = register_service_client(&app).await;
+    let client_id = &client.client.client_id;
+
+    // HTTP Basic, with every scope the client was registered with
+    let response = app
+        .post_token_with_basic_auth(
+            client_id,
+            &client.client_secret,
+            &[("grant_type", "client_credentials")],
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+    let token: AccessTokenResponse = response.json().await.unwrap();
+    assert_eq!(token.scope, "reports:read reports:write");
+    assert_eq!(token.expires_in, 300);
+    assert!(token.id_token.is_none());
+
+    // Not a user's session, so it only verifies for a service that names itself
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": token.access_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    let response = app
+        .post_verify_token(&serde_json::json!({
+            "token": token.access_token,
+            "audience": "reports-api"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let verified: VerifyTokenResponse = response.json().await.unwrap();
+    assert_eq!(verified.token_kind, TokenKind::Client);
+
+    // Credentials in the form body, narrowed to one scope
+    let response = app
+        .post_token(&[
+            ("grant_type", "client_credentials"),
+            ("client_id", client_id),
+            ("client_secret", &client.client_secret),
+            ("scope", "reports:read"),
+        ])
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let token: AccessTokenResponse = response.json().await.unwrap();
+    assert_eq!(token.scope, "reports:read");
+
+    // A client token acts for no user
+    let response = app
+        .http_client
+        .get(format!("{}/account/login-history", &app.address))
+        .bearer_auth(&token.access_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+    let response = app.get_userinfo(&token.access_token).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_reject_bad_client_credentials() {
+    let app = TestApp::new().await;
+    let client = register_service_client(&app).await;
+    let client_id = &client.client.client_id;
+
+    for (client_id, client_secret) in [
+        (client_id.as_str(), "wrong-secret"),
+        ("unknown-client", client.client_secret.as_str()),
+    ] {
+        let response = app
+            .post_token_with_basic_auth(
+                client_id,
+                client_secret,
+                &[("grant_type", "client_credentials")],
+            )
+            .await;
+        assert_eq!(response.status().as_u16(), 401);
+        assert_eq!(response.headers()[header::WWW_AUTHENTICATE], "Basic");
+        let error: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(error["error"], "invalid_client");
+    }
+
+    let response = app
+        .post_token(&[
+            ("grant_type", "client_credentials"),
+            ("client_id", client_id),
+        ])
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    // A scope the client was not registered with
+    let response = app
+        .post_token_with_basic_auth(
+            client_id,
+            &client.client_secret,
+            &[("grant_type", "client_credentials"), ("scope", "admin")],
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+    let error: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(error["error"], "invalid_scope");
+
+    // Deleted clients can no longer get tokens
+    app.delete_service_client(client_id).await;
+    let response = app
+        .post_token_with_basic_auth(
+            client_id,
+            &client.client_secret,
+            &[("grant_type", "client_credentials")],
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await;
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+
+use crate::helpers::{get_random_email, TestApp};
+
+#[tokio::test]
+async fn should_stop_serving_and_close_the_pool_on_shutdown() {
+    let mut app = TestApp::new().await;
+
+    let response = app.get_health_live().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.shutdown().await.expect("Shutdown failed");
+
+    assert!(app.pg_pool.is_closed());
+    let result = app
+        .http_client
+        .get(format!("{}/health/live", &app.address))
+        .send()
+        .await;
+    assert!(result.is_err(), "Server still accepted a connection");
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_finish_in_flight_requests_before_shutting_down() {
+    let mut app = TestApp::new().await;
+
+    let email = get_random_email();
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": "password123",
+            "requires2FA": true
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    // A slow email provider keeps the login in flight while the shutdown starts
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(100)))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let login = tokio::spawn({
+        let http_client = app.http_client.clone();
+        let url = format!("{}/login", &app.address);
+        async move {
+            http_client
+                .post(url)
+                .json(&serde_json::json!({
+                    "email": email,
+                    "password": "password123"
+                }))
+                .send()
+                .await
+        }
+    });
+
+    // Wait until the login is blocked on the email provider
+    let mut in_flight = false;
+    for _ in 0..200 {
+        let requests = app.email_server.received_requests().await.unwrap();
+        if !requests.is_empty() {
+            in_flight = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(in_flight, "Login never reached the email provider");
+
+    app.shutdown().await.expect("Shutdown failed");
+
+    let response = login.await.unwrap().expect("In-flight login was dropped");
+    assert_eq!(response.status().as_u16(), 206);
+
+    app.clean_up().await;
+}
@@ -0,0 +1,151 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use reqwest::{header, redirect::Policy, tls::TlsInfo, Certificate, Client};
+
+use crate::helpers::TestApp;
+
+// A self-signed certificate for the test app's address, written where `tls.cert_path` and
+// `tls.key_path` point
+struct TestCertificate {
+    pem: String,
+    der: Vec<u8>,
+}
+
+fn certificate_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("auth-tls-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write_certificate(dir: &Path) -> TestCertificate {
+    let certified =
+        rcgen::generate_simple_self_signed(vec!["127.0.0.1".to_owned(), "localhost".to_owned()])
+            .unwrap();
+    std::fs::write(dir.join("cert.pem"), certified.cert.pem()).unwrap();
+    std::fs::write(dir.join("key.pem"), certified.key_pair.serialize_pem()).unwrap();
+
+    TestCertificate {
+        pem: certified.cert.pem(),
+        der: certified.cert.der().to_vec(),
+    }
+}
+
+async fn spawn_tls_app(dir: &Path, redirect: bool) -> TestApp {
+    TestApp::with_settings(|settings| {
+        settings.tls.enabled = true;
+        settings.tls.cert_path = dir.join("cert.pem").display().to_string();
+        settings.tls.key_path = dir.join("key.pem").display().to_string();
+        settings.tls.reload_interval_secs = 1;
+        settings.tls.hsts_max_age_secs = Some(31536000);
+        if redirect {
+            settings.tls.redirect_address = Some("127.0.0.1:0".to_owned());
+        }
+    })
+    .await
+}
+
+fn https_client(trusted: &[&TestCertificate]) -> Client {
+    let mut builder = Client::builder().tls_info(true);
+    for certificate in trusted {
+        builder = builder
+            .add_root_certificate(Certificate::from_pem(certificate.pem.as_bytes()).unwrap());
+    }
+    builder.build().unwrap()
+}
+
+// A new client per call, so every check does a fresh handshake
+async fn served_certificate(app: &TestApp, trusted: &[&TestCertificate]) -> Vec<u8> {
+    let response = https_client(trusted)
+        .get(format!("{}/health/live", &app.address))
+        .send()
+        .await
+        .expect("HTTPS request failed");
+
+    response
+        .extensions()
+        .get::<TlsInfo>()
+        .and_then(|info| info.peer_certificate())
+        .expect("No peer certificate")
+        .to_vec()
+}
+
+#[tokio::test]
+async fn should_serve_https_with_hsts() {
+    let dir = certificate_dir();
+    let certificate = write_certificate(&dir);
+    let app = spawn_tls_app(&dir, false).await;
+    assert!(app.address.starts_with("https://"));
+
+    let response = https_client(&[&certificate])
+        .get(format!("{}/health/live", &app.address))
+        .send()
+        .await
+        .expect("HTTPS request failed");
+
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.headers()[header::STRICT_TRANSPORT_SECURITY],
+        "max-age=31536000"
+    );
+
+    app.clean_up().await;
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn should_serve_a_renewed_certificate_without_restarting() {
+    let dir = certificate_dir();
+    let first = write_certificate(&dir);
+    let app = spawn_tls_app(&dir, false).await;
+
+    assert_eq!(served_certificate(&app, &[&first]).await, first.der);
+
+    let second = write_certificate(&dir);
+    let mut reloaded = false;
+    for _ in 0..50 {
+        if served_certificate(&app, &[&first, &second]).await == second.der {
+            reloaded = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(reloaded, "The renewed certificate was never served");
+
+    app.clean_up().await;
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn should_redirect_plain_http_to_https() {
+    let dir = certificate_dir();
+    write_certificate(&dir);
+    let app = spawn_tls_app(&dir, true).await;
+    let redirect_address = app.redirect_address.clone().expect("No redirect listener");
+    let https_port = app.address.rsplit(':').next().unwrap();
+
+    let client = Client::builder().redirect(Policy::none()).build().unwrap();
+    let response = client
+        .get(format!(
+            "http://{}/account/login-history?page=2",
+            redirect_address
+        ))
+        .header(header::HOST, "localhost:8080")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status().as_u16(), 308);
+    assert_eq!(
+        response.headers()[header::LOCATION],
+        format!(
+            "https://localhost:{}/account/login-history?page=2",
+            https_port
+        )
+    );
+
+    app.clean_up().await;
+    std::fs::remove_dir_all(&dir).unwrap();
+}
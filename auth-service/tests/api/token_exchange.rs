@@ -0,0 +1,236 @@
+use auth_service::{
+    routes::{
+        AccessTokenResponse, AuthTokenResponse, CreateServiceClientResponse, IntrospectionResponse,
+        TokenExchangeResponse, VerifyTokenResponse,
+    },
+    utils::TokenKind,
+};
+
+use crate::helpers::{get_random_email, TestApp};
+
+const TOKEN_EXCHANGE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:token-exchange";
+const ACCESS_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:access_token";
+
+async fn register_service_client(
+    app: &TestApp,
+    name: &str,
+    scopes: &[&str],
+) -> CreateServiceClientResponse {
+    let response = app
+        .post_service_client(&serde_json::json!({ "name": name, "scopes": scopes }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+    response.json().await.unwrap()
+}
+
+// Signs a new user up and returns a session token
+async fn log_in(app: &TestApp) -> (String, String) {
+    let email = get_random_email();
+    let body = serde_json::json!({
+        "email": email,
+        "password": "password123",
+        "requires2FA": false
+    });
+    assert_eq!(app.post_signup(&body).await.status().as_u16(), 201);
+
+    let response = app
+        .post_login(&serde_json::json!({
+            "email": email,
+            "password": "password123",
+            "responseMode": "token"
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let token = response
+        .json::<AuthTokenResponse>()
+        .await
+        .unwrap()
+        .access_token;
+    (email, token)
+}
+
+async fn exchange(
+    app: &TestApp,
+    client: &CreateServiceClientResponse,
+    subject_token: &str,
+    extra: &[(&str, &str)],
+) -> reqwest::Response {
+    let mut form = vec![
+        ("grant_type", TOKEN_EXCHANGE_GRANT_TYPE),
+        ("subject_token", subject_token),
+        ("subject_token_type", ACCESS_TOKEN_TYPE),
+    ];
+    form.extend_from_slice(extra);
+    app.post_token_with_basic_auth(&client.client.client_id, &client.client_secret, &form)
+        .await
+}
+
+async fn introspect(
+    app: &TestApp,
+    client: &CreateServiceClientResponse,
+    token: &str,
+) -> IntrospectionResponse {
+    let response = app
+        .post_introspect(
+            Some((&client.client.client_id, &client.client_secret)),
+            &[("token", token)],
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    response.json().await.unwrap()
+}
+
+async fn exchange_error(response: reqwest::Response) -> String {
+    assert_eq!(response.status().as_u16(), 400);
+    let body: serde_json::Value = response.json().await.unwrap();
+    body["error"].as_str().unwrap().to_owned()
+}
+
+#[tokio::test]
+async fn should_exchange_a_user_token_for_a_delegated_one() {
+    let app = TestApp::new().await;
+    let caller = register_service_client(&app, "App", &["reports:read", "reports:write"]).await;
+    let reports = register_service_client(&app, "Reports", &["reports:read"]).await;
+    let (email, session_token) = log_in(&app).await;
+
+    let response = exchange(
+        &app,
+        &caller,
+        &session_token,
+        &[
+            ("audience", &reports.client.client_id),
+            ("scope", "reports:read"),
+        ],
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["cache-control"], "no-store");
+    let exchanged: TokenExchangeResponse = response.json().await.unwrap();
+    assert_eq!(exchanged.issued_token_type, ACCESS_TOKEN_TYPE);
+    assert_eq!(exchanged.token_type, "Bearer");
+    assert_eq!(exchanged.scope, "reports:read");
+    assert!(exchanged.expires_in > 0 && exchanged.expires_in <= 300);
+
+    // The audience sees who the token is for and who is acting for them
+    let introspection = introspect(&app, &reports, &exchanged.access_token).await;
+    assert!(introspection.active);
+    assert_eq!(introspection.sub.as_deref(), Some(email.as_str()));
+    assert_eq!(introspection.aud.as_ref(), Some(&reports.client.client_id));
+    assert_eq!(
+        introspection.act.map(|actor| actor.sub),
+        Some(caller.client.client_id.clone())
+    );
+    assert_eq!(introspection.scope.as_deref(), Some("reports:read"));
+
+    // No other service accepts it
+    assert!(
+        !introspect(&app, &caller, &exchanged.access_token)
+            .await
+            .active
+    );
+    let response = app
+        .post_verify_token(&serde_json::json!({ "token": exchanged.access_token }))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    let response = app
+        .post_verify_token(&serde_json::json!({
+            "token": exchanged.access_token,
+            "audience": reports.client.client_id,
+            "includeClaims": true
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let verified: VerifyTokenResponse = response.json().await.unwrap();
+    assert_eq!(verified.token_kind, TokenKind::User);
+    let claims = verified.claims.unwrap();
+    assert_eq!(claims.act.unwrap().sub, caller.client.client_id);
+
+    // Without a scope the token carries every scope the caller has
+    let response = exchange(
+        &app,
+        &caller,
+        &session_token,
+        &[("audience", &reports.client.client_id)],
+    )
+    .await;
+    let exchanged: TokenExchangeResponse = response.json().await.unwrap();
+    assert_eq!(exchanged.scope, "reports:read reports:write");
+
+    // The audience can revoke it
+    let response = app
+        .post_revoke(
+            Some((&reports.client.client_id, &reports.client_secret)),
+            &[("token", &exchanged.access_token)],
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(
+        !introspect(&app, &reports, &exchanged.access_token)
+            .await
+            .active
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_only_exchange_valid_user_tokens_for_allowed_scopes() {
+    let app = TestApp::new().await;
+    let caller = register_service_client(&app, "App", &["reports:read"]).await;
+    let reports = register_service_client(&app, "Reports", &["reports:read"]).await;
+    let (_, session_token) = log_in(&app).await;
+    let audience = ("audience", reports.client.client_id.as_str());
+
+    let response = exchange(
+        &app,
+        &caller,
+        &session_token,
+        &[audience, ("scope", "reports:write")],
+    )
+    .await;
+    assert_eq!(exchange_error(response).await, "invalid_scope");
+
+    let response = exchange(&app, &caller, &session_token, &[("audience", "unknown")]).await;
+    assert_eq!(exchange_error(response).await, "invalid_target");
+
+    let response = exchange(&app, &caller, "not-a-token", &[audience]).await;
+    assert_eq!(exchange_error(response).await, "invalid_grant");
+
+    // Service client tokens do not act for a user
+    let response = app
+        .post_token_with_basic_auth(
+            &caller.client.client_id,
+            &caller.client_secret,
+            &[("grant_type", "client_credentials")],
+        )
+        .await;
+    let client_token = response
+        .json::<AccessTokenResponse>()
+        .await
+        .unwrap()
+        .access_token;
+    let response = exchange(&app, &caller, &client_token, &[audience]).await;
+    assert_eq!(exchange_error(response).await, "invalid_grant");
+
+    // Delegated tokens cannot be exchanged again
+    let response = exchange(&app, &caller, &session_token, &[audience]).await;
+    let delegated: TokenExchangeResponse = response.json().await.unwrap();
+    let response = exchange(&app, &reports, &delegated.access_token, &[audience]).await;
+    assert_eq!(exchange_error(response).await, "invalid_grant");
+
+    let response = exchange(&app, &caller, &session_token, &[]).await;
+    assert_eq!(exchange_error(response).await, "invalid_request");
+
+    // Only authenticated service clients can exchange tokens
+    let response = app
+        .post_token(&[
+            ("grant_type", TOKEN_EXCHANGE_GRANT_TYPE),
+            ("subject_token", &session_token),
+            ("subject_token_type", ACCESS_TOKEN_TYPE),
+            audience,
+        ])
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    app.clean_up().await;
+}
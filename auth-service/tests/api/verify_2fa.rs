@@ -1,7 +1,6 @@
 use auth_service::{
     domain::{Email, LoginAttemptId},
-    routes::TwoFactorAuthResponse,
-    utils::JWT_COOKIE_NAME,
+    routes::{AuthTokenResponse, TwoFactorAuthResponse},
 };
 use quickcheck::{Arbitrary, Gen};
 use quickcheck_macros::quickcheck;
@@ -190,7 +189,7 @@ async fn should_return_401_if_incorrect_credentials() {
     let email = Email::parse(Secret::new(random_email.to_string())).unwrap();
     let stored_login_attempt_id = {
         let two_fa_code_store = app.two_fa_code_store.write().await;
-        let (stored_login_attempt_id, _stored_two_fa_code) = two_fa_code_store
+        let (stored_login_attempt_id, _stored_two_fa_code, _) = two_fa_code_store
             .get_code(&email)
             .await
             .expect("Could not get 2FA code from store");
@@ -253,7 +252,7 @@ async fn should_return_401_if_old_code() {
 
     // Get the first 2FA code from store
     let email = Email::parse(Secret::new(random_email.to_string())).unwrap();
-    let (first_stored_login_attempt_id, first_stored_code) = {
+    let (first_stored_login_attempt_id, first_stored_code, _) = {
         let two_fa_code_store = app.two_fa_code_store.write().await;
         let result = two_fa_code_store
             .get_code(&email)
@@ -278,7 +277,7 @@ async fn should_return_401_if_old_code() {
     // Get the second 2FA code from store - verify it's different from first
     {
         let two_fa_code_store = app.two_fa_code_store.write().await;
-        let (second_stored_login_attempt_id, second_stored_code) = two_fa_code_store
+        let (second_stored_login_attempt_id, second_stored_code, _) = two_fa_code_store
             .get_code(&email)
             .await
             .expect("Could not get 2FA code from store");
@@ -343,7 +342,7 @@ async fn should_return_200_if_correct_code() {
 
     // Step 3: Get the actual 2FA code from the store
     let email = Email::parse(Secret::new(random_email.to_string())).unwrap();
-    let (stored_login_attempt_id, stored_two_fa_code) = {
+    let (stored_login_attempt_id, stored_two_fa_code, _) = {
         let two_fa_code_store = app.two_fa_code_store.write().await;
         two_fa_code_store
             .get_code(&email)
@@ -366,7 +365,7 @@ async fn should_return_200_if_correct_code() {
     // Step 5: Assert auth cookie is set
     let auth_cookie = response
         .cookies()
-        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .find(|cookie| cookie.name() == app.auth_cookie_name())
         .expect("No auth cookie found");
 
     assert!(!auth_cookie.value().is_empty());
@@ -417,7 +416,7 @@ async fn should_return_401_if_same_code_twice() {
 
     // Step 3: Get the actual 2FA code from the store
     let email = Email::parse(Secret::new(random_email.to_string())).unwrap();
-    let (stored_login_attempt_id, stored_two_fa_code) = {
+    let (stored_login_attempt_id, stored_two_fa_code, _) = {
         let two_fa_code_store = app.two_fa_code_store.write().await;
         two_fa_code_store
             .get_code(&email)
@@ -443,3 +442,66 @@ async fn should_return_401_if_same_code_twice() {
 
     app.clean_up().await;
 }
+
+#[tokio::test]
+async fn should_return_token_in_body_in_token_mode() {
+    let app = TestApp::new().await;
+    let random_email = get_random_email();
+
+    let signup_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "requires2FA": true
+    });
+    let response = app.post_signup(&signup_body).await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let login_body = serde_json::json!({
+        "email": random_email,
+        "password": "pasword123",
+        "responseMode": "token"
+    });
+    let response = app.post_login(&login_body).await;
+    assert_eq!(response.status().as_u16(), 206);
+    assert!(
+        response.cookies().next().is_none(),
+        "Token mode must not set the auth cookie"
+    );
+
+    let email = Email::parse(Secret::new(random_email.to_string())).unwrap();
+    let (login_attempt_id, two_fa_code, _) = app
+        .two_fa_code_store
+        .read()
+        .await
+        .get_code(&email)
+        .await
+        .expect("Could not get 2FA code from store");
+
+    let json_body = serde_json::json!({
+        "email": random_email,
+        "loginAttemptId": login_attempt_id.as_ref(),
+        "2FACode": two_fa_code.as_ref(),
+        "responseMode": "token"
+    });
+    let response = app.post_verify_2fa(&json_body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response.cookies().next().is_none());
+
+    let body = response
+        .json::<AuthTokenResponse>()
+        .await
+        .expect("Could not deserialize response body to AuthTokenResponse");
+    assert_eq!(body.token_type, "Bearer");
+
+    let response = app.post_logout_with_bearer(&body.access_token).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    app.clean_up().await;
+}
@@ -1,5 +1,5 @@
 use crate::helpers::{get_random_email, TestApp};
-use auth_service::utils::{constants::JWT_SECRET, JWT_COOKIE_NAME};
+use auth_service::utils::{Profile, Settings};
 use chrono::Utc;
 use jsonwebtoken::{encode, EncodingKey, Header};
 use quickcheck::{Arbitrary, Gen};
@@ -86,6 +86,7 @@ async fn should_return_200_valid_token() {
     // Parse and verify response
     let response_body: serde_json::Value = response.json().await.expect("Failed to parse response");
     assert_eq!(response_body["message"], "Success verifying token");
+    assert_eq!(response_body["tokenKind"], "user");
 
     app.clean_up().await;
 }
@@ -149,7 +150,7 @@ async fn should_return_401_if_banned_token() {
 
     let auth_cookie = response
         .cookies()
-        .find(|cookie| cookie.name() == JWT_COOKIE_NAME)
+        .find(|cookie| cookie.name() == app.auth_cookie_name())
         .expect("No auth cookie found");
     let jwt_token = auth_cookie.value();
 
@@ -205,10 +206,11 @@ impl Arbitrary for ValidJwtToken {
         };
 
         // Generate valid JWT token using the same secret as the app
+        let settings = Settings::load_profile(Profile::Test).expect("Failed to load test settings");
         let token = encode(
             &Header::default(),
             &claims,
-            &EncodingKey::from_secret(JWT_SECRET.expose_secret().as_bytes()),
+            &EncodingKey::from_secret(settings.jwt.secret.expose_secret().as_bytes()),
         )
         .unwrap_or_else(|_| "invalid_token".to_string());
 
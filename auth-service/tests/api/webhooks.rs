@@ -0,0 +1,272 @@
+use auth_service::{
+    domain::sign_webhook_payload,
+    routes::{WebhookDeadLetterResponse, WebhookSubscriptionResponse},
+    services::{WEBHOOK_EVENT_HEADER, WEBHOOK_SIGNATURE_HEADER, WEBHOOK_TIMESTAMP_HEADER},
+    ErrorResponse,
+};
+use secrecy::Secret;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+use crate::helpers::{get_random_email, TestApp};
+
+const WEBHOOK_SECRET: &str = "whsec_test_secret";
+
+async fn subscribe(app: &TestApp, server: &MockServer, events: &[&str]) -> String {
+    let response = app
+        .post_webhook(&serde_json::json!({
+            "url": format!("{}/hooks", server.uri()),
+            "secret": WEBHOOK_SECRET,
+            "events": events,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+
+    let subscription = response
+        .json::<WebhookSubscriptionResponse>()
+        .await
+        .expect("Could not deserialize response body to WebhookSubscriptionResponse");
+    subscription.id.to_string()
+}
+
+async fn sign_up(app: &TestApp, email: &str, requires_2fa: bool) {
+    let response = app
+        .post_signup(&serde_json::json!({
+            "email": email,
+            "password": "password123",
+            "requires2FA": requires_2fa
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 201);
+}
+
+#[tokio::test]
+async fn should_require_the_admin_token() {
+    let app = TestApp::new().await;
+
+    let body = serde_json::json!({
+        "url": "https://example.com/hooks",
+        "secret": WEBHOOK_SECRET,
+        "events": ["user.signed_up"],
+    });
+
+    let response = app.post_webhook_with_token(&body, None).await;
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = app
+        .post_webhook_with_token(&body, Some("wrong-token"))
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(
+        response
+            .json::<ErrorResponse>()
+            .await
+            .expect("Could not deserialize response body to ErrorResponse")
+            .error,
+        "Invalid token".to_owned()
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_return_400_for_invalid_subscriptions() {
+    let app = TestApp::new().await;
+
+    let test_cases = [
+        serde_json::json!({ "url": "not a url", "secret": WEBHOOK_SECRET, "events": ["user.signed_up"] }),
+        serde_json::json!({ "url": "ftp://example.com/hooks", "secret": WEBHOOK_SECRET, "events": ["user.signed_up"] }),
+        serde_json::json!({ "url": "https://example.com/hooks", "secret": "", "events": ["user.signed_up"] }),
+        serde_json::json!({ "url": "https://example.com/hooks", "secret": WEBHOOK_SECRET, "events": [] }),
+        serde_json::json!({ "url": "https://example.com/hooks", "secret": WEBHOOK_SECRET, "events": ["user.renamed"] }),
+        // There is no email verification or account deletion to emit these yet
+        serde_json::json!({ "url": "https://example.com/hooks", "secret": WEBHOOK_SECRET, "events": ["user.email_verified"] }),
+        serde_json::json!({ "url": "https://example.com/hooks", "secret": WEBHOOK_SECRET, "events": ["user.deleted"] }),
+    ];
+
+    for test_case in test_cases.iter() {
+        let response = app.post_webhook(test_case).await;
+        assert_eq!(
+            response.status().as_u16(),
+            400,
+            "Failed for input: {:?}",
+            test_case
+        );
+    }
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_deliver_signed_webhook_when_user_signs_up() {
+    let app = TestApp::new().await;
+    let server = MockServer::start().await;
+
+    Mock::given(path("/hooks"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    subscribe(&app, &server, &["user.signed_up"]).await;
+
+    let email = get_random_email();
+    sign_up(&app, &email, false).await;
+
+    assert_eq!(app.deliver_webhooks().await, 1);
+    // Delivered webhooks leave the queue
+    assert_eq!(app.deliver_webhooks().await, 0);
+
+    let requests = server.received_requests().await.unwrap();
+    let request = &requests[0];
+    let header = |name: &str| request.headers.get(name).unwrap().to_str().unwrap();
+
+    let body = String::from_utf8(request.body.clone()).unwrap();
+    let timestamp: i64 = header(WEBHOOK_TIMESTAMP_HEADER).parse().unwrap();
+    let signature = sign_webhook_payload(&Secret::new(WEBHOOK_SECRET.to_owned()), timestamp, &body);
+
+    assert_eq!(header(WEBHOOK_EVENT_HEADER), "user.signed_up");
+    assert_eq!(
+        header(WEBHOOK_SIGNATURE_HEADER),
+        format!("sha256={}", signature)
+    );
+
+    let payload: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(payload["type"], "user.signed_up");
+    assert_eq!(payload["data"]["email"], email);
+    assert_eq!(payload["data"]["requires2FA"], false);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_only_deliver_subscribed_events() {
+    let app = TestApp::new().await;
+    let server = MockServer::start().await;
+
+    Mock::given(path("/hooks"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    subscribe(&app, &server, &["user.two_factor_enabled"]).await;
+
+    sign_up(&app, &get_random_email(), false).await;
+    assert_eq!(app.deliver_webhooks().await, 0);
+
+    sign_up(&app, &get_random_email(), true).await;
+    assert_eq!(app.deliver_webhooks().await, 1);
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(
+        requests[0].headers.get(WEBHOOK_EVENT_HEADER).unwrap(),
+        "user.two_factor_enabled"
+    );
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_dead_letter_failing_deliveries_and_replay_them() {
+    let app = TestApp::new().await;
+    let server = MockServer::start().await;
+
+    // The subscriber is down for every attempt the test policy allows
+    Mock::given(path("/hooks"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(3)
+        .expect(3)
+        .mount(&server)
+        .await;
+
+    let subscription_id = subscribe(&app, &server, &["user.signed_up"]).await;
+    sign_up(&app, &get_random_email(), false).await;
+
+    for _ in 0..3 {
+        assert_eq!(app.deliver_webhooks().await, 1);
+    }
+    assert_eq!(app.deliver_webhooks().await, 0);
+
+    let response = app.get_webhook_dead_letters().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let dead_letters = response
+        .json::<Vec<WebhookDeadLetterResponse>>()
+        .await
+        .expect("Could not deserialize response body to dead letters");
+    assert_eq!(dead_letters.len(), 1);
+    assert_eq!(dead_letters[0].subscription_id.to_string(), subscription_id);
+    assert_eq!(dead_letters[0].attempts, 3);
+    assert!(dead_letters[0]
+        .last_error
+        .as_deref()
+        .unwrap()
+        .contains("500"));
+
+    // Once the subscriber recovers the dead letter can be sent again
+    Mock::given(path("/hooks"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let delivery_id = dead_letters[0].id.to_string();
+    let response = app.post_replay_webhook_delivery(&delivery_id).await;
+    assert_eq!(response.status().as_u16(), 202);
+
+    assert_eq!(app.deliver_webhooks().await, 1);
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 4);
+    // Replays resend the original payload
+    assert_eq!(requests[3].body, requests[0].body);
+
+    let dead_letters = app
+        .get_webhook_dead_letters()
+        .await
+        .json::<Vec<WebhookDeadLetterResponse>>()
+        .await
+        .expect("Could not deserialize response body to dead letters");
+    assert!(dead_letters.is_empty());
+
+    let response = app.post_replay_webhook_delivery(&delivery_id).await;
+    assert_eq!(response.status().as_u16(), 404);
+
+    app.clean_up().await;
+}
+
+#[tokio::test]
+async fn should_list_and_delete_subscriptions() {
+    let app = TestApp::new().await;
+    let server = MockServer::start().await;
+
+    let subscription_id = subscribe(&app, &server, &["user.signed_up", "user.two_factor_enabled"]).await;
+
+    let response = app.get_webhooks().await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body[0]["id"], subscription_id);
+    assert_eq!(
+        body[0]["events"],
+        serde_json::json!(["user.signed_up", "user.two_factor_enabled"])
+    );
+    assert!(body[0].get("secret").is_none());
+
+    let response = app.delete_webhook(&subscription_id).await;
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = app.delete_webhook(&subscription_id).await;
+    assert_eq!(response.status().as_u16(), 404);
+
+    // Nothing is queued for a deleted subscription
+    sign_up(&app, &get_random_email(), false).await;
+    assert_eq!(app.deliver_webhooks().await, 0);
+
+    app.clean_up().await;
+}